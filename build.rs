@@ -4,8 +4,9 @@ fn main() {
     println!("cargo:rustc-link-lib=framework=CoreFoundation");
     println!("cargo:rustc-link-lib=framework=AudioUnit");
 
-    // Only build on macOS
-    if cfg!(target_os = "macos") {
+    // Only build on macOS, and only when the IOKit-backed detection is enabled
+    if cfg!(target_os = "macos") && std::env::var_os("CARGO_FEATURE_BLUETOOTH_DETECTION").is_some()
+    {
         println!("cargo:rustc-link-lib=framework=IOKit");
     }
 }
@@ -15,7 +15,7 @@ async fn main() -> Result<()> {
     let config = Config::default();
 
     // Create monitor
-    let monitor = AudioDeviceMonitor::new(config)?;
+    let monitor = AudioDeviceMonitor::new_production(&config)?;
 
     println!("\nStarting device monitoring...");
     println!("Monitoring for 10 seconds to test functionality");
@@ -5,18 +5,33 @@ use coreaudio_sys::*;
 // Removed cpal imports
 use std::os::raw::c_void;
 use std::ptr;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
-use super::device::{AudioDevice, DeviceInfo, DeviceType};
+use super::device::{
+    AudioDevice, DeviceInfo, DeviceType, FUZZY_MATCH_THRESHOLD, StreamFormat, TransportType,
+    latency_frames_to_ms, mark_default_devices, name_similarity, sample_rates_from_ranges,
+    sort_stream_formats, validate_buffer_frame_size,
+};
 
+#[derive(Clone)]
 pub struct DeviceController {
     // No longer need cpal host
+    // Move system alert sounds along with the default output device
+    sync_system_alerts: bool,
 }
 
 impl DeviceController {
     pub fn new() -> Result<Self> {
         debug!("Initialized audio device controller with CoreAudio");
-        Ok(Self {})
+        Ok(Self {
+            sync_system_alerts: false,
+        })
+    }
+
+    /// Keep the system alert device in step with every output switch
+    pub fn with_sync_system_alerts(mut self, sync_system_alerts: bool) -> Self {
+        self.sync_system_alerts = sync_system_alerts;
+        self
     }
 
     pub fn enumerate_devices(&self) -> Result<Vec<AudioDevice>> {
@@ -63,32 +78,28 @@ impl DeviceController {
             for &device_id in &device_ids {
                 if let Ok(name) = self.get_coreaudio_device_name(device_id) {
                     // Check if device supports input
-                    if self.device_supports_direction(device_id, true)? {
-                        let mut audio_device = AudioDevice::new(
-                            device_id.to_string(),
-                            name.clone(),
-                            DeviceType::Input,
-                        );
-
-                        // Get device UID for more reliable identification
-                        if let Ok(uid) = self.get_coreaudio_device_uid(device_id) {
-                            audio_device = audio_device.with_uid(uid);
+                    let channels = self.get_coreaudio_channel_count(device_id, true)?;
+                    if channels > 0 {
+                        let mut audio_device = self
+                            .build_audio_device(device_id, name.clone(), DeviceType::Input)
+                            .with_channels(channels);
+
+                        if let Ok(rates) = self.get_coreaudio_available_sample_rates(device_id) {
+                            audio_device = audio_device.with_sample_rates(rates);
                         }
 
                         devices.push(audio_device);
                     }
 
                     // Check if device supports output
-                    if self.device_supports_direction(device_id, false)? {
-                        let mut audio_device = AudioDevice::new(
-                            device_id.to_string(),
-                            name.clone(),
-                            DeviceType::Output,
-                        );
-
-                        // Get device UID for more reliable identification
-                        if let Ok(uid) = self.get_coreaudio_device_uid(device_id) {
-                            audio_device = audio_device.with_uid(uid);
+                    let channels = self.get_coreaudio_channel_count(device_id, false)?;
+                    if channels > 0 {
+                        let mut audio_device = self
+                            .build_audio_device(device_id, name.clone(), DeviceType::Output)
+                            .with_channels(channels);
+
+                        if let Ok(rates) = self.get_coreaudio_available_sample_rates(device_id) {
+                            audio_device = audio_device.with_sample_rates(rates);
                         }
 
                         devices.push(audio_device);
@@ -97,117 +108,663 @@ impl DeviceController {
             }
         }
 
+        let default_output = Self::get_default_device_id(kAudioHardwarePropertyDefaultOutputDevice);
+        let default_input = Self::get_default_device_id(kAudioHardwarePropertyDefaultInputDevice);
+        mark_default_devices(
+            &mut devices,
+            default_output.map(|id| id.to_string()).as_deref(),
+            default_input.map(|id| id.to_string()).as_deref(),
+        );
+
         debug!("Enumerated {} audio devices", devices.len());
         Ok(devices)
     }
 
-    pub fn get_default_input_device(&self) -> Result<Option<AudioDevice>> {
-        unsafe {
-            let property_address = AudioObjectPropertyAddress {
-                mSelector: kAudioHardwarePropertyDefaultInputDevice,
-                mScope: kAudioObjectPropertyScopeGlobal,
-                mElement: kAudioObjectPropertyElementMain,
-            };
+    /// Create an AudioDevice for a CoreAudio device, attaching its UID when available
+    /// for more reliable identification
+    fn build_audio_device(
+        &self,
+        device_id: AudioDeviceID,
+        name: String,
+        device_type: DeviceType,
+    ) -> AudioDevice {
+        let device = match self.get_coreaudio_device_uid(device_id) {
+            Ok(uid) => AudioDevice::new_with_uid(device_id.to_string(), name, device_type, uid),
+            Err(_) => AudioDevice::new(device_id.to_string(), name, device_type),
+        };
+        device.with_transport_type(self.get_transport_type(device_id))
+    }
+
+    /// How a device is attached to the system (USB, Bluetooth, HDMI, ...)
+    #[cfg(feature = "transport-type-query")]
+    pub fn get_transport_type(&self, device_id: AudioDeviceID) -> TransportType {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyTransportType,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
 
-            let mut device_id: AudioDeviceID = 0;
-            let mut property_size = std::mem::size_of::<AudioDeviceID>() as u32;
+        let mut transport: u32 = 0;
+        let mut property_size = std::mem::size_of::<u32>() as u32;
 
-            let result = AudioObjectGetPropertyData(
+        let result = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                &mut transport as *mut u32 as *mut c_void,
+            )
+        };
+
+        if result != kAudioHardwareNoError as i32 {
+            debug!("Failed to get transport type for device {}", device_id);
+            return TransportType::Unknown;
+        }
+
+        #[allow(non_upper_case_globals)]
+        match transport {
+            kAudioDeviceTransportTypeBuiltIn => TransportType::BuiltIn,
+            kAudioDeviceTransportTypeUSB => TransportType::Usb,
+            kAudioDeviceTransportTypeBluetooth | kAudioDeviceTransportTypeBluetoothLE => {
+                TransportType::Bluetooth
+            }
+            kAudioDeviceTransportTypeHDMI => TransportType::Hdmi,
+            kAudioDeviceTransportTypeDisplayPort => TransportType::DisplayPort,
+            kAudioDeviceTransportTypeThunderbolt => TransportType::Thunderbolt,
+            kAudioDeviceTransportTypeAirPlay => TransportType::AirPlay,
+            kAudioDeviceTransportTypeAggregate => TransportType::Aggregate,
+            kAudioDeviceTransportTypeVirtual => TransportType::Virtual,
+            _ => TransportType::Unknown,
+        }
+    }
+
+    /// Transport type queries are compiled out; every device reports Unknown
+    #[cfg(not(feature = "transport-type-query"))]
+    pub fn get_transport_type(&self, _device_id: AudioDeviceID) -> TransportType {
+        TransportType::Unknown
+    }
+
+    /// Find devices whose names fuzzily match a partial name, best match first
+    pub fn get_device_by_name_fuzzy(&self, partial_name: &str) -> Result<Vec<AudioDevice>> {
+        let mut scored: Vec<(f64, AudioDevice)> = self
+            .enumerate_devices()?
+            .into_iter()
+            .map(|device| (name_similarity(partial_name, &device.name), device))
+            .filter(|(score, _)| *score >= FUZZY_MATCH_THRESHOLD)
+            .collect();
+
+        // Stable sort keeps enumeration order for equally scored devices
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        debug!("Found {} devices matching '{}'", scored.len(), partial_name);
+        Ok(scored.into_iter().map(|(_, device)| device).collect())
+    }
+
+    /// CoreAudio ID of the system default device for `selector`, if there is one
+    fn get_default_device_id(selector: AudioObjectPropertySelector) -> Option<AudioDeviceID> {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: selector,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        let mut device_id: AudioDeviceID = 0;
+        let mut property_size = std::mem::size_of::<AudioDeviceID>() as u32;
+
+        let result = unsafe {
+            AudioObjectGetPropertyData(
                 kAudioObjectSystemObject,
                 &property_address,
                 0,
                 ptr::null(),
                 &mut property_size,
                 &mut device_id as *mut _ as *mut c_void,
+            )
+        };
+
+        if result != kAudioHardwareNoError as i32 || device_id == kAudioDeviceUnknown {
+            return None;
+        }
+        Some(device_id)
+    }
+
+    pub fn get_default_input_device(&self) -> Result<Option<AudioDevice>> {
+        let Some(device_id) = Self::get_default_device_id(kAudioHardwarePropertyDefaultInputDevice)
+        else {
+            debug!("No default input device found");
+            return Ok(None);
+        };
+
+        if let Ok(name) = self.get_coreaudio_device_name(device_id) {
+            let audio_device = self
+                .build_audio_device(device_id, name, DeviceType::Input)
+                .set_default(true);
+            Ok(Some(audio_device))
+        } else {
+            debug!("Could not get name for default input device");
+            Ok(None)
+        }
+    }
+
+    pub fn get_default_output_device(&self) -> Result<Option<AudioDevice>> {
+        let Some(device_id) =
+            Self::get_default_device_id(kAudioHardwarePropertyDefaultOutputDevice)
+        else {
+            debug!("No default output device found");
+            return Ok(None);
+        };
+
+        if let Ok(name) = self.get_coreaudio_device_name(device_id) {
+            let audio_device = self
+                .build_audio_device(device_id, name, DeviceType::Output)
+                .set_default(true);
+            Ok(Some(audio_device))
+        } else {
+            debug!("Could not get name for default output device");
+            Ok(None)
+        }
+    }
+
+    /// Device details including its current format. Properties CoreAudio does not
+    /// report are left unknown rather than failing the whole lookup.
+    pub fn get_device_info(&self, device: &AudioDevice) -> Result<DeviceInfo> {
+        let device_id = Self::parse_device_id(&device.id)?;
+        let is_input = device.device_type == DeviceType::Input;
+
+        let available_sample_rates = if device.available_sample_rates.is_empty() {
+            self.get_coreaudio_available_sample_rates(device_id)
+                .unwrap_or_default()
+        } else {
+            device.available_sample_rates.clone()
+        };
+        let channels = device.channels.or_else(|| {
+            self.get_coreaudio_channel_count(device_id, is_input)
+                .ok()
+                .filter(|&channels| channels > 0)
+        });
+
+        Ok(DeviceInfo {
+            name: device.name.clone(),
+            uid: device.uid.clone().unwrap_or_else(|| device.id.clone()),
+            device_type: device.device_type.clone(),
+            sample_rate: self.get_nominal_sample_rate(device).ok(),
+            available_sample_rates,
+            channels,
+            bit_depth: self.get_bit_depth(device).ok(),
+            is_default: device.is_default,
+        })
+    }
+
+    /// Bits per channel of the current physical format of the device's first
+    /// stream in its direction
+    pub fn get_bit_depth(&self, device: &AudioDevice) -> Result<u32> {
+        let device_id = Self::parse_device_id(&device.id)?;
+        let is_input = device.device_type == DeviceType::Input;
+
+        let stream_id = Self::get_coreaudio_streams(device_id, is_input)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Device {} has no streams", device.name))?;
+
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioStreamPropertyPhysicalFormat,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        let mut property_size = std::mem::size_of::<AudioStreamBasicDescription>() as u32;
+
+        let (result, format) = unsafe {
+            let mut format = std::mem::zeroed::<AudioStreamBasicDescription>();
+            let result = AudioObjectGetPropertyData(
+                stream_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                &mut format as *mut AudioStreamBasicDescription as *mut c_void,
             );
+            (result, format)
+        };
 
-            if result != kAudioHardwareNoError as i32 || device_id == kAudioDeviceUnknown {
-                debug!("No default input device found");
-                return Ok(None);
-            }
+        if result != kAudioHardwareNoError as i32 {
+            return Err(anyhow::anyhow!(
+                "Failed to get stream format for device: {}",
+                device.name
+            ));
+        }
+
+        Ok(format.mBitsPerChannel)
+    }
+
+    /// Latency in frames the device reports for its direction, excluding the
+    /// I/O buffer and safety offset
+    pub fn get_device_latency(&self, device: &AudioDevice) -> Result<u32> {
+        let device_id = Self::parse_device_id(&device.id)?;
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyLatency,
+            mScope: Self::device_scope(device),
+            mElement: kAudioObjectPropertyElementMain,
+        };
 
-            if let Ok(name) = self.get_coreaudio_device_name(device_id) {
-                let mut audio_device =
-                    AudioDevice::new(device_id.to_string(), name, DeviceType::Input);
+        let mut frames: u32 = 0;
+        let mut property_size = std::mem::size_of::<u32>() as u32;
 
-                if let Ok(uid) = self.get_coreaudio_device_uid(device_id) {
-                    audio_device = audio_device.with_uid(uid);
-                }
+        let result = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                &mut frames as *mut u32 as *mut c_void,
+            )
+        };
 
-                audio_device = audio_device.set_default(true);
-                Ok(Some(audio_device))
+        if result != kAudioHardwareNoError as i32 {
+            return Err(anyhow::anyhow!(
+                "Failed to get latency for device: {}",
+                device.name
+            ));
+        }
+
+        Ok(frames)
+    }
+
+    /// Sample rate the device is currently running at
+    pub fn get_nominal_sample_rate(&self, device: &AudioDevice) -> Result<f64> {
+        let device_id = Self::parse_device_id(&device.id)?;
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyNominalSampleRate,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        let mut sample_rate: f64 = 0.0;
+        let mut property_size = std::mem::size_of::<f64>() as u32;
+
+        let result = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                &mut sample_rate as *mut f64 as *mut c_void,
+            )
+        };
+
+        if result != kAudioHardwareNoError as i32 {
+            return Err(anyhow::anyhow!(
+                "Failed to get sample rate for device: {}",
+                device.name
+            ));
+        }
+
+        Ok(sample_rate)
+    }
+
+    /// Device latency converted to milliseconds at its nominal sample rate
+    pub fn get_device_latency_in_ms(&self, device: &AudioDevice) -> Result<f64> {
+        let frames = self.get_device_latency(device)?;
+        let sample_rate = self.get_nominal_sample_rate(device)?;
+        latency_frames_to_ms(frames, sample_rate)
+    }
+
+    /// Property scope matching the device's direction; input/output devices use output
+    fn device_scope(device: &AudioDevice) -> AudioObjectPropertyScope {
+        match device.device_type {
+            DeviceType::Input => kAudioDevicePropertyScopeInput,
+            DeviceType::Output | DeviceType::InputOutput => kAudioDevicePropertyScopeOutput,
+        }
+    }
+
+    /// Every physical format the device's input or output streams support
+    pub fn list_stream_formats(
+        &self,
+        device_id: AudioDeviceID,
+        is_input: bool,
+    ) -> Result<Vec<StreamFormat>> {
+        let mut formats = Vec::new();
+        for stream_id in Self::get_coreaudio_streams(device_id, is_input)? {
+            formats.extend(Self::get_stream_physical_formats(stream_id)?);
+        }
+        Ok(sort_stream_formats(formats))
+    }
+
+    /// IDs of a device's input or output streams
+    fn get_coreaudio_streams(
+        device_id: AudioDeviceID,
+        is_input: bool,
+    ) -> Result<Vec<AudioStreamID>> {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyStreams,
+            mScope: if is_input {
+                kAudioDevicePropertyScopeInput
             } else {
-                debug!("Could not get name for default input device");
-                Ok(None)
+                kAudioDevicePropertyScopeOutput
+            },
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        unsafe {
+            let mut property_size: u32 = 0;
+            let result = AudioObjectGetPropertyDataSize(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get stream list size"));
             }
+
+            let stream_count = property_size as usize / std::mem::size_of::<AudioStreamID>();
+            let mut streams: Vec<AudioStreamID> = vec![0; stream_count];
+
+            let result = AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                streams.as_mut_ptr() as *mut c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get device streams"));
+            }
+
+            Ok(streams)
         }
     }
 
-    pub fn get_default_output_device(&self) -> Result<Option<AudioDevice>> {
+    /// Physical formats of one stream. A format that accepts a continuous rate range
+    /// is listed once for each common sample rate in that range.
+    fn get_stream_physical_formats(stream_id: AudioStreamID) -> Result<Vec<StreamFormat>> {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioStreamPropertyAvailablePhysicalFormats,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        let descriptions = unsafe {
+            let mut property_size: u32 = 0;
+            let result = AudioObjectGetPropertyDataSize(
+                stream_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get stream format list size"));
+            }
+
+            let format_count =
+                property_size as usize / std::mem::size_of::<AudioStreamRangedDescription>();
+            let mut descriptions =
+                vec![std::mem::zeroed::<AudioStreamRangedDescription>(); format_count];
+
+            let result = AudioObjectGetPropertyData(
+                stream_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                descriptions.as_mut_ptr() as *mut c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get stream formats"));
+            }
+
+            descriptions
+        };
+
+        Ok(descriptions
+            .iter()
+            .flat_map(|description| {
+                let format = description.mFormat;
+                // A zero rate (kAudioStreamAnyRate) means any rate within the range
+                let sample_rates = if format.mSampleRate > 0.0 {
+                    vec![format.mSampleRate]
+                } else {
+                    let range = description.mSampleRateRange;
+                    sample_rates_from_ranges(&[(range.mMinimum, range.mMaximum)])
+                };
+
+                sample_rates
+                    .into_iter()
+                    .map(move |sample_rate| StreamFormat {
+                        sample_rate,
+                        bit_depth: format.mBitsPerChannel,
+                        channels: format.mChannelsPerFrame,
+                        is_float: format.mFormatFlags & kAudioFormatFlagIsFloat != 0,
+                    })
+            })
+            .collect())
+    }
+
+    /// Get the supported I/O buffer size range (min, max) in frames for a device
+    pub fn get_device_buffer_size_range(&self, device_id: &str) -> Result<(u32, u32)> {
+        let device_id = Self::parse_device_id(device_id)?;
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyBufferFrameSizeRange,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
         unsafe {
-            let property_address = AudioObjectPropertyAddress {
-                mSelector: kAudioHardwarePropertyDefaultOutputDevice,
-                mScope: kAudioObjectPropertyScopeGlobal,
-                mElement: kAudioObjectPropertyElementMain,
+            let mut range = AudioValueRange {
+                mMinimum: 0.0,
+                mMaximum: 0.0,
             };
+            let mut property_size = std::mem::size_of::<AudioValueRange>() as u32;
+
+            let result = AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                &mut range as *mut _ as *mut c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get buffer size range"));
+            }
+
+            Ok((range.mMinimum as u32, range.mMaximum as u32))
+        }
+    }
+
+    /// Set the I/O buffer size in frames for a device, validated against its supported range
+    pub fn set_device_buffer_size(&self, device_id: &str, frames: u32) -> Result<()> {
+        validate_buffer_frame_size(frames, self.get_device_buffer_size_range(device_id)?)?;
+
+        let coreaudio_id = Self::parse_device_id(device_id)?;
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyBufferFrameSize,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        unsafe {
+            let result = AudioObjectSetPropertyData(
+                coreaudio_id,
+                &property_address,
+                0,
+                ptr::null(),
+                std::mem::size_of::<u32>() as u32,
+                &frames as *const _ as *const c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                error!(
+                    "Failed to set buffer size for device {}: {}",
+                    device_id, result
+                );
+                return Err(anyhow::anyhow!("Failed to set buffer size"));
+            }
+        }
+
+        debug!(
+            "Set buffer size for device {} to {} frames",
+            device_id, frames
+        );
+        Ok(())
+    }
 
-            let mut device_id: AudioDeviceID = 0;
-            let mut property_size = std::mem::size_of::<AudioDeviceID>() as u32;
+    /// Output volume of the default output device, from 0.0 to 1.0
+    pub fn get_system_volume(&self) -> Result<f32> {
+        self.get_output_volume(self.default_output_coreaudio_id()?)
+    }
+
+    /// Set the output volume of the default output device, clamped to 0.0-1.0
+    pub fn set_system_volume(&self, volume: f32) -> Result<()> {
+        self.set_output_volume(self.default_output_coreaudio_id()?, volume)
+    }
 
+    /// Output volume of a device, from 0.0 to 1.0, read from its first adjustable channel
+    pub fn get_output_volume(&self, device_id: AudioDeviceID) -> Result<f32> {
+        let element = *Self::volume_elements(device_id)
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Device {} has no adjustable volume", device_id))?;
+
+        let property_address = Self::volume_address(element);
+        let mut volume: f32 = 0.0;
+        let mut property_size = std::mem::size_of::<f32>() as u32;
+
+        unsafe {
             let result = AudioObjectGetPropertyData(
-                kAudioObjectSystemObject,
+                device_id,
                 &property_address,
                 0,
                 ptr::null(),
                 &mut property_size,
-                &mut device_id as *mut _ as *mut c_void,
+                &mut volume as *mut _ as *mut c_void,
             );
 
-            if result != kAudioHardwareNoError as i32 || device_id == kAudioDeviceUnknown {
-                debug!("No default output device found");
-                return Ok(None);
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get output volume: {}", result));
             }
+        }
 
-            if let Ok(name) = self.get_coreaudio_device_name(device_id) {
-                let mut audio_device =
-                    AudioDevice::new(device_id.to_string(), name, DeviceType::Output);
+        Ok(volume)
+    }
 
-                if let Ok(uid) = self.get_coreaudio_device_uid(device_id) {
-                    audio_device = audio_device.with_uid(uid);
-                }
+    /// Set the output volume of a device on every adjustable channel, clamped to 0.0-1.0
+    pub fn set_output_volume(&self, device_id: AudioDeviceID, volume: f32) -> Result<()> {
+        let volume = volume.clamp(0.0, 1.0);
+        let elements = Self::volume_elements(device_id);
+        if elements.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Device {} has no adjustable volume",
+                device_id
+            ));
+        }
 
-                audio_device = audio_device.set_default(true);
-                Ok(Some(audio_device))
-            } else {
-                debug!("Could not get name for default output device");
-                Ok(None)
+        for element in elements {
+            let property_address = Self::volume_address(element);
+            unsafe {
+                let result = AudioObjectSetPropertyData(
+                    device_id,
+                    &property_address,
+                    0,
+                    ptr::null(),
+                    std::mem::size_of::<f32>() as u32,
+                    &volume as *const _ as *const c_void,
+                );
+
+                if result != kAudioHardwareNoError as i32 {
+                    return Err(anyhow::anyhow!("Failed to set output volume: {}", result));
+                }
             }
         }
+
+        debug!("Set output volume of device {} to {:.2}", device_id, volume);
+        Ok(())
     }
 
-    pub fn get_device_info(&self, device: &AudioDevice) -> Result<DeviceInfo> {
-        // This will be expanded with more detailed device information
-        Ok(DeviceInfo {
-            name: device.name.clone(),
-            uid: device.uid.clone().unwrap_or_else(|| device.id.clone()),
-            device_type: device.device_type.clone(),
-            sample_rate: None, // Will be filled with actual device capabilities
-            channels: None,    // Will be filled with actual device capabilities
-            is_default: device.is_default,
-        })
+    fn default_output_coreaudio_id(&self) -> Result<AudioDeviceID> {
+        let output = self
+            .get_default_output_device()?
+            .ok_or_else(|| anyhow::anyhow!("No default output device"))?;
+        Self::parse_device_id(&output.id)
+    }
+
+    fn volume_address(element: u32) -> AudioObjectPropertyAddress {
+        AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyVolumeScalar,
+            mScope: kAudioDevicePropertyScopeOutput,
+            mElement: element,
+        }
+    }
+
+    /// Elements carrying a device's output volume: the main element when the device
+    /// has one, otherwise its left and right channels
+    fn volume_elements(device_id: AudioDeviceID) -> Vec<u32> {
+        let has_volume = |element: &u32| unsafe {
+            AudioObjectHasProperty(device_id, &Self::volume_address(*element)) != 0
+        };
+
+        if has_volume(&kAudioObjectPropertyElementMain) {
+            return vec![kAudioObjectPropertyElementMain];
+        }
+        [1, 2].into_iter().filter(has_volume).collect()
     }
 
     /// Set the default output device by name
     pub fn set_default_output_device(&self, device_name: &str) -> Result<()> {
         debug!("Setting default output device to: {}", device_name);
 
-        // Find the CoreAudio device ID by name
-        if let Some(device_id) = self.find_coreaudio_device_by_name(device_name, false)? {
+        if let Some(device_id) = self.resolve_coreaudio_device(device_name, false)? {
             self.set_default_output_device_by_id(device_id)?;
         } else {
             return Err(anyhow::anyhow!("Output device '{}' not found", device_name));
         }
 
+        self.sync_system_alerts_to_output(device_name);
+
+        Ok(())
+    }
+
+    /// Move system alert sounds to the new output device when enabled
+    fn sync_system_alerts_to_output(&self, device_name: &str) {
+        if !self.sync_system_alerts {
+            return;
+        }
+
+        if let Err(e) = self.set_default_system_alert_device_to_match_output() {
+            warn!("Failed to move system alerts to {}: {}", device_name, e);
+        }
+    }
+
+    /// Set the system alert sound device by name
+    pub fn set_default_system_alert_device(&self, device_name: &str) -> Result<()> {
+        debug!("Setting system alert device to: {}", device_name);
+
+        let Some(device_id) = self.find_coreaudio_device_by_name(device_name, false)? else {
+            return Err(anyhow::anyhow!("Output device '{}' not found", device_name));
+        };
+
+        self.set_default_system_alert_device_by_id(device_id)
+    }
+
+    /// Point system alert sounds at the current default output device
+    pub fn set_default_system_alert_device_to_match_output(&self) -> Result<()> {
+        let output = self
+            .get_default_output_device()?
+            .ok_or_else(|| anyhow::anyhow!("No default output device to match"))?;
+
+        self.set_default_system_alert_device_by_id(Self::parse_device_id(&output.id)?)?;
+        debug!("System alerts now play on: {}", output.name);
         Ok(())
     }
 
@@ -215,8 +772,7 @@ impl DeviceController {
     pub fn set_default_input_device(&self, device_name: &str) -> Result<()> {
         debug!("Setting default input device to: {}", device_name);
 
-        // Find the CoreAudio device ID by name
-        if let Some(device_id) = self.find_coreaudio_device_by_name(device_name, true)? {
+        if let Some(device_id) = self.resolve_coreaudio_device(device_name, true)? {
             self.set_default_input_device_by_id(device_id)?;
         } else {
             return Err(anyhow::anyhow!("Input device '{}' not found", device_name));
@@ -253,6 +809,34 @@ impl DeviceController {
         Ok(())
     }
 
+    /// Set the system alert sound device by CoreAudio device ID
+    fn set_default_system_alert_device_by_id(&self, device_id: AudioDeviceID) -> Result<()> {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioHardwarePropertyDefaultSystemOutputDevice,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        unsafe {
+            let result = AudioObjectSetPropertyData(
+                kAudioObjectSystemObject,
+                &property_address,
+                0,
+                ptr::null(),
+                std::mem::size_of::<AudioDeviceID>() as u32,
+                &device_id as *const _ as *const c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                error!("Failed to set system alert device: {}", result);
+                return Err(anyhow::anyhow!("Failed to set system alert device"));
+            }
+        }
+
+        debug!("Successfully set system alert device ID: {}", device_id);
+        Ok(())
+    }
+
     /// Set default input device by CoreAudio device ID
     fn set_default_input_device_by_id(&self, device_id: AudioDeviceID) -> Result<()> {
         let property_address = AudioObjectPropertyAddress {
@@ -281,6 +865,28 @@ impl DeviceController {
         Ok(())
     }
 
+    /// CoreAudio ID of the output or input device named `device`. Only when no
+    /// device has that name is an all-digit `device` taken as a CoreAudio ID, so
+    /// a device whose name is a number is still found by name.
+    fn resolve_coreaudio_device(
+        &self,
+        device: &str,
+        is_input: bool,
+    ) -> Result<Option<AudioDeviceID>> {
+        if let Some(device_id) = self.find_coreaudio_device_by_name(device, is_input)? {
+            return Ok(Some(device_id));
+        }
+        if device.parse::<AudioDeviceID>().is_err() {
+            return Ok(None);
+        }
+
+        // Resolving the ID's name checks the device works in this direction
+        match self.get_device_name_by_id(device)? {
+            Some(name) => self.find_coreaudio_device_by_name(&name, is_input),
+            None => Ok(None),
+        }
+    }
+
     /// Find CoreAudio device ID by name
     fn find_coreaudio_device_by_name(
         &self,
@@ -347,6 +953,27 @@ impl DeviceController {
         Ok(None)
     }
 
+    /// Look up a device name by CoreAudio ID without enumerating all devices
+    pub fn get_device_name_by_id(&self, device_id: &str) -> Result<Option<String>> {
+        let device_id = Self::parse_device_id(device_id)?;
+
+        // CoreAudio rejects the property query for IDs that name no device
+        match self.get_coreaudio_device_name(device_id) {
+            Ok(name) => Ok(Some(name)),
+            Err(e) => {
+                debug!("No device name for ID {}: {}", device_id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Parse a device ID string produced by `enumerate_devices` back into a CoreAudio ID
+    pub(crate) fn parse_device_id(device_id: &str) -> Result<AudioDeviceID> {
+        device_id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid CoreAudio device ID: {}", device_id))
+    }
+
     /// Get the name of a CoreAudio device
     fn get_coreaudio_device_name(&self, device_id: AudioDeviceID) -> Result<String> {
         let property_address = AudioObjectPropertyAddress {
@@ -415,8 +1042,65 @@ impl DeviceController {
         }
     }
 
+    /// Get all sample rates supported by a CoreAudio device
+    fn get_coreaudio_available_sample_rates(&self, device_id: AudioDeviceID) -> Result<Vec<f64>> {
+        let property_address = AudioObjectPropertyAddress {
+            mSelector: kAudioDevicePropertyAvailableNominalSampleRates,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain,
+        };
+
+        unsafe {
+            let mut property_size: u32 = 0;
+            let result = AudioObjectGetPropertyDataSize(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get sample rate list size"));
+            }
+
+            let range_count = property_size as usize / std::mem::size_of::<AudioValueRange>();
+            let mut ranges = vec![
+                AudioValueRange {
+                    mMinimum: 0.0,
+                    mMaximum: 0.0,
+                };
+                range_count
+            ];
+
+            let result = AudioObjectGetPropertyData(
+                device_id,
+                &property_address,
+                0,
+                ptr::null(),
+                &mut property_size,
+                ranges.as_mut_ptr() as *mut c_void,
+            );
+
+            if result != kAudioHardwareNoError as i32 {
+                return Err(anyhow::anyhow!("Failed to get available sample rates"));
+            }
+
+            let ranges: Vec<(f64, f64)> = ranges
+                .iter()
+                .map(|range| (range.mMinimum, range.mMaximum))
+                .collect();
+            Ok(sample_rates_from_ranges(&ranges))
+        }
+    }
+
     /// Check if device supports input or output by checking actual channel count
     fn device_supports_direction(&self, device_id: AudioDeviceID, is_input: bool) -> Result<bool> {
+        Ok(self.get_coreaudio_channel_count(device_id, is_input)? > 0)
+    }
+
+    /// Total channel count across a device's input or output streams (0 if it has none)
+    fn get_coreaudio_channel_count(&self, device_id: AudioDeviceID, is_input: bool) -> Result<u32> {
         let property_address = AudioObjectPropertyAddress {
             mSelector: kAudioDevicePropertyStreamConfiguration,
             mScope: if is_input {
@@ -438,7 +1122,7 @@ impl DeviceController {
             );
 
             if result != kAudioHardwareNoError as i32 || property_size == 0 {
-                return Ok(false);
+                return Ok(0);
             }
 
             // Get the stream configuration to check actual channel counts
@@ -453,26 +1137,17 @@ impl DeviceController {
             );
 
             if result != kAudioHardwareNoError as i32 {
-                return Ok(false);
+                return Ok(0);
             }
 
-            // Parse AudioBufferList to check for actual channels
+            // Parse AudioBufferList and sum channels across all buffers
             let buffer_list = buffer.as_ptr() as *const AudioBufferList;
-            let buffer_count = (*buffer_list).mNumberBuffers;
-
-            if buffer_count == 0 {
-                return Ok(false);
-            }
-
-            // Check if any buffer has channels
-            for i in 0..buffer_count {
-                let buffer = &(*buffer_list).mBuffers[i as usize];
-                if buffer.mNumberChannels > 0 {
-                    return Ok(true);
-                }
-            }
+            let buffer_count = (*buffer_list).mNumberBuffers as usize;
 
-            Ok(false)
+            // mBuffers is declared with one element; the rest follow it in memory
+            let buffers =
+                std::slice::from_raw_parts((*buffer_list).mBuffers.as_ptr(), buffer_count);
+            Ok(buffers.iter().map(|buffer| buffer.mNumberChannels).sum())
         }
     }
 
@@ -1,12 +1,51 @@
 use anyhow::Result;
-use tracing::{debug, error, info};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
 use crate::notifications::{DefaultNotificationManager, SwitchReason};
+use crate::preference_debugging::{SwitchEvent, SwitchHistory};
 use crate::priority::DevicePriorityManager;
-use crate::system::AudioSystemInterface;
+use crate::system::{
+    AudioSystemInterface, FileSystemInterface, MacOSSystemService, StandardFileSystem,
+    SystemServiceInterface,
+};
 
-use super::device::{AudioDevice, DeviceInfo, DeviceType};
+use super::device::{
+    AudioDevice, DeviceInfo, DeviceType, VOLUME_SETTLE_DELAY, validate_buffer_frame_size,
+};
+
+/// Point-in-time view of the controller's tracked devices and the system's device state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceSnapshot {
+    /// Output device the controller last switched to
+    pub current_output: Option<AudioDevice>,
+    /// Input device the controller last switched to
+    pub current_input: Option<AudioDevice>,
+    pub available_devices: Vec<AudioDevice>,
+    /// System default output, which differs from `current_output` after a manual switch
+    pub default_output: Option<AudioDevice>,
+    /// System default input, which differs from `current_input` after a manual switch
+    pub default_input: Option<AudioDevice>,
+}
+
+/// What `dry_run_switch_to_output` found out about a switch without performing it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DryRunResult {
+    /// The device is currently connected
+    pub device_found: bool,
+    /// The device can act as an output
+    pub supports_direction: bool,
+    /// Device latency, when the device is connected and reports it
+    pub estimated_latency_ms: Option<f64>,
+    /// Name of the output device in use now
+    pub current_device: Option<String>,
+    /// The switch would go ahead and change the output device
+    pub would_switch: bool,
+}
 
 /// Refactored DeviceController that accepts an AudioSystemInterface for dependency injection
 pub struct DeviceController<A: AudioSystemInterface> {
@@ -15,6 +54,42 @@ pub struct DeviceController<A: AudioSystemInterface> {
     notification_manager: DefaultNotificationManager,
     current_output: Option<AudioDevice>,
     current_input: Option<AudioDevice>,
+    sync_system_alerts: bool,
+    restore_volume: bool,
+    /// Volume each output device had when the controller last switched away
+    /// from it, keyed by `volume_key`
+    saved_volumes: HashMap<String, f32>,
+    /// Runs rules' pre- and post-switch commands
+    hook_runner: Arc<dyn FileSystemInterface + Send + Sync>,
+    hook_timeout: Duration,
+    /// Waits for a switch to settle before the rule's volume is set
+    system_service: Arc<dyn SystemServiceInterface + Send + Sync>,
+    /// Extra attempts at a default device change the system refuses
+    switch_retries: u32,
+    switch_retry_delay: Duration,
+    /// Where successful switches are recorded, if anywhere
+    history: Option<SwitchHistory>,
+    switch_counts: SwitchCounts,
+    /// Devices seen at the last update, with `connected_since` set for the
+    /// stability checks; `None` before the first update
+    known_devices: Option<Vec<AudioDevice>>,
+    /// Sorted IDs of the devices stable enough to switch to at the last update
+    stable_device_ids: Vec<String>,
+    /// The rules changed since the priority rules last ran
+    rules_changed: bool,
+    /// The current output or input was chosen by the user or an IPC switch. It is
+    /// kept until its device goes away or a newly arrived device outranks it.
+    manual_output: bool,
+    manual_input: bool,
+}
+
+/// Default device changes made by `switch_to_output_device` and `switch_to_input_device`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwitchCounts {
+    pub output: u32,
+    pub input: u32,
+    /// Switches the system refused after every retry
+    pub failed: u64,
 }
 
 impl<A: AudioSystemInterface> DeviceController<A> {
@@ -25,9 +100,48 @@ impl<A: AudioSystemInterface> DeviceController<A> {
             notification_manager: DefaultNotificationManager::new(config),
             current_output: None,
             current_input: None,
+            sync_system_alerts: config.general.sync_system_alerts,
+            restore_volume: config.general.restore_volume,
+            saved_volumes: HashMap::new(),
+            hook_runner: Arc::new(StandardFileSystem),
+            hook_timeout: Duration::from_millis(config.general.hook_timeout_ms),
+            system_service: Arc::new(MacOSSystemService::new()),
+            switch_retries: config.general.switch_retry_count,
+            switch_retry_delay: Duration::from_millis(config.general.switch_retry_delay_ms),
+            history: None,
+            switch_counts: SwitchCounts::default(),
+            known_devices: None,
+            stable_device_ids: Vec::new(),
+            rules_changed: false,
+            manual_output: false,
+            manual_input: false,
         }
     }
 
+    /// Record every successful switch in `history`
+    pub fn with_history(mut self, history: SwitchHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Run switch hooks through `hook_runner` instead of the shell
+    pub fn with_hook_runner(
+        mut self,
+        hook_runner: Arc<dyn FileSystemInterface + Send + Sync>,
+    ) -> Self {
+        self.hook_runner = hook_runner;
+        self
+    }
+
+    /// Sleep through `system_service` instead of blocking the thread directly
+    pub fn with_system_service(
+        mut self,
+        system_service: Arc<dyn SystemServiceInterface + Send + Sync>,
+    ) -> Self {
+        self.system_service = system_service;
+        self
+    }
+
     /// Initialize the controller and start monitoring for device changes
     pub fn initialize(&mut self) -> Result<()> {
         info!("Initializing device controller with dependency injection");
@@ -56,115 +170,625 @@ impl<A: AudioSystemInterface> DeviceController<A> {
         Ok(())
     }
 
-    /// Update the current devices based on system defaults and priority rules
+    /// Update the current devices based on system defaults and priority rules.
+    /// The rules only run when the devices stable enough to switch to or the
+    /// rules changed, or nothing is selected, so a device picked by hand stays
+    /// selected.
     pub fn update_current_devices(&mut self) -> Result<()> {
         debug!("Updating current device state");
 
-        // First, check system defaults and sync our internal state
-        if let Ok(Some(system_output)) = self.audio_system.get_default_output_device() {
-            if self.current_output.as_ref().map(|d| &d.id) != Some(&system_output.id) {
-                self.current_output = Some(system_output);
-            }
+        let now = self.audio_system.now();
+        let available_devices = self.audio_system.enumerate_devices()?;
+        debug!("Found {} available devices", available_devices.len());
+        let stable_devices = self.stable_devices(&available_devices, now);
+        self.track_connections(&available_devices, now);
+        self.sync_with_system()?;
+        self.manual_output &= Self::is_listed(self.current_output.as_ref(), &available_devices);
+        self.manual_input &= Self::is_listed(self.current_input.as_ref(), &available_devices);
+
+        let arrived: Vec<AudioDevice> = stable_devices
+            .iter()
+            .filter(|device| self.stable_device_ids.binary_search(&device.id).is_err())
+            .cloned()
+            .collect();
+        let mut device_ids: Vec<String> = stable_devices.iter().map(|d| d.id.clone()).collect();
+        device_ids.sort();
+        let devices_changed = device_ids != self.stable_device_ids;
+        self.stable_device_ids = device_ids;
+
+        let nothing_current = self.current_output.is_none() || self.current_input.is_none();
+        let rules_changed = std::mem::take(&mut self.rules_changed);
+        if devices_changed || nothing_current || rules_changed {
+            self.apply_priority_rules(&stable_devices, &arrived)?;
         }
+        Ok(())
+    }
 
-        if let Ok(Some(system_input)) = self.audio_system.get_default_input_device() {
-            if self.current_input.as_ref().map(|d| &d.id) != Some(&system_input.id) {
-                self.current_input = Some(system_input);
-            }
+    /// When `device` connected: as the audio system reports, or else when this
+    /// controller first saw it. Devices present at the first update count as
+    /// connected long ago.
+    fn connected_since(&self, device: &AudioDevice, now: SystemTime) -> SystemTime {
+        if let Some(connected_since) = device.connected_since {
+            return connected_since;
+        }
+        match &self.known_devices {
+            None => SystemTime::UNIX_EPOCH,
+            Some(known_devices) => known_devices
+                .iter()
+                .find(|known| known.id == device.id)
+                .and_then(|known| known.connected_since)
+                .unwrap_or(now),
         }
+    }
 
-        // Only use priority-based switching if no current device is set
-        if self.current_output.is_none() || self.current_input.is_none() {
-            let available_devices = self.audio_system.enumerate_devices()?;
-            debug!("Found {} available devices", available_devices.len());
+    /// `devices` with `connected_since` set from `connected_since`
+    fn dated(&self, devices: &[AudioDevice], now: SystemTime) -> Vec<AudioDevice> {
+        devices
+            .iter()
+            .map(|device| {
+                let connected_since = self.connected_since(device, now);
+                device.clone().with_connected_since(connected_since)
+            })
+            .collect()
+    }
 
-            // Find the best output device if none is current
-            if self.current_output.is_none() {
-                let best_output = self
+    /// The devices connected long enough to switch to, each against the threshold
+    /// of its rule or its transport type
+    fn stable_devices(&self, devices: &[AudioDevice], now: SystemTime) -> Vec<AudioDevice> {
+        let devices = self.dated(devices, now);
+        devices
+            .iter()
+            .filter(|device| {
+                let stable = self
                     .priority_manager
-                    .find_best_output_device(&available_devices);
-                if let Some(ref device) = best_output {
-                    info!("Switching to output device: {}", device.name);
-                    self.switch_to_output_device(device)?;
+                    .is_device_stable(device, now, &devices);
+                if !stable {
+                    debug!(
+                        "Waiting {}ms for {} to settle",
+                        self.priority_manager.get_stability_threshold(device),
+                        device.name
+                    );
                 }
+                stable
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Remember when each device connected, and tell the priority manager about
+    /// devices gone since the last update
+    fn track_connections(&mut self, devices: &[AudioDevice], now: SystemTime) {
+        let devices = self.dated(devices, now);
+        for previous in self.known_devices.iter().flatten() {
+            if !devices.iter().any(|device| device.id == previous.id) {
+                info!("Device disconnected: {}", previous.name);
+                self.priority_manager.device_disconnected(previous);
             }
+        }
+        self.known_devices = Some(devices);
+    }
 
-            // Find the best input device if none is current
-            if self.current_input.is_none() {
-                let best_input = self
-                    .priority_manager
-                    .find_best_input_device(&available_devices);
-                if let Some(ref device) = best_input {
-                    info!("Switching to input device: {}", device.name);
-                    self.switch_to_input_device(device)?;
+    /// Phase 1: take the current devices from the system defaults, which another
+    /// application may have changed. A default changed away from a device that is
+    /// still available was chosen by the user and becomes a manual selection.
+    /// Returns whether either differed from the tracked device.
+    pub fn sync_with_system(&mut self) -> Result<bool> {
+        let mut changed = false;
+
+        if let Some(system_output) = self.audio_system.get_default_output_device()? {
+            if self.current_output.as_ref().map(|d| &d.id) != Some(&system_output.id) {
+                debug!("System output device is now: {}", system_output.name);
+                changed = true;
+                self.manual_output = self.is_still_available(&self.current_output);
+                if self.manual_output {
+                    info!(
+                        "Keeping manually selected output device: {}",
+                        system_output.name
+                    );
+                }
+            }
+            self.priority_manager
+                .update_current_output(system_output.clone());
+            self.current_output = Some(system_output);
+        }
+
+        if let Some(system_input) = self.audio_system.get_default_input_device()? {
+            if self.current_input.as_ref().map(|d| &d.id) != Some(&system_input.id) {
+                debug!("System input device is now: {}", system_input.name);
+                changed = true;
+                self.manual_input = self.is_still_available(&self.current_input);
+                if self.manual_input {
+                    info!(
+                        "Keeping manually selected input device: {}",
+                        system_input.name
+                    );
                 }
             }
+            self.priority_manager
+                .update_current_input(system_input.clone());
+            self.current_input = Some(system_input);
+        }
+
+        Ok(changed)
+    }
+
+    /// Whether a tracked device is still connected, so a default moved away from
+    /// it was not the system falling back from an unplugged device
+    fn is_still_available(&self, device: &Option<AudioDevice>) -> bool {
+        device.as_ref().is_some_and(|device| {
+            self.audio_system
+                .is_device_available(&device.id)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Phase 2: switch wherever the priority rules pick a different device than the
+    /// current one among `stable_devices`. A manual selection is only weighed
+    /// against `arrived` devices.
+    fn apply_priority_rules(
+        &mut self,
+        stable_devices: &[AudioDevice],
+        arrived: &[AudioDevice],
+    ) -> Result<()> {
+        let current = self.current_output.as_ref();
+        let candidates = Self::candidates(self.manual_output, current, stable_devices, arrived);
+        if let Some(device) = self.output_target(current, &candidates) {
+            info!("Switching to output device: {}", device.name);
+            self.switch_to_output_device(&device)?;
+        }
+
+        let current = self.current_input.as_ref();
+        let candidates = Self::candidates(self.manual_input, current, stable_devices, arrived);
+        if let Some(device) = self.input_target(current, &candidates) {
+            info!("Switching to input device: {}", device.name);
+            self.switch_to_input_device(&device)?;
         }
 
         Ok(())
     }
 
-    /// Switch to a specific output device
+    /// The output device the priority rules would switch to from `current`, if any.
+    /// A manual selection of `current` is kept, and devices still settling are skipped.
+    pub fn output_switch_target(
+        &self,
+        current: Option<&AudioDevice>,
+        available_devices: &[AudioDevice],
+    ) -> Option<AudioDevice> {
+        let manual =
+            self.manual_output && Self::is_same_device(current, self.current_output.as_ref());
+        let stable_devices = self.stable_devices(available_devices, self.audio_system.now());
+        let candidates = Self::candidates(manual, current, &stable_devices, &[]);
+        self.output_target(current, &candidates)
+    }
+
+    /// Input counterpart of `output_switch_target`
+    pub fn input_switch_target(
+        &self,
+        current: Option<&AudioDevice>,
+        available_devices: &[AudioDevice],
+    ) -> Option<AudioDevice> {
+        let manual =
+            self.manual_input && Self::is_same_device(current, self.current_input.as_ref());
+        let stable_devices = self.stable_devices(available_devices, self.audio_system.now());
+        let candidates = Self::candidates(manual, current, &stable_devices, &[]);
+        self.input_target(current, &candidates)
+    }
+
+    fn output_target(
+        &self,
+        current: Option<&AudioDevice>,
+        candidates: &[AudioDevice],
+    ) -> Option<AudioDevice> {
+        let device = self.priority_manager.find_best_output_device(candidates)?;
+        let switch = !Self::is_same_device(current, Some(&device))
+            && self
+                .priority_manager
+                .should_switch_output_from(current, &device);
+        switch.then_some(device)
+    }
+
+    fn input_target(
+        &self,
+        current: Option<&AudioDevice>,
+        candidates: &[AudioDevice],
+    ) -> Option<AudioDevice> {
+        let device = self.priority_manager.find_best_input_device(candidates)?;
+        let switch = !Self::is_same_device(current, Some(&device))
+            && self
+                .priority_manager
+                .should_switch_input_from(current, &device);
+        switch.then_some(device)
+    }
+
+    fn is_same_device(a: Option<&AudioDevice>, b: Option<&AudioDevice>) -> bool {
+        matches!((a, b), (Some(a), Some(b)) if a.id == b.id)
+    }
+
+    fn is_listed(device: Option<&AudioDevice>, devices: &[AudioDevice]) -> bool {
+        device.is_some_and(|device| devices.iter().any(|d| d.id == device.id))
+    }
+
+    /// Devices the rules may pick from: every available one, or for a manual
+    /// selection only the selected device and the newly arrived ones
+    fn candidates(
+        manual: bool,
+        current: Option<&AudioDevice>,
+        available_devices: &[AudioDevice],
+        arrived: &[AudioDevice],
+    ) -> Vec<AudioDevice> {
+        if !manual {
+            return available_devices.to_vec();
+        }
+        arrived.iter().chain(current).cloned().collect()
+    }
+
+    /// Switch to a specific output device automatically, ending any manual selection
     pub fn switch_to_output_device(&mut self, device: &AudioDevice) -> Result<()> {
+        let reason = Self::inferred_switch_reason(&self.current_output);
+        self.switch_to_output_device_for(device, reason)?;
+        self.manual_output = false;
+        Ok(())
+    }
+
+    /// Switch to a specific output device, reporting `reason` in the
+    /// notification and the switch history
+    pub fn switch_to_output_device_for(
+        &mut self,
+        device: &AudioDevice,
+        switch_reason: SwitchReason,
+    ) -> Result<()> {
         info!(
             "Switching to output device: {} ({})",
             device.name, device.id
         );
 
+        self.run_pre_switch_hook(device)?;
+
+        if self.restore_volume {
+            self.save_output_volume();
+        }
+
         // Use device name for switching (matching current DeviceController interface)
-        self.audio_system.set_default_output_device(&device.name)?;
+        if let Err(e) = self.audio_system.set_default_output_device_with_retry(
+            &device.name,
+            self.switch_retries,
+            self.switch_retry_delay,
+        ) {
+            self.switch_counts.failed += 1;
+            self.notify_switch_failed(device, &e);
+            return Err(e);
+        }
+        self.switch_counts.output += 1;
 
         // Update internal state
-        let previous_device = self.current_output.clone();
+        self.manual_output = Self::is_manual(&switch_reason);
         self.current_output = Some(device.clone());
+        self.priority_manager.update_current_output(device.clone());
+        self.priority_manager.record_output_switch(device);
 
         // Send notification
-        let switch_reason = if previous_device.is_some() {
-            SwitchReason::HigherPriority
-        } else {
-            SwitchReason::Manual
-        };
-
         if let Err(e) = self
             .notification_manager
-            .device_switched(device, switch_reason)
+            .device_switched(device, switch_reason.clone())
         {
             error!("Failed to send device switched notification: {}", e);
         }
+        self.record_switch(device, switch_reason);
+
+        self.apply_rule_buffer_size(device);
+
+        self.sync_system_alerts_to_output(device);
+
+        // A rule's on_select_volume is applied last so it wins over the saved volume
+        self.restore_saved_volume(device);
+        self.apply_rule_volume(device);
+
+        self.spawn_post_switch_hook(device);
 
         info!("Successfully switched to output device: {}", device.name);
         Ok(())
     }
 
-    /// Switch to a specific input device
+    /// Why an automatic switch away from `current` happened: a device that
+    /// outranks it, or the first pick when nothing was selected yet
+    fn inferred_switch_reason(current: &Option<AudioDevice>) -> SwitchReason {
+        if current.is_some() {
+            SwitchReason::HigherPriority
+        } else {
+            SwitchReason::Manual
+        }
+    }
+
+    /// Whether a switch for this reason was asked for by the user or another app
+    fn is_manual(reason: &SwitchReason) -> bool {
+        matches!(
+            reason,
+            SwitchReason::Manual | SwitchReason::AppRequest { .. }
+        )
+    }
+
+    /// Check whether switching the output to `device` would succeed and change
+    /// anything, without touching the system default
+    // Called by dry-run switch requests and tests
+    #[allow(dead_code)]
+    pub fn dry_run_switch_to_output(&self, device: &AudioDevice) -> Result<DryRunResult> {
+        let device_found = self
+            .audio_system
+            .enumerate_devices()?
+            .iter()
+            .any(|available| available.id == device.id);
+        let supports_direction = matches!(
+            device.device_type,
+            DeviceType::Output | DeviceType::InputOutput
+        );
+
+        let estimated_latency_ms = if device_found {
+            match self.audio_system.get_device_latency_ms(device) {
+                Ok(latency_ms) => Some(latency_ms),
+                Err(e) => {
+                    debug!("Latency unavailable for {}: {}", device.name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let current_device = self.current_output.as_ref().map(|d| d.name.clone());
+        let already_current = current_device.as_deref() == Some(device.name.as_str());
+
+        Ok(DryRunResult {
+            device_found,
+            supports_direction,
+            estimated_latency_ms,
+            current_device,
+            would_switch: device_found && supports_direction && !already_current,
+        })
+    }
+
+    /// Switch to a specific input device automatically, ending any manual selection
     pub fn switch_to_input_device(&mut self, device: &AudioDevice) -> Result<()> {
+        let reason = Self::inferred_switch_reason(&self.current_input);
+        self.switch_to_input_device_for(device, reason)?;
+        self.manual_input = false;
+        Ok(())
+    }
+
+    /// Input counterpart of `switch_to_output_device_for`
+    pub fn switch_to_input_device_for(
+        &mut self,
+        device: &AudioDevice,
+        switch_reason: SwitchReason,
+    ) -> Result<()> {
         info!("Switching to input device: {} ({})", device.name, device.id);
 
+        self.run_pre_switch_hook(device)?;
+
         // Use device name for switching (matching current DeviceController interface)
-        self.audio_system.set_default_input_device(&device.name)?;
+        if let Err(e) = self.audio_system.set_default_input_device_with_retry(
+            &device.name,
+            self.switch_retries,
+            self.switch_retry_delay,
+        ) {
+            self.switch_counts.failed += 1;
+            self.notify_switch_failed(device, &e);
+            return Err(e);
+        }
+        self.switch_counts.input += 1;
 
         // Update internal state
-        let previous_device = self.current_input.clone();
+        self.manual_input = Self::is_manual(&switch_reason);
         self.current_input = Some(device.clone());
+        self.priority_manager.update_current_input(device.clone());
+        self.priority_manager.record_input_switch(device);
 
         // Send notification
-        let switch_reason = if previous_device.is_some() {
-            SwitchReason::HigherPriority
-        } else {
-            SwitchReason::Manual
-        };
-
         if let Err(e) = self
             .notification_manager
-            .device_switched(device, switch_reason)
+            .device_switched(device, switch_reason.clone())
         {
             error!("Failed to send device switched notification: {}", e);
         }
+        self.record_switch(device, switch_reason);
+
+        self.apply_rule_buffer_size(device);
+
+        self.spawn_post_switch_hook(device);
 
         info!("Successfully switched to input device: {}", device.name);
         Ok(())
     }
 
+    /// Set the I/O buffer size in frames for a device, validated against its supported range
+    // Called at runtime after rule-triggered switches and by CLI tuning commands
+    #[allow(dead_code)]
+    pub fn set_device_buffer_size(&self, device_id: &str, frames: u32) -> Result<()> {
+        let range = self.audio_system.get_buffer_frame_size_range(device_id)?;
+        validate_buffer_frame_size(frames, range)?;
+
+        self.audio_system.set_buffer_frame_size(device_id, frames)?;
+        info!(
+            "Set buffer size for device {} to {} frames",
+            device_id, frames
+        );
+        Ok(())
+    }
+
+    /// Apply the buffer size configured by the device's matching rule, if any.
+    /// Failures are logged rather than failing the switch that triggered them.
+    fn apply_rule_buffer_size(&self, device: &AudioDevice) {
+        let Some(frames) = self.priority_manager.buffer_size_for(device) else {
+            return;
+        };
+
+        if let Err(e) = self.set_device_buffer_size(&device.id, frames) {
+            warn!("Failed to set buffer size for {}: {}", device.name, e);
+        }
+    }
+
+    /// Append a completed switch to the history, if one is kept.
+    /// Failures are logged rather than failing the switch.
+    fn record_switch(&self, device: &AudioDevice, reason: SwitchReason) {
+        let Some(history) = &self.history else {
+            return;
+        };
+
+        let event = SwitchEvent {
+            timestamp: std::time::SystemTime::now(),
+            device_name: device.name.clone(),
+            device_type: device.device_type.clone(),
+            reason,
+            matched_rule: self
+                .priority_manager
+                .matching_rule(device)
+                .map(|rule| rule.name.clone()),
+        };
+        if let Err(e) = history.append(&event) {
+            warn!(
+                "Failed to record switch to {} in history: {}",
+                device.name, e
+            );
+        }
+    }
+
+    /// Run the pre-switch command of the device's matching rule, if any, and wait
+    /// for it. A failing or hung command abandons the switch.
+    fn run_pre_switch_hook(&self, device: &AudioDevice) -> Result<()> {
+        let Some(command) = self
+            .priority_manager
+            .matching_rule(device)
+            .and_then(|rule| rule.pre_switch_command.as_deref())
+        else {
+            return Ok(());
+        };
+
+        debug!(
+            "Running pre-switch command for {}: {}",
+            device.name, command
+        );
+        let result = self
+            .hook_runner
+            .execute_hook(command, self.hook_timeout)
+            .and_then(|status| {
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Pre-switch command '{command}' failed ({status})"
+                    ))
+                }
+            });
+
+        if let Err(e) = &result {
+            self.notify_switch_failed(device, e);
+        }
+        result
+    }
+
+    /// Log a switch that did not happen and show a switch-failed notification
+    fn notify_switch_failed(&self, device: &AudioDevice, e: &anyhow::Error) {
+        error!("Not switching to {}: {}", device.name, e);
+        if let Err(notify_error) = self
+            .notification_manager
+            .switch_failed(&device.name, &e.to_string())
+        {
+            warn!(
+                "Failed to send switch failed notification: {}",
+                notify_error
+            );
+        }
+    }
+
+    /// Start the post-switch command of the device's matching rule, if any, on a
+    /// separate thread; its outcome is only logged
+    fn spawn_post_switch_hook(&self, device: &AudioDevice) {
+        let Some(command) = self
+            .priority_manager
+            .matching_rule(device)
+            .and_then(|rule| rule.post_switch_command.clone())
+        else {
+            return;
+        };
+
+        debug!(
+            "Starting post-switch command for {}: {}",
+            device.name, command
+        );
+        let hook_runner = Arc::clone(&self.hook_runner);
+        let timeout = self.hook_timeout;
+        std::thread::spawn(move || match hook_runner.execute_hook(&command, timeout) {
+            Ok(status) if status.success() => debug!("Post-switch command '{}' finished", command),
+            Ok(status) => warn!("Post-switch command '{}' failed ({})", command, status),
+            Err(e) => warn!("Post-switch command failed: {}", e),
+        });
+    }
+
+    /// Set the output volume configured by the device's matching rule, if any,
+    /// once macOS has had time to finish the switch
+    fn apply_rule_volume(&self, device: &AudioDevice) {
+        let Some(volume) = self.priority_manager.volume_for(device) else {
+            return;
+        };
+
+        if let Err(e) = self
+            .system_service
+            .sleep_ms(VOLUME_SETTLE_DELAY.as_millis() as u64)
+        {
+            warn!("Failed to wait for {} to settle: {}", device.name, e);
+        }
+        if let Err(e) = self.audio_system.set_system_volume(volume) {
+            warn!("Failed to set volume for {}: {}", device.name, e);
+        }
+    }
+
+    /// Remember the volume of the output device about to be switched away from
+    fn save_output_volume(&mut self) {
+        let outgoing = match self.audio_system.get_default_output_device() {
+            Ok(Some(device)) => device,
+            _ => match &self.current_output {
+                Some(device) => device.clone(),
+                None => return,
+            },
+        };
+
+        match self.audio_system.get_device_volume(&outgoing.id) {
+            Ok(volume) => {
+                debug!("Saved volume {:.2} for {}", volume, outgoing.name);
+                self.saved_volumes.insert(volume_key(&outgoing), volume);
+            }
+            Err(e) => debug!("Could not read volume of {}: {}", outgoing.name, e),
+        }
+    }
+
+    /// Set the volume `device` had when the controller last switched away from it
+    fn restore_saved_volume(&self, device: &AudioDevice) {
+        if !self.restore_volume {
+            return;
+        }
+        let Some(&volume) = self.saved_volumes.get(&volume_key(device)) else {
+            return;
+        };
+
+        match self.audio_system.set_device_volume(&device.id, volume) {
+            Ok(()) => debug!("Restored volume {:.2} for {}", volume, device.name),
+            Err(e) => warn!("Failed to restore volume for {}: {}", device.name, e),
+        }
+    }
+
+    /// Move system alert sounds to the new output device when enabled
+    fn sync_system_alerts_to_output(&self, device: &AudioDevice) {
+        if !self.sync_system_alerts {
+            return;
+        }
+
+        if let Err(e) = self
+            .audio_system
+            .set_default_system_alert_device(&device.name)
+        {
+            warn!("Failed to move system alerts to {}: {}", device.name, e);
+        }
+    }
+
     /// Get all available devices using the injected audio system
     // Called at runtime by CLI commands (device_info, check_device, list_devices, show_current_devices)
     #[allow(dead_code)]
@@ -200,6 +824,41 @@ impl<A: AudioSystemInterface> DeviceController<A> {
         self.current_input.as_ref()
     }
 
+    /// Record the power source used to boost wired devices under `prefer_wired_on_battery`
+    pub fn set_on_battery_power(&mut self, on_battery_power: bool) {
+        self.priority_manager.set_on_battery_power(on_battery_power);
+    }
+
+    /// Number of notifications still being delivered, both the controller's
+    /// switch notifications and the audio system's device notifications
+    pub fn pending_notifications(&self) -> usize {
+        self.notification_manager.pending_notifications()
+            + self.audio_system.pending_notifications()
+    }
+
+    /// Priority rules and switch history for the tracked devices
+    pub fn priority_manager(&self) -> &DevicePriorityManager {
+        &self.priority_manager
+    }
+
+    /// Access the notification manager (used by tests to inspect sent notifications)
+    #[cfg(any(test, feature = "test-mocks"))]
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn notification_manager(&self) -> &DefaultNotificationManager {
+        &self.notification_manager
+    }
+
+    /// Capture tracked and system device state in one value for diagnostics and tests
+    pub fn snapshot(&self) -> Result<DeviceSnapshot> {
+        Ok(DeviceSnapshot {
+            current_output: self.current_output.clone(),
+            current_input: self.current_input.clone(),
+            available_devices: self.audio_system.enumerate_devices()?,
+            default_output: self.audio_system.get_default_output_device()?,
+            default_input: self.audio_system.get_default_input_device()?,
+        })
+    }
+
     /// Get device information (for backward compatibility)
     // Called at runtime by CLI commands (device_info, list_devices with verbose flag)
     #[allow(dead_code)]
@@ -209,7 +868,9 @@ impl<A: AudioSystemInterface> DeviceController<A> {
             uid: device.uid.clone().unwrap_or_else(|| device.id.clone()),
             device_type: device.device_type.clone(),
             sample_rate: None,
-            channels: None,
+            available_sample_rates: device.available_sample_rates.clone(),
+            channels: device.channels,
+            bit_depth: None,
             is_default: device.is_default,
         })
     }
@@ -303,6 +964,7 @@ impl<A: AudioSystemInterface> DeviceController<A> {
     #[allow(dead_code)]
     pub fn handle_device_disconnected(&mut self, device: &AudioDevice) -> Result<()> {
         let mut cleared_current_device = false;
+        self.priority_manager.device_disconnected(device);
 
         // Clear internal state if this was the current device
         if self.current_output.as_ref().map(|d| &d.id) == Some(&device.id) {
@@ -368,12 +1030,25 @@ impl<A: AudioSystemInterface> DeviceController<A> {
         self.update_current_devices()
     }
 
+    /// Re-enumerate until the device list stops changing, as after a wake from sleep.
+    /// `sleep_ms` is called between enumerations.
+    pub fn wait_for_stable_devices(
+        &self,
+        sleep_ms: impl FnMut(u64) -> Result<()>,
+    ) -> Result<Vec<AudioDevice>> {
+        crate::system::sleep_wake::wait_for_stable_devices(&self.audio_system, sleep_ms)
+    }
+
     /// Set the default output device by name (for backward compatibility)
     // Called at runtime by CLI switch command and automatic switching logic
     #[allow(dead_code)]
     pub fn set_default_output_device(&self, device_name: &str) -> Result<()> {
         info!("Setting default output device to: {}", device_name);
-        self.audio_system.set_default_output_device(device_name)
+        self.audio_system.set_default_output_device_with_retry(
+            device_name,
+            self.switch_retries,
+            self.switch_retry_delay,
+        )
     }
 
     /// Set the default input device by name (for backward compatibility)
@@ -381,7 +1056,22 @@ impl<A: AudioSystemInterface> DeviceController<A> {
     #[allow(dead_code)]
     pub fn set_default_input_device(&self, device_name: &str) -> Result<()> {
         info!("Setting default input device to: {}", device_name);
-        self.audio_system.set_default_input_device(device_name)
+        self.audio_system.set_default_input_device_with_retry(
+            device_name,
+            self.switch_retries,
+            self.switch_retry_delay,
+        )
+    }
+
+    /// Use the rules and switching settings of a reloaded configuration
+    pub fn update_config(&mut self, config: &Config) {
+        self.priority_manager.update_config(config);
+        self.rules_changed = true;
+    }
+
+    /// Successful and failed switches since the controller was created
+    pub fn switch_counts(&self) -> SwitchCounts {
+        self.switch_counts
     }
 
     /// Get reference to the audio system (for testing)
@@ -391,6 +1081,19 @@ impl<A: AudioSystemInterface> DeviceController<A> {
     pub fn get_audio_system(&self) -> &A {
         &self.audio_system
     }
+
+    /// Get mutable reference to the audio system (for testing)
+    // Called by test code that sets up mock state after the controller took ownership
+    #[cfg(any(test, feature = "test-mocks"))]
+    #[allow(dead_code)]
+    pub fn get_audio_system_mut(&mut self) -> &mut A {
+        &mut self.audio_system
+    }
+}
+
+/// Saved volumes follow the device's UID, or its name when it has none
+fn volume_key(device: &AudioDevice) -> String {
+    device.uid.clone().unwrap_or_else(|| device.name.clone())
 }
 
 // Convenience constructor for production use with CoreAudioSystem
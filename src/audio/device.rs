@@ -1,5 +1,15 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Minimum similarity score for a device name to count as a fuzzy match
+pub const FUZZY_MATCH_THRESHOLD: f64 = 0.7;
+
+/// Standard sample rates probed when a device reports a continuous range
+pub const COMMON_SAMPLE_RATES: [f64; 6] = [44100.0, 48000.0, 88200.0, 96000.0, 176400.0, 192000.0];
+
+/// Time macOS needs to finish a default output switch before the new device's volume can be set
+pub const VOLUME_SETTLE_DELAY: Duration = Duration::from_millis(200);
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeviceType {
@@ -8,7 +18,23 @@ pub enum DeviceType {
     InputOutput,
 }
 
-#[derive(Debug, Clone)]
+/// How a device is attached to the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportType {
+    BuiltIn,
+    Usb,
+    Bluetooth,
+    Hdmi,
+    DisplayPort,
+    Thunderbolt,
+    AirPlay,
+    Aggregate,
+    Virtual,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AudioDevice {
     #[allow(dead_code)]
     pub id: String,
@@ -18,6 +44,25 @@ pub struct AudioDevice {
     pub is_available: bool,
     #[allow(dead_code)]
     pub uid: Option<String>,
+    #[allow(dead_code)]
+    pub manufacturer: Option<String>,
+    /// How the device is attached, when CoreAudio reports it
+    pub transport_type: Option<TransportType>,
+    /// Channel count in this device's direction, if known
+    pub channels: Option<u32>,
+    /// All sample rates the device supports, in ascending order (empty if unknown)
+    pub available_sample_rates: Vec<f64>,
+    /// When the device was first seen in this session, if it is being tracked
+    pub connected_since: Option<SystemTime>,
+}
+
+/// A device appearing or disappearing, as reported by the CoreAudio device list listener
+// Constructed by integration tests through AudioDeviceService::inject_device_event
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEvent {
+    Connected(AudioDevice),
+    Disconnected(AudioDevice),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,11 +70,54 @@ pub struct DeviceInfo {
     pub name: String,
     pub uid: String,
     pub device_type: DeviceType,
-    pub sample_rate: Option<u32>,
+    /// Sample rate the device is currently running at
+    pub sample_rate: Option<f64>,
+    pub available_sample_rates: Vec<f64>,
     pub channels: Option<u32>,
+    /// Bits per channel of the device's current physical format
+    pub bit_depth: Option<u32>,
     pub is_default: bool,
 }
 
+impl DeviceInfo {
+    /// Current sample rate and bit depth, e.g. "48000 Hz, 24-bit", leaving out
+    /// whichever is unknown; None if both are
+    pub fn current_format(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            self.sample_rate.map(|rate| format!("{rate} Hz")),
+            self.bit_depth.map(|bits| format!("{bits}-bit")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// One physical format a device stream supports
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StreamFormat {
+    pub sample_rate: f64,
+    pub bit_depth: u32,
+    pub channels: u32,
+    /// Samples are floating point rather than integer
+    pub is_float: bool,
+}
+
+impl fmt::Display for StreamFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} Hz, {}-bit {}, {} ch",
+            self.sample_rate,
+            self.bit_depth,
+            if self.is_float { "float" } else { "integer" },
+            self.channels
+        )
+    }
+}
+
 impl fmt::Display for DeviceType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -40,6 +128,23 @@ impl fmt::Display for DeviceType {
     }
 }
 
+impl fmt::Display for TransportType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportType::BuiltIn => write!(f, "Built-in"),
+            TransportType::Usb => write!(f, "USB"),
+            TransportType::Bluetooth => write!(f, "Bluetooth"),
+            TransportType::Hdmi => write!(f, "HDMI"),
+            TransportType::DisplayPort => write!(f, "DisplayPort"),
+            TransportType::Thunderbolt => write!(f, "Thunderbolt"),
+            TransportType::AirPlay => write!(f, "AirPlay"),
+            TransportType::Aggregate => write!(f, "Aggregate"),
+            TransportType::Virtual => write!(f, "Virtual"),
+            TransportType::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
 impl fmt::Display for AudioDevice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -61,6 +166,52 @@ impl fmt::Display for AudioDevice {
     }
 }
 
+/// Level of detail used when printing a device in CLI output
+#[derive(Debug, Clone, Copy)]
+enum DisplayMode {
+    Short,
+    Long,
+    Verbose,
+}
+
+/// Borrowed view of a device that formats it in a given `DisplayMode`
+struct DeviceDisplay<'a> {
+    device: &'a AudioDevice,
+    mode: DisplayMode,
+}
+
+impl fmt::Display for DeviceDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let device = self.device;
+        match self.mode {
+            DisplayMode::Short => write!(f, "{}", device.name),
+            DisplayMode::Long => write!(f, "{} [{}]", device.name, device.device_type),
+            DisplayMode::Verbose => {
+                writeln!(f, "{} [{}]", device.name, device.device_type)?;
+                writeln!(f, "    UID: {}", device.uid.as_deref().unwrap_or("Unknown"))?;
+                match device.transport_type {
+                    Some(transport_type) => writeln!(f, "    Transport: {transport_type}")?,
+                    None => writeln!(f, "    Transport: Unknown")?,
+                }
+                if device.available_sample_rates.is_empty() {
+                    writeln!(f, "    Sample Rates: Unknown")?;
+                } else {
+                    let rates: Vec<String> = device
+                        .available_sample_rates
+                        .iter()
+                        .map(|rate| format!("{rate} Hz"))
+                        .collect();
+                    writeln!(f, "    Sample Rates: {}", rates.join(", "))?;
+                }
+                match device.channels {
+                    Some(channels) => write!(f, "    Channels: {channels}"),
+                    None => write!(f, "    Channels: Unknown"),
+                }
+            }
+        }
+    }
+}
+
 impl AudioDevice {
     pub fn new(id: String, name: String, device_type: DeviceType) -> Self {
         Self {
@@ -70,15 +221,79 @@ impl AudioDevice {
             is_default: false,
             is_available: true,
             uid: None,
+            manufacturer: None,
+            transport_type: None,
+            channels: None,
+            available_sample_rates: Vec::new(),
+            connected_since: None,
         }
     }
 
+    pub fn new_with_uid(id: String, name: String, device_type: DeviceType, uid: String) -> Self {
+        Self::new(id, name, device_type).with_uid(uid)
+    }
+
     #[allow(dead_code)]
     pub fn with_uid(mut self, uid: String) -> Self {
         self.uid = Some(uid);
         self
     }
 
+    #[allow(dead_code)]
+    pub fn with_manufacturer(mut self, manufacturer: String) -> Self {
+        self.manufacturer = Some(manufacturer);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_transport_type(mut self, transport_type: TransportType) -> Self {
+        self.transport_type = Some(transport_type);
+        self
+    }
+
+    pub fn with_channels(mut self, channels: u32) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_sample_rates(mut self, sample_rates: Vec<f64>) -> Self {
+        self.available_sample_rates = sample_rates;
+        self
+    }
+
+    /// Record when the device was first seen
+    pub fn with_connected_since(mut self, connected_since: SystemTime) -> Self {
+        self.connected_since = Some(connected_since);
+        self
+    }
+
+    /// Whether the device is attached over Bluetooth. The CoreAudio transport
+    /// type decides when known; otherwise guess from the name.
+    pub fn is_bluetooth(&self) -> bool {
+        match self.transport_type {
+            Some(TransportType::Bluetooth) => true,
+            None | Some(TransportType::Unknown) => Self::is_likely_bluetooth_name(&self.name),
+            Some(_) => false,
+        }
+    }
+
+    fn is_likely_bluetooth_name(device_name: &str) -> bool {
+        let bluetooth_keywords = [
+            "airpod",
+            "bluetooth",
+            "beats",
+            "bose",
+            "sony",
+            "jabra",
+            "jbl",
+        ];
+        let name_lower = device_name.to_lowercase();
+        bluetooth_keywords
+            .iter()
+            .any(|keyword| name_lower.contains(keyword))
+    }
+
     pub fn set_default(mut self, is_default: bool) -> Self {
         self.is_default = is_default;
         self
@@ -89,4 +304,129 @@ impl AudioDevice {
         self.is_available = is_available;
         self
     }
+
+    /// Just the device name
+    #[allow(dead_code)]
+    pub fn display_short(&self) -> impl fmt::Display + '_ {
+        self.display_as(DisplayMode::Short)
+    }
+
+    /// `NAME [TYPE]` on one line
+    pub fn display_long(&self) -> impl fmt::Display + '_ {
+        self.display_as(DisplayMode::Long)
+    }
+
+    /// `NAME [TYPE]` followed by indented UID, transport, sample rate and channel lines
+    pub fn display_verbose(&self) -> impl fmt::Display + '_ {
+        self.display_as(DisplayMode::Verbose)
+    }
+
+    fn display_as(&self, mode: DisplayMode) -> DeviceDisplay<'_> {
+        DeviceDisplay { device: self, mode }
+    }
+}
+
+/// Score how closely a user-supplied partial name matches a device name (0.0 to 1.0)
+///
+/// Uses case-insensitive Jaro-Winkler similarity. Names that contain the query are
+/// always scored at least `FUZZY_MATCH_THRESHOLD` so substring lookups keep working.
+pub fn name_similarity(query: &str, device_name: &str) -> f64 {
+    if query == device_name {
+        return 1.0;
+    }
+
+    let query_lower = query.to_lowercase();
+    let name_lower = device_name.to_lowercase();
+    let score = strsim::jaro_winkler(&query_lower, &name_lower);
+
+    if !query_lower.is_empty() && name_lower.contains(&query_lower) {
+        score.max(FUZZY_MATCH_THRESHOLD)
+    } else {
+        score
+    }
+}
+
+/// Expand CoreAudio sample rate ranges (min, max) into a sorted list of rates
+///
+/// Discrete rates are reported as ranges with equal bounds and are kept as-is.
+/// Continuous ranges contribute each of `COMMON_SAMPLE_RATES` that falls inside them.
+pub fn sample_rates_from_ranges(ranges: &[(f64, f64)]) -> Vec<f64> {
+    let mut rates = Vec::new();
+
+    for &(minimum, maximum) in ranges {
+        if minimum == maximum {
+            rates.push(minimum);
+        } else {
+            rates.extend(
+                COMMON_SAMPLE_RATES
+                    .iter()
+                    .copied()
+                    .filter(|rate| (minimum..=maximum).contains(rate)),
+            );
+        }
+    }
+
+    rates.sort_by(f64::total_cmp);
+    rates.dedup();
+    rates
+}
+
+/// Sort stream formats by sample rate, bit depth, channel count and sample type,
+/// dropping formats reported by more than one stream
+pub fn sort_stream_formats(mut formats: Vec<StreamFormat>) -> Vec<StreamFormat> {
+    formats.sort_by(|a, b| {
+        a.sample_rate
+            .total_cmp(&b.sample_rate)
+            .then(a.bit_depth.cmp(&b.bit_depth))
+            .then(a.channels.cmp(&b.channels))
+            .then(a.is_float.cmp(&b.is_float))
+    });
+    formats.dedup();
+    formats
+}
+
+/// Flag the output device with ID `default_output_id` and the input device with ID
+/// `default_input_id` as defaults and clear the flag on every other device. A device
+/// that has both directions appears twice in an enumeration, once per direction.
+pub fn mark_default_devices(
+    devices: &mut [AudioDevice],
+    default_output_id: Option<&str>,
+    default_input_id: Option<&str>,
+) {
+    for device in devices {
+        let id = Some(device.id.as_str());
+        device.is_default = match device.device_type {
+            DeviceType::Output => id == default_output_id,
+            DeviceType::Input => id == default_input_id,
+            DeviceType::InputOutput => id == default_output_id || id == default_input_id,
+        };
+    }
+}
+
+/// Convert a latency in frames to milliseconds at the given sample rate
+pub fn latency_frames_to_ms(frames: u32, sample_rate: f64) -> anyhow::Result<f64> {
+    if sample_rate <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "Cannot convert latency to milliseconds: sample rate is {}",
+            sample_rate
+        ));
+    }
+    Ok(f64::from(frames) / sample_rate * 1000.0)
+}
+
+/// Check that a buffer size lies within a device's supported (min, max) frame range
+pub fn validate_buffer_frame_size(
+    frames: u32,
+    (minimum, maximum): (u32, u32),
+) -> anyhow::Result<()> {
+    if (minimum..=maximum).contains(&frames) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Buffer size {} frames is outside the supported range {}-{}",
+            frames,
+            minimum,
+            maximum
+        ))
+    }
 }
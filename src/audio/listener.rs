@@ -1,44 +1,129 @@
+//! CoreAudio property listeners that react to device changes
+//!
+//! Ownership contract: while its property listeners are registered, CoreAudio
+//! holds a strong reference to the `CoreAudioListener` as callback client
+//! data, so a callback never sees a freed listener. The flip side is that a
+//! registered listener outlives every handle to it and keeps reacting to
+//! device changes until `deregister_listeners` releases that reference. A
+//! `Drop` on the listener itself could never run while it is registered, so
+//! whoever registers the listeners deregisters them: `AudioDeviceMonitor` and
+//! `CoreAudioSystem` do so when dropped, which covers early returns and error
+//! paths. `stop_run_loop` is separate and only needed by callers that ran the
+//! loop with `start_monitoring`.
+//!
+//! A listener made with `new` switches to the best device itself. One made
+//! with `observer` only tracks devices and reports each change to its owner,
+//! for owners such as the service that switch through their own controller.
+
 use anyhow::Result;
 use core_foundation::runloop::CFRunLoop;
 use coreaudio_sys::*;
-use std::collections::HashMap;
 use std::os::raw::c_void;
+use std::ptr;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
 use tracing::{debug, error, info, warn};
 
 use super::AudioDevice;
 use super::controller::DeviceController;
+use super::device::VOLUME_SETTLE_DELAY;
 use crate::config::Config;
 use crate::notifications::{DefaultNotificationManager, SwitchReason};
 use crate::priority::DevicePriorityManager;
 
-/// Time a device must be present before we consider it stable for switching
-const DEVICE_STABILITY_THRESHOLD_MS: u64 = 750;
-
-/// Extended stability threshold for Bluetooth devices (input/output may appear separately)
-const BLUETOOTH_DEVICE_STABILITY_THRESHOLD_MS: u64 = 1500;
+/// Property listeners registered by `register_listeners`: device list, default output, default input
+pub const EXPECTED_LISTENER_COUNT: usize = 3;
 
 pub struct CoreAudioListener {
     controller: DeviceController,
     priority_manager: Arc<Mutex<DevicePriorityManager>>,
-    notification_manager: DefaultNotificationManager,
+    notification_manager: Arc<DefaultNotificationManager>,
     device_list_address: AudioObjectPropertyAddress,
     default_output_address: AudioObjectPropertyAddress,
     default_input_address: AudioObjectPropertyAddress,
+    // Devices seen at the last change, with `connected_since` set for debouncing
     previous_devices: Arc<Mutex<Vec<AudioDevice>>>,
-    // Track when devices first appeared to implement debouncing
-    device_appearance_times: Arc<Mutex<HashMap<String, Instant>>>,
+    // Property listeners currently registered with CoreAudio by this instance
+    listener_count: AtomicUsize,
+    // Leaked strong reference passed to CoreAudio as callback client data; null when unregistered
+    callback_context: AtomicPtr<CoreAudioListener>,
+    // Set for observers: called on every change instead of switching devices
+    on_change: Option<ChangeCallback>,
+}
+
+type ChangeCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Compare two CoreAudio property addresses field by field
+fn property_address_eq(a: &AudioObjectPropertyAddress, b: &AudioObjectPropertyAddress) -> bool {
+    a.mSelector == b.mSelector && a.mScope == b.mScope && a.mElement == b.mElement
+}
+
+/// Clones share the same device tracking state; property addresses are copied.
+/// A clone starts with no registered listeners since CoreAudio keys them by instance.
+impl Clone for CoreAudioListener {
+    fn clone(&self) -> Self {
+        Self {
+            controller: self.controller.clone(),
+            priority_manager: Arc::clone(&self.priority_manager),
+            notification_manager: Arc::clone(&self.notification_manager),
+            device_list_address: self.device_list_address,
+            default_output_address: self.default_output_address,
+            default_input_address: self.default_input_address,
+            previous_devices: Arc::clone(&self.previous_devices),
+            listener_count: AtomicUsize::new(0),
+            callback_context: AtomicPtr::new(ptr::null_mut()),
+            on_change: self.on_change.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for CoreAudioListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let previous_devices: Vec<String> = self
+            .previous_devices
+            .lock()
+            .map(|devices| devices.iter().map(|d| d.name.clone()).collect())
+            .unwrap_or_default();
+
+        f.debug_struct("CoreAudioListener")
+            .field("device_list_address", &self.device_list_address)
+            .field("default_output_address", &self.default_output_address)
+            .field("default_input_address", &self.default_input_address)
+            .field("previous_devices", &previous_devices)
+            .field("listener_count", &self.listener_count())
+            .field("observer", &self.on_change.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Listeners are equal when they watch the same properties and track the same devices
+impl PartialEq for CoreAudioListener {
+    fn eq(&self, other: &Self) -> bool {
+        let device_ids = |listener: &Self| -> Vec<String> {
+            listener
+                .previous_devices
+                .lock()
+                .map(|devices| devices.iter().map(|d| d.id.clone()).collect())
+                .unwrap_or_default()
+        };
+
+        property_address_eq(&self.device_list_address, &other.device_list_address)
+            && property_address_eq(&self.default_output_address, &other.default_output_address)
+            && property_address_eq(&self.default_input_address, &other.default_input_address)
+            && device_ids(self) == device_ids(other)
+    }
 }
 
 impl CoreAudioListener {
     pub fn new(config: &Config) -> Result<Self> {
         debug!("Creating CoreAudio listener");
 
-        let controller = DeviceController::new()?;
+        let controller =
+            DeviceController::new()?.with_sync_system_alerts(config.general.sync_system_alerts);
         let priority_manager = Arc::new(Mutex::new(DevicePriorityManager::new(config)));
-        let notification_manager = DefaultNotificationManager::new(config);
+        let notification_manager = Arc::new(DefaultNotificationManager::new(config));
 
         // Property addresses for listening to device changes
         let device_list_address = AudioObjectPropertyAddress {
@@ -60,14 +145,13 @@ impl CoreAudioListener {
         };
 
         // Initialize with current devices to avoid false notifications on startup
-        let initial_devices = controller.enumerate_devices().unwrap_or_default();
-
-        // Initialize appearance times for existing devices
-        let mut appearance_times = HashMap::new();
-        let now = Instant::now();
-        for device in &initial_devices {
-            appearance_times.insert(device.id.clone(), now);
-        }
+        let now = SystemTime::now();
+        let initial_devices: Vec<AudioDevice> = controller
+            .enumerate_devices()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|device| device.with_connected_since(now))
+            .collect();
 
         Ok(Self {
             controller,
@@ -77,13 +161,38 @@ impl CoreAudioListener {
             default_output_address,
             default_input_address,
             previous_devices: Arc::new(Mutex::new(initial_devices)),
-            device_appearance_times: Arc::new(Mutex::new(appearance_times)),
+            listener_count: AtomicUsize::new(0),
+            callback_context: AtomicPtr::new(ptr::null_mut()),
+            on_change: None,
+        })
+    }
+
+    /// A listener that tracks devices and sends connection notifications like
+    /// `new`, but never switches: it calls `on_change` after every device list
+    /// or default device change and leaves switching to its owner
+    pub fn observer(config: &Config, on_change: impl Fn() + Send + Sync + 'static) -> Result<Self> {
+        Ok(Self {
+            on_change: Some(Arc::new(on_change)),
+            ..Self::new(config)?
         })
     }
 
-    pub fn register_listeners(&self) -> Result<()> {
+    /// Report a change to the owner of an observing listener
+    fn notify_change(&self) {
+        if let Some(on_change) = &self.on_change {
+            on_change();
+        }
+    }
+
+    /// Register the property listeners. CoreAudio holds a strong reference to the
+    /// listener until `deregister_listeners`, so dropping every other handle is safe.
+    /// Fails only when no listener could be registered; `listener_count` tells
+    /// whether all of them were.
+    pub fn register_listeners(self: &Arc<Self>) -> Result<()> {
         info!("Registering CoreAudio property listeners");
 
+        let client_data = self.acquire_callback_context();
+
         unsafe {
             // Configure CFRunLoop for CoreAudio property listeners
             // This is critical for reliable event delivery, especially for rapid device changes
@@ -112,54 +221,66 @@ impl CoreAudioListener {
                 info!("CoreAudio run loop configured successfully");
             }
 
-            // Register listener for device list changes
+            // Register each listener on its own so one failure does not leave the
+            // others unregistered
             let result = AudioObjectAddPropertyListener(
                 kAudioObjectSystemObject,
                 &self.device_list_address,
                 Some(device_list_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_added(result, "device list");
 
-            if result != kAudioHardwareNoError as i32 {
-                error!("Failed to register device list listener: {}", result);
-                return Err(anyhow::anyhow!("Failed to register device list listener"));
-            }
-
-            // Register listener for default output device changes
             let result = AudioObjectAddPropertyListener(
                 kAudioObjectSystemObject,
                 &self.default_output_address,
                 Some(default_output_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_added(result, "default output");
 
-            if result != kAudioHardwareNoError as i32 {
-                error!("Failed to register default output listener: {}", result);
-                return Err(anyhow::anyhow!(
-                    "Failed to register default output listener"
-                ));
-            }
-
-            // Register listener for default input device changes
             let result = AudioObjectAddPropertyListener(
                 kAudioObjectSystemObject,
                 &self.default_input_address,
                 Some(default_input_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_added(result, "default input");
+        }
 
-            if result != kAudioHardwareNoError as i32 {
-                error!("Failed to register default input listener: {}", result);
-                return Err(anyhow::anyhow!("Failed to register default input listener"));
-            }
+        let registered = self.listener_count();
+        if registered == 0 {
+            // No callback holds the context, so CoreAudio's reference can go
+            drop(self.take_callback_context());
+            return Err(anyhow::anyhow!(
+                "Failed to register any CoreAudio property listener"
+            ));
+        }
+        if registered != EXPECTED_LISTENER_COUNT {
+            warn!(
+                "Expected {} CoreAudio property listeners but {} are registered; device changes may be missed",
+                EXPECTED_LISTENER_COUNT, registered
+            );
+            return Ok(());
         }
 
         info!("CoreAudio property listeners registered successfully");
         Ok(())
     }
 
+    /// Register the property listeners on an observer that does nothing, remove
+    /// them again and report how many CoreAudio accepted
+    pub fn probe_listener_count(config: &Config) -> Result<usize> {
+        let listener = Arc::new(Self::observer(config, || {})?);
+        let registered = listener
+            .register_listeners()
+            .map(|()| listener.listener_count());
+        listener.deregister_listeners();
+        registered
+    }
+
     #[allow(dead_code)]
-    pub fn start_monitoring(&self) -> Result<()> {
+    pub fn start_monitoring(self: &Arc<Self>) -> Result<()> {
         info!("Starting CoreAudio device monitoring");
 
         // Register all property listeners
@@ -178,66 +299,138 @@ impl CoreAudioListener {
         Ok(())
     }
 
-    pub fn stop_monitoring(&self) -> Result<()> {
-        info!("Stopping CoreAudio device monitoring");
+    /// Number of device notifications this listener is still delivering
+    pub fn pending_notifications(&self) -> usize {
+        self.notification_manager.pending_notifications()
+    }
+
+    /// Remove the property listeners and release CoreAudio's reference to this
+    /// listener. Safe to call repeatedly or when nothing is registered.
+    pub fn deregister_listeners(&self) {
+        let Some(context) = self.take_callback_context() else {
+            debug!("No CoreAudio property listeners registered");
+            return;
+        };
+
+        info!("Removing CoreAudio property listeners");
+        let client_data = Arc::as_ptr(&context) as *mut c_void;
 
         unsafe {
-            // Remove all property listeners
-            AudioObjectRemovePropertyListener(
+            let result = AudioObjectRemovePropertyListener(
                 kAudioObjectSystemObject,
                 &self.device_list_address,
                 Some(device_list_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_removed(result);
 
-            AudioObjectRemovePropertyListener(
+            let result = AudioObjectRemovePropertyListener(
                 kAudioObjectSystemObject,
                 &self.default_output_address,
                 Some(default_output_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_removed(result);
 
-            AudioObjectRemovePropertyListener(
+            let result = AudioObjectRemovePropertyListener(
                 kAudioObjectSystemObject,
                 &self.default_input_address,
                 Some(default_input_listener),
-                self as *const _ as *mut c_void,
+                client_data,
             );
+            self.record_listener_removed(result);
+        }
+
+        // Only release CoreAudio's reference once no callback can receive it
+        drop(context);
+    }
 
-            // Stop the run loop
-            CFRunLoop::get_current().stop();
+    /// Stop the run loop `start_monitoring` runs on the current thread
+    pub fn stop_run_loop(&self) {
+        info!("Stopping Core Foundation run loop");
+        CFRunLoop::get_current().stop();
+    }
+
+    /// Client data for CoreAudio callbacks: a leaked strong reference that keeps this
+    /// listener alive while callbacks can run. Repeated calls reuse the same reference.
+    fn acquire_callback_context(self: &Arc<Self>) -> *mut c_void {
+        let leaked = Arc::into_raw(Arc::clone(self)).cast_mut();
+        match self.callback_context.compare_exchange(
+            ptr::null_mut(),
+            leaked,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => leaked.cast(),
+            Err(existing) => {
+                // SAFETY: `leaked` came from `Arc::into_raw` above and was never shared
+                drop(unsafe { Arc::from_raw(leaked) });
+                existing.cast()
+            }
         }
+    }
 
-        Ok(())
+    /// Take back the reference leaked by `acquire_callback_context`, if any
+    fn take_callback_context(&self) -> Option<Arc<Self>> {
+        let context = self
+            .callback_context
+            .swap(ptr::null_mut(), Ordering::SeqCst);
+        // SAFETY: a non-null context was produced by `Arc::into_raw` and is reclaimed once
+        (!context.is_null()).then(|| unsafe { Arc::from_raw(context) })
+    }
+
+    /// Number of property listeners currently registered with CoreAudio
+    pub fn listener_count(&self) -> usize {
+        self.listener_count.load(Ordering::SeqCst)
     }
 
-    /// Check if a device is likely a Bluetooth device based on its name
-    fn is_likely_bluetooth_device(device_name: &str) -> bool {
-        let bluetooth_keywords = [
-            "airpod",
-            "bluetooth",
-            "beats",
-            "bose",
-            "sony",
-            "jabra",
-            "jbl",
-        ];
-        let name_lower = device_name.to_lowercase();
-        bluetooth_keywords
-            .iter()
-            .any(|keyword| name_lower.contains(keyword))
-    }
-
-    /// Check if both input and output devices exist for a given device name pattern
-    fn has_paired_input_output(devices: &[AudioDevice], device_name: &str) -> bool {
-        let has_output = devices.iter().any(|d| {
-            d.name.contains(device_name)
-                && matches!(d.device_type, crate::audio::DeviceType::Output)
-        });
-        let has_input = devices.iter().any(|d| {
-            d.name.contains(device_name) && matches!(d.device_type, crate::audio::DeviceType::Input)
-        });
-        has_output && has_input
+    fn record_listener_added(&self, result: OSStatus, property: &str) {
+        if result != kAudioHardwareNoError as i32 {
+            error!("Failed to register {} listener: {}", property, result);
+            return;
+        }
+        self.listener_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn record_listener_removed(&self, result: OSStatus) {
+        if result != kAudioHardwareNoError as i32 {
+            warn!("Failed to remove property listener: {}", result);
+            return;
+        }
+
+        // Never underflow if CoreAudio reports removing a listener we did not count
+        let _ = self
+            .listener_count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                count.checked_sub(1)
+            });
+    }
+
+    /// Apply the buffer size configured by the device's matching rule, if any
+    fn apply_rule_buffer_size(
+        &self,
+        priority_manager: &DevicePriorityManager,
+        device: &AudioDevice,
+    ) {
+        let Some(frames) = priority_manager.buffer_size_for(device) else {
+            return;
+        };
+
+        if let Err(e) = self.controller.set_device_buffer_size(&device.id, frames) {
+            warn!("Failed to set buffer size for {}: {}", device.name, e);
+        }
+    }
+
+    /// Set the output volume configured by the device's matching rule, if any
+    fn apply_rule_volume(&self, priority_manager: &DevicePriorityManager, device: &AudioDevice) {
+        let Some(volume) = priority_manager.volume_for(device) else {
+            return;
+        };
+
+        std::thread::sleep(VOLUME_SETTLE_DELAY);
+        if let Err(e) = self.controller.set_system_volume(volume) {
+            warn!("Failed to set volume for {}: {}", device.name, e);
+        }
     }
 
     fn handle_device_list_change(&self) {
@@ -251,187 +444,172 @@ impl CoreAudioListener {
                     current_devices.len()
                 );
 
-                let now = Instant::now();
+                let now = SystemTime::now();
+                let mut current_devices = current_devices;
 
                 // Check for device connections/disconnections and send notifications
+                let mut disconnected_devices = Vec::new();
                 if let Ok(mut previous_devices) = self.previous_devices.lock() {
-                    if let Ok(mut appearance_times) = self.device_appearance_times.lock() {
-                        // Find newly connected devices
-                        for device in &current_devices {
-                            if !previous_devices.iter().any(|prev| prev.id == device.id) {
-                                // Device was connected - record appearance time
-                                appearance_times.insert(device.id.clone(), now);
-                                info!(
-                                    "New device detected: {} (will debounce for {}ms)",
-                                    device.name, DEVICE_STABILITY_THRESHOLD_MS
-                                );
+                    for device in &mut current_devices {
+                        if let Some(prev) =
+                            previous_devices.iter().find(|prev| prev.id == device.id)
+                        {
+                            // Still connected - keep the original connection time
+                            device.connected_since = prev.connected_since;
+                            continue;
+                        }
 
-                                if let Err(e) = self.notification_manager.device_connected(device) {
-                                    warn!("Failed to send device connected notification: {}", e);
-                                }
-                            }
+                        // Device was connected - record when it appeared
+                        device.connected_since = Some(now);
+                        info!("New device detected: {}", device.name);
+
+                        if let Err(e) = self.notification_manager.device_connected(device) {
+                            warn!("Failed to send device connected notification: {}", e);
                         }
+                    }
 
-                        // Find disconnected devices and clean up appearance times
-                        for prev_device in &*previous_devices {
-                            if !current_devices.iter().any(|curr| curr.id == prev_device.id) {
-                                // Device was disconnected
-                                appearance_times.remove(&prev_device.id);
-                                info!("Device disconnected: {}", prev_device.name);
-
-                                if let Err(e) =
-                                    self.notification_manager.device_disconnected(prev_device)
-                                {
-                                    warn!("Failed to send device disconnected notification: {}", e);
-                                }
+                    // Find disconnected devices
+                    for prev_device in &*previous_devices {
+                        if !current_devices.iter().any(|curr| curr.id == prev_device.id) {
+                            info!("Device disconnected: {}", prev_device.name);
+                            disconnected_devices.push(prev_device.clone());
+
+                            if let Err(e) =
+                                self.notification_manager.device_disconnected(prev_device)
+                            {
+                                warn!("Failed to send device disconnected notification: {}", e);
                             }
                         }
-
-                        // Update previous devices list
-                        *previous_devices = current_devices.clone();
                     }
+
+                    // Update previous devices list
+                    *previous_devices = current_devices.clone();
+                }
+
+                // An observer's owner switches through its own controller
+                if self.on_change.is_some() {
+                    self.notify_change();
+                    return;
                 }
 
                 // Check if we need to switch to a higher priority device
                 // Only consider devices that have been stable for the threshold duration
-                if let Ok(priority_manager) = self.priority_manager.lock() {
-                    if let Ok(appearance_times) = self.device_appearance_times.lock() {
-                        // Filter devices to only those that are stable
-                        // Use extended threshold for Bluetooth devices that may have separate input/output
-                        let stable_devices: Vec<_> = current_devices
-                            .iter()
-                            .filter(|d| {
-                                appearance_times
-                                    .get(&d.id)
-                                    .map(|&appeared_at| {
-                                        let elapsed = now.duration_since(appeared_at);
-                                        let is_bluetooth =
-                                            Self::is_likely_bluetooth_device(&d.name);
-                                        let threshold = if is_bluetooth {
-                                            BLUETOOTH_DEVICE_STABILITY_THRESHOLD_MS
-                                        } else {
-                                            DEVICE_STABILITY_THRESHOLD_MS
-                                        };
-
-                                        // For Bluetooth devices, also check if paired device exists
-                                        if is_bluetooth && elapsed.as_millis() >= threshold as u128
-                                        {
-                                            // Extract common name part (e.g., "AirPods Pro" from "AirPods Pro - Output")
-                                            let base_name =
-                                                d.name.split('-').next().unwrap_or(&d.name).trim();
-                                            Self::has_paired_input_output(
-                                                &current_devices,
-                                                base_name,
-                                            )
-                                        } else {
-                                            elapsed.as_millis() >= threshold as u128
-                                        }
-                                    })
-                                    .unwrap_or(false)
-                            })
-                            .cloned()
-                            .collect();
-
-                        let stable_output_devices: Vec<_> = stable_devices
-                            .iter()
-                            .filter(|d| matches!(d.device_type, crate::audio::DeviceType::Output))
-                            .cloned()
-                            .collect();
-
-                        let stable_input_devices: Vec<_> = stable_devices
-                            .iter()
-                            .filter(|d| matches!(d.device_type, crate::audio::DeviceType::Input))
-                            .cloned()
-                            .collect();
-
-                        let bluetooth_count = stable_devices
-                            .iter()
-                            .filter(|d| Self::is_likely_bluetooth_device(&d.name))
-                            .count();
-                        debug!(
-                            "Found {} stable devices out of {} total ({} Bluetooth with {}ms threshold, {} other with {}ms threshold)",
-                            stable_devices.len(),
-                            current_devices.len(),
-                            bluetooth_count,
-                            BLUETOOTH_DEVICE_STABILITY_THRESHOLD_MS,
-                            stable_devices.len() - bluetooth_count,
-                            DEVICE_STABILITY_THRESHOLD_MS
-                        );
-
-                        // Find best available stable devices
-                        if let Some(best_output) =
-                            priority_manager.find_best_output_device(&stable_output_devices)
-                        {
-                            if priority_manager.should_switch_output(&best_output) {
-                                info!("Switching to stable output device: {}", best_output.name);
-                                match self.controller.set_default_output_device(&best_output.name) {
-                                    Ok(()) => {
-                                        info!(
-                                            "Successfully switched to output device: {}",
-                                            best_output.name
-                                        );
-                                        // Send notification for successful switch
-                                        if let Err(e) = self.notification_manager.device_switched(
-                                            &best_output,
-                                            SwitchReason::HigherPriority,
-                                        ) {
-                                            warn!(
-                                                "Failed to send device switched notification: {}",
-                                                e
-                                            );
-                                        }
+                if let Ok(mut priority_manager) = self.priority_manager.lock() {
+                    for device in &disconnected_devices {
+                        priority_manager.device_disconnected(device);
+                    }
+                    if priority_manager.prefers_wired_on_battery() {
+                        priority_manager
+                            .set_on_battery_power(crate::system::query_on_battery_power());
+                    }
+
+                    // Filter devices to only those that are stable, each against the
+                    // threshold of its rule or its transport type
+                    let stable_devices: Vec<_> = current_devices
+                        .iter()
+                        .filter(|d| {
+                            let stable =
+                                priority_manager.is_device_stable(d, now, &current_devices);
+                            if !stable {
+                                debug!(
+                                    "Waiting {}ms for {} to settle",
+                                    priority_manager.get_stability_threshold(d),
+                                    d.name
+                                );
+                            }
+                            stable
+                        })
+                        .cloned()
+                        .collect();
+
+                    let stable_output_devices: Vec<_> = stable_devices
+                        .iter()
+                        .filter(|d| matches!(d.device_type, crate::audio::DeviceType::Output))
+                        .cloned()
+                        .collect();
+
+                    let stable_input_devices: Vec<_> = stable_devices
+                        .iter()
+                        .filter(|d| matches!(d.device_type, crate::audio::DeviceType::Input))
+                        .cloned()
+                        .collect();
+
+                    let bluetooth_count =
+                        stable_devices.iter().filter(|d| d.is_bluetooth()).count();
+                    debug!(
+                        "Found {} stable devices out of {} total ({} Bluetooth, {} other)",
+                        stable_devices.len(),
+                        current_devices.len(),
+                        bluetooth_count,
+                        stable_devices.len() - bluetooth_count
+                    );
+
+                    // Find best available stable devices
+                    if let Some(best_output) =
+                        priority_manager.find_best_output_device(&stable_output_devices)
+                    {
+                        if priority_manager.should_switch_output(&best_output) {
+                            info!("Switching to stable output device: {}", best_output.name);
+                            match self.controller.set_default_output_device(&best_output.name) {
+                                Ok(()) => {
+                                    info!(
+                                        "Successfully switched to output device: {}",
+                                        best_output.name
+                                    );
+                                    priority_manager.record_output_switch(&best_output);
+                                    self.apply_rule_buffer_size(&priority_manager, &best_output);
+                                    self.apply_rule_volume(&priority_manager, &best_output);
+                                    // Send notification for successful switch
+                                    if let Err(e) = self
+                                        .notification_manager
+                                        .device_switched(&best_output, SwitchReason::HigherPriority)
+                                    {
+                                        warn!("Failed to send device switched notification: {}", e);
                                     }
-                                    Err(e) => {
-                                        error!("Failed to switch output device: {}", e);
-                                        // Send notification for failed switch
-                                        if let Err(e) = self
-                                            .notification_manager
-                                            .switch_failed(&best_output.name, &e.to_string())
-                                        {
-                                            warn!(
-                                                "Failed to send switch failed notification: {}",
-                                                e
-                                            );
-                                        }
+                                }
+                                Err(e) => {
+                                    error!("Failed to switch output device: {}", e);
+                                    // Send notification for failed switch
+                                    if let Err(e) = self
+                                        .notification_manager
+                                        .switch_failed(&best_output.name, &e.to_string())
+                                    {
+                                        warn!("Failed to send switch failed notification: {}", e);
                                     }
                                 }
                             }
                         }
+                    }
 
-                        if let Some(best_input) =
-                            priority_manager.find_best_input_device(&stable_input_devices)
-                        {
-                            if priority_manager.should_switch_input(&best_input) {
-                                info!("Switching to stable input device: {}", best_input.name);
-                                match self.controller.set_default_input_device(&best_input.name) {
-                                    Ok(()) => {
-                                        info!(
-                                            "Successfully switched to input device: {}",
-                                            best_input.name
-                                        );
-                                        // Send notification for successful switch
-                                        if let Err(e) = self.notification_manager.device_switched(
-                                            &best_input,
-                                            SwitchReason::HigherPriority,
-                                        ) {
-                                            warn!(
-                                                "Failed to send device switched notification: {}",
-                                                e
-                                            );
-                                        }
+                    if let Some(best_input) =
+                        priority_manager.find_best_input_device(&stable_input_devices)
+                    {
+                        if priority_manager.should_switch_input(&best_input) {
+                            info!("Switching to stable input device: {}", best_input.name);
+                            match self.controller.set_default_input_device(&best_input.name) {
+                                Ok(()) => {
+                                    info!(
+                                        "Successfully switched to input device: {}",
+                                        best_input.name
+                                    );
+                                    priority_manager.record_input_switch(&best_input);
+                                    self.apply_rule_buffer_size(&priority_manager, &best_input);
+                                    // Send notification for successful switch
+                                    if let Err(e) = self
+                                        .notification_manager
+                                        .device_switched(&best_input, SwitchReason::HigherPriority)
+                                    {
+                                        warn!("Failed to send device switched notification: {}", e);
                                     }
-                                    Err(e) => {
-                                        error!("Failed to switch input device: {}", e);
-                                        // Send notification for failed switch
-                                        if let Err(e) = self
-                                            .notification_manager
-                                            .switch_failed(&best_input.name, &e.to_string())
-                                        {
-                                            warn!(
-                                                "Failed to send switch failed notification: {}",
-                                                e
-                                            );
-                                        }
+                                }
+                                Err(e) => {
+                                    error!("Failed to switch input device: {}", e);
+                                    // Send notification for failed switch
+                                    if let Err(e) = self
+                                        .notification_manager
+                                        .switch_failed(&best_input.name, &e.to_string())
+                                    {
+                                        warn!("Failed to send switch failed notification: {}", e);
                                     }
                                 }
                             }
@@ -453,8 +631,9 @@ impl CoreAudioListener {
                 info!("Default output device is now: {}", device.name);
 
                 if let Ok(mut priority_manager) = self.priority_manager.lock() {
-                    priority_manager.update_current_output(device.name);
+                    priority_manager.update_current_output(device);
                 }
+                self.notify_change();
             }
             Ok(None) => {
                 warn!("No default output device available");
@@ -473,8 +652,9 @@ impl CoreAudioListener {
                 info!("Default input device is now: {}", device.name);
 
                 if let Ok(mut priority_manager) = self.priority_manager.lock() {
-                    priority_manager.update_current_input(device.name);
+                    priority_manager.update_current_input(device);
                 }
+                self.notify_change();
             }
             Ok(None) => {
                 warn!("No default input device available");
@@ -525,3 +705,149 @@ extern "C" fn default_input_listener(
     }
     kAudioHardwareNoError as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::DeviceType;
+
+    #[test]
+    fn test_clone_shares_device_tracking_state() {
+        let listener = CoreAudioListener::new(&Config::default()).unwrap();
+        let cloned = listener.clone();
+
+        assert_eq!(listener, cloned);
+        assert!(Arc::ptr_eq(
+            &listener.previous_devices,
+            &cloned.previous_devices
+        ));
+        assert!(property_address_eq(
+            &listener.default_output_address,
+            &cloned.default_output_address
+        ));
+    }
+
+    #[test]
+    fn test_listeners_differ_when_tracked_devices_differ() {
+        let listener = CoreAudioListener::new(&Config::default()).unwrap();
+        let other = CoreAudioListener::new(&Config::default()).unwrap();
+        assert_eq!(listener, other);
+
+        other
+            .previous_devices
+            .lock()
+            .unwrap()
+            .push(AudioDevice::new(
+                "test-device".to_string(),
+                "Test Device".to_string(),
+                DeviceType::Output,
+            ));
+
+        assert_ne!(listener, other);
+        assert!(format!("{other:?}").contains("Test Device"));
+    }
+
+    #[test]
+    fn test_callback_context_keeps_dropped_listener_alive() {
+        let listener = Arc::new(CoreAudioListener::new(&Config::default()).unwrap());
+        let weak = Arc::downgrade(&listener);
+
+        let context = listener.acquire_callback_context();
+        assert_eq!(listener.acquire_callback_context(), context);
+        drop(listener);
+
+        // A callback arriving after the last handle is dropped still sees a live listener
+        let from_callback = unsafe { &*(context as *const CoreAudioListener) };
+        assert_eq!(from_callback.listener_count(), 0);
+        assert!(weak.upgrade().is_some());
+
+        let reclaimed = weak.upgrade().unwrap().take_callback_context();
+        assert!(reclaimed.is_some());
+        drop(reclaimed);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_deregistering_releases_callback_context() {
+        let listener = Arc::new(CoreAudioListener::new(&Config::default()).unwrap());
+        let weak = Arc::downgrade(&listener);
+        listener.acquire_callback_context();
+
+        listener.deregister_listeners();
+        // A second call finds nothing to remove
+        listener.deregister_listeners();
+
+        assert!(listener.take_callback_context().is_none());
+        drop(listener);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_clone_has_no_callback_context() {
+        let listener = Arc::new(CoreAudioListener::new(&Config::default()).unwrap());
+        listener.acquire_callback_context();
+
+        let cloned = CoreAudioListener::clone(&listener);
+        assert!(cloned.take_callback_context().is_none());
+        assert!(listener.take_callback_context().is_some());
+    }
+
+    #[test]
+    fn test_listener_count_starts_at_zero() {
+        let listener = CoreAudioListener::new(&Config::default()).unwrap();
+        assert_eq!(listener.listener_count(), 0);
+
+        listener
+            .listener_count
+            .store(EXPECTED_LISTENER_COUNT, Ordering::SeqCst);
+        listener.record_listener_removed(kAudioHardwareNoError as i32);
+        assert_eq!(listener.listener_count(), EXPECTED_LISTENER_COUNT - 1);
+
+        // Failed removals and clones do not change the count
+        listener.record_listener_removed(kAudioHardwareUnspecifiedError as i32);
+        assert_eq!(listener.listener_count(), EXPECTED_LISTENER_COUNT - 1);
+        assert_eq!(listener.clone().listener_count(), 0);
+    }
+
+    #[test]
+    fn test_failed_registration_does_not_count() {
+        let listener = CoreAudioListener::new(&Config::default()).unwrap();
+
+        listener.record_listener_added(kAudioHardwareNoError as i32, "device list");
+        listener.record_listener_added(kAudioHardwareUnspecifiedError as i32, "default output");
+        listener.record_listener_added(kAudioHardwareNoError as i32, "default input");
+
+        assert_eq!(listener.listener_count(), 2);
+    }
+
+    #[test]
+    fn test_observer_clones_share_change_callback() {
+        let changes = Arc::new(AtomicUsize::new(0));
+        let observed = Arc::clone(&changes);
+        let listener = CoreAudioListener::observer(&Config::default(), move || {
+            observed.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        listener.notify_change();
+        listener.clone().notify_change();
+        assert_eq!(changes.load(Ordering::SeqCst), 2);
+
+        let switching = CoreAudioListener::new(&Config::default()).unwrap();
+        assert!(switching.on_change.is_none());
+    }
+
+    #[test]
+    fn test_property_address_eq() {
+        let listener = CoreAudioListener::new(&Config::default()).unwrap();
+
+        assert!(property_address_eq(
+            &listener.device_list_address,
+            &listener.device_list_address
+        ));
+        assert!(!property_address_eq(
+            &listener.default_output_address,
+            &listener.default_input_address
+        ));
+    }
+}
@@ -7,5 +7,9 @@ pub mod monitor;
 #[allow(unused_imports)] // Used by examples
 pub use controller::DeviceController;
 pub use controller_v2::DeviceController as DeviceControllerV2;
-pub use device::{AudioDevice, DeviceType};
+#[allow(unused_imports)]
+// DeviceSnapshot, DryRunResult and SwitchCounts are used by library consumers
+pub use controller_v2::{DeviceSnapshot, DryRunResult, SwitchCounts};
+#[allow(unused_imports)] // TransportType is used by library consumers
+pub use device::{AudioDevice, DeviceEvent, DeviceType, StreamFormat, TransportType};
 pub use monitor::AudioDeviceMonitor;
@@ -1,29 +1,28 @@
 use anyhow::Result;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{debug, info};
 
-use super::controller::DeviceController;
 use super::listener::CoreAudioListener;
 use crate::config::Config;
+use crate::system::{AudioSystemInterface, CoreAudioSystem};
 
-pub struct AudioDeviceMonitor {
-    controller: DeviceController,
+pub struct AudioDeviceMonitor<A: AudioSystemInterface = CoreAudioSystem> {
+    audio_system: A,
     #[allow(dead_code)]
     config: Config,
-    listener: CoreAudioListener,
+    // Only present in production, where CoreAudio drives the run loop
+    listener: Option<Arc<CoreAudioListener>>,
 }
 
-impl AudioDeviceMonitor {
-    pub fn new(config: Config) -> Result<Self> {
-        let controller = DeviceController::new()?;
-        let listener = CoreAudioListener::new(&config)?;
+impl<A: AudioSystemInterface> AudioDeviceMonitor<A> {
+    pub fn new(audio_system: A, config: &Config) -> Self {
+        info!("Created audio device monitor");
 
-        info!("Created audio device monitor with CoreAudio listener");
-
-        Ok(Self {
-            controller,
-            config,
-            listener,
-        })
+        Self {
+            audio_system,
+            config: config.clone(),
+            listener: None,
+        }
     }
 
     #[allow(dead_code)]
@@ -36,8 +35,11 @@ impl AudioDeviceMonitor {
         // Phase 2: Real-time device change monitoring
         info!("Starting real-time device monitoring");
 
-        // This will block and run the CoreAudio event loop
-        self.listener.start_monitoring()?;
+        match &self.listener {
+            // This will block and run the CoreAudio event loop
+            Some(listener) => listener.start_monitoring()?,
+            None => self.register_change_callback()?,
+        }
 
         Ok(())
     }
@@ -49,9 +51,13 @@ impl AudioDeviceMonitor {
         self.list_initial_devices().await?;
 
         // Register listeners but don't start the run loop yet
-        self.listener.register_listeners()?;
+        match &self.listener {
+            // Warns itself when only some of the listeners could be registered
+            Some(listener) => listener.register_listeners()?,
+            None => self.register_change_callback()?,
+        }
 
-        info!("CoreAudio listeners registered, monitoring device changes...");
+        info!("Device change listeners registered, monitoring device changes...");
         println!("Device monitoring active - try plugging/unplugging audio devices");
         println!("Press Ctrl+C to stop");
 
@@ -60,14 +66,37 @@ impl AudioDeviceMonitor {
 
     pub fn stop(&self) -> Result<()> {
         info!("Stopping audio device monitor");
-        self.listener.stop_monitoring()?;
+        if let Some(listener) = &self.listener {
+            listener.deregister_listeners();
+            listener.stop_run_loop();
+        }
         Ok(())
     }
 
+    /// Number of CoreAudio property listeners currently registered (0 without a listener)
+    // Called by monitor tests and embedders checking the monitor is listening
+    #[allow(dead_code)]
+    pub fn get_listener_count(&self) -> usize {
+        self.listener
+            .as_ref()
+            .map_or(0, |listener| listener.listener_count())
+    }
+
+    /// Get the underlying audio system (used by tests to inspect mocks)
+    #[allow(dead_code)]
+    pub fn get_audio_system(&self) -> &A {
+        &self.audio_system
+    }
+
+    fn register_change_callback(&self) -> Result<()> {
+        self.audio_system
+            .add_device_change_listener(Box::new(|| debug!("Device change detected")))
+    }
+
     async fn list_initial_devices(&self) -> Result<()> {
         info!("Enumerating initial devices");
 
-        let devices = self.controller.enumerate_devices()?;
+        let devices = self.audio_system.enumerate_devices()?;
 
         println!("Found {} audio devices:", devices.len());
         for device in &devices {
@@ -75,14 +104,98 @@ impl AudioDeviceMonitor {
         }
 
         // Show default devices
-        if let Ok(Some(default_input)) = self.controller.get_default_input_device() {
+        if let Ok(Some(default_input)) = self.audio_system.get_default_input_device() {
             println!("Default input: {}", default_input.name);
         }
 
-        if let Ok(Some(default_output)) = self.controller.get_default_output_device() {
+        if let Ok(Some(default_output)) = self.audio_system.get_default_output_device() {
             println!("Default output: {}", default_output.name);
         }
 
         Ok(())
     }
 }
+
+/// Deregisters the listener's property listeners, which would otherwise keep
+/// the listener alive and switching devices after the monitor is gone
+impl<A: AudioSystemInterface> Drop for AudioDeviceMonitor<A> {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.deregister_listeners();
+        }
+    }
+}
+
+// Convenience constructor for production use
+impl AudioDeviceMonitor<CoreAudioSystem> {
+    pub fn new_production(config: &Config) -> Result<Self> {
+        let mut monitor = Self::new(CoreAudioSystem::new()?, config);
+        monitor.listener = Some(Arc::new(CoreAudioListener::new(config)?));
+
+        info!("Attached CoreAudio listener to audio device monitor");
+        Ok(monitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioDevice, DeviceType};
+    use crate::system::MockAudioSystem;
+
+    fn create_monitor() -> AudioDeviceMonitor<MockAudioSystem> {
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(AudioDevice::new(
+            "speaker-1".to_string(),
+            "Test Speaker".to_string(),
+            DeviceType::Output,
+        ));
+
+        AudioDeviceMonitor::new(audio_system, &Config::default())
+    }
+
+    #[tokio::test]
+    async fn test_start_monitoring_registers_callback() {
+        let monitor = create_monitor();
+
+        monitor.start_monitoring_async().await.unwrap();
+
+        let audio_system = monitor.get_audio_system();
+        assert_eq!(audio_system.callback_count(), 1);
+        assert!(audio_system.get_enumerate_calls() > 0);
+
+        // CoreAudio property listeners only exist in production
+        assert_eq!(monitor.get_listener_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_start_fails_when_enumeration_fails() {
+        let monitor = create_monitor();
+        monitor.get_audio_system().set_enumeration_failure(true);
+
+        assert!(monitor.start().await.is_err());
+        assert_eq!(monitor.get_audio_system().callback_count(), 0);
+    }
+
+    #[test]
+    fn test_dropping_monitor_deregisters_listener() {
+        let mut monitor = create_monitor();
+        let listener = Arc::new(CoreAudioListener::new(&Config::default()).unwrap());
+        monitor.listener = Some(Arc::clone(&listener));
+        // Registration needs CoreAudio; where it fails, what it registered is
+        // still left to the monitor to remove
+        let _ = listener.register_listeners();
+
+        drop(monitor);
+
+        assert_eq!(listener.listener_count(), 0);
+        // CoreAudio's reference is released, leaving only ours
+        assert_eq!(Arc::strong_count(&listener), 1);
+    }
+
+    #[test]
+    fn test_stop_without_listener() {
+        let monitor = create_monitor();
+        assert!(monitor.stop().is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+//! Detection of device rules that compete in confusing ways
+//!
+//! Two kinds of conflict are reported, only between enabled rules that select
+//! devices in a common direction:
+//!
+//! - `SameWeight`: both rules match some device name and have the same weight,
+//!   so which one decides the device's priority depends on rule order
+//! - `SubsumedBy`: a `contains` rule's text includes another `contains` rule's
+//!   text, so every device the first matches is also matched by the second
+//!
+//! Example names are built from the two rules' patterns. Regex rules are only
+//! checked against names built from the other rule, so two regex rules are
+//! never reported.
+
+use super::{Config, DeviceRule, MatchType};
+
+/// What makes two rules conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    SameWeight,
+    SubsumedBy,
+}
+
+impl ConflictKind {
+    /// Short name shown in `check-config` output
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictKind::SameWeight => "same weight",
+            ConflictKind::SubsumedBy => "subsumed",
+        }
+    }
+
+    /// What the conflict means, with an example, for `check-config` output
+    pub fn description(&self) -> &'static str {
+        match self {
+            ConflictKind::SameWeight => {
+                "two rules with the same weight match one device, e.g. contains \"Headset\" \
+                 and starts_with \"USB\" both match \"USB Headset\"; rule order decides which applies"
+            }
+            ConflictKind::SubsumedBy => {
+                "a contains rule's text includes another's, e.g. every device matching \
+                 \"AirPods Pro\" also matches \"AirPods\""
+            }
+        }
+    }
+}
+
+/// Two rules that compete for the same devices
+#[derive(Debug, Clone)]
+pub struct RuleConflict {
+    /// For `SubsumedBy`, the rule whose every device `other` also matches
+    pub rule: DeviceRule,
+    pub other: DeviceRule,
+    pub kind: ConflictKind,
+    /// A device name both rules match; a UID for `uid` rules
+    pub example: String,
+}
+
+impl std::fmt::Display for RuleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ConflictKind::SameWeight => write!(
+                f,
+                "Rules '{}' and '{}' both match '{}' with weight {}",
+                self.rule.label(),
+                self.other.label(),
+                self.example,
+                self.rule.weight
+            ),
+            ConflictKind::SubsumedBy => write!(
+                f,
+                "Every device rule '{}' matches, such as '{}', is also matched by rule '{}'",
+                self.rule.label(),
+                self.example,
+                self.other.label()
+            ),
+        }
+    }
+}
+
+impl Config {
+    /// Pairs of enabled rules that compete for the same devices, in rule order
+    pub fn validate_rules(&self) -> Vec<RuleConflict> {
+        let rules: Vec<(&DeviceRule, bool, bool)> = self
+            .output_devices
+            .iter()
+            .map(|rule| (rule, true, false))
+            .chain(self.input_devices.iter().map(|rule| (rule, false, true)))
+            .chain(self.devices.iter().map(|rule| {
+                (
+                    rule,
+                    rule.applies_to.includes_output(),
+                    rule.applies_to.includes_input(),
+                )
+            }))
+            .filter(|(rule, _, _)| rule.enabled)
+            .collect();
+
+        let mut conflicts = Vec::new();
+        for (i, &(first, first_output, first_input)) in rules.iter().enumerate() {
+            for &(second, second_output, second_input) in &rules[i + 1..] {
+                let shares_direction =
+                    (first_output && second_output) || (first_input && second_input);
+                if !shares_direction || !may_match_same_device(first, second) {
+                    continue;
+                }
+
+                if let Some(conflict) = same_weight_conflict(first, second) {
+                    conflicts.push(conflict);
+                }
+                if let Some(conflict) = subsumed_conflict(first, second) {
+                    conflicts.push(conflict);
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// False when the rules' filters keep them apart: one matches UIDs and the
+/// other names, or they require different transports or UIDs
+fn may_match_same_device(first: &DeviceRule, second: &DeviceRule) -> bool {
+    let differ = |a: Option<&str>, b: Option<&str>| matches!((a, b), (Some(a), Some(b)) if a != b);
+
+    first.uid.is_some() == second.uid.is_some()
+        && !matches!(
+            (first.transport_type, second.transport_type),
+            (Some(a), Some(b)) if a != b
+        )
+        && !differ(first.require_uid.as_deref(), second.require_uid.as_deref())
+}
+
+fn same_weight_conflict(first: &DeviceRule, second: &DeviceRule) -> Option<RuleConflict> {
+    // A formula's weight changes over time
+    if first.weight != second.weight
+        || first.weight_formula.is_some()
+        || second.weight_formula.is_some()
+    {
+        return None;
+    }
+
+    let example = example_names(first, second)
+        .into_iter()
+        .find(|name| first.matches(name) && second.matches(name))?;
+
+    Some(RuleConflict {
+        rule: first.clone(),
+        other: second.clone(),
+        kind: ConflictKind::SameWeight,
+        example,
+    })
+}
+
+/// Report the `contains` rule with the longer text as subsumed by the other.
+/// Identical texts are reported once, the later rule subsumed by the earlier.
+fn subsumed_conflict(first: &DeviceRule, second: &DeviceRule) -> Option<RuleConflict> {
+    if !matches!(first.match_type, MatchType::Contains)
+        || !matches!(second.match_type, MatchType::Contains)
+    {
+        return None;
+    }
+
+    let (narrower, broader) = if subsumes(first, second) {
+        (second, first)
+    } else if subsumes(second, first) {
+        (first, second)
+    } else {
+        return None;
+    };
+
+    Some(RuleConflict {
+        rule: narrower.clone(),
+        other: broader.clone(),
+        kind: ConflictKind::SubsumedBy,
+        example: pattern(narrower).to_string(),
+    })
+}
+
+/// Whether every identifier `narrower` matches also contains `broader`'s text.
+/// A case-sensitive rule does not cover one that ignores case.
+fn subsumes(broader: &DeviceRule, narrower: &DeviceRule) -> bool {
+    broader.matches(pattern(narrower)) && (!broader.case_sensitive || narrower.case_sensitive)
+}
+
+/// Names that combine the two rules' patterns, simplest first
+fn example_names(first: &DeviceRule, second: &DeviceRule) -> Vec<String> {
+    let literal = |rule: &DeviceRule| {
+        (!matches!(rule.match_type, MatchType::Regex)).then(|| pattern(rule).to_string())
+    };
+
+    match (literal(first), literal(second)) {
+        (Some(a), Some(b)) => vec![
+            a.clone(),
+            b.clone(),
+            format!("{a} {b}"),
+            format!("{b} {a}"),
+            format!("{a}{b}"),
+            format!("{b}{a}"),
+        ],
+        (Some(a), None) | (None, Some(a)) => vec![a],
+        (None, None) => Vec::new(),
+    }
+}
+
+/// The text a rule matches identifiers against
+fn pattern(rule: &DeviceRule) -> &str {
+    rule.uid.as_deref().unwrap_or(&rule.name)
+}
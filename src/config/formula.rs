@@ -0,0 +1,305 @@
+//! Weight formulas for device rules
+//!
+//! A rule's `weight_formula` is a small arithmetic expression over integers and
+//! two variables, evaluated whenever the rule's weight is needed:
+//!
+//! - `base`: the weight of the first rule without a formula, in the same
+//!   direction, that matches the same device (0 if there is none)
+//! - `time_bonus`: `TIME_BONUS_WEIGHT` during business hours, 0 otherwise
+//!
+//! Supported operators are `+`, `-`, `*`, unary minus and parentheses.
+
+use anyhow::Result;
+
+use super::TimeOfDay;
+
+/// Weight `time_bonus` adds during business hours
+pub const TIME_BONUS_WEIGHT: i64 = 50;
+
+/// Start of business hours, inclusive
+const BUSINESS_HOURS_START: (u8, u8) = (9, 0);
+
+/// End of business hours, exclusive
+const BUSINESS_HOURS_END: (u8, u8) = (17, 0);
+
+/// Values the variables in a formula evaluate to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormulaContext {
+    pub base: i64,
+    pub time_bonus: i64,
+}
+
+impl FormulaContext {
+    /// Context for a rule whose base weight is `base`, at local time `now`
+    pub fn at(base: i64, now: TimeOfDay) -> Self {
+        let start = TimeOfDay::new(BUSINESS_HOURS_START.0, BUSINESS_HOURS_START.1)
+            .expect("business hours start is a valid time");
+        let end = TimeOfDay::new(BUSINESS_HOURS_END.0, BUSINESS_HOURS_END.1)
+            .expect("business hours end is a valid time");
+
+        Self {
+            base,
+            time_bonus: if now.is_within(start, end) {
+                TIME_BONUS_WEIGHT
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// A parsed weight formula
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WeightFormula {
+    Number(i64),
+    Base,
+    TimeBonus,
+    Negate(Box<WeightFormula>),
+    Add(Box<WeightFormula>, Box<WeightFormula>),
+    Subtract(Box<WeightFormula>, Box<WeightFormula>),
+    Multiply(Box<WeightFormula>, Box<WeightFormula>),
+}
+
+impl WeightFormula {
+    /// Parse a formula such as `"base + 50"`, rejecting unknown variables
+    pub fn parse(formula: &str) -> Result<Self> {
+        let tokens = tokenize(formula)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+
+        let parsed = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow::anyhow!(
+                "Unexpected '{token}' in weight formula '{formula}'"
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Evaluate the formula, saturating instead of overflowing
+    pub fn evaluate(&self, context: &FormulaContext) -> i64 {
+        match self {
+            WeightFormula::Number(value) => *value,
+            WeightFormula::Base => context.base,
+            WeightFormula::TimeBonus => context.time_bonus,
+            WeightFormula::Negate(inner) => inner.evaluate(context).saturating_neg(),
+            WeightFormula::Add(left, right) => left
+                .evaluate(context)
+                .saturating_add(right.evaluate(context)),
+            WeightFormula::Subtract(left, right) => left
+                .evaluate(context)
+                .saturating_sub(right.evaluate(context)),
+            WeightFormula::Multiply(left, right) => left
+                .evaluate(context)
+                .saturating_mul(right.evaluate(context)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    OpenParen,
+    CloseParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{value}"),
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = formula.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '(' | ')' => {
+                chars.next();
+                tokens.push(match c {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' => Token::Star,
+                    '(' => Token::OpenParen,
+                    _ => Token::CloseParen,
+                });
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                let value = digits.parse().map_err(|_| {
+                    anyhow::anyhow!("Number {digits} is too large in weight formula '{formula}'")
+                })?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&d) = chars
+                    .peek()
+                    .filter(|d| d.is_ascii_alphanumeric() || **d == '_')
+                {
+                    name.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Identifier(name));
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unexpected character '{other}' in weight formula '{formula}'"
+                ));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser; `*` binds tighter than `+` and `-`
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<WeightFormula> {
+        let mut left = self.term()?;
+        loop {
+            left = match self.peek() {
+                Some(Token::Plus) => {
+                    self.next();
+                    WeightFormula::Add(Box::new(left), Box::new(self.term()?))
+                }
+                Some(Token::Minus) => {
+                    self.next();
+                    WeightFormula::Subtract(Box::new(left), Box::new(self.term()?))
+                }
+                _ => return Ok(left),
+            };
+        }
+    }
+
+    fn term(&mut self) -> Result<WeightFormula> {
+        let mut left = self.factor()?;
+        while self.peek() == Some(&Token::Star) {
+            self.next();
+            left = WeightFormula::Multiply(Box::new(left), Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<WeightFormula> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(WeightFormula::Number(value)),
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "base" => Ok(WeightFormula::Base),
+                "time_bonus" => Ok(WeightFormula::TimeBonus),
+                _ => Err(anyhow::anyhow!(
+                    "Unknown variable '{name}' in weight formula (expected base or time_bonus)"
+                )),
+            },
+            Some(Token::Minus) => Ok(WeightFormula::Negate(Box::new(self.factor()?))),
+            Some(Token::OpenParen) => {
+                let inner = self.expression()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(inner),
+                    _ => Err(anyhow::anyhow!("Missing ')' in weight formula")),
+                }
+            }
+            Some(token) => Err(anyhow::anyhow!("Unexpected '{token}' in weight formula")),
+            None => Err(anyhow::anyhow!("Weight formula ended unexpectedly")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(formula: &str, base: i64, time_bonus: i64) -> i64 {
+        WeightFormula::parse(formula)
+            .unwrap()
+            .evaluate(&FormulaContext { base, time_bonus })
+    }
+
+    #[test]
+    fn test_base_plus_constant() {
+        assert_eq!(evaluate("base + 50", 40, 0), 90);
+        assert_eq!(evaluate("base-10", 40, 0), 30);
+    }
+
+    #[test]
+    fn test_precedence_and_parentheses() {
+        assert_eq!(evaluate("base + 2 * 10", 5, 0), 25);
+        assert_eq!(evaluate("(base + 2) * 10", 5, 0), 70);
+        assert_eq!(evaluate("-base + 100", 30, 0), 70);
+    }
+
+    #[test]
+    fn test_time_bonus_variable() {
+        assert_eq!(evaluate("time_bonus", 0, 50), 50);
+        assert_eq!(evaluate("base + time_bonus", 100, 0), 100);
+    }
+
+    #[test]
+    fn test_time_bonus_applies_during_business_hours() {
+        let at = |hour, minute| FormulaContext::at(10, TimeOfDay::new(hour, minute).unwrap());
+
+        assert_eq!(at(9, 0).time_bonus, TIME_BONUS_WEIGHT);
+        assert_eq!(at(16, 59).time_bonus, TIME_BONUS_WEIGHT);
+        assert_eq!(at(17, 0).time_bonus, 0);
+        assert_eq!(at(8, 59).time_bonus, 0);
+        assert_eq!(at(12, 0).base, 10);
+    }
+
+    #[test]
+    fn test_overflow_saturates() {
+        assert_eq!(evaluate("base * 2", i64::MAX, 0), i64::MAX);
+    }
+
+    #[test]
+    fn test_unknown_variable_is_rejected() {
+        let error = WeightFormula::parse("speakers + 50").unwrap_err();
+        assert!(error.to_string().contains("Unknown variable 'speakers'"));
+    }
+
+    #[test]
+    fn test_malformed_formulas_are_rejected() {
+        for formula in ["", "base +", "(base + 1", "base 50", "base / 2"] {
+            assert!(
+                WeightFormula::parse(formula).is_err(),
+                "'{formula}' should not parse"
+            );
+        }
+    }
+}
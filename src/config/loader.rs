@@ -1,15 +1,50 @@
 use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Value};
 use tracing::{debug, info, warn};
 
-use crate::system::FileSystemInterface;
+use crate::system::{FileSystemInterface, FileWatch};
 
-use super::types::Config;
+use super::conflicts::RuleConflict;
+use super::types::{Config, DeviceRule};
+
+/// Location of the config file relative to the user's config directory
+const CONFIG_FILE_RELATIVE_PATH: &str = "audio-device-monitor/config.toml";
+
+/// Config directory relative to the home directory used by versions before the
+/// platform config directory
+const LEGACY_CONFIG_DIR: &str = ".config";
+
+/// A rule added to or removed from the config file by the `add-rule` and
+/// `remove-rule` commands
+#[derive(Debug, Clone)]
+pub struct RuleEdit {
+    pub rule: DeviceRule,
+    /// Whether the rule is in `input_devices` rather than `output_devices`
+    pub input: bool,
+    /// Conflicts `Config::validate_rules` reports after the edit but not before
+    pub new_conflicts: Vec<RuleConflict>,
+}
+
+impl RuleEdit {
+    /// The rule as it appears in the config file, under its `[[output_devices]]`
+    /// or `[[input_devices]]` header
+    pub fn to_toml(&self) -> Result<String> {
+        let section = rules_section(self.input);
+        // Not a `toml::Table`, which would sort the rule's keys
+        let document = HashMap::from([(section, [&self.rule])]);
+        toml::to_string_pretty(&document).context("Failed to serialize rule")
+    }
+}
 
 /// Configuration loader that uses dependency injection for file system operations
 pub struct ConfigLoader<F: FileSystemInterface> {
     file_system: F,
     config_path: PathBuf,
+    /// Write events for the config file; `None` means changes are detected by polling
+    watch: Option<FileWatch>,
 }
 
 impl<F: FileSystemInterface> ConfigLoader<F> {
@@ -17,9 +52,37 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
         Self {
             file_system,
             config_path,
+            watch: None,
         }
     }
 
+    /// Watch the configuration file for changes instead of polling its modification time
+    pub fn watch_config(&mut self) -> Result<()> {
+        let watch = self
+            .file_system
+            .watch_config_path(&self.config_path)
+            .with_context(|| {
+                format!(
+                    "Failed to watch configuration file: {}",
+                    self.config_path.display()
+                )
+            })?;
+
+        self.watch = Some(watch);
+        Ok(())
+    }
+
+    /// Whether config changes are detected by a file watch rather than polling
+    pub fn is_watching(&self) -> bool {
+        self.watch.is_some()
+    }
+
+    /// Whether the watch saw the config file change since the last call.
+    /// Never touches the file system; always false when not watching.
+    pub fn watched_change(&self) -> bool {
+        self.watch.as_ref().is_some_and(FileWatch::changed)
+    }
+
     /// Load configuration from the configured path
     pub fn load_config(&self) -> Result<Config> {
         debug!("Loading configuration from: {}", self.config_path.display());
@@ -39,16 +102,13 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
                 )
             })?;
 
-        let mut config: Config = toml::from_str(&config_content).with_context(|| {
+        let config = Config::from_str(&config_content).with_context(|| {
             format!(
                 "Failed to parse configuration file: {}",
                 self.config_path.display()
             )
         })?;
 
-        // Handle backward compatibility for notification config
-        config.notifications = config.notifications.migrate_from_old_config();
-
         debug!("Configuration loaded successfully");
         Ok(config)
     }
@@ -57,6 +117,16 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
     pub fn save_config(&self, config: &Config) -> Result<()> {
         debug!("Saving configuration to: {}", self.config_path.display());
 
+        let config_content =
+            toml::to_string_pretty(config).context("Failed to serialize configuration")?;
+        self.write_config_content(&config_content)?;
+
+        info!("Configuration saved to: {}", self.config_path.display());
+        Ok(())
+    }
+
+    /// Write `content` to the configured path, creating its directory if needed
+    fn write_config_content(&self, content: &str) -> Result<()> {
         // Create parent directories if they don't exist
         if let Some(parent) = self.config_path.parent() {
             self.file_system
@@ -66,20 +136,145 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
                 })?;
         }
 
-        let config_content =
-            toml::to_string_pretty(config).context("Failed to serialize configuration")?;
-
         self.file_system
-            .write_config_file(&self.config_path, &config_content)
+            .write_config_file(&self.config_path, content)
             .with_context(|| {
                 format!(
                     "Failed to write configuration file: {}",
                     self.config_path.display()
                 )
+            })
+    }
+
+    /// Append `rule` to the output rules, or the input rules, and save the
+    /// configuration unless `dry_run`. Only the rule list changes in the file;
+    /// comments and formatting elsewhere are kept.
+    pub fn add_rule(&self, rule: DeviceRule, input: bool, dry_run: bool) -> Result<RuleEdit> {
+        let config = self.load_config_for_edit()?;
+        let mut document = self.load_document_for_edit()?;
+
+        let table = toml::to_string(&rule)
+            .context("Failed to serialize rule")?
+            .parse::<DocumentMut>()
+            .context("Failed to serialize rule")?
+            .as_table()
+            .clone();
+        let section = rules_section(input);
+        match document
+            .entry(section)
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+        {
+            Item::ArrayOfTables(rules) => rules.push(table),
+            Item::Value(Value::Array(rules)) => rules.push(table.into_inline_table()),
+            _ => return Err(self.not_a_rule_list(section)),
+        }
+
+        self.finish_rule_edit(&config, document, rule, input, dry_run)
+    }
+
+    /// Remove the first output rule, or input rule, named exactly `name` and
+    /// save the configuration unless `dry_run`, keeping the file's comments and
+    /// formatting as `add_rule` does
+    pub fn remove_rule(&self, name: &str, input: bool, dry_run: bool) -> Result<RuleEdit> {
+        let config = self.load_config_for_edit()?;
+        let mut document = self.load_document_for_edit()?;
+
+        let index = rules(&config, input)
+            .iter()
+            .position(|rule| rule.name == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No {} rule named '{}' in {}",
+                    if input { "input" } else { "output" },
+                    name,
+                    self.config_path.display()
+                )
             })?;
+        let rule = rules(&config, input)[index].clone();
+
+        // The list in the file holds exactly the rules parsed into the config
+        let section = rules_section(input);
+        match document.get_mut(section) {
+            Some(Item::ArrayOfTables(rules)) => rules.remove(index),
+            Some(Item::Value(Value::Array(rules))) => {
+                rules.remove(index);
+            }
+            _ => return Err(self.not_a_rule_list(section)),
+        }
 
-        info!("Configuration saved to: {}", self.config_path.display());
-        Ok(())
+        self.finish_rule_edit(&config, document, rule, input, dry_run)
+    }
+
+    /// The configuration to edit. Unlike `load_config`, a missing file is not
+    /// written, so a dry run never touches the file system.
+    fn load_config_for_edit(&self) -> Result<Config> {
+        if self.file_system.config_file_exists(&self.config_path) {
+            self.load_config()
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// The config file as an editable document, or the default configuration
+    /// when there is no file yet
+    fn load_document_for_edit(&self) -> Result<DocumentMut> {
+        let content = if self.file_system.config_file_exists(&self.config_path) {
+            self.file_system.read_config_file(&self.config_path)?
+        } else {
+            toml::to_string_pretty(&Config::default())
+                .context("Failed to serialize configuration")?
+        };
+
+        content.parse().with_context(|| {
+            format!(
+                "Failed to parse configuration file: {}",
+                self.config_path.display()
+            )
+        })
+    }
+
+    fn not_a_rule_list(&self, section: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "'{}' in {} is not a list of rules",
+            section,
+            self.config_path.display()
+        )
+    }
+
+    fn finish_rule_edit(
+        &self,
+        config_before: &Config,
+        document: DocumentMut,
+        rule: DeviceRule,
+        input: bool,
+        dry_run: bool,
+    ) -> Result<RuleEdit> {
+        // Parsed back so that a rule the daemon would refuse to load, such as
+        // an invalid regex, is never saved
+        let content = document.to_string();
+        let config = Config::from_str(&content).with_context(|| {
+            format!("Rule '{}' would make the configuration invalid", rule.name)
+        })?;
+
+        let conflicts_before = conflict_descriptions(config_before);
+        let new_conflicts = config
+            .validate_rules()
+            .into_iter()
+            .filter(|conflict| !conflicts_before.contains(&conflict.to_string()))
+            .collect();
+
+        if dry_run {
+            debug!("Dry run, not saving configuration");
+        } else {
+            self.write_config_content(&content)?;
+            info!("Configuration saved to: {}", self.config_path.display());
+        }
+
+        Ok(RuleEdit {
+            rule,
+            input,
+            new_conflicts,
+        })
     }
 
     /// Reload configuration from file (useful for config hot reloading)
@@ -91,7 +286,14 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
     }
 
     /// Check if configuration file has been modified since last load
+    ///
+    /// When watching, this reports (and clears) a change seen by the watch and
+    /// ignores `last_modified`; otherwise it compares modification times.
     pub fn is_config_modified(&self, last_modified: std::time::SystemTime) -> Result<bool> {
+        if self.is_watching() {
+            return Ok(self.watched_change());
+        }
+
         if !self.file_system.config_file_exists(&self.config_path) {
             return Ok(false);
         }
@@ -102,6 +304,11 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
         Ok(current_modified > last_modified)
     }
 
+    /// Get the last modified time of the configuration file
+    pub fn config_modified_time(&self) -> Result<std::time::SystemTime> {
+        self.file_system.get_config_modified_time(&self.config_path)
+    }
+
     /// Get the configuration file path
     pub fn get_config_path(&self) -> &Path {
         &self.config_path
@@ -156,6 +363,31 @@ impl<F: FileSystemInterface> ConfigLoader<F> {
     }
 }
 
+fn rules(config: &Config, input: bool) -> &[DeviceRule] {
+    if input {
+        &config.input_devices
+    } else {
+        &config.output_devices
+    }
+}
+
+/// The config file key holding the output rules, or the input rules
+fn rules_section(input: bool) -> &'static str {
+    if input {
+        "input_devices"
+    } else {
+        "output_devices"
+    }
+}
+
+fn conflict_descriptions(config: &Config) -> HashSet<String> {
+    config
+        .validate_rules()
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
 // Convenience constructor for production use with StandardFileSystem
 impl ConfigLoader<crate::system::StandardFileSystem> {
     // Called at runtime by production code that needs to create config loader with real file system
@@ -173,9 +405,51 @@ impl ConfigLoader<crate::system::StandardFileSystem> {
     }
 
     /// Get the default configuration path
+    ///
+    /// Uses `$XDG_CONFIG_HOME/audio-device-monitor/config.toml` when `XDG_CONFIG_HOME` is set
+    /// to an absolute path, otherwise `audio-device-monitor/config.toml` in the platform's
+    /// config directory (`~/Library/Application Support` on macOS). A config file that
+    /// only exists at the old `~/.config` location is used from there, with a warning.
     pub fn default_config_path() -> Result<PathBuf> {
-        let home_dir = dirs::home_dir().context("Failed to get home directory")?;
-        Ok(home_dir.join(".config/audio-device-monitor/config.toml"))
+        // The XDG spec says empty or relative values must be ignored
+        if let Some(xdg_config_home) = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+        {
+            debug!(
+                "Using XDG_CONFIG_HOME for configuration: {}",
+                xdg_config_home.display()
+            );
+            return Ok(xdg_config_home.join(CONFIG_FILE_RELATIVE_PATH));
+        }
+
+        let config_dir = dirs::config_dir().context("Failed to get config directory")?;
+        let legacy_path = dirs::home_dir().map(|home| home.join(LEGACY_CONFIG_DIR));
+        Ok(with_legacy_fallback(
+            config_dir.join(CONFIG_FILE_RELATIVE_PATH),
+            legacy_path.map(|dir| dir.join(CONFIG_FILE_RELATIVE_PATH)),
+            |path| path.exists(),
+        ))
+    }
+}
+
+/// `config_path`, unless the config file only exists at `legacy_path`, where
+/// older versions kept it
+fn with_legacy_fallback(
+    config_path: PathBuf,
+    legacy_path: Option<PathBuf>,
+    exists: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    match legacy_path {
+        Some(legacy_path) if !exists(&config_path) && exists(&legacy_path) => {
+            warn!(
+                "Using the config file at its old location {}. Move it to {} to silence this warning",
+                legacy_path.display(),
+                config_path.display()
+            );
+            legacy_path
+        }
+        _ => config_path,
     }
 }
 
@@ -281,4 +555,120 @@ show_switching_actions = true
         mock_fs.add_file(&config_path, "test content".to_string());
         assert!(loader.config_exists());
     }
+
+    #[test]
+    fn test_config_modified_follows_modification_time() {
+        let mock_fs = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        mock_fs.add_file(&config_path, "test content".to_string());
+        let loader = ConfigLoader::new(mock_fs.clone(), config_path.clone());
+
+        let loaded_at = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100);
+        mock_fs.set_config_modified_time(&config_path, loaded_at);
+        assert!(!loader.is_config_modified(loaded_at).unwrap());
+
+        mock_fs
+            .set_config_modified_time(&config_path, loaded_at + std::time::Duration::from_secs(1));
+        assert!(loader.is_config_modified(loaded_at).unwrap());
+    }
+
+    #[test]
+    fn test_watch_config_registers_watch_for_config_path() {
+        let mock_fs = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        let mut loader = ConfigLoader::new(mock_fs.clone(), config_path.clone());
+
+        loader.watch_config().unwrap();
+
+        assert!(loader.is_watching());
+        assert_eq!(mock_fs.get_watch_calls(), vec![config_path]);
+    }
+
+    #[test]
+    fn test_watched_config_reports_each_change_once() {
+        let mock_fs = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        mock_fs.add_file(&config_path, "test content".to_string());
+        let mut loader = ConfigLoader::new(mock_fs.clone(), config_path.clone());
+        loader.watch_config().unwrap();
+
+        // Modification times are ignored while watching
+        let long_ago = std::time::SystemTime::UNIX_EPOCH;
+        assert!(!loader.is_config_modified(long_ago).unwrap());
+
+        mock_fs.trigger_file_change(&config_path);
+        assert!(loader.is_config_modified(long_ago).unwrap());
+        assert!(!loader.is_config_modified(long_ago).unwrap());
+    }
+
+    #[test]
+    fn test_watched_config_does_not_read_modification_time() {
+        let mock_fs = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        mock_fs.add_file(&config_path, "test content".to_string());
+        let mut loader = ConfigLoader::new(mock_fs.clone(), config_path.clone());
+        loader.watch_config().unwrap();
+
+        assert!(!loader.watched_change());
+        mock_fs.trigger_config_change();
+        mock_fs.trigger_config_change();
+        assert!(loader.watched_change());
+        assert!(!loader.watched_change());
+
+        assert!(mock_fs.get_modified_time_calls().is_empty());
+    }
+
+    #[test]
+    fn test_watched_change_is_false_when_not_watching() {
+        let mock_fs = MockFileSystem::new();
+        let loader = ConfigLoader::new(mock_fs.clone(), PathBuf::from("/test/config.toml"));
+
+        mock_fs.trigger_config_change();
+
+        assert!(!loader.watched_change());
+    }
+
+    #[test]
+    fn test_failed_watch_falls_back_to_polling() {
+        let mock_fs = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        mock_fs.add_file(&config_path, "test content".to_string());
+        mock_fs.set_watch_failure(true);
+        let mut loader = ConfigLoader::new(mock_fs, config_path);
+
+        assert!(loader.watch_config().is_err());
+        assert!(!loader.is_watching());
+        assert!(
+            loader
+                .is_config_modified(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_legacy_config_used_when_only_it_exists() {
+        let config_path = PathBuf::from("/support/config.toml");
+        let legacy_path = PathBuf::from("/home/.config/config.toml");
+
+        let only_legacy = |path: &Path| path == legacy_path;
+        let both = |_: &Path| true;
+        let neither = |_: &Path| false;
+
+        assert_eq!(
+            with_legacy_fallback(config_path.clone(), Some(legacy_path.clone()), only_legacy),
+            legacy_path
+        );
+        assert_eq!(
+            with_legacy_fallback(config_path.clone(), Some(legacy_path.clone()), both),
+            config_path
+        );
+        assert_eq!(
+            with_legacy_fallback(config_path.clone(), Some(legacy_path), neither),
+            config_path
+        );
+        assert_eq!(
+            with_legacy_fallback(config_path.clone(), None, neither),
+            config_path
+        );
+    }
 }
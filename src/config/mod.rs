@@ -1,5 +1,9 @@
+pub mod conflicts;
+#[cfg(feature = "weight-formulas")]
+pub mod formula;
 pub mod loader;
 pub mod types;
 
-pub use loader::ConfigLoader;
+pub use conflicts::ConflictKind;
+pub use loader::{ConfigLoader, RuleEdit};
 pub use types::*;
@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tracing::{debug, info, warn};
 
+use crate::audio::{AudioDevice, TransportType};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -17,6 +24,25 @@ pub struct Config {
 
     #[serde(default)]
     pub input_devices: Vec<DeviceRule>,
+
+    /// Rules for devices that serve both roles, scoped per rule with `applies_to`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<DeviceRule>,
+
+    /// Named rule sets selected with `--profile`, e.g. `[profiles.office]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ConfigOverride>,
+}
+
+/// Rules a profile uses instead of the base configuration's. A section the
+/// profile sets replaces the base section completely; one it leaves out is
+/// kept. `devices` rules always come from the base configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_devices: Option<Vec<DeviceRule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_devices: Option<Vec<DeviceRule>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,12 +52,90 @@ pub struct GeneralConfig {
     pub poll_interval_ms: u64,
     pub log_level: String,
     pub daemon_mode: bool,
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Time a new device must be present before it is considered for switching
+    #[serde(default = "default_device_change_debounce_ms")]
+    pub device_change_debounce_ms: u64,
+    /// Longer settle time for Bluetooth devices, whose input and output may appear separately
+    #[serde(default = "default_bluetooth_device_change_debounce_ms")]
+    pub bluetooth_device_change_debounce_ms: u64,
+    /// Move macOS system alert sounds to the new output device after each output switch
+    #[serde(default)]
+    pub sync_system_alerts: bool,
+    /// Favor USB devices while running on battery, e.g. wired headphones over AirPods
+    #[serde(default)]
+    pub prefer_wired_on_battery: bool,
+    /// Weight lead a device needs over the current one before switching to it
+    #[serde(default)]
+    pub min_weight_to_switch: u32,
+    /// How long shutdown waits for notifications still being delivered
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+    /// Remember each output device's volume when switching away from it and
+    /// restore it when switching back
+    #[serde(default)]
+    pub restore_volume: bool,
+    /// How long a rule's `pre_switch_command` may run before the switch is abandoned
+    #[serde(default = "default_hook_timeout_ms")]
+    pub hook_timeout_ms: u64,
+    /// Switch events kept in the history file before it is rotated; 0 keeps no history
+    #[serde(default = "default_history_max_lines")]
+    pub history_max_lines: usize,
+    /// How many more times a refused default device change is attempted
+    #[serde(default = "default_switch_retry_count")]
+    pub switch_retry_count: u32,
+    /// Pause before each retry of a refused default device change
+    #[serde(default = "default_switch_retry_delay_ms")]
+    pub switch_retry_delay_ms: u64,
 }
 
 fn default_poll_interval_ms() -> u64 {
     10_000 // 10 seconds
 }
 
+fn default_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000 // 30 seconds
+}
+
+fn default_device_change_debounce_ms() -> u64 {
+    750
+}
+
+fn default_bluetooth_device_change_debounce_ms() -> u64 {
+    1500
+}
+
+fn default_shutdown_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_hook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_history_max_lines() -> usize {
+    1000
+}
+
+fn default_switch_retry_count() -> u32 {
+    3
+}
+
+fn default_switch_retry_delay_ms() -> u64 {
+    250
+}
+
+fn default_true() -> bool {
+    true
+}
+
 // Helper struct for deserialization that preserves field presence information
 #[derive(Debug, Clone, Deserialize)]
 struct NotificationConfigHelper {
@@ -41,6 +145,10 @@ struct NotificationConfigHelper {
     show_switching_actions: bool,
     #[serde(alias = "show_device_changes")]
     show_device_changes: Option<bool>,
+    #[serde(default)]
+    quiet_hours_start: Option<TimeOfDay>,
+    #[serde(default)]
+    quiet_hours_end: Option<TimeOfDay>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +160,13 @@ pub struct NotificationConfig {
     // Keep old field for backward compatibility
     #[serde(skip)]
     pub show_device_changes: Option<bool>,
+
+    /// Start of the daily window in which notifications are suppressed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_hours_start: Option<TimeOfDay>,
+    /// End of the quiet window; may be earlier than the start to span midnight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_hours_end: Option<TimeOfDay>,
 }
 
 fn default_show_switching_actions() -> bool {
@@ -65,6 +180,8 @@ impl From<NotificationConfigHelper> for NotificationConfig {
             show_device_availability: helper.show_device_availability.unwrap_or(false),
             show_switching_actions: helper.show_switching_actions,
             show_device_changes: helper.show_device_changes,
+            quiet_hours_start: helper.quiet_hours_start,
+            quiet_hours_end: helper.quiet_hours_end,
         };
 
         // Apply migration logic with presence information
@@ -89,6 +206,11 @@ impl NotificationConfig {
         self
     }
 
+    /// The quiet window as `(start, end)`, if both ends are configured
+    pub fn quiet_hours(&self) -> Option<(TimeOfDay, TimeOfDay)> {
+        self.quiet_hours_start.zip(self.quiet_hours_end)
+    }
+
     /// Internal method used during deserialization to handle migration
     fn migrate_with_presence_info(mut self, was_explicitly_set: bool) -> Self {
         if let Some(old_value) = self.show_device_changes {
@@ -103,22 +225,296 @@ impl NotificationConfig {
     }
 }
 
+/// Wall-clock time of day with minute precision, written as `"HH:MM"` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    pub fn new(hour: u8, minute: u8) -> Result<Self> {
+        if hour > 23 || minute > 59 {
+            return Err(anyhow::anyhow!(
+                "Invalid time of day: {hour:02}:{minute:02}"
+            ));
+        }
+        Ok(Self { hour, minute })
+    }
+
+    /// Current time of day in the system's local time zone
+    pub fn now_local() -> Self {
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        let converted = unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            !libc::localtime_r(&now, &mut tm).is_null()
+        };
+        if !converted {
+            warn!("Failed to read local time, assuming midnight");
+        }
+
+        Self {
+            hour: tm.tm_hour as u8,
+            minute: tm.tm_min as u8,
+        }
+    }
+
+    /// Whether this time falls in the window from `start` (inclusive) to `end`
+    /// (exclusive). The window wraps past midnight when `end` is before `start`.
+    pub fn is_within(self, start: TimeOfDay, end: TimeOfDay) -> bool {
+        if start <= end {
+            start <= self && self < end
+        } else {
+            self >= start || self < end
+        }
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (hour, minute) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Expected a time as HH:MM, got '{s}'"))?;
+        let hour = hour
+            .parse()
+            .with_context(|| format!("Invalid hour in '{s}'"))?;
+        let minute = minute
+            .parse()
+            .with_context(|| format!("Invalid minute in '{s}'"))?;
+        Self::new(hour, minute)
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceRule {
     pub name: String,
-    pub weight: u32,
+    /// Priority weight; negative weights blacklist the matched device
+    pub weight: i64,
     pub match_type: MatchType,
     pub enabled: bool,
+    /// Claim matched devices for this direction only: other rules in the same
+    /// direction are ignored for them and the other direction never selects them
+    #[serde(default)]
+    pub exclusive: bool,
+    /// Keep a device this rule selected until it disconnects, even if a higher-weight device appears
+    #[serde(default)]
+    pub sticky: bool,
+    /// Reduce a matched device's weight for this long after it disconnects, so a
+    /// device that power-cycles does not immediately take over again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_decay_after_disconnect_ms: Option<u64>,
+    /// Multiplier applied to the weight during the decay window (default 0.5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_decay_factor: Option<f32>,
+    /// I/O buffer size in frames to apply after switching to a matched device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_size_frames: Option<u32>,
+    /// Output volume (0.0-1.0) to set after switching to a matched device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_select_volume: Option<f32>,
+    /// Human-readable label for the rule shown in CLI output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Internal note about the rule; never shown in CLI output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Only match devices with at least this many channels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_channels: Option<u32>,
+    /// Only match devices with at most this many channels, e.g. to keep large mixers out of casual use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_channels: Option<u32>,
+    /// Directions a rule in the unified `devices` list applies to. Ignored in
+    /// `output_devices` and `input_devices`, where the list sets the direction.
+    #[serde(default, skip_serializing_if = "DeviceTypeFilter::is_both")]
+    pub applies_to: DeviceTypeFilter,
+    /// Only apply this rule while a device whose name contains this text is
+    /// also connected, e.g. speakers that should follow their display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_other_device: Option<String>,
+    /// Only match the device with this CoreAudio UID, for telling apart devices
+    /// that share a name. Shown by the `device-info` command. Cannot be
+    /// combined with `uid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_uid: Option<String>,
+    /// Expression that replaces `weight`, e.g. "base + 50" (requires the
+    /// `weight-formulas` feature; see `config::formula`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_formula: Option<String>,
+    /// Compare `name` with device names case-sensitively; when false both are
+    /// lowercased first, for names whose capitalization varies between macOS
+    /// versions or firmware
+    #[serde(default = "default_true")]
+    pub case_sensitive: bool,
+    /// Only match devices attached this way, e.g. "usb" to keep a Bluetooth
+    /// device with the same name out. Devices whose transport is unknown pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport_type: Option<TransportType>,
+    /// Match this pattern against the device's CoreAudio UID instead of matching
+    /// `name` against its display name. UIDs survive renames and macOS updates.
+    /// Takes precedence over `name`, which then only labels the rule. Cannot
+    /// be combined with `require_uid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// How long a matching device must stay connected before it can be
+    /// switched to, overriding `device_change_debounce_ms` and
+    /// `bluetooth_device_change_debounce_ms` for this device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability_ms: Option<u64>,
+    /// Shell command run before switching to a matching device. The switch
+    /// waits for it and is abandoned if it fails or outlasts `hook_timeout_ms`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_switch_command: Option<String>,
+    /// Shell command started after switching to a matching device; the switch
+    /// does not wait for it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_switch_command: Option<String>,
+    /// `name` compiled as a regular expression for `MatchType::Regex` rules.
+    /// Filled in when the configuration is parsed; rules built in code without
+    /// it compile the pattern on each match.
+    #[serde(skip)]
+    pub compiled_regex: Option<Regex>,
+    /// `weight_formula` parsed, filled in when the configuration is parsed like
+    /// `compiled_regex`; rules built in code without it parse the formula on each use
+    #[cfg(feature = "weight-formulas")]
+    #[serde(skip)]
+    pub compiled_formula: Option<super::formula::WeightFormula>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceTypeFilter {
+    OutputOnly,
+    InputOnly,
+    #[default]
+    Both,
+}
+
+impl DeviceTypeFilter {
+    pub fn includes_output(self) -> bool {
+        matches!(self, DeviceTypeFilter::OutputOnly | DeviceTypeFilter::Both)
+    }
+
+    pub fn includes_input(self) -> bool {
+        matches!(self, DeviceTypeFilter::InputOnly | DeviceTypeFilter::Both)
+    }
+
+    fn is_both(&self) -> bool {
+        *self == DeviceTypeFilter::Both
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum MatchType {
     Exact,
     Contains,
+    #[serde(alias = "startswith")]
     StartsWith,
+    #[serde(alias = "endswith")]
     EndsWith,
     Regex,
+    /// Inverted forms select every device the positive form would not
+    NotExact,
+    NotContains,
+    #[serde(alias = "not_startswith")]
+    NotStartsWith,
+    #[serde(alias = "not_endswith")]
+    NotEndsWith,
+}
+
+impl MatchType {
+    /// The name used for this match type in the config file, e.g. "starts_with"
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            MatchType::Exact => "exact",
+            MatchType::Contains => "contains",
+            MatchType::StartsWith => "starts_with",
+            MatchType::EndsWith => "ends_with",
+            MatchType::Regex => "regex",
+            MatchType::NotExact => "not_exact",
+            MatchType::NotContains => "not_contains",
+            MatchType::NotStartsWith => "not_starts_with",
+            MatchType::NotEndsWith => "not_ends_with",
+        }
+    }
+
+    /// The older spelling without the underscore, e.g. "startswith", still
+    /// accepted so existing configs keep loading
+    fn alias(&self) -> Option<&'static str> {
+        match self {
+            MatchType::StartsWith => Some("startswith"),
+            MatchType::EndsWith => Some("endswith"),
+            MatchType::NotStartsWith => Some("not_startswith"),
+            MatchType::NotEndsWith => Some("not_endswith"),
+            _ => None,
+        }
+    }
+
+    /// Every match type, in the order the documentation lists them
+    pub const ALL: [MatchType; 9] = [
+        MatchType::Exact,
+        MatchType::Contains,
+        MatchType::StartsWith,
+        MatchType::EndsWith,
+        MatchType::Regex,
+        MatchType::NotExact,
+        MatchType::NotContains,
+        MatchType::NotStartsWith,
+        MatchType::NotEndsWith,
+    ];
+
+    /// Whether this match type selects devices the pattern does not match
+    pub fn is_inverted(&self) -> bool {
+        matches!(
+            self,
+            MatchType::NotExact
+                | MatchType::NotContains
+                | MatchType::NotStartsWith
+                | MatchType::NotEndsWith
+        )
+    }
+}
+
+/// Parses the name used in the config file, e.g. "starts_with", or its alias
+impl FromStr for MatchType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        MatchType::ALL
+            .into_iter()
+            .find(|match_type| match_type.config_name() == s || match_type.alias() == Some(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = MatchType::ALL.iter().map(MatchType::config_name).collect();
+                anyhow::anyhow!(
+                    "Unknown match type '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
 }
 
 impl Default for GeneralConfig {
@@ -128,6 +524,19 @@ impl Default for GeneralConfig {
             poll_interval_ms: default_poll_interval_ms(),
             log_level: "info".to_string(),
             daemon_mode: false,
+            backoff_ms: default_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            device_change_debounce_ms: default_device_change_debounce_ms(),
+            bluetooth_device_change_debounce_ms: default_bluetooth_device_change_debounce_ms(),
+            sync_system_alerts: false,
+            prefer_wired_on_battery: false,
+            min_weight_to_switch: 0,
+            shutdown_timeout_ms: default_shutdown_timeout_ms(),
+            restore_volume: false,
+            hook_timeout_ms: default_hook_timeout_ms(),
+            history_max_lines: default_history_max_lines(),
+            switch_retry_count: default_switch_retry_count(),
+            switch_retry_delay_ms: default_switch_retry_delay_ms(),
         }
     }
 }
@@ -138,6 +547,8 @@ impl Default for NotificationConfig {
             show_device_availability: false, // Default: no device availability notifications
             show_switching_actions: true,    // Default: show switching notifications
             show_device_changes: None,       // Backward compatibility field
+            quiet_hours_start: None,
+            quiet_hours_end: None,
         }
     }
 }
@@ -148,39 +559,78 @@ impl Default for Config {
             general: GeneralConfig::default(),
             notifications: NotificationConfig::default(),
             output_devices: vec![
-                DeviceRule {
-                    name: "AirPods".to_string(),
-                    weight: 100,
-                    match_type: MatchType::Contains,
-                    enabled: true,
-                },
-                DeviceRule {
-                    name: "MacBook Pro Speakers".to_string(),
-                    weight: 10,
-                    match_type: MatchType::Exact,
-                    enabled: true,
-                },
+                DeviceRule::new("AirPods", 100, MatchType::Contains),
+                DeviceRule::new("MacBook Pro Speakers", 10, MatchType::Exact),
             ],
             input_devices: vec![
-                DeviceRule {
-                    name: "AirPods".to_string(),
-                    weight: 100,
-                    match_type: MatchType::Contains,
-                    enabled: true,
-                },
-                DeviceRule {
-                    name: "MacBook Pro Microphone".to_string(),
-                    weight: 10,
-                    match_type: MatchType::Exact,
-                    enabled: true,
-                },
+                DeviceRule::new("AirPods", 100, MatchType::Contains),
+                DeviceRule::new("MacBook Pro Microphone", 10, MatchType::Exact),
             ],
+            devices: vec![],
+            profiles: BTreeMap::new(),
         }
     }
 }
 
+/// Whole seconds as "30s", anything else as "500ms"
+fn format_interval(ms: u64) -> String {
+    if ms >= 1000 && ms.is_multiple_of(1000) {
+        format!("{}s", ms / 1000)
+    } else {
+        format!("{ms}ms")
+    }
+}
+
+/// Replace the file at `path` with what `write` produces. The content goes to a
+/// `.toml.tmp` sibling that is renamed over `path` once complete, so a crash or
+/// failed write never leaves a half-written file behind.
+pub fn write_file_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> Result<()> {
+    let temp_path = path.with_extension("toml.tmp");
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e).with_context(|| format!("Failed to write {}", temp_path.display()));
+    }
+    Ok(())
+}
+
+/// Parse a configuration from TOML content without touching the filesystem
+impl FromStr for Config {
+    type Err = anyhow::Error;
+
+    fn from_str(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)?;
+
+        // Handle backward compatibility for notification config
+        config.notifications = config.notifications.migrate_from_old_config();
+
+        config.compile_weight_formulas()?;
+        config.check_uid_fields()?;
+        config.check_rule_volumes()?;
+        config.check_quiet_hours()?;
+        config.compile_regex_patterns()?;
+
+        Ok(config)
+    }
+}
+
 impl Config {
     pub fn load(config_path: Option<&str>) -> Result<Self> {
+        Self::load_with_profile(config_path, None)
+    }
+
+    /// Load the configuration as `load` does, then apply the named profile
+    pub fn load_with_profile(config_path: Option<&str>, profile: Option<&str>) -> Result<Self> {
         let path = match config_path {
             Some(path) => PathBuf::from(path),
             None => Self::default_config_path()?,
@@ -196,11 +646,15 @@ impl Config {
         let config_content = fs::read_to_string(&path)
             .with_context(|| format!("Failed to read configuration file: {}", path.display()))?;
 
-        let mut config: Config = toml::from_str(&config_content)
+        let mut config = Config::from_str(&config_content)
             .with_context(|| format!("Failed to parse configuration file: {}", path.display()))?;
+        if let Some(profile) = profile {
+            config = config.with_profile(profile)?;
+        }
 
-        // Handle backward compatibility for notification config
-        config.notifications = config.notifications.migrate_from_old_config();
+        for conflict in config.validate_rules() {
+            warn!("{}", conflict);
+        }
 
         debug!("Configuration loaded successfully");
         Ok(config)
@@ -222,17 +676,226 @@ impl Config {
         let config_content =
             toml::to_string_pretty(self).context("Failed to serialize configuration")?;
 
-        fs::write(&path, config_content)
+        write_file_atomically(&path, |file| file.write_all(config_content.as_bytes()))
             .with_context(|| format!("Failed to write configuration file: {}", path.display()))?;
 
         info!("Configuration saved to: {}", path.display());
         Ok(())
     }
 
-    fn default_config_path() -> Result<PathBuf> {
-        let home_dir = dirs::home_dir().context("Failed to get home directory")?;
+    /// Rules that select output devices: `output_devices` followed by the
+    /// `devices` rules that apply to output
+    pub fn output_rules(&self) -> Vec<DeviceRule> {
+        self.output_devices
+            .iter()
+            .chain(
+                self.devices
+                    .iter()
+                    .filter(|rule| rule.applies_to.includes_output()),
+            )
+            .cloned()
+            .collect()
+    }
+
+    /// Rules that select input devices: `input_devices` followed by the
+    /// `devices` rules that apply to input
+    pub fn input_rules(&self) -> Vec<DeviceRule> {
+        self.input_devices
+            .iter()
+            .chain(
+                self.devices
+                    .iter()
+                    .filter(|rule| rule.applies_to.includes_input()),
+            )
+            .cloned()
+            .collect()
+    }
+
+    /// One-line overview for status output, e.g.
+    /// "5 output rules, 3 input rules, poll=30s, check=500ms, notifications=ON"
+    pub fn summarize(&self) -> String {
+        format!(
+            "{} output rules, {} input rules, poll={}, check={}, notifications={}",
+            self.output_rules().len(),
+            self.input_rules().len(),
+            format_interval(self.general.poll_interval_ms),
+            format_interval(self.general.check_interval_ms),
+            if self.notifications.show_switching_actions {
+                "ON"
+            } else {
+                "OFF"
+            }
+        )
+    }
+
+    /// This configuration with the rules of profile `name` in place of the
+    /// sections the profile sets. The profiles are kept, so another one can be
+    /// applied to the result.
+    pub fn with_profile(mut self, name: &str) -> Result<Self> {
+        let Some(profile) = self.profiles.get(name) else {
+            return Err(if self.profiles.is_empty() {
+                anyhow::anyhow!("Unknown profile '{name}': no profiles are configured")
+            } else {
+                let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                anyhow::anyhow!(
+                    "Unknown profile '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            });
+        };
+
+        debug!("Applying configuration profile '{}'", name);
+        if let Some(rules) = &profile.output_devices {
+            self.output_devices = rules.clone();
+        }
+        if let Some(rules) = &profile.input_devices {
+            self.input_devices = rules.clone();
+        }
+        Ok(self)
+    }
+
+    /// Settings that load but are probably not what was meant, for `check-config`
+    pub fn lint_warnings(&self) -> Vec<String> {
+        self.all_rules()
+            .filter(|rule| matches!(rule.match_type, MatchType::Regex) && !rule.case_sensitive)
+            .map(|rule| {
+                format!(
+                    "Rule '{}' sets case_sensitive = false on a regex; add (?i) to the pattern instead",
+                    rule.name
+                )
+            })
+            .collect()
+    }
+
+    /// Parse the `weight_formula` of every rule, rejecting the configuration
+    /// if any of them does not parse
+    #[cfg(feature = "weight-formulas")]
+    fn compile_weight_formulas(&mut self) -> Result<()> {
+        for rule in self.all_rules_mut() {
+            if let Some(formula) = &rule.weight_formula {
+                let formula = super::formula::WeightFormula::parse(formula)
+                    .with_context(|| format!("Invalid weight_formula for rule '{}'", rule.name))?;
+                rule.compiled_formula = Some(formula);
+            }
+        }
+        Ok(())
+    }
+
+    /// Without the feature formulas are ignored in favor of `weight`
+    #[cfg(not(feature = "weight-formulas"))]
+    fn compile_weight_formulas(&mut self) -> Result<()> {
+        for rule in self.all_rules() {
+            if rule.weight_formula.is_some() {
+                warn!(
+                    "Ignoring weight_formula for rule '{}': built without the weight-formulas feature",
+                    rule.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject rules that set both `uid` and `require_uid`, which would each
+    /// compare the device UID and could silently contradict one another
+    fn check_uid_fields(&self) -> Result<()> {
+        if let Some(rule) = self
+            .all_rules()
+            .find(|rule| rule.uid.is_some() && rule.require_uid.is_some())
+        {
+            return Err(anyhow::anyhow!(
+                "Rule '{}' sets both uid and require_uid; use uid to match by UID, or require_uid to narrow a name match",
+                rule.label()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject rules whose `on_select_volume` is outside the 0.0-1.0 range
+    /// CoreAudio accepts, which would otherwise only fail at switch time
+    fn check_rule_volumes(&self) -> Result<()> {
+        if let Some((rule, volume)) = self.all_rules().find_map(|rule| {
+            rule.on_select_volume
+                .filter(|volume| !(0.0..=1.0).contains(volume))
+                .map(|volume| (rule, volume))
+        }) {
+            return Err(anyhow::anyhow!(
+                "Rule '{}' sets on_select_volume to {}; it must be between 0.0 and 1.0",
+                rule.label(),
+                volume
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject quiet hours with only one bound, which would otherwise be ignored
+    fn check_quiet_hours(&self) -> Result<()> {
+        let notifications = &self.notifications;
+        let missing = match (
+            notifications.quiet_hours_start,
+            notifications.quiet_hours_end,
+        ) {
+            (Some(_), None) => "quiet_hours_end",
+            (None, Some(_)) => "quiet_hours_start",
+            _ => return Ok(()),
+        };
+        Err(anyhow::anyhow!(
+            "Quiet hours need both quiet_hours_start and quiet_hours_end; {missing} is missing"
+        ))
+    }
+
+    /// Compile the pattern of every `MatchType::Regex` rule, rejecting the
+    /// configuration if any of them is not a valid regular expression
+    fn compile_regex_patterns(&mut self) -> Result<()> {
+        for rule in self
+            .all_rules_mut()
+            .filter(|rule| matches!(rule.match_type, MatchType::Regex))
+        {
+            let regex = rule.build_regex().with_context(|| {
+                format!(
+                    "Invalid regex pattern '{}' for rule '{}'",
+                    rule.pattern(),
+                    rule.label()
+                )
+            })?;
+            rule.compiled_regex = Some(regex);
+        }
+        Ok(())
+    }
+
+    /// Every rule, including those of profiles that are not applied
+    fn all_rules(&self) -> impl Iterator<Item = &DeviceRule> {
+        let profile_rules = self.profiles.values().flat_map(|profile| {
+            profile
+                .output_devices
+                .iter()
+                .chain(&profile.input_devices)
+                .flatten()
+        });
+        self.output_devices
+            .iter()
+            .chain(&self.input_devices)
+            .chain(&self.devices)
+            .chain(profile_rules)
+    }
+
+    /// Every rule, as for `all_rules`, for filling in what parsing derives
+    fn all_rules_mut(&mut self) -> impl Iterator<Item = &mut DeviceRule> {
+        let profile_rules = self.profiles.values_mut().flat_map(|profile| {
+            profile
+                .output_devices
+                .iter_mut()
+                .chain(&mut profile.input_devices)
+                .flatten()
+        });
+        self.output_devices
+            .iter_mut()
+            .chain(&mut self.input_devices)
+            .chain(&mut self.devices)
+            .chain(profile_rules)
+    }
 
-        Ok(home_dir.join(".config/audio-device-monitor/config.toml"))
+    fn default_config_path() -> Result<PathBuf> {
+        super::ConfigLoader::default_config_path()
     }
 
     fn create_default_config(path: &Path) -> Result<Self> {
@@ -267,21 +930,201 @@ impl Config {
 }
 
 impl DeviceRule {
-    pub fn matches(&self, device_name: &str) -> bool {
+    /// An enabled, case-sensitive rule with every optional setting left unset,
+    /// as a rule written with only `name`, `weight` and `match_type` would be
+    pub fn new(name: impl Into<String>, weight: i64, match_type: MatchType) -> Self {
+        Self {
+            name: name.into(),
+            weight,
+            match_type,
+            enabled: true,
+            exclusive: false,
+            sticky: false,
+            weight_decay_after_disconnect_ms: None,
+            weight_decay_factor: None,
+            buffer_size_frames: None,
+            on_select_volume: None,
+            description: None,
+            comment: None,
+            min_channels: None,
+            max_channels: None,
+            applies_to: DeviceTypeFilter::Both,
+            requires_other_device: None,
+            require_uid: None,
+            weight_formula: None,
+            case_sensitive: true,
+            transport_type: None,
+            uid: None,
+            stability_ms: None,
+            pre_switch_command: None,
+            post_switch_command: None,
+            compiled_regex: None,
+            #[cfg(feature = "weight-formulas")]
+            compiled_formula: None,
+        }
+    }
+
+    /// The weight this rule gives a device. A `weight_formula` replaces `weight`,
+    /// with `base` being the weight of the first formula-free rule in the same
+    /// direction that matches the device.
+    #[cfg(feature = "weight-formulas")]
+    pub fn effective_weight(&self, base: i64) -> i64 {
+        if self.weight_formula.is_none() {
+            return self.weight;
+        }
+        self.effective_weight_at(base, TimeOfDay::now_local())
+    }
+
+    /// The weight this rule gives a device: always `weight` without the
+    /// `weight-formulas` feature
+    #[cfg(not(feature = "weight-formulas"))]
+    pub fn effective_weight(&self, _base: i64) -> i64 {
+        self.weight
+    }
+
+    /// `effective_weight` at a given local time, which decides `time_bonus`
+    #[cfg(feature = "weight-formulas")]
+    pub fn effective_weight_at(&self, base: i64, now: TimeOfDay) -> i64 {
+        let Some(formula) = &self.weight_formula else {
+            return self.weight;
+        };
+        let context = super::formula::FormulaContext::at(base, now);
+        if let Some(compiled) = &self.compiled_formula {
+            return compiled.evaluate(&context);
+        }
+
+        match super::formula::WeightFormula::parse(formula) {
+            Ok(formula) => formula.evaluate(&context),
+            Err(e) => {
+                warn!(
+                    "Using weight {} for rule '{}': {:#}",
+                    self.weight, self.name, e
+                );
+                self.weight
+            }
+        }
+    }
+
+    /// Rule name followed by its description, if any, for CLI output
+    pub fn label(&self) -> String {
+        match &self.description {
+            Some(description) => format!("{} - {}", self.name, description),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether the rule matches a device by UID if `uid` is set and by name otherwise, its
+    /// UID is the `require_uid` if one is set, its transport is the `transport_type` if one
+    /// is set, and its channel count is within `min_channels..=max_channels`. Devices with
+    /// an unknown transport or channel count pass those checks; devices without a UID never
+    /// match a `uid` rule.
+    pub fn matches_device(&self, device: &AudioDevice) -> bool {
+        let identifier = match self.uid {
+            Some(_) => device.uid.as_deref(),
+            None => Some(device.name.as_str()),
+        };
+
+        identifier.is_some_and(|identifier| self.matches(identifier))
+            && self.accepts_uid(device.uid.as_deref())
+            && self.accepts_transport_type(device.transport_type)
+            && self.accepts_channel_count(device.channels)
+    }
+
+    /// Whether the device named by `requires_other_device`, if any, is among `available_devices`
+    pub fn required_device_present(&self, available_devices: &[AudioDevice]) -> bool {
+        self.requires_other_device.as_ref().is_none_or(|required| {
+            available_devices
+                .iter()
+                .any(|device| device.name.contains(required.as_str()))
+        })
+    }
+
+    fn accepts_uid(&self, uid: Option<&str>) -> bool {
+        self.require_uid
+            .as_deref()
+            .is_none_or(|required| uid == Some(required))
+    }
+
+    fn accepts_transport_type(&self, transport_type: Option<TransportType>) -> bool {
+        match (self.transport_type, transport_type) {
+            (Some(required), Some(actual)) if actual != TransportType::Unknown => {
+                actual == required
+            }
+            _ => true,
+        }
+    }
+
+    fn accepts_channel_count(&self, channels: Option<u32>) -> bool {
+        let Some(channels) = channels else {
+            return true;
+        };
+
+        self.min_channels.is_none_or(|min| channels >= min)
+            && self.max_channels.is_none_or(|max| channels <= max)
+    }
+
+    /// Whether `identifier` matches the rule's pattern: the `uid` pattern if one is set,
+    /// otherwise `name`. `matches_device` picks the UID or display name to pass in.
+    pub fn matches(&self, identifier: &str) -> bool {
         if !self.enabled {
             return false;
         }
 
+        let matched = self.inner_matches(identifier);
+        if self.match_type.is_inverted() {
+            !matched
+        } else {
+            matched
+        }
+    }
+
+    /// Match against the pattern, ignoring whether the match type is inverted
+    fn inner_matches(&self, identifier: &str) -> bool {
+        let pattern = self.fold_case(self.pattern());
+        let name = self.fold_case(identifier);
+
         match self.match_type {
-            MatchType::Exact => device_name == self.name,
-            MatchType::Contains => device_name.contains(&self.name),
-            MatchType::StartsWith => device_name.starts_with(&self.name),
-            MatchType::EndsWith => device_name.ends_with(&self.name),
-            MatchType::Regex => {
-                // For now, treat regex as contains. Will implement proper regex later
-                warn!("Regex matching not yet implemented, using contains instead");
-                device_name.contains(&self.name)
+            MatchType::Exact | MatchType::NotExact => name == pattern,
+            MatchType::Contains | MatchType::NotContains => name.contains(&*pattern),
+            MatchType::StartsWith | MatchType::NotStartsWith => name.starts_with(&*pattern),
+            MatchType::EndsWith | MatchType::NotEndsWith => name.ends_with(&*pattern),
+            MatchType::Regex => self.regex_matches(identifier),
+        }
+    }
+
+    /// The text device identifiers are matched against
+    fn pattern(&self) -> &str {
+        self.uid.as_deref().unwrap_or(&self.name)
+    }
+
+    /// `text` lowercased unless the rule is case-sensitive
+    fn fold_case<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.case_sensitive {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.to_lowercase())
+        }
+    }
+
+    fn regex_matches(&self, identifier: &str) -> bool {
+        if let Some(regex) = &self.compiled_regex {
+            return regex.is_match(identifier);
+        }
+
+        match self.build_regex() {
+            Ok(regex) => regex.is_match(identifier),
+            Err(e) => {
+                warn!("Rule '{}' is not a valid regex: {}", self.pattern(), e);
+                false
             }
         }
     }
+
+    /// Compile the pattern as a regex. Lowercasing a pattern would change escapes
+    /// such as `\S`, so `case_sensitive = false` sets the case-insensitive flag.
+    fn build_regex(&self) -> std::result::Result<Regex, regex::Error> {
+        RegexBuilder::new(self.pattern())
+            .case_insensitive(!self.case_sensitive)
+            .build()
+    }
 }
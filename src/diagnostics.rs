@@ -0,0 +1,388 @@
+//! Environment self-test for the `diagnose` command
+//!
+//! Each check passes or fails with a reason and a suggested fix. Checks take
+//! what they inspect as arguments, so the command gathers devices and paths
+//! once and tests can point the checks at temporary files.
+
+use anyhow::Result;
+use std::ffi::OsStr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::audio::listener::EXPECTED_LISTENER_COUNT;
+use crate::audio::{AudioDevice, DeviceType};
+use crate::config::Config;
+use crate::service::lock::LockStatus;
+
+/// File `check_log_dir_writable` creates and removes again
+const WRITE_PROBE_FILE_NAME: &str = ".diagnose-write-test";
+
+/// Why a check failed and what to do about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckFailure {
+    pub reason: String,
+    pub fix: String,
+}
+
+/// The outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticCheck {
+    pub name: &'static str,
+    pub failure: Option<CheckFailure>,
+}
+
+impl DiagnosticCheck {
+    fn pass(name: &'static str) -> Self {
+        Self {
+            name,
+            failure: None,
+        }
+    }
+
+    fn fail(name: &'static str, reason: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            failure: Some(CheckFailure {
+                reason: reason.into(),
+                fix: fix.into(),
+            }),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// "✓ PASS" or "✗ FAIL: <reason>"
+impl std::fmt::Display for DiagnosticCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            None => write!(f, "✓ PASS"),
+            Some(failure) => write!(f, "✗ FAIL: {}", failure.reason),
+        }
+    }
+}
+
+/// CoreAudio answered the enumeration with at least one device
+pub fn check_audio_devices(devices: &Result<Vec<AudioDevice>>) -> DiagnosticCheck {
+    const NAME: &str = "CoreAudio accessible";
+    match devices {
+        Ok(devices) if !devices.is_empty() => DiagnosticCheck::pass(NAME),
+        Ok(_) => DiagnosticCheck::fail(
+            NAME,
+            "CoreAudio reported no audio devices",
+            "Check that Audio MIDI Setup lists your devices, or restart CoreAudio with `sudo killall coreaudiod`",
+        ),
+        Err(e) => DiagnosticCheck::fail(
+            NAME,
+            format!("could not enumerate audio devices: {e:#}"),
+            "Restart CoreAudio with `sudo killall coreaudiod` and try again",
+        ),
+    }
+}
+
+/// CoreAudio accepted every property listener the daemon relies on to notice
+/// device changes
+pub fn check_property_listeners(registered: &Result<usize>) -> DiagnosticCheck {
+    const NAME: &str = "CoreAudio listeners";
+    match registered {
+        Ok(count) if *count == EXPECTED_LISTENER_COUNT => DiagnosticCheck::pass(NAME),
+        Ok(count) => DiagnosticCheck::fail(
+            NAME,
+            format!(
+                "only {count} of {EXPECTED_LISTENER_COUNT} property listeners could be registered, so some device changes would be missed"
+            ),
+            "Restart CoreAudio with `sudo killall coreaudiod` and try again",
+        ),
+        Err(e) => DiagnosticCheck::fail(
+            NAME,
+            format!("could not register property listeners: {e:#}"),
+            "Restart CoreAudio with `sudo killall coreaudiod` and try again",
+        ),
+    }
+}
+
+/// The config file at `path` exists and parses. Unlike `Config::load` a
+/// missing file is not replaced with the default configuration.
+pub fn load_config_file(path: &Path) -> (DiagnosticCheck, Option<Config>) {
+    const NAME: &str = "Config file";
+    if !path.exists() {
+        return (
+            DiagnosticCheck::fail(
+                NAME,
+                format!("{} does not exist", path.display()),
+                "Run `audio-device-monitor check-config` once to create the default configuration",
+            ),
+            None,
+        );
+    }
+
+    match std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Config::from_str(&content))
+    {
+        Ok(config) => (DiagnosticCheck::pass(NAME), Some(config)),
+        Err(e) => (
+            DiagnosticCheck::fail(
+                NAME,
+                format!("{} could not be loaded: {e:#}", path.display()),
+                "Fix the reported problem, then confirm with `audio-device-monitor check-config`",
+            ),
+            None,
+        ),
+    }
+}
+
+/// At least one output rule is enabled
+pub fn check_output_rules(config: Option<&Config>) -> DiagnosticCheck {
+    const NAME: &str = "Enabled output rule";
+    let Some(config) = config else {
+        return config_not_loaded(NAME);
+    };
+
+    if config.output_rules().iter().any(|rule| rule.enabled) {
+        DiagnosticCheck::pass(NAME)
+    } else {
+        DiagnosticCheck::fail(
+            NAME,
+            "no output rule is enabled",
+            "Add an [[output_devices]] rule with enabled = true",
+        )
+    }
+}
+
+/// At least one connected output device matches an output rule
+pub fn check_matching_device(config: Option<&Config>, devices: &[AudioDevice]) -> DiagnosticCheck {
+    const NAME: &str = "Connected device matches an output rule";
+    let Some(config) = config else {
+        return config_not_loaded(NAME);
+    };
+
+    let outputs: Vec<&AudioDevice> = devices
+        .iter()
+        .filter(|device| {
+            matches!(
+                device.device_type,
+                DeviceType::Output | DeviceType::InputOutput
+            )
+        })
+        .collect();
+    let rules = config.output_rules();
+
+    if outputs
+        .iter()
+        .any(|device| rules.iter().any(|rule| rule.matches_device(device)))
+    {
+        DiagnosticCheck::pass(NAME)
+    } else {
+        DiagnosticCheck::fail(
+            NAME,
+            format!(
+                "none of the {} connected output devices matches an output rule",
+                outputs.len()
+            ),
+            "Run `audio-device-monitor list-devices` and use one of the listed names in a rule",
+        )
+    }
+}
+
+/// `osascript`, which delivers notifications, is on `path_var`
+pub fn check_osascript(path_var: Option<&OsStr>) -> DiagnosticCheck {
+    const NAME: &str = "osascript available";
+    let found = path_var.is_some_and(|path_var| {
+        std::env::split_paths(path_var).any(|dir| is_executable(&dir.join("osascript")))
+    });
+
+    if found {
+        DiagnosticCheck::pass(NAME)
+    } else {
+        DiagnosticCheck::fail(
+            NAME,
+            "osascript was not found on PATH, so notifications cannot be shown",
+            "Make sure /usr/bin is on PATH; osascript ships with macOS in /usr/bin",
+        )
+    }
+}
+
+/// The LaunchAgent plist exists at `plist_path`
+pub fn check_launch_agent(plist_path: &Path) -> DiagnosticCheck {
+    const NAME: &str = "LaunchAgent installed";
+    if plist_path.exists() {
+        DiagnosticCheck::pass(NAME)
+    } else {
+        DiagnosticCheck::fail(
+            NAME,
+            format!("{} does not exist", plist_path.display()),
+            "Run `audio-device-monitor install-service`",
+        )
+    }
+}
+
+/// The daemon's PID file names a live process
+pub fn check_daemon(status: LockStatus) -> DiagnosticCheck {
+    const NAME: &str = "Daemon running";
+    match status {
+        LockStatus::Running(_) => DiagnosticCheck::pass(NAME),
+        LockStatus::NotRunning => DiagnosticCheck::fail(
+            NAME,
+            "no daemon PID file, so the daemon is not running",
+            "Load the LaunchAgent with `launchctl load ~/Library/LaunchAgents/com.audiodevicemonitor.daemon.plist`, or run `audio-device-monitor daemon`",
+        ),
+        LockStatus::Stale(pid) => DiagnosticCheck::fail(
+            NAME,
+            format!("no daemon holds the PID file lock left by process {pid}"),
+            "Start the daemon again; it replaces the stale PID file. Check the logs for why it stopped",
+        ),
+    }
+}
+
+/// A file can be created in `log_dir`, creating the directory if needed
+pub fn check_log_dir_writable(log_dir: &Path) -> DiagnosticCheck {
+    const NAME: &str = "Log directory writable";
+    let probe = log_dir.join(WRITE_PROBE_FILE_NAME);
+    let result = std::fs::create_dir_all(log_dir)
+        .and_then(|()| std::fs::write(&probe, b""))
+        .and_then(|()| std::fs::remove_file(&probe));
+
+    match result {
+        Ok(()) => DiagnosticCheck::pass(NAME),
+        Err(e) => DiagnosticCheck::fail(
+            NAME,
+            format!("cannot write to {}: {e}", log_dir.display()),
+            "Fix the directory's permissions, or log elsewhere with --log-dir",
+        ),
+    }
+}
+
+fn config_not_loaded(name: &'static str) -> DiagnosticCheck {
+    DiagnosticCheck::fail(
+        name,
+        "the configuration did not load",
+        "Fix the config file first",
+    )
+}
+
+fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_WITH_RULE: &str = r#"
+[[output_devices]]
+name = "AirPods"
+weight = 100
+match_type = "contains"
+enabled = true
+"#;
+
+    fn device(name: &str, device_type: DeviceType) -> AudioDevice {
+        AudioDevice::new(format!("{name}-id"), name.to_string(), device_type)
+    }
+
+    #[test]
+    fn test_audio_devices_must_be_listed() {
+        let devices = vec![device("Speakers", DeviceType::Output)];
+
+        assert!(check_audio_devices(&Ok(devices)).passed());
+        assert!(!check_audio_devices(&Ok(Vec::new())).passed());
+        assert!(!check_audio_devices(&Err(anyhow::anyhow!("no access"))).passed());
+    }
+
+    #[test]
+    fn test_every_property_listener_must_register() {
+        assert!(check_property_listeners(&Ok(EXPECTED_LISTENER_COUNT)).passed());
+        assert!(!check_property_listeners(&Ok(EXPECTED_LISTENER_COUNT - 1)).passed());
+        assert!(!check_property_listeners(&Err(anyhow::anyhow!("no access"))).passed());
+    }
+
+    #[test]
+    fn test_config_file_must_exist_and_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let (check, config) = load_config_file(&path);
+        assert!(!check.passed());
+        assert!(config.is_none());
+        // Not created as a side effect
+        assert!(!path.exists());
+
+        std::fs::write(&path, "[[output_devices]\n").unwrap();
+        let (check, config) = load_config_file(&path);
+        assert!(check.to_string().starts_with("✗ FAIL: "));
+        assert!(config.is_none());
+
+        std::fs::write(&path, CONFIG_WITH_RULE).unwrap();
+        let (check, config) = load_config_file(&path);
+        assert_eq!(check.to_string(), "✓ PASS");
+        assert!(config.is_some());
+    }
+
+    #[test]
+    fn test_output_rule_must_be_enabled() {
+        let mut config = Config::from_str(CONFIG_WITH_RULE).unwrap();
+        assert!(check_output_rules(Some(&config)).passed());
+
+        config.output_devices[0].enabled = false;
+        assert!(!check_output_rules(Some(&config)).passed());
+        assert!(!check_output_rules(None).passed());
+    }
+
+    #[test]
+    fn test_connected_output_must_match_a_rule() {
+        let config = Config::from_str(CONFIG_WITH_RULE).unwrap();
+        let speakers = device("Speakers", DeviceType::Output);
+        let airpods_mic = device("AirPods Pro", DeviceType::Input);
+        let airpods = device("AirPods Pro", DeviceType::Output);
+
+        let check = check_matching_device(Some(&config), &[speakers.clone(), airpods_mic]);
+        assert_eq!(
+            check.failure.unwrap().reason,
+            "none of the 1 connected output devices matches an output rule"
+        );
+        assert!(check_matching_device(Some(&config), &[speakers, airpods]).passed());
+    }
+
+    #[test]
+    fn test_osascript_is_looked_up_on_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let osascript = dir.path().join("osascript");
+        std::fs::write(&osascript, "#!/bin/sh\n").unwrap();
+
+        assert!(!check_osascript(Some(dir.path().as_os_str())).passed());
+
+        std::fs::set_permissions(&osascript, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(check_osascript(Some(dir.path().as_os_str())).passed());
+        assert!(!check_osascript(None).passed());
+    }
+
+    #[test]
+    fn test_daemon_must_be_running() {
+        assert!(check_daemon(LockStatus::Running(42)).passed());
+        assert!(!check_daemon(LockStatus::NotRunning).passed());
+
+        let stale = check_daemon(LockStatus::Stale(42));
+        assert!(stale.failure.unwrap().reason.contains("process 42"));
+    }
+
+    #[test]
+    fn test_launch_agent_and_log_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let plist = dir.path().join("agent.plist");
+        assert!(!check_launch_agent(&plist).passed());
+        std::fs::write(&plist, "").unwrap();
+        assert!(check_launch_agent(&plist).passed());
+
+        let log_dir = dir.path().join("logs");
+        assert!(check_log_dir_writable(&log_dir).passed());
+        assert!(!log_dir.join(WRITE_PROBE_FILE_NAME).exists());
+
+        // A file where the directory should be
+        assert!(!check_log_dir_writable(&plist.join("logs")).passed());
+    }
+}
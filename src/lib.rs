@@ -1,26 +1,33 @@
 pub mod audio;
 pub mod config;
+pub mod diagnostics;
 pub mod notifications;
+pub mod output;
 pub mod preference_debugging;
 pub mod priority;
 pub mod service;
 pub mod system;
 
-pub use audio::{AudioDevice, AudioDeviceMonitor, DeviceControllerV2, DeviceType};
+pub use audio::{
+    AudioDevice, AudioDeviceMonitor, DeviceControllerV2, DeviceEvent, DeviceSnapshot, DeviceType,
+    DryRunResult, TransportType,
+};
 pub use config::{Config, ConfigLoader};
 pub use notifications::{DefaultNotificationManager, NotificationManager, SwitchReason};
-pub use preference_debugging::{PreferenceChanges, PreferenceStatus};
+pub use preference_debugging::{PreferenceChanges, PreferenceStatus, UrgencyLevel};
 
 #[cfg(any(test, feature = "test-mocks"))]
 pub use notifications::TestNotificationSender;
-pub use service::AudioDeviceService;
+pub use service::{
+    AudioDeviceService, HealthStatus, IpcCommand, ServiceStatistics, SwitchDirection,
+};
 
 // Re-export common functionality for library users
 pub use audio::controller::DeviceController;
 
 // Export system traits and adapters
 pub use system::{
-    AudioSystemInterface, CoreAudioSystem, FileSystemInterface, MacOSSystemService,
+    AudioSystemInterface, CoreAudioSystem, FileSystemInterface, FileWatch, MacOSSystemService,
     StandardFileSystem, SystemServiceInterface,
 };
 
@@ -1,7 +1,13 @@
 use anyhow::Result;
-use std::path::PathBuf;
-use tracing::Level;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{Level, Subscriber};
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::RollingFileAppender;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{EnvFilter, Layer, fmt, prelude::*};
 
 /// Enhanced logging configuration
@@ -11,6 +17,37 @@ pub struct LoggingConfig {
     pub console_output: bool,
     pub log_dir: Option<PathBuf>,
     pub json_format: bool,
+    /// Start a new log file once the current one reaches this size, on top of daily rotation
+    pub max_log_file_size_mb: Option<u64>,
+    /// Which span lifecycle events to log, e.g. `Full` for span timings when profiling
+    pub span_events: SpanEvents,
+}
+
+/// Span lifecycle events written to the log
+// The CLI only selects None or Full; Enter and Close are for finer-grained profiling setups
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpanEvents {
+    /// Only events inside spans are logged
+    #[default]
+    None,
+    /// Log when a span is entered
+    Enter,
+    /// Log when a span closes, with its busy and idle time
+    Close,
+    /// Log every span lifecycle event
+    Full,
+}
+
+impl SpanEvents {
+    fn fmt_span(self) -> FmtSpan {
+        match self {
+            SpanEvents::None => FmtSpan::NONE,
+            SpanEvents::Enter => FmtSpan::ENTER,
+            SpanEvents::Close => FmtSpan::CLOSE,
+            SpanEvents::Full => FmtSpan::FULL,
+        }
+    }
 }
 
 impl Default for LoggingConfig {
@@ -21,7 +58,82 @@ impl Default for LoggingConfig {
             console_output: true,
             log_dir: None,
             json_format: false,
+            max_log_file_size_mb: None,
+            span_events: SpanEvents::None,
+        }
+    }
+}
+
+const LOG_FILE_PREFIX: &str = "audio-device-monitor.log";
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Daily rolling log appender that also moves to a new file once `max_bytes` have been written.
+/// Files started because of the size limit get a millisecond timestamp in their prefix.
+pub struct SizeRotatingAppender {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: u64,
+    inner: RollingFileAppender,
+    written: u64,
+    /// UTC day of the current file; the daily roll starts a fresh file
+    day: u64,
+}
+
+impl SizeRotatingAppender {
+    pub fn new(dir: &Path, prefix: &str, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            prefix: prefix.to_string(),
+            max_bytes,
+            inner: tracing_appender::rolling::daily(dir, prefix),
+            written: 0,
+            day: Self::current_day(),
+        }
+    }
+
+    fn current_day() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+            .unwrap_or_default()
+    }
+
+    /// Close the current file and continue in a new timestamped one
+    fn rotate(&mut self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let prefix = match self.prefix.strip_suffix(".log") {
+            Some(stem) => format!("{stem}.{timestamp}.log"),
+            None => format!("{}.{timestamp}", self.prefix),
+        };
+        self.inner = tracing_appender::rolling::daily(&self.dir, prefix);
+        self.written = 0;
+    }
+}
+
+impl Write for SizeRotatingAppender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let day = Self::current_day();
+        if day != self.day {
+            self.day = day;
+            self.written = 0;
         }
+
+        // A single oversized write still goes to a fresh file rather than being split
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate();
+        }
+
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
     }
 }
 
@@ -40,23 +152,12 @@ pub fn initialize_logging(config: LoggingConfig) -> Result<(Option<WorkerGuard>,
 
     // Console output layer
     if config.console_output {
-        let console_layer = if config.json_format {
-            fmt::layer()
-                .json()
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_file(true)
-                .with_line_number(true)
-                .boxed()
-        } else {
-            fmt::layer()
-                .with_target(true)
-                .with_thread_ids(false)
-                .with_file(false)
-                .with_line_number(false)
-                .boxed()
-        };
-        layers.push(console_layer);
+        layers.push(fmt_layer(
+            std::io::stdout,
+            config.json_format,
+            config.json_format,
+            config.span_events,
+        ));
     }
 
     // File output layer with rotation
@@ -70,29 +171,21 @@ pub fn initialize_logging(config: LoggingConfig) -> Result<(Option<WorkerGuard>,
         // Create log directory if it doesn't exist
         std::fs::create_dir_all(&dir)?;
 
-        // Create file appender with daily rotation
-        let file_appender = tracing_appender::rolling::daily(&dir, "audio-device-monitor.log");
-        let (non_blocking, worker_guard) = tracing_appender::non_blocking(file_appender);
+        // Create file appender with daily rotation, also rotating by size when limited
+        let (non_blocking, worker_guard) = match config.max_log_file_size_mb {
+            Some(max_mb) => tracing_appender::non_blocking(SizeRotatingAppender::new(
+                &dir,
+                LOG_FILE_PREFIX,
+                max_mb.saturating_mul(1024 * 1024),
+            )),
+            None => tracing_appender::non_blocking(tracing_appender::rolling::daily(
+                &dir,
+                LOG_FILE_PREFIX,
+            )),
+        };
         guard = Some(worker_guard);
 
-        let file_layer = if config.json_format {
-            fmt::layer()
-                .json()
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_file(true)
-                .with_line_number(true)
-                .with_writer(non_blocking)
-                .boxed()
-        } else {
-            fmt::layer()
-                .with_target(true)
-                .with_thread_ids(false)
-                .with_file(true)
-                .with_line_number(true)
-                .with_writer(non_blocking)
-                .boxed()
-        };
+        let file_layer = fmt_layer(non_blocking, config.json_format, true, config.span_events);
         layers.push(file_layer);
 
         Some(dir)
@@ -109,6 +202,40 @@ pub fn initialize_logging(config: LoggingConfig) -> Result<(Option<WorkerGuard>,
     Ok((guard, log_dir))
 }
 
+/// Formatting layer writing to `writer`. JSON output always includes thread IDs and
+/// source locations; plain text includes source locations only when `with_location` is set.
+fn fmt_layer<S, W>(
+    writer: W,
+    json_format: bool,
+    with_location: bool,
+    span_events: SpanEvents,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    if json_format {
+        fmt::layer()
+            .json()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_span_events(span_events.fmt_span())
+            .with_writer(writer)
+            .boxed()
+    } else {
+        fmt::layer()
+            .with_target(true)
+            .with_thread_ids(false)
+            .with_file(with_location)
+            .with_line_number(with_location)
+            .with_span_events(span_events.fmt_span())
+            .with_writer(writer)
+            .boxed()
+    }
+}
+
 /// Get the default log directory path
 pub fn get_default_log_dir() -> Result<PathBuf> {
     let home_dir =
@@ -163,3 +290,130 @@ pub fn cleanup_old_logs(log_dir: &PathBuf, keep_days: u64) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Log writer that keeps everything written in memory
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tracing::instrument]
+    fn instrumented_work() {
+        tracing::info!("doing work");
+    }
+
+    /// Run `instrumented_work` with a plain text layer and return what it logged
+    fn log_instrumented_work(span_events: SpanEvents) -> String {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = {
+            let buffer = Arc::clone(&buffer);
+            move || CapturedLogs(Arc::clone(&buffer))
+        };
+        let subscriber =
+            tracing_subscriber::registry().with(fmt_layer(writer, false, false, span_events));
+
+        tracing::subscriber::with_default(subscriber, instrumented_work);
+
+        String::from_utf8(buffer.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_full_span_events_log_span_close() {
+        let logs = log_instrumented_work(SpanEvents::Full);
+
+        assert!(logs.contains("doing work"));
+        assert!(logs.contains("instrumented_work"));
+        assert!(logs.contains("close"));
+        assert!(logs.contains("time.busy"));
+    }
+
+    #[test]
+    fn test_no_span_events_by_default() {
+        let logs = log_instrumented_work(SpanEvents::default());
+
+        assert!(logs.contains("doing work"));
+        assert!(!logs.contains("close"));
+    }
+
+    fn log_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_new_file_opened_when_size_limit_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut appender = SizeRotatingAppender::new(dir.path(), LOG_FILE_PREFIX, 1024);
+        let chunk = vec![b'x'; 600];
+
+        appender.write_all(&chunk).unwrap();
+        appender.flush().unwrap();
+        assert_eq!(log_files(dir.path()).len(), 1);
+
+        appender.write_all(&chunk).unwrap();
+        appender.flush().unwrap();
+        let files = log_files(dir.path());
+        assert_eq!(files.len(), 2);
+        for file in files {
+            assert_eq!(std::fs::metadata(file).unwrap().len(), 600);
+        }
+    }
+
+    #[test]
+    fn test_writes_under_limit_share_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut appender = SizeRotatingAppender::new(dir.path(), LOG_FILE_PREFIX, 1024);
+
+        for _ in 0..4 {
+            appender.write_all(&[b'x'; 256]).unwrap();
+        }
+        appender.flush().unwrap();
+
+        let files = log_files(dir.path());
+        assert_eq!(files.len(), 1);
+        assert_eq!(std::fs::metadata(&files[0]).unwrap().len(), 1024);
+    }
+
+    #[test]
+    fn test_rotated_files_keep_log_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut appender = SizeRotatingAppender::new(dir.path(), LOG_FILE_PREFIX, 10);
+
+        appender.write_all(b"first line\n").unwrap();
+        appender.write_all(b"second line\n").unwrap();
+        appender.flush().unwrap();
+
+        let names: Vec<String> = log_files(dir.path())
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(
+            names
+                .iter()
+                .all(|name| name.starts_with("audio-device-monitor."))
+        );
+        assert!(
+            names
+                .iter()
+                .any(|name| name.starts_with("audio-device-monitor.log."))
+        );
+    }
+}
@@ -1,20 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use std::io::Write;
 use tracing::{debug, info, warn};
 
 mod audio;
 mod config;
+mod diagnostics;
 mod logging;
 mod notifications;
+mod output;
 mod preference_debugging;
 mod priority;
 mod service;
 mod system;
 
-use audio::AudioDeviceMonitor;
-use config::Config;
-use logging::{LoggingConfig, cleanup_old_logs, get_default_log_dir, initialize_logging};
+use audio::device::name_similarity;
+use audio::{AudioDevice, AudioDeviceMonitor, DeviceType};
+use config::{Config, ConflictKind, DeviceRule, MatchType, RuleEdit};
+use logging::{
+    LoggingConfig, SpanEvents, cleanup_old_logs, get_default_log_dir, initialize_logging,
+};
 use notifications::DefaultNotificationManager;
+use output::{JsonOutput, OutputFormat, QuietOutput, RuleListing, write_rules_table};
+use service::ipc::{IpcClient, IpcRequest, SwitchDirection, default_socket_path};
 use service::{AudioDeviceService, daemon::ServiceInstaller};
 
 #[derive(Parser)]
@@ -29,7 +37,15 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
-    /// Configuration file path
+    /// Suppress non-error output from switch, pause, resume, set-volume,
+    /// apply-preferences, health, wait-for-device and list-rules (overridden by
+    /// --verbose). Other commands print as usual
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Configuration file path [default: $XDG_CONFIG_HOME/audio-device-monitor/config.toml,
+    /// or ~/Library/Application Support/audio-device-monitor/config.toml when
+    /// XDG_CONFIG_HOME is unset]
     #[arg(short, long)]
     config: Option<String>,
 
@@ -37,6 +53,14 @@ struct Cli {
     #[arg(long)]
     json_logs: bool,
 
+    /// Log when each tracing span opens and closes, with its duration
+    #[arg(long)]
+    trace_spans: bool,
+
+    /// Disable colored command output
+    #[arg(long)]
+    no_color: bool,
+
     /// Disable file logging (console only)
     #[arg(long)]
     no_file_logs: bool,
@@ -44,6 +68,24 @@ struct Cli {
     /// Custom log directory
     #[arg(long)]
     log_dir: Option<String>,
+
+    /// Start a new log file once the current one reaches this many megabytes
+    #[arg(long)]
+    max_log_file_size_mb: Option<u64>,
+
+    /// Print help instead of starting the daemon when no command is given
+    /// and the config sets daemon_mode
+    #[arg(long)]
+    no_daemon: bool,
+
+    /// Output format of list-devices, list-rules, show-default, show-current,
+    /// device-info, check-device and status
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Print JSON on a single line, for piping
+    #[arg(long, global = true)]
+    compact: bool,
 }
 
 #[derive(Subcommand)]
@@ -57,9 +99,63 @@ enum Commands {
     /// Test device monitoring (prints device changes)
     TestMonitor,
     /// Run in daemon mode
-    Daemon,
+    Daemon {
+        /// Stop an already running daemon first instead of refusing to start
+        #[arg(long)]
+        force: bool,
+        /// Use the rules of this profile from the config file's [profiles] section
+        #[arg(long)]
+        profile: Option<String>,
+    },
     /// Validate configuration file
-    CheckConfig,
+    CheckConfig {
+        /// Fail when rules conflict, not just warn
+        #[arg(long)]
+        strict: bool,
+        /// Validate the rules as this profile applies them
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Add a device rule to the config file
+    AddRule {
+        /// Device name, or the pattern to match device names against
+        #[arg(short, long)]
+        name: String,
+        /// Priority weight; negative weights blacklist the matched devices
+        #[arg(short, long, allow_negative_numbers = true)]
+        weight: i64,
+        /// How the name is matched: exact, contains, starts_with, ends_with, regex,
+        /// not_exact, not_contains, not_starts_with or not_ends_with
+        #[arg(short, long, default_value = "exact")]
+        match_type: MatchType,
+        /// Add an input rule instead of an output rule
+        #[arg(short, long)]
+        input: bool,
+        /// Whether the rule is enabled
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        enabled: bool,
+        /// Print the rule that would be added without writing the config file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove the first device rule with the given name from the config file
+    RemoveRule {
+        /// Exact name of the rule to remove
+        #[arg(short, long)]
+        name: String,
+        /// Remove an input rule instead of an output rule
+        #[arg(short, long)]
+        input: bool,
+        /// Print the rule that would be removed without writing the config file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List configured device rules, highest weight first
+    ListRules {
+        /// Print the rules as JSON, like --format json
+        #[arg(long)]
+        json: bool,
+    },
     /// Show current default devices
     ShowDefault,
     /// Switch to a specific device
@@ -70,11 +166,41 @@ enum Commands {
         /// Switch input device instead of output
         #[arg(short, long)]
         input: bool,
+        /// Use the closest match when the device name is ambiguous
+        #[arg(long)]
+        best: bool,
+        /// Name of the application asking for the switch, shown in the
+        /// notification and the switch history
+        #[arg(long)]
+        app: Option<String>,
+    },
+    /// Stop the running daemon from switching devices automatically, e.g. during a call
+    Pause {
+        /// Resume automatically after this many seconds instead of waiting for `resume`
+        #[arg(long, value_name = "SECONDS")]
+        pause_for: Option<u64>,
+    },
+    /// Let the running daemon switch devices automatically again
+    Resume,
+    /// Set the output volume of the default output device, or of another output device
+    SetVolume {
+        /// Volume from 0.0 (muted) to 1.0 (full)
+        volume: f32,
+        /// Output device to change instead of the default output
+        #[arg(short, long)]
+        device: Option<String>,
+        /// Use the closest match when the device name is ambiguous
+        #[arg(long)]
+        best: bool,
     },
     /// Install system service
     InstallService,
     /// Uninstall system service
-    UninstallService,
+    UninstallService {
+        /// Remove the plist without running `launchctl unload` first
+        #[arg(long)]
+        force: bool,
+    },
     /// Clean up old log files
     CleanupLogs {
         /// Number of days to keep (default: 30)
@@ -88,29 +214,107 @@ enum Commands {
         /// Device name to inspect
         #[arg(short, long)]
         device: String,
+        /// Use the closest match when the device name is ambiguous
+        #[arg(long)]
+        best: bool,
+        /// List every supported stream format
+        #[arg(long)]
+        formats: bool,
     },
     /// Check if a device is currently available
     CheckDevice {
         /// Device name to check
         #[arg(short, long)]
         device: String,
+        /// Use the closest match when the device name is ambiguous
+        #[arg(long)]
+        best: bool,
+        /// Preview whether connecting this device would trigger a switch
+        #[arg(long)]
+        simulate_connect: bool,
+    },
+    /// Wait until a device appears, e.g. after powering on a USB hub port
+    WaitForDevice {
+        /// Device name, or part of it, to wait for
+        #[arg(short, long)]
+        device: String,
+        /// Give up after this many milliseconds
+        #[arg(long, default_value = "10000")]
+        timeout_ms: u64,
     },
     /// Show current service status and configuration
-    Status,
+    Status {
+        /// Also show the priority score of each available device
+        #[arg(long)]
+        include_scores: bool,
+        /// Time device enumeration to diagnose slow responses
+        #[arg(long, hide = true)]
+        benchmark: bool,
+    },
     /// Show current active/selected devices
     ShowCurrent,
+    /// Show which devices the rules would select right now, without switching
+    Simulate {
+        /// Use the rules of this profile instead
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Show how every rule judges a device and how the connected devices rank
+    Explain {
+        /// Device name to explain
+        #[arg(short, long)]
+        device: String,
+        /// Use the closest match when the device name is ambiguous
+        #[arg(long)]
+        best: bool,
+    },
     /// Check if current devices match configured preferences
     CheckPreferences,
     /// Apply configured preferences by switching to preferred devices
     ApplyPreferences,
+    /// Check service health; exits 0 if healthy, 1 if degraded, 2 if critical
+    Health,
+    /// Show the most recent device switches and why they happened
+    History {
+        /// Number of switches to show
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+    },
+    /// Show switch counts and how often each rule caused a switch
+    Stats,
+    /// Check the environment and report what keeps the tool from working;
+    /// exits 0 only if every check passes
+    Diagnose,
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete for [default: the shell in $SHELL]
+        shell: Option<clap_complete::Shell>,
+        /// Write the script to the shell's completion directory instead of printing it
+        #[arg(long)]
+        completion_install: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Check if we're running in daemon mode
-    let is_daemon = matches!(cli.command, Some(Commands::Daemon));
+    // Before logging and config loading, neither of which may write to the script
+    if let Some(Commands::Completions {
+        shell,
+        completion_install,
+    }) = cli.command
+    {
+        return completions(shell, completion_install);
+    }
+
+    // Check if we're running in daemon mode; a bare invocation runs the daemon
+    // when the config sets daemon_mode, so it needs the same logging
+    let is_daemon = match cli.command {
+        Some(Commands::Daemon { .. }) => true,
+        None => !cli.no_daemon && daemon_mode_configured(cli.config.as_deref()),
+        Some(_) => false,
+    };
 
     // Initialize enhanced logging
     let logging_config = LoggingConfig {
@@ -120,9 +324,16 @@ async fn main() -> Result<()> {
             tracing::Level::INFO
         },
         file_output: is_daemon || !cli.no_file_logs,
-        console_output: true,
+        // Console logs share stdout with the JSON output
+        console_output: is_daemon || cli.format == OutputFormat::Text,
         log_dir: cli.log_dir.as_ref().map(|d| d.into()),
         json_format: cli.json_logs,
+        max_log_file_size_mb: cli.max_log_file_size_mb,
+        span_events: if cli.trace_spans {
+            SpanEvents::Full
+        } else {
+            SpanEvents::None
+        },
     };
 
     let (_guard, log_dir) = initialize_logging(logging_config)?;
@@ -138,35 +349,99 @@ async fn main() -> Result<()> {
 
     debug!("Starting audio device monitor");
 
+    // Before config loading, which replaces a missing config file and stops on a broken one
+    match cli.command {
+        Some(Commands::Diagnose) => {
+            return diagnose(cli.config.as_deref(), cli.log_dir.as_deref());
+        }
+        Some(Commands::AddRule {
+            ref name,
+            weight,
+            ref match_type,
+            input,
+            enabled,
+            dry_run,
+        }) => {
+            let mut rule = DeviceRule::new(name.clone(), weight, match_type.clone());
+            rule.enabled = enabled;
+            return add_rule(cli.config.as_deref(), rule, input, dry_run);
+        }
+        Some(Commands::RemoveRule {
+            ref name,
+            input,
+            dry_run,
+        }) => {
+            return remove_rule(cli.config.as_deref(), name, input, dry_run);
+        }
+        _ => {}
+    }
+
     // Load configuration
-    let config = Config::load(cli.config.as_deref())?;
+    let mut config = Config::load(cli.config.as_deref())?;
     debug!("Configuration loaded successfully");
 
+    if cli.no_daemon && config.general.daemon_mode {
+        debug!("Ignoring daemon_mode from configuration (--no-daemon)");
+        config.general.daemon_mode = false;
+    }
+
+    let json_output = JsonOutput::for_format(cli.format, cli.compact);
+
     // Handle commands
     match cli.command {
         Some(Commands::ListDevices { verbose }) => {
-            list_devices(verbose).await?;
+            list_devices(verbose, json_output).await?;
         }
         Some(Commands::TestMonitor) => {
             test_monitor().await?;
         }
-        Some(Commands::Daemon) => {
-            run_daemon(cli.config.as_deref()).await?;
+        Some(Commands::Daemon { force, profile }) => {
+            run_daemon(cli.config.as_deref(), force, profile.as_deref()).await?;
         }
-        Some(Commands::CheckConfig) => {
-            check_config(&config)?;
+        Some(Commands::CheckConfig { strict, profile }) => {
+            let config = with_profile(config, profile.as_deref())?;
+            check_config(&config, strict)?;
+        }
+        Some(Commands::ListRules { json }) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            let json = json_output.or(json.then_some(JsonOutput {
+                compact: cli.compact,
+            }));
+            list_rules(&mut out, &config, json, !cli.no_color)?;
         }
         Some(Commands::ShowDefault) => {
-            show_default_devices().await?;
+            show_default_devices(json_output).await?;
+        }
+        Some(Commands::Switch {
+            device,
+            input,
+            best,
+            app,
+        }) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            switch_device(&device, input, best, app.as_deref(), &mut out).await?;
+        }
+        Some(Commands::Pause { pause_for }) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            pause_switching(pause_for, &mut out).await?;
+        }
+        Some(Commands::Resume) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            resume_switching(&mut out)?;
         }
-        Some(Commands::Switch { device, input }) => {
-            switch_device(&device, input).await?;
+        Some(Commands::SetVolume {
+            volume,
+            device,
+            best,
+        }) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            set_volume(volume, device.as_deref(), best, &mut out)?;
         }
         Some(Commands::InstallService) => {
             install_service()?;
         }
-        Some(Commands::UninstallService) => {
-            uninstall_service()?;
+        Some(Commands::UninstallService { force }) => {
+            uninstall_service(force)?;
         }
         Some(Commands::CleanupLogs { keep_days }) => {
             cleanup_logs(keep_days)?;
@@ -174,23 +449,68 @@ async fn main() -> Result<()> {
         Some(Commands::TestNotification) => {
             test_notification()?;
         }
-        Some(Commands::DeviceInfo { device }) => {
-            device_info(&device).await?;
+        Some(Commands::DeviceInfo {
+            device,
+            best,
+            formats,
+        }) => {
+            device_info(&device, best, formats, json_output).await?;
         }
-        Some(Commands::CheckDevice { device }) => {
-            check_device(&device).await?;
+        Some(Commands::CheckDevice {
+            device,
+            best,
+            simulate_connect,
+        }) => {
+            if simulate_connect {
+                simulate_device_connect(&device, best).await?;
+            } else {
+                check_device(&device, best, json_output).await?;
+            }
+        }
+        Some(Commands::WaitForDevice { device, timeout_ms }) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            wait_for_device(&mut out, &device, timeout_ms)?;
         }
-        Some(Commands::Status) => {
-            show_status().await?;
+        Some(Commands::Status {
+            include_scores,
+            benchmark,
+        }) => {
+            show_status(include_scores, benchmark, json_output).await?;
         }
         Some(Commands::ShowCurrent) => {
-            show_current_devices().await?;
+            show_current_devices(json_output).await?;
+        }
+        Some(Commands::Simulate { profile }) => {
+            let config = with_profile(config, profile.as_deref())?;
+            simulate_selection(&config, json_output)?;
+        }
+        Some(Commands::Explain { device, best }) => {
+            explain_device(&config, &device, best, json_output)?;
         }
         Some(Commands::CheckPreferences) => {
             check_preferences().await?;
         }
         Some(Commands::ApplyPreferences) => {
-            apply_preferences().await?;
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            apply_preferences(&mut out).await?;
+        }
+        Some(Commands::Health) => {
+            let mut out = QuietOutput::stdout(cli.quiet, cli.verbose);
+            health_check(&mut out)?;
+        }
+        Some(Commands::History { lines }) => {
+            show_history(lines)?;
+        }
+        Some(Commands::Stats) => {
+            show_stats(json_output)?;
+        }
+        Some(Commands::Completions { .. }) => unreachable!("handled before logging starts"),
+        Some(Commands::Diagnose | Commands::AddRule { .. } | Commands::RemoveRule { .. }) => {
+            unreachable!("handled before config loading")
+        }
+        None if config.general.daemon_mode => {
+            // daemon_mode makes the daemon the default command
+            run_daemon(cli.config.as_deref(), false, None).await?;
         }
         None => {
             // No command specified - print help
@@ -203,12 +523,17 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn list_devices(verbose: bool) -> Result<()> {
+async fn list_devices(verbose: bool, json: Option<JsonOutput>) -> Result<()> {
     debug!("Listing audio devices");
 
     let controller = audio::controller::DeviceController::new()?;
     let devices = controller.enumerate_devices()?;
 
+    if let Some(json) = json {
+        json.write(&mut std::io::stdout(), &devices)?;
+        return Ok(());
+    }
+
     println!("Available audio devices:");
     if devices.is_empty() {
         println!("  No audio devices found!");
@@ -216,28 +541,24 @@ async fn list_devices(verbose: bool) -> Result<()> {
     }
 
     for (i, device) in devices.iter().enumerate() {
-        println!("  {}. {}", i + 1, device);
-    }
-
-    // Show default devices
-    if let Ok(Some(default_input)) = controller.get_default_input_device() {
-        println!("Default input: {}", default_input.name);
-    }
-
-    if let Ok(Some(default_output)) = controller.get_default_output_device() {
-        println!("Default output: {}", default_output.name);
-    }
-
-    if verbose {
-        println!("\n--- Detailed Device Information ---");
-        for device in &devices {
-            if let Ok(info) = controller.get_device_info(device) {
-                println!("Device: {}", info.name);
-                println!("  UID: {}", info.uid);
-                println!("  Type: {}", info.device_type);
-                println!("  Default: {}", info.is_default);
-                println!();
+        // enumerate_devices already flags the current defaults
+        if verbose {
+            println!("  {}. {}", i + 1, device.display_verbose());
+            let current_format = controller
+                .get_device_info(device)
+                .ok()
+                .and_then(|info| info.current_format());
+            println!(
+                "    Current Format: {}",
+                current_format.as_deref().unwrap_or("Unknown")
+            );
+            if device.is_default {
+                println!("    ✓ Default");
             }
+        } else if device.is_default {
+            println!("  {}. {} ✓ Default", i + 1, device.display_long());
+        } else {
+            println!("  {}. {}", i + 1, device.display_long());
         }
     }
 
@@ -251,7 +572,7 @@ async fn test_monitor() -> Result<()> {
 
     // Load configuration and create monitor
     let config = Config::load(None)?;
-    let monitor = AudioDeviceMonitor::new(config)?;
+    let monitor = AudioDeviceMonitor::new_production(&config)?;
 
     // Start monitoring in async mode
     monitor.start_monitoring_async().await?;
@@ -265,56 +586,221 @@ async fn test_monitor() -> Result<()> {
     Ok(())
 }
 
-async fn run_daemon(config_path: Option<&str>) -> Result<()> {
+async fn run_daemon(config_path: Option<&str>, force: bool, profile: Option<&str>) -> Result<()> {
     info!("Starting daemon mode");
 
+    let lock_path = service::lock::default_lock_path()?;
+    if force {
+        service::lock::stop_running_instance(&lock_path)?;
+    }
+
     // Create the service with either custom or default config path
-    let mut service = if let Some(path) = config_path {
+    let service = if let Some(path) = config_path {
         let config_path = std::path::PathBuf::from(path);
         AudioDeviceService::new_production(config_path)?
     } else {
         AudioDeviceService::new_with_default_config()?
     };
+    let service = service.with_daemon_lock(lock_path);
+    let service = match preference_debugging::default_history_path() {
+        Ok(history_path) => service.with_switch_history(history_path),
+        Err(e) => {
+            warn!("Switch history is unavailable: {}", e);
+            service
+        }
+    };
+    let mut service = match default_socket_path() {
+        Ok(socket_path) => service.with_ipc_socket(socket_path),
+        Err(e) => {
+            warn!("Runtime control over IPC is unavailable: {}", e);
+            service
+        }
+    };
+
+    if profile.is_some() {
+        service.set_profile(profile)?;
+    }
 
     println!("Audio device monitor daemon started");
     println!("  Enhanced signal handling enabled");
     println!("  Send SIGTERM or SIGINT to stop gracefully");
     println!("  Send SIGHUP to reload configuration");
+    println!("  Switch and show-current commands talk to this daemon while it runs");
 
     // Start the service (this will block until shutdown)
     service.start()?;
+    service.graceful_shutdown()?;
 
     info!("Daemon stopped");
     Ok(())
 }
 
-fn check_config(config: &Config) -> Result<()> {
+/// `config` with `profile` applied, if one is given
+fn with_profile(config: Config, profile: Option<&str>) -> Result<Config> {
+    match profile {
+        Some(profile) => config.with_profile(profile),
+        None => Ok(config),
+    }
+}
+
+fn check_config(config: &Config, strict: bool) -> Result<()> {
     debug!("Validating configuration");
 
     println!("Configuration validation:");
     println!("  ✓ Configuration file parsed successfully");
-    println!("  ✓ Output devices: {}", config.output_devices.len());
-    println!("  ✓ Input devices: {}", config.input_devices.len());
+    println!("  ✓ {}", config.summarize());
+
+    for warning in config.lint_warnings() {
+        println!("  ⚠️  {warning}");
+    }
+
+    let conflicts = config.validate_rules();
+    for conflict in &conflicts {
+        println!("  ⚠️  [{}] {conflict}", conflict.kind.label());
+    }
+
+    if !conflicts.is_empty() {
+        println!();
+        println!("Rule conflicts:");
+        for kind in [ConflictKind::SameWeight, ConflictKind::SubsumedBy] {
+            if conflicts.iter().any(|conflict| conflict.kind == kind) {
+                println!("  {}: {}", kind.label(), kind.description());
+            }
+        }
+
+        if strict {
+            return Err(anyhow::anyhow!(
+                "{} rule conflict(s) found",
+                conflicts.len()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn add_rule(config_path: Option<&str>, rule: DeviceRule, input: bool, dry_run: bool) -> Result<()> {
+    let loader = config::ConfigLoader::new_production(resolve_config_path(config_path)?);
+    let edit = loader.add_rule(rule, input, dry_run)?;
+
+    let action = if dry_run { "Would add" } else { "Added" };
+    print_rule_edit(action, loader.get_config_path(), &edit)
+}
+
+fn remove_rule(config_path: Option<&str>, name: &str, input: bool, dry_run: bool) -> Result<()> {
+    let loader = config::ConfigLoader::new_production(resolve_config_path(config_path)?);
+    let edit = loader.remove_rule(name, input, dry_run)?;
+
+    let action = if dry_run { "Would remove" } else { "Removed" };
+    print_rule_edit(action, loader.get_config_path(), &edit)
+}
+
+/// Print the edited rule as TOML, followed by any conflicts the edit introduced
+fn print_rule_edit(action: &str, config_path: &std::path::Path, edit: &RuleEdit) -> Result<()> {
+    let direction = if edit.input { "input" } else { "output" };
+    println!("{action} {direction} rule in {}:", config_path.display());
+    println!();
+    print!("{}", edit.to_toml()?);
+
+    if !edit.new_conflicts.is_empty() {
+        println!();
+        println!("New rule conflicts:");
+        for conflict in &edit.new_conflicts {
+            println!("  ⚠️  [{}] {conflict}", conflict.kind.label());
+        }
+    }
 
-    // Additional validation will be added as we implement more features
+    Ok(())
+}
+
+fn list_rules(
+    out: &mut impl Write,
+    config: &Config,
+    json: Option<JsonOutput>,
+    color: bool,
+) -> Result<()> {
+    debug!("Listing device rules");
+
+    let rules = RuleListing::from_config(config);
+    if let Some(json) = json {
+        json.write(out, &rules)?;
+    } else if rules.is_empty() {
+        writeln!(out, "No device rules configured")?;
+    } else {
+        write_rules_table(out, &rules, color)?;
+    }
+
+    Ok(())
+}
+
+fn set_volume(
+    volume: f32,
+    device_name: Option<&str>,
+    best: bool,
+    out: &mut impl Write,
+) -> Result<()> {
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(anyhow::anyhow!(
+            "Volume must be between 0.0 and 1.0, got {}",
+            volume
+        ));
+    }
+
+    let controller = audio::controller::DeviceController::new()?;
+
+    let Some(device_name) = device_name else {
+        controller.set_system_volume(volume)?;
+        writeln!(out, "✓ Set output volume to {:.0}%", volume * 100.0)?;
+        return Ok(());
+    };
 
+    let matches: Vec<AudioDevice> = controller
+        .get_device_by_name_fuzzy(device_name)?
+        .into_iter()
+        .filter(|d| d.device_type != DeviceType::Input)
+        .collect();
+    let device = select_device_match(device_name, &matches, best)?
+        .ok_or_else(|| anyhow::anyhow!("Output device '{}' not found", device_name))?;
+    let device_id = device
+        .id
+        .parse()
+        .with_context(|| format!("Invalid CoreAudio device ID: {}", device.id))?;
+
+    controller.set_output_volume(device_id, volume)?;
+    writeln!(
+        out,
+        "✓ Set output volume of {} to {:.0}%",
+        device.name,
+        volume * 100.0
+    )?;
     Ok(())
 }
 
-async fn show_default_devices() -> Result<()> {
+async fn show_default_devices(json: Option<JsonOutput>) -> Result<()> {
     debug!("Showing current default devices");
 
     let controller = audio::controller::DeviceController::new()?;
+    let default_input = controller.get_default_input_device().ok().flatten();
+    let default_output = controller.get_default_output_device().ok().flatten();
+
+    if let Some(json) = json {
+        let defaults = serde_json::json!({
+            "input": default_input,
+            "output": default_output,
+        });
+        json.write(&mut std::io::stdout(), &defaults)?;
+        return Ok(());
+    }
 
     println!("Current default devices:");
 
-    if let Ok(Some(default_input)) = controller.get_default_input_device() {
+    if let Some(default_input) = default_input {
         println!("  Input:  {default_input}");
     } else {
         println!("  Input:  None available");
     }
 
-    if let Ok(Some(default_output)) = controller.get_default_output_device() {
+    if let Some(default_output) = default_output {
         println!("  Output: {default_output}");
     } else {
         println!("  Output: None available");
@@ -323,7 +809,13 @@ async fn show_default_devices() -> Result<()> {
     Ok(())
 }
 
-async fn switch_device(device_name: &str, is_input: bool) -> Result<()> {
+async fn switch_device(
+    device_name: &str,
+    is_input: bool,
+    best: bool,
+    app: Option<&str>,
+    out: &mut impl Write,
+) -> Result<()> {
     debug!(
         "Manual device switch requested: {} ({})",
         device_name,
@@ -331,14 +823,63 @@ async fn switch_device(device_name: &str, is_input: bool) -> Result<()> {
     );
 
     let controller = audio::controller::DeviceController::new()?;
+    let wanted_type = if is_input {
+        DeviceType::Input
+    } else {
+        DeviceType::Output
+    };
+    // Fuzzy rather than the service's case-sensitive substring search, so
+    // `--device airpod` still finds "AirPods Pro"
+    let matches: Vec<AudioDevice> = controller
+        .get_device_by_name_fuzzy(device_name)?
+        .into_iter()
+        .filter(|d| d.device_type == wanted_type)
+        .collect();
+
+    // Fall back to the name as given so an unknown device reports the usual error
+    let device_name = match select_device_match(device_name, &matches, best)? {
+        Some(device) => device.name,
+        None => device_name.to_string(),
+    };
+    let device_name = device_name.as_str();
+
+    // A running daemon switches by exact name, so it gets the resolved one
+    let request = IpcRequest::Switch {
+        device: device_name.to_string(),
+        direction: if is_input {
+            SwitchDirection::Input
+        } else {
+            SwitchDirection::Output
+        },
+        requester: app.map(str::to_string),
+    };
+    if let Some(result) = request_from_daemon(&request) {
+        result?;
+        writeln!(
+            out,
+            "✓ Running daemon switched {} device to: {}",
+            if is_input { "input" } else { "output" },
+            device_name
+        )?;
+        return Ok(());
+    }
+
     let config = Config::load(None)?;
+    let controller = controller.with_sync_system_alerts(config.general.sync_system_alerts);
     let notification_manager = DefaultNotificationManager::new(&config);
+    let reason = match app {
+        Some(app_name) => crate::notifications::SwitchReason::AppRequest {
+            app_name: app_name.to_string(),
+        },
+        None => crate::notifications::SwitchReason::Manual,
+    };
 
-    println!(
+    writeln!(
+        out,
         "Switching {} device to: {}",
         if is_input { "input" } else { "output" },
         device_name
-    );
+    )?;
 
     let result = if is_input {
         controller.set_default_input_device(device_name)
@@ -348,25 +889,24 @@ async fn switch_device(device_name: &str, is_input: bool) -> Result<()> {
 
     match result {
         Ok(()) => {
-            println!(
+            writeln!(
+                out,
                 "✓ Successfully switched {} device to: {}",
                 if is_input { "input" } else { "output" },
                 device_name
-            );
+            )?;
 
             // Send manual switch notification
             if let Ok(devices) = controller.enumerate_devices() {
                 if let Some(device) = devices.iter().find(|d| d.name == device_name) {
-                    if let Err(e) = notification_manager
-                        .device_switched(device, crate::notifications::SwitchReason::Manual)
-                    {
+                    if let Err(e) = notification_manager.device_switched(device, reason) {
                         warn!("Failed to send manual switch notification: {}", e);
                     }
                 }
             }
         }
         Err(e) => {
-            println!("✗ Failed to switch device: {e}");
+            eprintln!("✗ Failed to switch device: {e}");
 
             // Send switch failed notification
             if let Err(notification_err) =
@@ -385,6 +925,37 @@ async fn switch_device(device_name: &str, is_input: bool) -> Result<()> {
     Ok(())
 }
 
+/// Pause the running daemon's automatic switching. With `pause_for`, wait that
+/// many seconds (or until Ctrl+C) and resume.
+async fn pause_switching(pause_for: Option<u64>, out: &mut impl Write) -> Result<()> {
+    request_from_running_daemon(&IpcRequest::Pause)?;
+
+    let Some(seconds) = pause_for else {
+        writeln!(
+            out,
+            "✓ Automatic switching paused; run `resume` to switch again"
+        )?;
+        return Ok(());
+    };
+
+    writeln!(
+        out,
+        "✓ Automatic switching paused for {}",
+        format_uptime(seconds)
+    )?;
+    tokio::select! {
+        () = tokio::time::sleep(std::time::Duration::from_secs(seconds)) => {}
+        _ = tokio::signal::ctrl_c() => debug!("Interrupted, resuming early"),
+    }
+    resume_switching(out)
+}
+
+fn resume_switching(out: &mut impl Write) -> Result<()> {
+    request_from_running_daemon(&IpcRequest::Resume)?;
+    writeln!(out, "✓ Automatic switching resumed")?;
+    Ok(())
+}
+
 fn install_service() -> Result<()> {
     info!("Installing system service");
 
@@ -400,15 +971,15 @@ fn install_service() -> Result<()> {
     Ok(())
 }
 
-fn uninstall_service() -> Result<()> {
+fn uninstall_service(force: bool) -> Result<()> {
     info!("Uninstalling system service");
 
-    ServiceInstaller::uninstall_launch_agent()?;
+    ServiceInstaller::uninstall_launch_agent(force)?;
 
     println!("✓ Audio device monitor service uninstalled successfully");
-    println!(
-        "  To stop if running: launchctl unload ~/Library/LaunchAgents/com.audiodevicemonitor.daemon.plist"
-    );
+    if force {
+        println!("  The service was not unloaded and keeps running until logout or reboot");
+    }
 
     Ok(())
 }
@@ -455,56 +1026,200 @@ fn test_notification() -> Result<()> {
     Ok(())
 }
 
-async fn device_info(device_name: &str) -> Result<()> {
+async fn device_info(
+    device_name: &str,
+    best: bool,
+    formats: bool,
+    json: Option<JsonOutput>,
+) -> Result<()> {
     debug!("Getting device information for: {}", device_name);
 
     let controller = audio::controller::DeviceController::new()?;
-    let devices = controller.enumerate_devices()?;
+    let matches = controller.get_device_by_name_fuzzy(device_name)?;
 
     // Find the device
-    let device = devices
-        .iter()
-        .find(|d| d.name.contains(device_name) || d.name == device_name)
+    let device = select_device_match(device_name, &matches, best)?
         .ok_or_else(|| anyhow::anyhow!("Device '{}' not found", device_name))?;
 
+    if let Some(json) = json {
+        let latency = device_latency(&controller, &device).ok();
+        let mut details = serde_json::json!({
+            "device": device,
+            "info": controller.get_device_info(&device).ok(),
+            "latency": latency.map(|(frames, ms, sample_rate)| serde_json::json!({
+                "frames": frames,
+                "ms": ms,
+                "sample_rate": sample_rate,
+            })),
+        });
+        if formats {
+            let stream_formats: serde_json::Map<String, serde_json::Value> =
+                stream_formats(&controller, &device)?
+                    .into_iter()
+                    .map(|(label, formats)| (label.to_lowercase(), serde_json::json!(formats)))
+                    .collect();
+            details["formats"] = stream_formats.into();
+        }
+        json.write(&mut std::io::stdout(), &details)?;
+        return Ok(());
+    }
+
     // Get detailed info
-    if let Ok(info) = controller.get_device_info(device) {
+    if let Ok(info) = controller.get_device_info(&device) {
         println!("Device Information:");
         println!("  Name: {}", info.name);
         println!("  UID: {}", info.uid);
         println!("  Type: {}", info.device_type);
+        match device.transport_type {
+            Some(transport_type) => println!("  Transport: {transport_type}"),
+            None => println!("  Transport: Unknown"),
+        }
         println!("  Default: {}", if info.is_default { "Yes" } else { "No" });
         println!(
             "  Available: {}",
             if device.is_available { "Yes" } else { "No" }
         );
+        println!(
+            "  Current Format: {}",
+            info.current_format().as_deref().unwrap_or("Unknown")
+        );
+        match info.channels {
+            Some(channels) => println!("  Channels: {channels}"),
+            None => println!("  Channels: Unknown"),
+        }
+        if info.available_sample_rates.is_empty() {
+            println!("  Sample Rates: Unknown");
+        } else {
+            let rates: Vec<String> = info
+                .available_sample_rates
+                .iter()
+                .map(|rate| format!("{rate} Hz"))
+                .collect();
+            println!("  Sample Rates: {}", rates.join(", "));
+        }
+        print_device_latency(&controller, &device);
     } else {
         println!(
             "Device '{}' found but detailed info unavailable",
             device.name
         );
+        if let Some(uid) = &device.uid {
+            println!("  UID: {uid}");
+        }
+    }
+
+    if formats {
+        print_stream_formats(&controller, &device)?;
+    }
+
+    Ok(())
+}
+
+/// Device latency as (frames, milliseconds, sample rate)
+fn device_latency(
+    controller: &audio::controller::DeviceController,
+    device: &audio::AudioDevice,
+) -> Result<(u32, f64, f64)> {
+    let frames = controller.get_device_latency(device)?;
+    let sample_rate = controller.get_nominal_sample_rate(device)?;
+    let ms = audio::device::latency_frames_to_ms(frames, sample_rate)?;
+    Ok((frames, ms, sample_rate))
+}
+
+/// Print the device latency in frames and milliseconds, e.g. "256 frames (5.3ms @ 48000Hz)"
+fn print_device_latency(
+    controller: &audio::controller::DeviceController,
+    device: &audio::AudioDevice,
+) {
+    match device_latency(controller, device) {
+        Ok((frames, ms, sample_rate)) => {
+            println!("  Latency: {frames} frames ({ms:.1}ms @ {sample_rate}Hz)")
+        }
+        Err(e) => {
+            debug!("Could not read latency for {}: {}", device.name, e);
+            println!("  Latency: Unknown");
+        }
+    }
+}
+
+/// The formats supported in each of the device's directions, labelled "Output" or "Input"
+fn stream_formats(
+    controller: &audio::controller::DeviceController,
+    device: &audio::AudioDevice,
+) -> Result<Vec<(&'static str, Vec<audio::device::StreamFormat>)>> {
+    let device_id: u32 = device
+        .id
+        .parse()
+        .with_context(|| format!("Invalid CoreAudio device ID: {}", device.id))?;
+    let directions: &[(&'static str, bool)] = match device.device_type {
+        audio::DeviceType::Output => &[("Output", false)],
+        audio::DeviceType::Input => &[("Input", true)],
+        audio::DeviceType::InputOutput => &[("Output", false), ("Input", true)],
+    };
+
+    directions
+        .iter()
+        .map(|&(label, is_input)| Ok((label, controller.list_stream_formats(device_id, is_input)?)))
+        .collect()
+}
+
+/// Print a table of the formats supported in each of the device's directions
+fn print_stream_formats(
+    controller: &audio::controller::DeviceController,
+    device: &audio::AudioDevice,
+) -> Result<()> {
+    for (label, formats) in stream_formats(controller, device)? {
+        println!("  {label} Formats:");
+        if formats.is_empty() {
+            println!("    None reported");
+            continue;
+        }
+
+        println!(
+            "    {:>11}  {:>9}  {:>8}  {:<7}",
+            "Sample Rate", "Bit Depth", "Channels", "Type"
+        );
+        for format in formats {
+            println!(
+                "    {:>11}  {:>9}  {:>8}  {:<7}",
+                format!("{} Hz", format.sample_rate),
+                format.bit_depth,
+                format.channels,
+                if format.is_float { "float" } else { "integer" }
+            );
+        }
     }
 
     Ok(())
 }
 
-async fn check_device(device_name: &str) -> Result<()> {
+async fn check_device(device_name: &str, best: bool, json: Option<JsonOutput>) -> Result<()> {
     debug!("Checking device availability: {}", device_name);
 
     let controller = audio::controller::DeviceController::new()?;
 
+    if let Some(json) = json {
+        let matches = controller.get_device_by_name_fuzzy(device_name)?;
+        let device = select_device_match(device_name, &matches, best)?;
+        let availability = serde_json::json!({
+            "query": device_name,
+            "available": device.as_ref().is_some_and(|d| d.is_available),
+            "device": device,
+        });
+        json.write(&mut std::io::stdout(), &availability)?;
+        return Ok(());
+    }
+
     // Check if device is available using the controller method
-    match controller.enumerate_devices() {
-        Ok(devices) => {
-            let device = devices
-                .iter()
-                .find(|d| d.name.contains(device_name) || d.name == device_name);
+    match controller.get_device_by_name_fuzzy(device_name) {
+        Ok(matches) => {
+            let device = select_device_match(device_name, &matches, best)?;
 
             match device {
                 Some(d) => {
                     println!(
                         "Device '{}': {}",
-                        device_name,
+                        d.name,
                         if d.is_available {
                             "✓ Available"
                         } else {
@@ -525,22 +1240,489 @@ async fn check_device(device_name: &str) -> Result<()> {
     Ok(())
 }
 
-async fn show_status() -> Result<()> {
+async fn simulate_device_connect(query: &str, best: bool) -> Result<()> {
+    debug!("Simulating connection of device: {}", query);
+
+    let config = Config::load(None)?;
+    let priority_manager = priority::DevicePriorityManager::new(&config);
+    let controller = audio::controller::DeviceController::new()?;
+    let all_devices = controller.enumerate_devices()?;
+
+    // Resolve the query the same way `check` does so partial names find connected hardware
+    let matches = controller.get_device_by_name_fuzzy(query)?;
+    let device_name = match select_device_match(query, &matches, best)? {
+        Some(device) => device.name,
+        None => query.to_string(),
+    };
+
+    // A connected device is treated as arriving fresh; otherwise imagine it in both directions
+    let (mut arriving, current): (Vec<AudioDevice>, Vec<AudioDevice>) =
+        all_devices.into_iter().partition(|d| d.name == device_name);
+    if arriving.is_empty() {
+        arriving = vec![
+            AudioDevice::new(
+                "simulated-output".to_string(),
+                device_name.to_string(),
+                DeviceType::Output,
+            ),
+            AudioDevice::new(
+                "simulated-input".to_string(),
+                device_name.to_string(),
+                DeviceType::Input,
+            ),
+        ];
+    }
+
+    println!("Simulated connection of '{device_name}':");
+    for device in &arriving {
+        match priority_manager.simulate_device_arrival(device, &current) {
+            Some(decision) => {
+                let rule_label = priority_manager
+                    .matching_rule(device)
+                    .map(|rule| rule.label())
+                    .or(decision.matched_rule)
+                    .unwrap_or_default();
+                let would_switch = match device.device_type {
+                    DeviceType::Input => decision.should_switch_input,
+                    DeviceType::Output => decision.should_switch_output,
+                    DeviceType::InputOutput => {
+                        decision.should_switch_output || decision.should_switch_input
+                    }
+                };
+                println!(
+                    "  {} ({}): matched rule '{}' -> {}",
+                    device.name,
+                    device.device_type,
+                    rule_label,
+                    if would_switch {
+                        "would switch"
+                    } else {
+                        "would not switch"
+                    }
+                );
+            }
+            None => {
+                println!(
+                    "  {} ({}): no enabled rule matches, would not switch",
+                    device.name, device.device_type
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print which devices the rules would select among the connected ones and why.
+/// Read-only: nothing is switched.
+fn simulate_selection(config: &Config, json: Option<JsonOutput>) -> Result<()> {
+    debug!("Simulating device selection");
+
+    let controller = audio::controller::DeviceController::new()?;
+    let devices = controller.enumerate_devices()?;
+    let priority_manager = priority::DevicePriorityManager::new(config);
+    let output = priority_manager.explain_output_selection(&devices);
+    let input = priority_manager.explain_input_selection(&devices);
+
+    if let Some(json) = json {
+        let selection = serde_json::json!({ "output": output, "input": input });
+        json.write(&mut std::io::stdout(), &selection)?;
+        return Ok(());
+    }
+
+    println!("Simulated selection (no devices are switched):");
+    print_selection_explanation("🔊 Output", &output);
+    print_selection_explanation("🎤 Input", &input);
+    Ok(())
+}
+
+fn print_selection_explanation(label: &str, explanation: &priority::SelectionExplanation) {
+    let Some(device) = &explanation.selected_device else {
+        println!("  {label}: none - no enabled rule selects a connected device");
+        return;
+    };
+
+    println!("  {label}: {}", device.name);
+    println!(
+        "     Rule: '{}' (weight {}, {})",
+        explanation.matched_rule_name.as_deref().unwrap_or_default(),
+        explanation.matched_rule_weight.unwrap_or_default(),
+        explanation
+            .matched_rule_match_type
+            .as_ref()
+            .map(|match_type| match_type.config_name())
+            .unwrap_or_default()
+    );
+    if !explanation.runners_up.is_empty() {
+        println!("     Runners-up:");
+        for (device, rule) in &explanation.runners_up {
+            println!(
+                "       {} - rule '{}' (weight {}, {})",
+                device.name,
+                rule.label(),
+                rule.weight,
+                rule.match_type.config_name()
+            );
+        }
+    }
+}
+
+/// Print the last `lines` switches from the daemon's history file, oldest first
+fn show_history(lines: usize) -> Result<()> {
+    debug!("Showing switch history");
+
+    // Only the path matters for reading; rotation happens when the daemon writes
+    let history = preference_debugging::SwitchHistory::new(
+        preference_debugging::default_history_path()?,
+        usize::MAX,
+    );
+    let events = history.recent(lines)?;
+
+    if events.is_empty() {
+        println!("No device switches recorded yet");
+        return Ok(());
+    }
+
+    println!("Last {} device switches:", events.len());
+    for event in &events {
+        let direction = match event.device_type {
+            audio::DeviceType::Input => "🎤",
+            audio::DeviceType::Output => "🔊",
+            audio::DeviceType::InputOutput => "🎧",
+        };
+        let rule = event
+            .matched_rule
+            .as_ref()
+            .map(|rule| format!(", rule '{rule}'"))
+            .unwrap_or_default();
+        println!(
+            "  {}  {} {} ({}{})",
+            preference_debugging::format_local_timestamp(event.timestamp),
+            direction,
+            event.device_name,
+            event.reason,
+            rule
+        );
+    }
+    Ok(())
+}
+
+/// Print switching statistics from the running daemon, or rebuilt from the
+/// switch history when no daemon answers
+fn show_stats(json: Option<JsonOutput>) -> Result<()> {
+    debug!("Showing switching statistics");
+
+    let from_daemon = match request_from_daemon(&IpcRequest::Stats) {
+        Some(Ok(Some(stats))) => Some(serde_json::from_value::<service::ServiceStatistics>(stats)?),
+        Some(Ok(None)) => {
+            warn!("Running daemon sent empty statistics, reading the switch history");
+            None
+        }
+        Some(Err(e)) => {
+            warn!("Running daemon could not report statistics, reading the switch history: {e:#}");
+            None
+        }
+        None => None,
+    };
+
+    let (source, statistics) = match from_daemon {
+        Some(statistics) => ("daemon", statistics),
+        None => {
+            let history = preference_debugging::SwitchHistory::new(
+                preference_debugging::default_history_path()?,
+                usize::MAX,
+            );
+            (
+                "history",
+                service::ServiceStatistics::from_history(&history.recent(usize::MAX)?),
+            )
+        }
+    };
+
+    if let Some(json) = json {
+        let mut value = serde_json::to_value(&statistics)?;
+        value["source"] = serde_json::json!(source);
+        json.write(&mut std::io::stdout(), &value)?;
+        return Ok(());
+    }
+
+    if source == "daemon" {
+        println!("Switching statistics (from running daemon):");
+        println!(
+            "  Uptime:          {}",
+            format_uptime(statistics.uptime_seconds)
+        );
+    } else {
+        println!("Switching statistics (from switch history, daemon not running):");
+    }
+    println!(
+        "  Switches:        {} ({} output, {} input)",
+        statistics.total_switch_count,
+        statistics.output_switch_count,
+        statistics.input_switch_count
+    );
+    if source == "daemon" {
+        println!("  Failed switches: {}", statistics.failed_switch_count);
+    }
+
+    let mut rule_hits: Vec<(String, u64)> = statistics.rule_hits.into_iter().collect();
+    rule_hits.sort_by(|(a_rule, a_hits), (b_rule, b_hits)| {
+        b_hits.cmp(a_hits).then_with(|| a_rule.cmp(b_rule))
+    });
+
+    println!();
+    if rule_hits.is_empty() {
+        println!("  No rule has caused a switch yet");
+        return Ok(());
+    }
+    println!("  {:>6}  Rule", "Hits");
+    for (rule, hits) in rule_hits {
+        println!("  {hits:>6}  {rule}");
+    }
+    Ok(())
+}
+
+/// "2h 5m 3s", leaving out leading zero units; also used for "3m 42s ago"
+fn format_uptime(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// The `--config` path, or the default config file location
+fn resolve_config_path(config_path: Option<&str>) -> Result<std::path::PathBuf> {
+    match config_path {
+        Some(path) => Ok(std::path::PathBuf::from(path)),
+        None => config::ConfigLoader::default_config_path(),
+    }
+}
+
+/// Whether the config file asks for the daemon when no command is given, read
+/// without `Config::load` since logging is not set up yet and that may create the file
+fn daemon_mode_configured(config_path: Option<&str>) -> bool {
+    resolve_config_path(config_path)
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .and_then(|content| content.parse::<Config>())
+        .is_ok_and(|config| config.general.daemon_mode)
+}
+
+/// Run the environment checks, printing each with a suggested fix when it
+/// fails, and exit with status 1 unless all of them pass
+fn diagnose(config_path: Option<&str>, log_dir: Option<&str>) -> Result<()> {
+    debug!("Diagnosing the environment");
+
+    let devices = audio::controller::DeviceController::new()
+        .and_then(|controller| controller.enumerate_devices());
+    let config_path = resolve_config_path(config_path)?;
+    let log_dir = match log_dir {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => get_default_log_dir()?,
+    };
+
+    let (config_check, config) = diagnostics::load_config_file(&config_path);
+    let listeners = audio::listener::CoreAudioListener::probe_listener_count(
+        &config.clone().unwrap_or_default(),
+    );
+    let checks = [
+        diagnostics::check_audio_devices(&devices),
+        diagnostics::check_property_listeners(&listeners),
+        config_check,
+        diagnostics::check_output_rules(config.as_ref()),
+        diagnostics::check_matching_device(config.as_ref(), devices.as_deref().unwrap_or_default()),
+        diagnostics::check_osascript(std::env::var_os("PATH").as_deref()),
+        diagnostics::check_launch_agent(&ServiceInstaller::get_launch_agent_path()?),
+        diagnostics::check_daemon(service::lock::lock_status(
+            &service::lock::default_lock_path()?,
+        )),
+        diagnostics::check_log_dir_writable(&log_dir),
+    ];
+
+    println!("Environment diagnosis:");
+    for check in &checks {
+        println!("  {}: {check}", check.name);
+        if let Some(failure) = &check.failure {
+            println!("      Fix: {}", failure.fix);
+        }
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+    println!();
+    if failed == 0 {
+        println!("All {} checks passed", checks.len());
+        return Ok(());
+    }
+    println!("{failed} of {} checks failed", checks.len());
+    std::process::exit(1);
+}
+
+/// Print every rule's verdict on one device and the current ranking of the
+/// connected devices. Read-only, so safe while the daemon runs.
+fn explain_device(
+    config: &Config,
+    device_name: &str,
+    best: bool,
+    json: Option<JsonOutput>,
+) -> Result<()> {
+    debug!("Explaining selection of device: {}", device_name);
+
+    let controller = audio::controller::DeviceController::new()?;
+    let devices = controller.enumerate_devices()?;
+    let matches = controller.get_device_by_name_fuzzy(device_name)?;
+    let device = select_device_match(device_name, &matches, best)?
+        .ok_or_else(|| anyhow::anyhow!("Device '{}' not found", device_name))?;
+
+    let priority_manager = priority::DevicePriorityManager::new(config);
+    let results = priority_manager.explain_device(&device);
+    let output_ranking = priority_manager.rank_output_devices(&devices);
+    let input_ranking = priority_manager.rank_input_devices(&devices);
+
+    if let Some(json) = json {
+        let ranking = |ranking: &[priority::RankedDevice]| -> Vec<serde_json::Value> {
+            ranking
+                .iter()
+                .map(|ranked| {
+                    serde_json::json!({
+                        "device": ranked.device.name,
+                        "score": ranked.score,
+                        "rule": ranked.matched_rule,
+                    })
+                })
+                .collect()
+        };
+        let explanation = serde_json::json!({
+            "device": device,
+            "rules": results,
+            "ranking": {
+                "output": ranking(&output_ranking),
+                "input": ranking(&input_ranking),
+            },
+        });
+        json.write(&mut std::io::stdout(), &explanation)?;
+        return Ok(());
+    }
+
+    println!("Rules for {} ({}):", device.name, device.device_type);
+    if results.is_empty() {
+        println!("  No rules configured");
+    }
+    for result in &results {
+        let direction = match result.direction {
+            audio::DeviceType::Input => "🎤",
+            _ => "🔊",
+        };
+        let verdict = match (result.matched, result.score) {
+            (true, Some(score)) => format!("matches, score {score}"),
+            (true, None) => "matches, but the rule is disabled".to_string(),
+            (false, _) => "does not match".to_string(),
+        };
+        println!(
+            "  {} {} '{}' ({}, weight {}, {}) - {}",
+            direction,
+            if result.matched { "✓" } else { "✗" },
+            result.rule.label(),
+            result.rule.match_type.config_name(),
+            result.rule.weight,
+            if result.rule.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            verdict
+        );
+    }
+
+    println!();
+    println!("Current ranking:");
+    for (label, ranking) in [("🔊 Output", &output_ranking), ("🎤 Input", &input_ranking)] {
+        println!("  {label}:");
+        if ranking.is_empty() {
+            println!("     No connected devices");
+        }
+        for (i, ranked) in ranking.iter().enumerate() {
+            let marker = if ranked.device.name == device.name {
+                "  ←"
+            } else {
+                ""
+            };
+            let rule = ranked
+                .matched_rule
+                .as_ref()
+                .map(|rule| format!(", rule '{rule}'"))
+                .unwrap_or_default();
+            println!(
+                "     {}. {} (score {}{}){}",
+                i + 1,
+                ranked.device.name,
+                ranked.score,
+                rule,
+                marker
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Resolve fuzzy lookup results to a single device, listing candidates when ambiguous
+fn select_device_match(
+    query: &str,
+    matches: &[AudioDevice],
+    best: bool,
+) -> Result<Option<AudioDevice>> {
+    let Some(first) = matches.first() else {
+        return Ok(None);
+    };
+
+    // Input and output entries of the same hardware share a name, so only
+    // differing names make the lookup ambiguous
+    let ambiguous = matches.iter().any(|d| d.name != first.name);
+    if best || !ambiguous || first.name == query {
+        return Ok(Some(first.clone()));
+    }
+
+    println!("Multiple devices match '{query}':");
+    for device in matches {
+        println!(
+            "  {} ({}) - similarity {:.2}",
+            device.name,
+            device.device_type,
+            name_similarity(query, &device.name)
+        );
+    }
+    println!("Use a more specific name, or pass --best to use the closest match");
+
+    Err(anyhow::anyhow!("Device name '{}' is ambiguous", query))
+}
+
+async fn show_status(
+    include_scores: bool,
+    benchmark: bool,
+    json: Option<JsonOutput>,
+) -> Result<()> {
     debug!("Showing service status");
 
+    if let Some(json) = json {
+        let status = status_json(include_scores, benchmark).await?;
+        json.write(&mut std::io::stdout(), &status)?;
+        return Ok(());
+    }
+
     println!("Audio Device Monitor Status:");
     println!("============================");
 
     // Load and show config
     let config = Config::load(None)?;
     println!("  Configuration:");
-    println!("    Check interval: {}ms", config.general.check_interval_ms);
+    println!("    {}", config.summarize());
     println!("    Log level: {}", config.general.log_level);
-    println!("    Output device rules: {}", config.output_devices.len());
-    println!("    Input device rules: {}", config.input_devices.len());
 
     // Show current devices
     let controller = audio::controller::DeviceController::new()?;
+    let daemon_status = daemon_status(include_scores);
 
     if let Ok(Some(output)) = controller.get_default_output_device() {
         println!("    Current output: {}", output.name);
@@ -550,17 +1732,269 @@ async fn show_status() -> Result<()> {
         println!("    Current input: {}", input.name);
     }
 
+    if include_scores {
+        let (output_scores, input_scores) =
+            device_scores(daemon_status.as_ref(), &controller, &config)?;
+        print_device_scores("Output", output_scores);
+        print_device_scores("Input", input_scores);
+    }
+
+    if benchmark {
+        let service = service::AudioDeviceService::new_with_default_config()?;
+        let latency = service.benchmark_device_enumeration()?;
+        println!("    Enumeration latency: {}ms", latency.as_millis());
+    }
+
     // Show process info
     println!("    Process ID: {}", std::process::id());
+    match service::lock::default_lock_path() {
+        Ok(lock_path) => println!("    Daemon: {}", service::lock::lock_status(&lock_path)),
+        Err(e) => warn!("Failed to locate the daemon PID file: {}", e),
+    }
+    if let Some(status) = &daemon_status {
+        print_daemon_status(status);
+    }
+
+    // Compare installed LaunchAgent binaries with this one
+    println!("  Version:");
+    println!(
+        "    Current binary: {}",
+        ServiceInstaller::current_version()
+    );
+    match ServiceInstaller::list_versions() {
+        Ok(versions) if versions.is_empty() => {
+            println!("    Installed service: not installed");
+        }
+        Ok(versions) => {
+            for installed in &versions {
+                let binary = installed
+                    .binary_path
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "unknown binary".to_string());
+                let version = installed.version.as_deref().unwrap_or("unknown");
+                println!("    Installed service: {version} ({binary})");
+
+                if installed.differs_from_current() {
+                    println!(
+                        "    ⚠️  {} runs version {}, but this binary is {}. Reinstall the service to update it.",
+                        installed.plist_path.display(),
+                        version,
+                        ServiceInstaller::current_version()
+                    );
+                }
+            }
+        }
+        Err(e) => warn!("Failed to list installed service versions: {}", e),
+    }
+
+    // A failed release check (e.g. offline) should not fail the status command
+    match service::updates::fetch_available_updates(ServiceInstaller::current_version()).await {
+        Ok(updates) => {
+            for update in updates {
+                println!("    Update available: {}", update.version);
+            }
+        }
+        Err(e) => debug!("Failed to check for updates: {}", e),
+    }
 
     Ok(())
 }
 
-async fn show_current_devices() -> Result<()> {
+/// The running daemon's own view, from its `status` reply
+fn print_daemon_status(status: &serde_json::Value) {
+    if let Some(uptime) = status["uptime_seconds"].as_u64() {
+        println!("    Daemon uptime: {}", format_uptime(uptime));
+    }
+    if let Some(profile) = status["profile"].as_str() {
+        println!("    Daemon profile: {profile}");
+    }
+    for (label, key) in [("output", "current_output"), ("input", "current_input")] {
+        if let Some(device) = status[key].as_str() {
+            println!("    Daemon {label}: {device}");
+        }
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    for (label, key) in [
+        ("output", "last_output_switch"),
+        ("input", "last_input_switch"),
+    ] {
+        if let Some(switched_at) = status[key].as_u64() {
+            println!(
+                "    Last {label} switch: {} ago",
+                format_uptime(now.saturating_sub(switched_at))
+            );
+        }
+    }
+    if status["switching_paused"].as_bool() == Some(true) {
+        println!("    Automatic switching: PAUSED");
+    }
+    if let Some(error) = status["last_error"].as_str() {
+        println!("    ⚠ Last error: {error}");
+    }
+}
+
+/// What `status` reports, for `--format json`. Uptime comes from the running
+/// daemon over its control socket and is null when none answers.
+async fn status_json(include_scores: bool, benchmark: bool) -> Result<serde_json::Value> {
+    let config = Config::load(None)?;
+    let controller = audio::controller::DeviceController::new()?;
+    let name = |device: Result<Option<AudioDevice>>| device.ok().flatten().map(|d| d.name);
+
+    let lock_status = service::lock::default_lock_path()
+        .map(|path| service::lock::lock_status(&path))
+        .unwrap_or(service::lock::LockStatus::NotRunning);
+    let daemon_status = daemon_status(include_scores);
+    let from_daemon = |key: &str| daemon_status.as_ref().map(|status| status[key].clone());
+
+    let mut status = serde_json::json!({
+        "process_id": std::process::id(),
+        "version": ServiceInstaller::current_version(),
+        "config_summary": config.summarize(),
+        "log_level": config.general.log_level,
+        "current_output": name(controller.get_default_output_device()),
+        "current_input": name(controller.get_default_input_device()),
+        "daemon": {
+            "state": lock_status.state(),
+            "pid": lock_status.pid(),
+            "uptime_seconds": from_daemon("uptime_seconds"),
+            "switching_paused": from_daemon("switching_paused"),
+            "last_error": from_daemon("last_error"),
+            "last_output_switch": from_daemon("last_output_switch"),
+            "last_input_switch": from_daemon("last_input_switch"),
+        },
+    });
+
+    if include_scores {
+        let (output_scores, input_scores) =
+            device_scores(daemon_status.as_ref(), &controller, &config)?;
+        status["scores"] = serde_json::json!({
+            "output": output_scores,
+            "input": input_scores,
+        });
+    }
+
+    if benchmark {
+        let service = service::AudioDeviceService::new_with_default_config()?;
+        let latency = service.benchmark_device_enumeration()?;
+        status["enumeration_latency_ms"] = serde_json::json!(latency.as_millis());
+    }
+
+    // A failed release check (e.g. offline) should not fail the status command
+    match service::updates::fetch_available_updates(ServiceInstaller::current_version()).await {
+        Ok(updates) => status["updates"] = serde_json::json!(updates),
+        Err(e) => debug!("Failed to check for updates: {}", e),
+    }
+
+    Ok(status)
+}
+
+/// The running daemon's status over its control socket, or `None` when no daemon answers
+fn daemon_status(include_scores: bool) -> Option<serde_json::Value> {
+    match request_from_daemon(&IpcRequest::Status { include_scores }) {
+        Some(Ok(status)) => status,
+        Some(Err(e)) => {
+            debug!("Running daemon could not report status: {:#}", e);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Output and input scores by device name, as the running daemon computes them from
+/// its power source and recent disconnects, or from the rules alone without a daemon
+fn device_scores(
+    daemon_status: Option<&serde_json::Value>,
+    controller: &audio::controller::DeviceController,
+    config: &Config,
+) -> Result<(
+    std::collections::HashMap<String, u32>,
+    std::collections::HashMap<String, u32>,
+)> {
+    if let Some(scores) = daemon_status.map(|status| &status["scores"])
+        && !scores.is_null()
+    {
+        return Ok((
+            serde_json::from_value(scores["output"].clone())?,
+            serde_json::from_value(scores["input"].clone())?,
+        ));
+    }
+
+    let devices = controller.enumerate_devices()?;
+    let priority_manager = priority::DevicePriorityManager::new(config);
+    Ok((
+        priority_manager.score_all_output_devices(&devices),
+        priority_manager.score_all_input_devices(&devices),
+    ))
+}
+
+/// Print device scores, highest first
+fn print_device_scores(direction: &str, scores: std::collections::HashMap<String, u32>) {
+    let mut scores: Vec<(String, u32)> = scores.into_iter().collect();
+    scores.sort_by(|(a_name, a_score), (b_name, b_score)| {
+        b_score.cmp(a_score).then_with(|| a_name.cmp(b_name))
+    });
+
+    println!("  {direction} device scores:");
+    if scores.is_empty() {
+        println!("    No {} devices available", direction.to_lowercase());
+    }
+    for (name, score) in scores {
+        println!("    {score:>6}  {name}");
+    }
+}
+
+async fn show_current_devices(json: Option<JsonOutput>) -> Result<()> {
     debug!("Showing current active devices");
 
+    match request_from_daemon(&IpcRequest::Status {
+        include_scores: false,
+    }) {
+        Some(Ok(Some(status))) => {
+            if let Some(json) = json {
+                let current = serde_json::json!({
+                    "source": "daemon",
+                    "output": status["current_output"],
+                    "input": status["current_input"],
+                });
+                json.write(&mut std::io::stdout(), &current)?;
+                return Ok(());
+            }
+
+            println!("Current Active Devices (from running daemon):");
+            println!("=============================================");
+            for (label, key) in [
+                ("🔊 Output", "current_output"),
+                ("🎤 Input", "current_input"),
+            ] {
+                println!(
+                    "  {}: {}",
+                    label,
+                    status[key].as_str().unwrap_or("None available")
+                );
+            }
+            return Ok(());
+        }
+        Some(Ok(None)) => warn!("Running daemon sent an empty status, querying CoreAudio"),
+        Some(Err(e)) => warn!("Running daemon could not report status, querying CoreAudio: {e:#}"),
+        None => {}
+    }
+
     let controller = audio::controller::DeviceController::new()?;
 
+    if let Some(json) = json {
+        let name = |device: Result<Option<AudioDevice>>| device.ok().flatten().map(|d| d.name);
+        let current = serde_json::json!({
+            "source": "coreaudio",
+            "output": name(controller.get_default_output_device()),
+            "input": name(controller.get_default_input_device()),
+        });
+        json.write(&mut std::io::stdout(), &current)?;
+        return Ok(());
+    }
+
     println!("Current Active Devices:");
     println!("======================");
 
@@ -583,13 +2017,58 @@ async fn show_current_devices() -> Result<()> {
     Ok(())
 }
 
+/// Send `request` to a running daemon over its control socket; `None` when no
+/// daemon is listening and the caller should use CoreAudio directly
+fn request_from_daemon(request: &IpcRequest) -> Option<Result<Option<serde_json::Value>>> {
+    let path = default_socket_path().ok()?;
+    let mut client = IpcClient::connect(&path)?;
+    debug!("Sending {:?} to the daemon at {}", request, path.display());
+    Some(client.request(request))
+}
+
+/// Send `request` to the running daemon, for commands that only make sense
+/// while one runs
+fn request_from_running_daemon(request: &IpcRequest) -> Result<Option<serde_json::Value>> {
+    request_from_daemon(request).unwrap_or_else(|| {
+        Err(anyhow::anyhow!(
+            "No daemon is running; only the daemon switches devices automatically"
+        ))
+    })
+}
+
+fn wait_for_device(out: &mut impl Write, device_name: &str, timeout_ms: u64) -> Result<()> {
+    debug!("Waiting for device: {}", device_name);
+
+    let service = service::AudioDeviceService::new_with_default_config()?;
+    let device = service.wait_for_device(device_name, timeout_ms)?;
+
+    writeln!(out, "✓ Device available: {}", device.name)?;
+    Ok(())
+}
+
+/// Report the running daemon's health, which covers its last device operation.
+/// Without a daemon, check the audio system and preferences from this process.
+fn health_check(out: &mut impl Write) -> Result<()> {
+    debug!("Running health check");
+
+    let health = match daemon_status(false) {
+        Some(status) => serde_json::from_value::<service::HealthStatus>(status["health"].clone())
+            .context("Running daemon sent a status without its health")?,
+        None => service::AudioDeviceService::new_with_default_config()?.emit_health_check(),
+    };
+
+    writeln!(out, "Health: {health}")?;
+    out.flush()?;
+    std::process::exit(health.exit_code());
+}
+
 async fn check_preferences() -> Result<()> {
     debug!("Checking if current devices match configured preferences");
 
     let _config = Config::load(None)?;
 
     // Use the default config path for the service
-    let service = service::AudioDeviceService::new_with_default_config()?;
+    let mut service = service::AudioDeviceService::new_with_default_config()?;
     let status = service.check_preferences()?;
 
     println!("Preference Status:");
@@ -647,49 +2126,162 @@ async fn check_preferences() -> Result<()> {
     Ok(())
 }
 
-async fn apply_preferences() -> Result<()> {
+async fn apply_preferences(out: &mut impl Write) -> Result<()> {
     debug!("Applying configured device preferences");
 
     let _config = Config::load(None)?;
 
     // Use the default config path for the service
-    let service = service::AudioDeviceService::new_with_default_config()?;
+    let mut service = service::AudioDeviceService::new_with_default_config()?;
     let changes = service.apply_preferences()?;
 
     if !changes.output_changed && !changes.input_changed {
-        println!("🎯 All devices already match your configured preferences!");
+        writeln!(
+            out,
+            "🎯 All devices already match your configured preferences!"
+        )?;
         return Ok(());
     }
 
-    println!("Applied Preference Changes:");
-    println!("===========================");
+    writeln!(out, "Applied Preference Changes:")?;
+    writeln!(out, "===========================")?;
 
     if changes.output_changed {
-        println!("🔊 Output Device:");
-        println!(
+        writeln!(out, "🔊 Output Device:")?;
+        writeln!(
+            out,
             "  Switched to: {}",
             changes
                 .new_output
                 .unwrap_or_else(|| "Failed to switch".to_string())
-        );
+        )?;
     } else {
-        println!("🔊 Output Device: No change needed");
+        writeln!(out, "🔊 Output Device: No change needed")?;
     }
 
     if changes.input_changed {
-        println!("🎤 Input Device:");
-        println!(
+        writeln!(out, "🎤 Input Device:")?;
+        writeln!(
+            out,
             "  Switched to: {}",
             changes
                 .new_input
                 .unwrap_or_else(|| "Failed to switch".to_string())
-        );
+        )?;
     } else {
-        println!("🎤 Input Device: No change needed");
+        writeln!(out, "🎤 Input Device: No change needed")?;
     }
 
-    println!();
-    println!("✅ Preferences applied successfully!");
+    writeln!(out)?;
+    writeln!(out, "✅ Preferences applied successfully!")?;
+
+    Ok(())
+}
+
+fn completions(shell: Option<clap_complete::Shell>, install: bool) -> Result<()> {
+    let shell = shell
+        .or_else(clap_complete::Shell::from_env)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not detect your shell from $SHELL; name it, e.g. `completions zsh`"
+            )
+        })?;
+
+    if !install {
+        write_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
 
+    let path = completion_install_path(shell)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut script = Vec::new();
+    write_completions(shell, &mut script);
+    std::fs::write(&path, script).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!("✓ Installed {shell} completions to {}", path.display());
+    if shell == clap_complete::Shell::Zsh {
+        println!("  Start a new shell, or run `compinit`, to load them");
+    }
     Ok(())
 }
+
+/// Write the completion script for `shell`, covering every subcommand and flag
+fn write_completions(shell: clap_complete::Shell, out: &mut impl Write) {
+    use clap::CommandFactory;
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Where each shell picks up completion scripts without further setup
+fn completion_install_path(shell: clap_complete::Shell) -> Result<std::path::PathBuf> {
+    use clap_complete::Shell;
+
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Failed to get home directory"))?;
+    match shell {
+        Shell::Bash => Ok(home_dir
+            .join(".local/share/bash-completion/completions")
+            .join("audio-device-monitor")),
+        Shell::Zsh => Ok(std::path::PathBuf::from(
+            "/usr/local/share/zsh/site-functions/_audio-device-monitor",
+        )),
+        Shell::Fish => Ok(home_dir
+            .join(".config/fish/completions")
+            .join("audio-device-monitor.fish")),
+        _ => Err(anyhow::anyhow!(
+            "Installing {shell} completions is not supported; load the output of \
+             `audio-device-monitor completions {shell}` from your shell profile instead"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use clap_complete::Shell;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_completions_for_every_shell() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::PowerShell,
+            Shell::Elvish,
+        ] {
+            let mut out = Vec::new();
+            write_completions(shell, &mut out);
+            let script = String::from_utf8(out).unwrap();
+
+            assert!(
+                script.contains("audio-device-monitor"),
+                "{shell} completions do not name the binary"
+            );
+            assert!(
+                script.contains("list-devices") && script.contains("completion-install"),
+                "{shell} completions are missing subcommands or flags"
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_paths() {
+        let fish = completion_install_path(Shell::Fish).unwrap();
+        assert!(fish.ends_with(".config/fish/completions/audio-device-monitor.fish"));
+
+        let zsh = completion_install_path(Shell::Zsh).unwrap();
+        assert_eq!(zsh.file_name().unwrap(), "_audio-device-monitor");
+
+        assert!(completion_install_path(Shell::PowerShell).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, error, info, warn};
 
 use crate::audio::AudioDevice;
-use crate::config::Config;
+use crate::config::{Config, TimeOfDay};
 
 // Type alias for the default notification manager type
 #[cfg(not(any(test, feature = "test-mocks")))]
@@ -14,14 +17,45 @@ pub type DefaultNotificationManager = NotificationManager<TestNotificationSender
 /// Trait for sending notifications - allows for testing without system calls
 pub trait NotificationSender {
     fn send(&self, title: &str, body: &str) -> Result<()>;
+
+    /// Number of notifications still being delivered
+    fn pending_notifications(&self) -> usize {
+        0
+    }
 }
 
 /// Production notification sender using macOS osascript
-pub struct MacOSNotificationSender;
+#[derive(Clone, Default)]
+pub struct MacOSNotificationSender {
+    /// Notifications whose osascript process has not exited yet
+    pending: Arc<AtomicUsize>,
+}
 
 impl NotificationSender for MacOSNotificationSender {
     fn send(&self, title: &str, body: &str) -> Result<()> {
-        send_native_macos_notification(title, body)
+        let child = spawn_native_macos_notification(title, body)?;
+
+        // osascript returns only once the notification is delivered, so wait
+        // for it off the caller's thread and let shutdown drain the count
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let pending = Arc::clone(&self.pending);
+        std::thread::spawn(move || {
+            match child.wait_with_output() {
+                Ok(output) if !output.status.success() => {
+                    let error = String::from_utf8_lossy(&output.stderr);
+                    warn!("osascript failed: {}", error.trim());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to wait for osascript: {}", e),
+            }
+            pending.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        Ok(())
+    }
+
+    fn pending_notifications(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
     }
 }
 
@@ -29,6 +63,8 @@ impl NotificationSender for MacOSNotificationSender {
 #[cfg(any(test, feature = "test-mocks"))]
 pub struct TestNotificationSender {
     pub sent_notifications: std::sync::Mutex<Vec<(String, String)>>,
+    /// Simulated in-flight notifications reported by `pending_notifications`
+    pub pending: Arc<AtomicUsize>,
 }
 
 #[cfg(any(test, feature = "test-mocks"))]
@@ -44,6 +80,7 @@ impl TestNotificationSender {
     pub fn new() -> Self {
         Self {
             sent_notifications: std::sync::Mutex::new(Vec::new()),
+            pending: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -56,6 +93,67 @@ impl TestNotificationSender {
     pub fn clear(&self) {
         self.sent_notifications.lock().unwrap().clear();
     }
+
+    /// Most recently sent `(title, body)` pair
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn get_last_notification(&self) -> Option<(String, String)> {
+        self.sent_notifications.lock().unwrap().last().cloned()
+    }
+
+    /// Total number of notifications sent
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn count(&self) -> usize {
+        self.sent_notifications.lock().unwrap().len()
+    }
+
+    /// Panic unless the last notification has `title` and a body containing `body_contains`
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    #[track_caller]
+    pub fn assert_last_notification(&self, title: &str, body_contains: &str) {
+        match self.get_last_notification() {
+            Some((last_title, last_body)) => assert!(
+                last_title == title && last_body.contains(body_contains),
+                "Expected last notification '{title}' containing '{body_contains}', \
+                 got '{last_title}': '{last_body}'"
+            ),
+            None => panic!(
+                "Expected last notification '{title}' containing '{body_contains}', \
+                 but no notifications were sent"
+            ),
+        }
+    }
+
+    /// Panic unless exactly `expected` notifications were sent
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    #[track_caller]
+    pub fn assert_notification_count(&self, expected: usize) {
+        let sent = self.get_sent_notifications();
+        assert!(
+            sent.len() == expected,
+            "Expected {expected} notifications, got {}: {sent:?}",
+            sent.len()
+        );
+    }
+
+    /// Panic if any notification was sent
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    #[track_caller]
+    pub fn assert_no_notifications(&self) {
+        self.assert_notification_count(0);
+    }
+
+    /// Panic unless some notification's title and body contain the given text
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    #[track_caller]
+    pub fn assert_notification_sent(&self, title_contains: &str, body_contains: &str) {
+        let sent = self.get_sent_notifications();
+        assert!(
+            sent.iter()
+                .any(|(title, body)| title.contains(title_contains) && body.contains(body_contains)),
+            "Expected a notification with title containing '{title_contains}' and body \
+             containing '{body_contains}', got: {sent:?}"
+        );
+    }
 }
 
 #[cfg(any(test, feature = "test-mocks"))]
@@ -68,6 +166,10 @@ impl NotificationSender for TestNotificationSender {
             .push((title.to_string(), body.to_string()));
         Ok(())
     }
+
+    fn pending_notifications(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
 }
 
 /// Manages system notifications for audio device events
@@ -75,6 +177,7 @@ pub struct NotificationManager<T: NotificationSender = MacOSNotificationSender>
     enabled: bool,
     show_device_availability: bool, // Device connect/disconnect notifications
     show_switching_actions: bool,   // Device switching notifications
+    quiet_hours: Option<(TimeOfDay, TimeOfDay)>, // Suppress notifications from start to end
     sender: T,
 }
 
@@ -87,7 +190,8 @@ impl DefaultNotificationManager {
                 enabled: true, // Can be controlled by config in the future
                 show_device_availability: config.notifications.show_device_availability,
                 show_switching_actions: config.notifications.show_switching_actions,
-                sender: MacOSNotificationSender,
+                quiet_hours: config.notifications.quiet_hours(),
+                sender: MacOSNotificationSender::default(),
             }
         }
         #[cfg(any(test, feature = "test-mocks"))]
@@ -98,6 +202,7 @@ impl DefaultNotificationManager {
                 enabled: true,
                 show_device_availability: config.notifications.show_device_availability,
                 show_switching_actions: config.notifications.show_switching_actions,
+                quiet_hours: config.notifications.quiet_hours(),
                 sender: test_sender,
             }
         }
@@ -112,10 +217,18 @@ impl<T: NotificationSender> NotificationManager<T> {
             enabled: true,
             show_device_availability: config.notifications.show_device_availability,
             show_switching_actions: config.notifications.show_switching_actions,
+            quiet_hours: config.notifications.quiet_hours(),
             sender,
         }
     }
 
+    /// Access the notification sender (used by tests to inspect sent notifications)
+    #[cfg(any(test, feature = "test-mocks"))]
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn sender(&self) -> &T {
+        &self.sender
+    }
+
     /// Send notification when a device comes online
     pub fn device_connected(&self, device: &AudioDevice) -> Result<()> {
         if !self.enabled || !self.show_device_availability {
@@ -187,6 +300,12 @@ impl<T: NotificationSender> NotificationManager<T> {
             SwitchReason::Manual => {
                 format!("{} manually switched to {}", device_type, device.name)
             }
+            SwitchReason::AppRequest { app_name } => {
+                format!(
+                    "{} manually switched to {} by {}",
+                    device_type, device.name, app_name
+                )
+            }
         };
 
         self.send_notification(title, &body, NotificationType::SwitchAction)?;
@@ -220,6 +339,22 @@ impl<T: NotificationSender> NotificationManager<T> {
         body: &str,
         _notification_type: NotificationType,
     ) -> Result<()> {
+        self.send_notification_at(TimeOfDay::now_local(), title, body)
+    }
+
+    /// Send a notification as if the local time were `now`, skipping it during quiet hours
+    pub fn send_notification_at(&self, now: TimeOfDay, title: &str, body: &str) -> Result<()> {
+        let quiet_window = self
+            .quiet_hours
+            .filter(|&(start, end)| now.is_within(start, end));
+        if let Some((start, end)) = quiet_window {
+            debug!(
+                "Suppressing notification during quiet hours ({}-{}): {} - {}",
+                start, end, title, body
+            );
+            return Ok(());
+        }
+
         debug!("Sending notification: {} - {}", title, body);
 
         self.sender.send(title, body)?;
@@ -228,6 +363,11 @@ impl<T: NotificationSender> NotificationManager<T> {
         Ok(())
     }
 
+    /// Number of notifications still being delivered
+    pub fn pending_notifications(&self) -> usize {
+        self.sender.pending_notifications()
+    }
+
     /// Check if notifications are enabled
     #[allow(dead_code)]
     pub fn is_enabled(&self) -> bool {
@@ -269,6 +409,13 @@ impl<T: NotificationSender> NotificationManager<T> {
             }
         }
 
+        // Delivery finishes in the background; wait so a failing osascript
+        // is still logged before the command exits
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while self.sender.pending_notifications() > 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+
         info!("Test notification completed");
         Ok(())
     }
@@ -283,18 +430,34 @@ enum NotificationType {
 }
 
 /// Reasons for device switching (for notification context)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SwitchReason {
     HigherPriority, // A higher priority device became available
     // Used by device_switched notification system when previous device becomes unavailable
     #[allow(dead_code)]
     PreviousUnavailable, // Previous device became unavailable
     Manual, // User manually switched
+    // Constructed by IPC switch commands that name the requesting app
+    AppRequest {
+        app_name: String,
+    }, // Another app asked for the switch
 }
 
-/// Send notification using native macOS osascript (more reliable for unsigned apps)
-fn send_native_macos_notification(title: &str, body: &str) -> Result<()> {
-    use std::process::Command;
+impl std::fmt::Display for SwitchReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwitchReason::HigherPriority => write!(f, "higher priority"),
+            SwitchReason::PreviousUnavailable => write!(f, "previous device unavailable"),
+            SwitchReason::Manual => write!(f, "manual"),
+            SwitchReason::AppRequest { app_name } => write!(f, "requested by {app_name}"),
+        }
+    }
+}
+
+/// Start a notification using native macOS osascript (more reliable for unsigned apps)
+fn spawn_native_macos_notification(title: &str, body: &str) -> Result<std::process::Child> {
+    use std::process::{Command, Stdio};
 
     let script = format!(
         r#"display notification "{}" with title "{}" subtitle """#,
@@ -302,14 +465,13 @@ fn send_native_macos_notification(title: &str, body: &str) -> Result<()> {
         title.replace('"', "\\\"")
     );
 
-    let output = Command::new("osascript").args(["-e", &script]).output()?;
+    let child = Command::new("osascript")
+        .args(["-e", &script])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        Err(anyhow::anyhow!("osascript failed: {}", error))
-    }
+    Ok(child)
 }
 
 impl Default for NotificationManager {
@@ -318,7 +480,8 @@ impl Default for NotificationManager {
             enabled: true,
             show_device_availability: false, // Default: no device availability notifications
             show_switching_actions: true,    // Default: show switching notifications
-            sender: MacOSNotificationSender,
+            quiet_hours: None,
+            sender: MacOSNotificationSender::default(),
         }
     }
 }
@@ -0,0 +1,317 @@
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::config::{Config, DeviceRule};
+
+const GREEN: &str = "\x1b[32m";
+const GRAY: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+/// Writer for CLI command output that discards everything in quiet mode
+///
+/// Only stdout output goes through this; tracing logs and error messages are unaffected.
+pub struct QuietOutput<W: Write> {
+    inner: W,
+    quiet: bool,
+}
+
+impl<W: Write> QuietOutput<W> {
+    pub fn new(inner: W, quiet: bool) -> Self {
+        Self { inner, quiet }
+    }
+
+    /// Get the wrapped writer back
+    // Called by tests to inspect what reached the underlying writer
+    #[allow(dead_code)]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl QuietOutput<io::Stdout> {
+    /// Command output on stdout; `--verbose` overrides `--quiet`
+    pub fn stdout(quiet: bool, verbose: bool) -> Self {
+        Self::new(io::stdout(), quiet && !verbose)
+    }
+}
+
+impl<W: Write> Write for QuietOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.quiet {
+            // Report the bytes as written so callers carry on as normal
+            return Ok(buf.len());
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+        self.inner.flush()
+    }
+}
+
+/// Output format of listing commands, chosen with `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How listing commands print JSON: pretty-printed, or on one line with `--compact`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOutput {
+    pub compact: bool,
+}
+
+impl JsonOutput {
+    /// JSON settings for `format`, or None when the command should print text
+    pub fn for_format(format: OutputFormat, compact: bool) -> Option<Self> {
+        (format == OutputFormat::Json).then_some(Self { compact })
+    }
+
+    /// Write `value` followed by a newline
+    pub fn write(&self, out: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+        let json = if self.compact {
+            serde_json::to_string(value)
+        } else {
+            serde_json::to_string_pretty(value)
+        }
+        .map_err(io::Error::other)?;
+        writeln!(out, "{json}")
+    }
+}
+
+/// One configured device rule as listed by `list-rules`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleListing {
+    pub direction: &'static str,
+    pub name: String,
+    pub match_type: String,
+    pub weight: i64,
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl RuleListing {
+    /// Every output and input rule in the config, highest weight first
+    pub fn from_config(config: &Config) -> Vec<Self> {
+        let mut listings: Vec<Self> = config
+            .output_rules()
+            .iter()
+            .map(|rule| Self::new("output", rule))
+            .chain(
+                config
+                    .input_rules()
+                    .iter()
+                    .map(|rule| Self::new("input", rule)),
+            )
+            .collect();
+        listings.sort_by_key(|rule| std::cmp::Reverse(rule.weight));
+        listings
+    }
+
+    fn new(direction: &'static str, rule: &DeviceRule) -> Self {
+        Self {
+            direction,
+            name: rule.name.clone(),
+            match_type: rule.match_type.config_name().to_string(),
+            weight: rule.weight,
+            enabled: rule.enabled,
+            description: rule.description.clone(),
+        }
+    }
+}
+
+/// Print rules as an aligned table. With `color`, the direction of enabled rules
+/// is shown in green and that of disabled rules in gray.
+pub fn write_rules_table(
+    out: &mut impl Write,
+    rules: &[RuleListing],
+    color: bool,
+) -> io::Result<()> {
+    let show_description = rules.iter().any(|rule| rule.description.is_some());
+    let name_width = rules
+        .iter()
+        .map(|rule| rule.name.chars().count())
+        .chain(std::iter::once("Name".len()))
+        .max()
+        .unwrap_or_default();
+    let match_width = rules
+        .iter()
+        .map(|rule| rule.match_type.len())
+        .chain(std::iter::once("MatchType".len()))
+        .max()
+        .unwrap_or_default();
+
+    write!(
+        out,
+        "{:<9}  {:<name_width$}  {:<match_width$}  {:>6}  {:<7}",
+        "Direction", "Name", "MatchType", "Weight", "Enabled"
+    )?;
+    if show_description {
+        write!(out, "  Description")?;
+    }
+    writeln!(out)?;
+
+    for rule in rules {
+        let direction = format!("{:<9}", rule.direction);
+        let direction = match (color, rule.enabled) {
+            (false, _) => direction,
+            (true, true) => format!("{GREEN}{direction}{RESET}"),
+            (true, false) => format!("{GRAY}{direction}{RESET}"),
+        };
+        write!(
+            out,
+            "{}  {:<name_width$}  {:<match_width$}  {:>6}  {:<7}",
+            direction,
+            rule.name,
+            rule.match_type,
+            rule.weight,
+            if rule.enabled { "yes" } else { "disabled" }
+        )?;
+        if let Some(description) = rule.description.as_deref().filter(|_| show_description) {
+            write!(out, "  {description}")?;
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_output_writes_nothing() {
+        let mut out = QuietOutput::new(Vec::new(), true);
+        writeln!(out, "Switching output device to: AirPods Pro").unwrap();
+        out.flush().unwrap();
+
+        assert!(out.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_quiet_output_accepts_writes_to_sink() {
+        let mut out = QuietOutput::new(io::sink(), true);
+
+        assert_eq!(out.write(b"discarded").unwrap(), 9);
+    }
+
+    #[test]
+    fn test_normal_output_passes_through() {
+        let mut out = QuietOutput::new(Vec::new(), false);
+        writeln!(out, "✓ Done").unwrap();
+
+        assert_eq!(out.into_inner(), "✓ Done\n".as_bytes());
+    }
+
+    #[test]
+    fn test_verbose_overrides_quiet() {
+        assert!(QuietOutput::stdout(true, false).quiet);
+        assert!(!QuietOutput::stdout(true, true).quiet);
+        assert!(!QuietOutput::stdout(false, false).quiet);
+    }
+
+    #[test]
+    fn test_json_output_only_for_json_format() {
+        assert_eq!(JsonOutput::for_format(OutputFormat::Text, true), None);
+        assert_eq!(
+            JsonOutput::for_format(OutputFormat::Json, true),
+            Some(JsonOutput { compact: true })
+        );
+    }
+
+    #[test]
+    fn test_compact_json_is_one_line() {
+        let value = serde_json::json!({"output": "AirPods Pro", "input": null});
+
+        let mut compact = Vec::new();
+        JsonOutput { compact: true }
+            .write(&mut compact, &value)
+            .unwrap();
+        let mut pretty = Vec::new();
+        JsonOutput { compact: false }
+            .write(&mut pretty, &value)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(compact).unwrap(),
+            "{\"input\":null,\"output\":\"AirPods Pro\"}\n"
+        );
+        assert!(String::from_utf8(pretty).unwrap().lines().count() > 1);
+    }
+
+    fn rules_config() -> Config {
+        r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 10
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "AirPods"
+weight = 100
+match_type = "startswith"
+enabled = false
+description = "Commute headphones"
+
+[[input_devices]]
+name = "Blue Yeti"
+weight = 50
+match_type = "contains"
+enabled = true
+"#
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rule_listing_sorted_by_weight() {
+        let listings = RuleListing::from_config(&rules_config());
+
+        assert_eq!(listings.len(), 3);
+        let names: Vec<&str> = listings.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["AirPods", "Blue Yeti", "Built-in Speakers"]);
+        assert_eq!(listings[1].direction, "input");
+        assert_eq!(listings[0].match_type, "starts_with");
+    }
+
+    #[test]
+    fn test_rules_table_labels_disabled_rules() {
+        let listings = RuleListing::from_config(&rules_config());
+        let mut out = Vec::new();
+        write_rules_table(&mut out, &listings, false).unwrap();
+        let table = String::from_utf8(out).unwrap();
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Direction"));
+        assert!(lines[1].contains("AirPods") && lines[1].contains("disabled"));
+        assert!(lines[1].ends_with("Commute headphones"));
+        assert!(lines[2].contains("yes"));
+        assert!(!table.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_rules_table_colors_direction_by_enabled() {
+        let listings = RuleListing::from_config(&rules_config());
+        let mut out = Vec::new();
+        write_rules_table(&mut out, &listings, true).unwrap();
+        let table = String::from_utf8(out).unwrap();
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].starts_with(GRAY));
+        assert!(lines[2].starts_with(GREEN));
+    }
+}
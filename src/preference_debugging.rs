@@ -1,22 +1,37 @@
 //! Preference debugging functionality
 //!
 //! Provides utilities for checking if current devices match configured preferences
-//! and applying preferences when they don't match.
+//! and applying preferences when they don't match, and the switch history that
+//! records which device was selected and why.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::audio::DeviceType;
+use crate::notifications::SwitchReason;
+
+/// Switch history location, relative to the home directory
+const HISTORY_RELATIVE_PATH: &str = ".local/share/audio-device-monitor/history.jsonl";
 
 /// Status of current devices compared to configured preferences
 #[derive(Debug, PartialEq, Clone)]
 pub struct PreferenceStatus {
-    /// Whether current output device matches highest priority configured device
+    /// Whether the priority rules keep the current output device
     pub output_matches: bool,
-    /// Whether current input device matches highest priority configured device  
+    /// Whether the priority rules keep the current input device
     pub input_matches: bool,
     /// Name of currently selected output device
     pub current_output: Option<String>,
     /// Name of currently selected input device
     pub current_input: Option<String>,
-    /// Name of preferred output device based on configuration
+    /// Name of the output device the priority rules would switch to, or of the
+    /// current one when they keep it
     pub preferred_output: Option<String>,
-    /// Name of preferred input device based on configuration
+    /// Input counterpart of `preferred_output`
     pub preferred_input: Option<String>,
     /// Alternative name for output device (for API compatibility)
     pub output_device_name: Option<String>,
@@ -24,6 +39,17 @@ pub struct PreferenceStatus {
     pub input_device_name: Option<String>,
 }
 
+/// How urgently the current devices need attention, from a `PreferenceStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UrgencyLevel {
+    /// No direction is on a device the priority rules would switch away from
+    Ok,
+    /// One direction is on a device the priority rules would switch away from
+    Warning,
+    /// Both directions are on devices the priority rules would switch away from
+    Critical,
+}
+
 /// Changes made when applying preferences
 #[derive(Debug, PartialEq, Clone)]
 pub struct PreferenceChanges {
@@ -67,6 +93,26 @@ impl PreferenceStatus {
             input_device_name: Some(input_name),
         }
     }
+
+    /// Classify how far the current devices are from the configured preferences
+    pub fn urgency_level(&self) -> UrgencyLevel {
+        match (self.output_mismatched(), self.input_mismatched()) {
+            (false, false) => UrgencyLevel::Ok,
+            (true, true) => UrgencyLevel::Critical,
+            _ => UrgencyLevel::Warning,
+        }
+    }
+
+    /// The output is on a device the rules would replace with the preferred one.
+    /// No current device, or no preferred device to move to, is no mismatch.
+    pub fn output_mismatched(&self) -> bool {
+        !self.output_matches && self.current_output.is_some() && self.preferred_output.is_some()
+    }
+
+    /// Input counterpart of `output_mismatched`
+    pub fn input_mismatched(&self) -> bool {
+        !self.input_matches && self.current_input.is_some() && self.preferred_input.is_some()
+    }
 }
 
 impl PreferenceChanges {
@@ -113,3 +159,270 @@ impl PreferenceChanges {
         }
     }
 }
+
+/// A completed device switch, one line of the switch history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchEvent {
+    pub timestamp: SystemTime,
+    pub device_name: String,
+    pub device_type: DeviceType,
+    pub reason: SwitchReason,
+    /// Name of the rule that selected the device, if one did
+    pub matched_rule: Option<String>,
+}
+
+/// `~/.local/share/audio-device-monitor/history.jsonl`
+pub fn default_history_path() -> Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Failed to get home directory"))?;
+    Ok(home_dir.join(HISTORY_RELATIVE_PATH))
+}
+
+/// Switch events stored as JSON lines. Once the file holds `max_lines` events it
+/// is moved to `<path>.1`, replacing the previous backup, and a new file is started.
+/// A `max_lines` of 0 turns recording off.
+#[derive(Debug, Clone)]
+pub struct SwitchHistory {
+    path: PathBuf,
+    max_lines: usize,
+}
+
+impl SwitchHistory {
+    pub fn new(path: PathBuf, max_lines: usize) -> Self {
+        Self { path, max_lines }
+    }
+
+    /// Append an event, rotating the file first if it is full
+    pub fn append(&self, event: &SwitchEvent) -> Result<()> {
+        // Would otherwise rotate a file that does not exist yet
+        if self.max_lines == 0 {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create history directory {}", parent.display())
+            })?;
+        }
+
+        if count_lines(&self.path) >= self.max_lines {
+            std::fs::rename(&self.path, self.backup_path())
+                .with_context(|| format!("Failed to rotate {}", self.path.display()))?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+        Ok(())
+    }
+
+    /// The last `count` events, oldest first, including those in the backup.
+    /// Lines that do not parse are skipped.
+    pub fn recent(&self, count: usize) -> Result<Vec<SwitchEvent>> {
+        let mut events = read_events(&self.backup_path())?;
+        events.extend(read_events(&self.path)?);
+
+        let skip = events.len().saturating_sub(count);
+        Ok(events.split_off(skip))
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(".1");
+        PathBuf::from(backup)
+    }
+}
+
+fn count_lines(path: &Path) -> usize {
+    std::fs::File::open(path)
+        .map(|file| BufReader::new(file).lines().count())
+        .unwrap_or(0)
+}
+
+fn read_events(path: &Path) -> Result<Vec<SwitchEvent>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(event) => events.push(event),
+            Err(e) => warn!(
+                "Skipping unreadable history line in {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(events)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in the system's local time zone
+pub fn format_local_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let seconds = libc::time_t::try_from(seconds).unwrap_or(libc::time_t::MAX);
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers refer to live locals for the duration of the call
+    if unsafe { libc::localtime_r(&seconds, &mut tm) }.is_null() {
+        warn!("Failed to convert timestamp to local time");
+    }
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urgency_ok_when_both_match() {
+        let status = PreferenceStatus::all_match("Headphones".to_string(), "Mic".to_string());
+
+        assert_eq!(status.urgency_level(), UrgencyLevel::Ok);
+    }
+
+    #[test]
+    fn test_urgency_warning_when_one_direction_differs() {
+        let mut status = PreferenceStatus::all_match("Headphones".to_string(), "Mic".to_string());
+        status.output_matches = false;
+        status.current_output = Some("Speakers".to_string());
+        assert_eq!(status.urgency_level(), UrgencyLevel::Warning);
+
+        let mut status = PreferenceStatus::all_match("Headphones".to_string(), "Mic".to_string());
+        status.input_matches = false;
+        assert_eq!(status.urgency_level(), UrgencyLevel::Warning);
+    }
+
+    #[test]
+    fn test_urgency_ok_without_preferred_device() {
+        let mut status = PreferenceStatus::no_matches();
+        status.current_output = Some("Speakers".to_string());
+        status.current_input = Some("Built-in Mic".to_string());
+
+        assert_eq!(status.urgency_level(), UrgencyLevel::Ok);
+    }
+
+    #[test]
+    fn test_urgency_ok_without_current_device() {
+        let mut status = PreferenceStatus::all_match("Headphones".to_string(), "Mic".to_string());
+        status.input_matches = false;
+        status.current_input = None;
+
+        assert_eq!(status.urgency_level(), UrgencyLevel::Ok);
+    }
+
+    #[test]
+    fn test_urgency_critical_when_both_directions_differ() {
+        let status = PreferenceStatus {
+            output_matches: false,
+            input_matches: false,
+            current_output: Some("Speakers".to_string()),
+            current_input: Some("Built-in Mic".to_string()),
+            preferred_output: Some("Headphones".to_string()),
+            preferred_input: Some("Studio Mic".to_string()),
+            output_device_name: Some("Headphones".to_string()),
+            input_device_name: Some("Studio Mic".to_string()),
+        };
+
+        assert_eq!(status.urgency_level(), UrgencyLevel::Critical);
+    }
+
+    fn event(device_name: &str) -> SwitchEvent {
+        SwitchEvent {
+            timestamp: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000),
+            device_name: device_name.to_string(),
+            device_type: DeviceType::Output,
+            reason: SwitchReason::HigherPriority,
+            matched_rule: Some("Headphones".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_history_round_trips_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = SwitchHistory::new(dir.path().join("history.jsonl"), 100);
+
+        history.append(&event("Headphones")).unwrap();
+        history.append(&event("Speakers")).unwrap();
+
+        assert_eq!(
+            history.recent(10).unwrap(),
+            vec![event("Headphones"), event("Speakers")]
+        );
+        assert_eq!(history.recent(1).unwrap(), vec![event("Speakers")]);
+    }
+
+    #[test]
+    fn test_history_rotates_to_single_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let history = SwitchHistory::new(path.clone(), 2);
+
+        for name in ["One", "Two", "Three", "Four", "Five"] {
+            history.append(&event(name)).unwrap();
+        }
+
+        assert_eq!(count_lines(&path), 1);
+        assert_eq!(count_lines(&dir.path().join("history.jsonl.1")), 2);
+        let names: Vec<String> = history
+            .recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|event| event.device_name)
+            .collect();
+        assert_eq!(names, vec!["Three", "Four", "Five"]);
+    }
+
+    #[test]
+    fn test_zero_max_lines_disables_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let history = SwitchHistory::new(path.clone(), 0);
+
+        history.append(&event("Headphones")).unwrap();
+
+        assert!(!path.exists());
+        assert!(history.recent(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_history_is_empty_and_bad_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let history = SwitchHistory::new(path.clone(), 100);
+        assert!(history.recent(10).unwrap().is_empty());
+
+        std::fs::write(&path, "not json\n").unwrap();
+        history.append(&event("Headphones")).unwrap();
+        assert_eq!(history.recent(10).unwrap(), vec![event("Headphones")]);
+    }
+
+    #[test]
+    fn test_local_timestamp_format() {
+        let formatted = format_local_timestamp(SystemTime::now());
+
+        assert_eq!(formatted.len(), "2026-01-31 23:59:59".len());
+        assert_eq!(&formatted[4..5], "-");
+        assert_eq!(&formatted[10..11], " ");
+    }
+}
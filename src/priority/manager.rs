@@ -1,50 +1,570 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::debug;
 
-use crate::audio::{AudioDevice, DeviceType};
-use crate::config::{Config, DeviceRule};
+use crate::audio::{AudioDevice, DeviceType, TransportType};
+use crate::config::{Config, DeviceRule, GeneralConfig, MatchType};
+
+/// Outcome of a what-if device arrival computed by `simulate_device_arrival`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchDecision {
+    pub should_switch_output: bool,
+    pub should_switch_input: bool,
+    /// Name of the rule that matched the arriving device
+    pub matched_rule: Option<String>,
+}
+
+/// Which device `explain_output_selection` or `explain_input_selection` would pick, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionExplanation {
+    pub selected_device: Option<AudioDevice>,
+    /// Name of the rule that decides the selected device
+    pub matched_rule_name: Option<String>,
+    /// Weight the selected device scored, after weight decay and the battery bonus
+    pub matched_rule_weight: Option<i64>,
+    /// How that rule matches, for showing alongside its name
+    pub matched_rule_match_type: Option<MatchType>,
+    /// Every other selectable device, best first, with the rule that decides it
+    pub runners_up: Vec<(AudioDevice, DeviceRule)>,
+}
+
+/// How one rule judges one device, from `explain_device`
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatchResult {
+    /// Whether the rule comes from the output or the input rules
+    pub direction: DeviceType,
+    pub rule: DeviceRule,
+    /// Whether the rule's pattern and filters accept the device, ignoring
+    /// whether the rule is enabled
+    pub matched: bool,
+    /// Score the rule gives the device, clamped like `score_all_output_devices`;
+    /// `None` when the rule does not match or is disabled
+    pub score: Option<u32>,
+}
+
+/// One device's place in `rank_output_devices` or `rank_input_devices`
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedDevice {
+    pub device: AudioDevice,
+    /// Final weight, after weight decay and the battery bonus, clamped like
+    /// `score_all_output_devices`; 0 for devices that cannot be selected
+    pub score: u32,
+    /// Name of the rule that decides the device; `None` when it cannot be selected
+    pub matched_rule: Option<String>,
+}
+
+/// Weight added to USB devices on battery power when `prefer_wired_on_battery` is set
+pub const WIRED_ON_BATTERY_BONUS: i64 = 1000;
+
+/// Weight multiplier for a recently disconnected device when its rule sets no `weight_decay_factor`
+pub const DEFAULT_WEIGHT_DECAY_FACTOR: f32 = 0.5;
 
 pub struct DevicePriorityManager {
     output_priorities: Vec<DeviceRule>,
     input_priorities: Vec<DeviceRule>,
-    current_output: Option<String>,
-    current_input: Option<String>,
+    /// Devices currently selected, with the UID and transport rules may match on
+    current_output: Option<AudioDevice>,
+    current_input: Option<AudioDevice>,
+    prefer_wired_on_battery: bool,
+    on_battery_power: bool,
+    min_weight_to_switch: u32,
+    /// Stability thresholds for devices whose rule sets no `stability_ms`
+    stability_threshold_ms: u64,
+    bluetooth_stability_threshold_ms: u64,
+    /// When each device last disconnected, by device ID
+    disconnected_at: HashMap<String, Instant>,
+    last_output_switch: Option<SystemTime>,
+    last_input_switch: Option<SystemTime>,
+    /// Switches each rule caused, by rule name
+    rule_hit_counters: HashMap<String, u64>,
 }
 
 impl DevicePriorityManager {
     pub fn new(config: &Config) -> Self {
+        Self::new_from_rules(&config.output_rules(), &config.input_rules())
+            .with_prefer_wired_on_battery(config.general.prefer_wired_on_battery)
+            .with_min_weight_to_switch(config.general.min_weight_to_switch)
+            .with_stability_thresholds(
+                config.general.device_change_debounce_ms,
+                config.general.bluetooth_device_change_debounce_ms,
+            )
+    }
+
+    /// Build a manager directly from rule lists, without a full `Config`
+    pub fn new_from_rules(output_rules: &[DeviceRule], input_rules: &[DeviceRule]) -> Self {
         debug!("Creating device priority manager");
 
+        let general = GeneralConfig::default();
         Self {
-            output_priorities: config.output_devices.clone(),
-            input_priorities: config.input_devices.clone(),
+            output_priorities: output_rules.to_vec(),
+            input_priorities: input_rules.to_vec(),
             current_output: None,
             current_input: None,
+            prefer_wired_on_battery: false,
+            on_battery_power: false,
+            min_weight_to_switch: 0,
+            stability_threshold_ms: general.device_change_debounce_ms,
+            bluetooth_stability_threshold_ms: general.bluetooth_device_change_debounce_ms,
+            disconnected_at: HashMap::new(),
+            last_output_switch: None,
+            last_input_switch: None,
+            rule_hit_counters: HashMap::new(),
         }
     }
 
+    /// Swap in the rules and switching settings of a reloaded config.
+    /// Current devices and disconnect history are kept, so sticky rules and
+    /// weight decay carry over the reload.
+    // Called by config reload paths and tests that simulate a SIGHUP reload
+    #[allow(dead_code)]
+    pub fn update_config(&mut self, config: &Config) {
+        debug!("Updating device priority rules from config");
+        self.update_output_rules(&config.output_rules());
+        self.update_input_rules(&config.input_rules());
+        self.prefer_wired_on_battery = config.general.prefer_wired_on_battery;
+        self.min_weight_to_switch = config.general.min_weight_to_switch;
+        self.stability_threshold_ms = config.general.device_change_debounce_ms;
+        self.bluetooth_stability_threshold_ms = config.general.bluetooth_device_change_debounce_ms;
+        self.prune_disconnects();
+    }
+
+    /// Replace the output device rules
+    pub fn update_output_rules(&mut self, rules: &[DeviceRule]) {
+        self.output_priorities = rules.to_vec();
+    }
+
+    /// Replace the input device rules
+    pub fn update_input_rules(&mut self, rules: &[DeviceRule]) {
+        self.input_priorities = rules.to_vec();
+    }
+
+    /// Require a device to outweigh the current one by this much before switching to it
+    pub fn with_min_weight_to_switch(mut self, min_weight_to_switch: u32) -> Self {
+        self.min_weight_to_switch = min_weight_to_switch;
+        self
+    }
+
+    /// How long devices must stay connected before they can be switched to, for
+    /// Bluetooth devices and all others, unless their rule sets `stability_ms`
+    pub fn with_stability_thresholds(
+        mut self,
+        stability_threshold_ms: u64,
+        bluetooth_stability_threshold_ms: u64,
+    ) -> Self {
+        self.stability_threshold_ms = stability_threshold_ms;
+        self.bluetooth_stability_threshold_ms = bluetooth_stability_threshold_ms;
+        self
+    }
+
+    /// Boost USB devices by `WIRED_ON_BATTERY_BONUS` while on battery power
+    pub fn with_prefer_wired_on_battery(mut self, enabled: bool) -> Self {
+        self.prefer_wired_on_battery = enabled;
+        self
+    }
+
+    /// Whether callers need to keep the power source up to date
+    pub fn prefers_wired_on_battery(&self) -> bool {
+        self.prefer_wired_on_battery
+    }
+
+    /// Record whether the system currently runs on battery power
+    pub fn set_on_battery_power(&mut self, on_battery_power: bool) {
+        self.on_battery_power = on_battery_power;
+    }
+
     pub fn find_best_output_device(
         &self,
         available_devices: &[AudioDevice],
     ) -> Option<AudioDevice> {
-        self.find_best_device(
+        Self::best_of(
+            self.rank_output_devices(available_devices),
+            DeviceType::Output,
+        )
+    }
+
+    pub fn find_best_input_device(&self, available_devices: &[AudioDevice]) -> Option<AudioDevice> {
+        Self::best_of(
+            self.rank_input_devices(available_devices),
+            DeviceType::Input,
+        )
+    }
+
+    /// Every available output device, the one `find_best_output_device` picks
+    /// first. Selectable devices are ordered by score, then by the position of
+    /// their deciding rule; the rest follow with score 0 in the order given.
+    pub fn rank_output_devices(&self, available_devices: &[AudioDevice]) -> Vec<RankedDevice> {
+        self.rank_devices(
             available_devices,
             &self.output_priorities,
+            &self.input_priorities,
             DeviceType::Output,
         )
     }
 
-    pub fn find_best_input_device(&self, available_devices: &[AudioDevice]) -> Option<AudioDevice> {
-        self.find_best_device(available_devices, &self.input_priorities, DeviceType::Input)
+    /// Every available input device, ordered as for `rank_output_devices`
+    pub fn rank_input_devices(&self, available_devices: &[AudioDevice]) -> Vec<RankedDevice> {
+        self.rank_devices(
+            available_devices,
+            &self.input_priorities,
+            &self.output_priorities,
+            DeviceType::Input,
+        )
+    }
+
+    /// Explain which output device `find_best_output_device` picks from
+    /// `available_devices` and which selectable devices it passes over
+    pub fn explain_output_selection(
+        &self,
+        available_devices: &[AudioDevice],
+    ) -> SelectionExplanation {
+        Self::explain(self.ranked_devices(
+            available_devices,
+            &self.output_priorities,
+            &self.input_priorities,
+            DeviceType::Output,
+        ))
+    }
+
+    /// Explain which input device `find_best_input_device` picks, as for
+    /// `explain_output_selection`
+    pub fn explain_input_selection(
+        &self,
+        available_devices: &[AudioDevice],
+    ) -> SelectionExplanation {
+        Self::explain(self.ranked_devices(
+            available_devices,
+            &self.input_priorities,
+            &self.output_priorities,
+            DeviceType::Input,
+        ))
+    }
+
+    fn explain(ranked: Vec<(&AudioDevice, DeviceRule, i64)>) -> SelectionExplanation {
+        let mut ranked = ranked.into_iter();
+        let Some((selected, rule, weight)) = ranked.next() else {
+            return SelectionExplanation {
+                selected_device: None,
+                matched_rule_name: None,
+                matched_rule_weight: None,
+                matched_rule_match_type: None,
+                runners_up: Vec::new(),
+            };
+        };
+
+        SelectionExplanation {
+            selected_device: Some(selected.clone()),
+            matched_rule_weight: Some(weight),
+            matched_rule_match_type: Some(rule.match_type),
+            matched_rule_name: Some(rule.name),
+            runners_up: ranked
+                .map(|(device, rule, _)| (device.clone(), rule))
+                .collect(),
+        }
+    }
+
+    /// Priority score of each available output device: its score in
+    /// `rank_output_devices`, so weight decay, the battery bonus and exclusive
+    /// rules count, or 0 when it cannot be selected
+    pub fn score_all_output_devices(&self, devices: &[AudioDevice]) -> HashMap<String, u32> {
+        Self::scores_by_name(self.rank_output_devices(devices))
+    }
+
+    /// Priority score of each available input device, as for `score_all_output_devices`
+    pub fn score_all_input_devices(&self, devices: &[AudioDevice]) -> HashMap<String, u32> {
+        Self::scores_by_name(self.rank_input_devices(devices))
+    }
+
+    /// Check every output rule, then every input rule, against `device`,
+    /// including disabled rules, for diagnosing why a device is or is not selected
+    pub fn explain_device(&self, device: &AudioDevice) -> Vec<RuleMatchResult> {
+        let check = |direction: DeviceType, priorities: &[DeviceRule]| -> Vec<RuleMatchResult> {
+            priorities
+                .iter()
+                .map(|rule| {
+                    // A disabled rule never matches; report whether it would if enabled
+                    let mut enabled_rule = rule.clone();
+                    enabled_rule.enabled = true;
+                    let matched = enabled_rule.matches_device(device);
+                    let score = (matched && rule.enabled).then(|| {
+                        let weight = Self::rule_weight(priorities, rule, device);
+                        u32::try_from(weight.max(0)).unwrap_or(u32::MAX)
+                    });
+                    RuleMatchResult {
+                        direction: direction.clone(),
+                        rule: rule.clone(),
+                        matched,
+                        score,
+                    }
+                })
+                .collect()
+        };
+
+        let mut results = check(DeviceType::Output, &self.output_priorities);
+        results.extend(check(DeviceType::Input, &self.input_priorities));
+        results
+    }
+
+    /// Ranked scores keyed by device name; devices sharing a name keep the highest
+    fn scores_by_name(ranked: Vec<RankedDevice>) -> HashMap<String, u32> {
+        let mut scores = HashMap::new();
+        for ranked in ranked {
+            let score = scores.entry(ranked.device.name).or_insert(ranked.score);
+            *score = (*score).max(ranked.score);
+        }
+        scores
+    }
+
+    /// Preview whether connecting `new_device` would change the selected devices.
+    /// Returns `None` when no enabled rule matches the device, since it can never be selected.
+    pub fn simulate_device_arrival(
+        &self,
+        new_device: &AudioDevice,
+        current_devices: &[AudioDevice],
+    ) -> Option<SwitchDecision> {
+        let matched_rule = self.matching_rule(new_device)?.name.clone();
+
+        let mut devices_after: Vec<AudioDevice> = current_devices
+            .iter()
+            .filter(|device| device.id != new_device.id)
+            .cloned()
+            .collect();
+        devices_after.push(new_device.clone());
+
+        // The new device must win after arrival and differ from the previous winner
+        let takes_over = |before: Option<AudioDevice>, after: Option<AudioDevice>| {
+            after.is_some_and(|after| {
+                after.id == new_device.id && before.is_none_or(|before| before.id != after.id)
+            })
+        };
+
+        let decision = SwitchDecision {
+            should_switch_output: takes_over(
+                self.find_best_output_device(current_devices),
+                self.find_best_output_device(&devices_after),
+            ),
+            should_switch_input: takes_over(
+                self.find_best_input_device(current_devices),
+                self.find_best_input_device(&devices_after),
+            ),
+            matched_rule: Some(matched_rule),
+        };
+
+        debug!("Simulated arrival of {}: {:?}", new_device.name, decision);
+        Some(decision)
+    }
+
+    /// Find the rule that decides a device's priority, if any rule matches it
+    pub fn matching_rule(&self, device: &AudioDevice) -> Option<&DeviceRule> {
+        match device.device_type {
+            DeviceType::Output => Self::deciding_rule(&self.output_priorities, device),
+            DeviceType::Input => Self::deciding_rule(&self.input_priorities, device),
+            DeviceType::InputOutput => Self::deciding_rule(&self.output_priorities, device)
+                .or_else(|| Self::deciding_rule(&self.input_priorities, device)),
+        }
+    }
+
+    /// Buffer size configured by the rule that selects this device
+    pub fn buffer_size_for(&self, device: &AudioDevice) -> Option<u32> {
+        self.matching_rule(device)
+            .and_then(|rule| rule.buffer_size_frames)
+    }
+
+    /// Output volume configured by the rule that selects this device
+    pub fn volume_for(&self, device: &AudioDevice) -> Option<f32> {
+        self.matching_rule(device)
+            .and_then(|rule| rule.on_select_volume)
+    }
+
+    /// How long this device must stay connected before it can be switched to:
+    /// its rule's `stability_ms`, or else the Bluetooth or general threshold
+    pub fn get_stability_threshold(&self, device: &AudioDevice) -> u64 {
+        self.matching_rule(device)
+            .and_then(|rule| rule.stability_ms)
+            .unwrap_or(if device.is_bluetooth() {
+                self.bluetooth_stability_threshold_ms
+            } else {
+                self.stability_threshold_ms
+            })
+    }
+
+    /// Whether a device has been present since its `connected_since` time for at
+    /// least its stability threshold. Bluetooth devices also need their paired
+    /// input/output present, since the two halves can appear separately.
+    pub fn is_device_stable(
+        &self,
+        device: &AudioDevice,
+        now: SystemTime,
+        current_devices: &[AudioDevice],
+    ) -> bool {
+        let Some(connected_since) = device.connected_since else {
+            return false;
+        };
+        // A clock set backwards counts as just connected
+        let elapsed_ms = now
+            .duration_since(connected_since)
+            .unwrap_or_default()
+            .as_millis();
+
+        if elapsed_ms < u128::from(self.get_stability_threshold(device)) {
+            return false;
+        }
+
+        if device.is_bluetooth() {
+            // Extract common name part (e.g., "AirPods Pro" from "AirPods Pro - Output")
+            let base_name = device.name.split('-').next().unwrap_or(&device.name).trim();
+            return Self::has_paired_input_output(current_devices, base_name);
+        }
+
+        true
+    }
+
+    /// Check if both input and output devices exist for a given device name pattern
+    fn has_paired_input_output(devices: &[AudioDevice], device_name: &str) -> bool {
+        let has_output = devices
+            .iter()
+            .any(|d| d.name.contains(device_name) && d.device_type == DeviceType::Output);
+        let has_input = devices
+            .iter()
+            .any(|d| d.name.contains(device_name) && d.device_type == DeviceType::Input);
+        has_output && has_input
+    }
+
+    /// The highest-weight rule matching a device. An exclusive match shuts
+    /// out every non-exclusive rule for the device.
+    fn deciding_rule<'a>(
+        priorities: &'a [DeviceRule],
+        device: &AudioDevice,
+    ) -> Option<&'a DeviceRule> {
+        let matching_rules: Vec<&DeviceRule> = priorities
+            .iter()
+            .filter(|rule| {
+                let matches = rule.matches_device(device);
+                debug!(
+                    "    Rule '{}' (type: {:?}, weight: {}) -> matches: {}",
+                    rule.name, rule.match_type, rule.weight, matches
+                );
+                matches
+            })
+            .collect();
+
+        let has_exclusive = matching_rules.iter().any(|rule| rule.exclusive);
+
+        matching_rules
+            .into_iter()
+            .filter(|rule| rule.exclusive || !has_exclusive)
+            .map(|rule| (rule, Self::rule_weight(priorities, rule, device)))
+            .fold(
+                None,
+                |best: Option<(&DeviceRule, i64)>, (rule, weight)| match best {
+                    Some(best) if best.1 >= weight => Some(best),
+                    _ => Some((rule, weight)),
+                },
+            )
+            .map(|(rule, _)| rule)
+    }
+
+    /// A rule's weight for a device, resolving its `weight_formula` against the
+    /// first formula-free rule in `priorities` that matches the same device
+    fn rule_weight(priorities: &[DeviceRule], rule: &DeviceRule, device: &AudioDevice) -> i64 {
+        if rule.weight_formula.is_none() {
+            return rule.weight;
+        }
+
+        let base = priorities
+            .iter()
+            .find(|other| other.weight_formula.is_none() && other.matches_device(device))
+            .map_or(0, |other| other.weight);
+        rule.effective_weight(base)
+    }
+
+    /// The first ranked device, unless no device can be selected
+    fn best_of(ranked: Vec<RankedDevice>, device_type: DeviceType) -> Option<AudioDevice> {
+        let best = ranked.into_iter().next().filter(|ranked| ranked.score > 0);
+
+        if let Some(best) = &best {
+            debug!(
+                "Best {} device: {} (weight: {})",
+                device_type, best.device.name, best.score
+            );
+        } else {
+            debug!("No matching {} device found", device_type);
+        }
+
+        best.map(|ranked| ranked.device)
     }
 
-    fn find_best_device(
+    /// `ranked_devices` followed by the devices of `device_type` it leaves out
+    fn rank_devices(
         &self,
         available_devices: &[AudioDevice],
         priorities: &[DeviceRule],
+        other_priorities: &[DeviceRule],
         device_type: DeviceType,
-    ) -> Option<AudioDevice> {
-        let mut best_device: Option<AudioDevice> = None;
-        let mut best_weight = 0;
+    ) -> Vec<RankedDevice> {
+        let selectable = self.ranked_devices(
+            available_devices,
+            priorities,
+            other_priorities,
+            device_type.clone(),
+        );
+        let mut ranked: Vec<RankedDevice> = selectable
+            .iter()
+            .map(|(device, rule, weight)| RankedDevice {
+                device: (*device).clone(),
+                score: u32::try_from(*weight).unwrap_or(u32::MAX),
+                matched_rule: Some(rule.name.clone()),
+            })
+            .collect();
+
+        // Compared by address, since IDs need not be unique among the devices given
+        let unselectable = available_devices
+            .iter()
+            .filter(|device| device.device_type == device_type)
+            .filter(|device| {
+                !selectable
+                    .iter()
+                    .any(|(ranked, _, _)| std::ptr::eq(*ranked, *device))
+            })
+            .map(|device| RankedDevice {
+                device: device.clone(),
+                score: 0,
+                matched_rule: None,
+            });
+
+        ranked.extend(unselectable);
+        ranked
+    }
+
+    /// Every selectable device of `device_type` with its deciding rule and final
+    /// weight, highest weight first. Ties go to the device whose rule comes
+    /// first, then keep the order of `available_devices`. Devices that do not
+    /// reach a positive weight are left out.
+    fn ranked_devices<'a>(
+        &self,
+        available_devices: &'a [AudioDevice],
+        priorities: &[DeviceRule],
+        other_priorities: &[DeviceRule],
+        device_type: DeviceType,
+    ) -> Vec<(&'a AudioDevice, DeviceRule, i64)> {
+        let mut ranked = Vec::new();
+
+        // Rules whose companion device is missing do not apply at all
+        let active_priorities: Vec<DeviceRule> = priorities
+            .iter()
+            .filter(|rule| {
+                let present = rule.required_device_present(available_devices);
+                if !present {
+                    debug!(
+                        "  Skipping rule '{}': required device '{}' is not connected",
+                        rule.name,
+                        rule.requires_other_device.as_deref().unwrap_or_default()
+                    );
+                }
+                present
+            })
+            .cloned()
+            .collect();
 
         // Filter devices by type first
         let filtered_devices: Vec<&AudioDevice> = available_devices
@@ -61,54 +581,250 @@ impl DevicePriorityManager {
 
         for device in filtered_devices {
             debug!("  Checking device: '{}'", device.name);
-            for rule in priorities {
-                let matches = rule.matches(&device.name);
+
+            if let Some(rule) = other_priorities
+                .iter()
+                .find(|rule| rule.exclusive && rule.matches_device(device))
+            {
                 debug!(
-                    "    Rule '{}' (type: {:?}, weight: {}) -> matches: {}",
-                    rule.name, rule.match_type, rule.weight, matches
+                    "    Skipping device claimed by exclusive rule '{}' for the other direction",
+                    rule.name
                 );
-                if matches && rule.weight > best_weight {
-                    best_device = Some(device.clone());
-                    best_weight = rule.weight;
-                    debug!(
-                        "Found {} device match: {} (weight: {})",
-                        device_type, device.name, rule.weight
-                    );
-                }
+                continue;
+            }
+
+            let Some(rule) = Self::deciding_rule(&active_priorities, device) else {
+                continue;
+            };
+            let rule_weight = Self::rule_weight(&active_priorities, rule, device);
+            if rule_weight < 0 {
+                debug!(
+                    "Skipping blacklisted {} device: {} (weight: {})",
+                    device_type, device.name, rule_weight
+                );
+                continue;
+            }
+
+            let weight =
+                self.decayed_weight(rule, rule_weight, device) + self.battery_bonus(device);
+
+            if weight > 0 {
+                debug!(
+                    "Found {} device match: {} (weight: {})",
+                    device_type, device.name, weight
+                );
+                let rule_position = active_priorities
+                    .iter()
+                    .position(|active| std::ptr::eq(active, rule))
+                    .unwrap_or(usize::MAX);
+                ranked.push((device, rule.clone(), weight, rule_position));
             }
         }
 
-        if let Some(ref device) = best_device {
+        // Stable, so the first of equally placed devices stays ahead
+        ranked.sort_by_key(|(_, _, weight, rule_position)| {
+            (std::cmp::Reverse(*weight), *rule_position)
+        });
+        ranked
+            .into_iter()
+            .map(|(device, rule, weight, _)| (device, rule, weight))
+            .collect()
+    }
+
+    /// The `weight` a rule gives a device, reduced by the rule's decay factor while the
+    /// device is within `weight_decay_after_disconnect_ms` of its last disconnect
+    fn decayed_weight(&self, rule: &DeviceRule, weight: i64, device: &AudioDevice) -> i64 {
+        let Some(window_ms) = rule.weight_decay_after_disconnect_ms else {
+            return weight;
+        };
+        let Some(disconnected_at) = self.disconnected_at.get(&device.id) else {
+            return weight;
+        };
+        if disconnected_at.elapsed() >= Duration::from_millis(window_ms) {
+            return weight;
+        }
+
+        let factor = rule
+            .weight_decay_factor
+            .unwrap_or(DEFAULT_WEIGHT_DECAY_FACTOR);
+        let decayed = (weight as f64 * f64::from(factor)) as i64;
+        debug!(
+            "    Decaying weight of recently disconnected device '{}' from {} to {}",
+            device.name, weight, decayed
+        );
+        decayed
+    }
+
+    /// Extra weight for wired devices while on battery, if enabled
+    fn battery_bonus(&self, device: &AudioDevice) -> i64 {
+        if self.prefer_wired_on_battery
+            && self.on_battery_power
+            && device.transport_type == Some(TransportType::Usb)
+        {
             debug!(
-                "Best {} device: {} (weight: {})",
-                device_type, device.name, best_weight
+                "    Applying battery bonus of {} to wired device '{}'",
+                WIRED_ON_BATTERY_BONUS, device.name
             );
+            WIRED_ON_BATTERY_BONUS
         } else {
-            debug!("No matching {} device found", device_type);
+            0
         }
-
-        best_device
     }
 
     pub fn should_switch_output(&self, new_device: &AudioDevice) -> bool {
-        match &self.current_output {
-            Some(current) => current != &new_device.name,
-            None => true,
-        }
+        self.should_switch_output_from(self.current_output.as_ref(), new_device)
     }
 
     pub fn should_switch_input(&self, new_device: &AudioDevice) -> bool {
-        match &self.current_input {
-            Some(current) => current != &new_device.name,
-            None => true,
+        self.should_switch_input_from(self.current_input.as_ref(), new_device)
+    }
+
+    /// `should_switch_output` from `current` instead of the tracked output device
+    pub fn should_switch_output_from(
+        &self,
+        current: Option<&AudioDevice>,
+        new_device: &AudioDevice,
+    ) -> bool {
+        self.should_switch(current, new_device, &self.output_priorities)
+    }
+
+    /// `should_switch_input` from `current` instead of the tracked input device
+    pub fn should_switch_input_from(
+        &self,
+        current: Option<&AudioDevice>,
+        new_device: &AudioDevice,
+    ) -> bool {
+        self.should_switch(current, new_device, &self.input_priorities)
+    }
+
+    /// Switch to a different device only if it leads the current one by
+    /// `min_weight_to_switch` and the current one was not chosen by a sticky rule.
+    /// A device that does not outrank the current one can only be offered because
+    /// the current one is gone, so it is always switched to, as is any device when
+    /// either weight is unknown.
+    fn should_switch(
+        &self,
+        current: Option<&AudioDevice>,
+        new_device: &AudioDevice,
+        priorities: &[DeviceRule],
+    ) -> bool {
+        let Some(current_device) = current else {
+            return true;
+        };
+        let current = current_device.name.as_str();
+        if current == new_device.name {
+            return false;
+        }
+
+        let (Some(current_rule), Some(new_rule)) = (
+            Self::deciding_rule(priorities, current_device),
+            Self::deciding_rule(priorities, new_device),
+        ) else {
+            return true;
+        };
+
+        let new_weight = Self::rule_weight(priorities, new_rule, new_device);
+        let current_weight = Self::rule_weight(priorities, current_rule, current_device);
+        let lead = self.decayed_weight(new_rule, new_weight, new_device)
+            + self.battery_bonus(new_device)
+            - current_weight;
+        if lead >= 0 && current_rule.sticky {
+            debug!(
+                "Keeping '{}' selected by sticky rule '{}' over '{}'",
+                current, current_rule.name, new_device.name
+            );
+            return false;
+        }
+        if (0..i64::from(self.min_weight_to_switch)).contains(&lead) {
+            debug!(
+                "Keeping '{}': '{}' leads by {} but min_weight_to_switch is {}",
+                current, new_device.name, lead, self.min_weight_to_switch
+            );
+            return false;
+        }
+        true
+    }
+
+    pub fn update_current_output(&mut self, device: AudioDevice) {
+        self.current_output = Some(device);
+    }
+
+    pub fn update_current_input(&mut self, device: AudioDevice) {
+        self.current_input = Some(device);
+    }
+
+    /// Note that the output device was just switched to `device`, counting a
+    /// hit for the output rule that selects it
+    pub fn record_output_switch(&mut self, device: &AudioDevice) {
+        self.last_output_switch = Some(SystemTime::now());
+        if let Some(rule) = Self::deciding_rule(&self.output_priorities, device) {
+            *self.rule_hit_counters.entry(rule.name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    /// Note that the input device was just switched to `device`, counting a
+    /// hit for the input rule that selects it
+    pub fn record_input_switch(&mut self, device: &AudioDevice) {
+        self.last_input_switch = Some(SystemTime::now());
+        if let Some(rule) = Self::deciding_rule(&self.input_priorities, device) {
+            *self.rule_hit_counters.entry(rule.name.clone()).or_insert(0) += 1;
         }
     }
 
-    pub fn update_current_output(&mut self, device_name: String) {
-        self.current_output = Some(device_name);
+    /// How many switches each rule caused, by rule name. Rules that never
+    /// caused a switch are missing.
+    pub fn rule_hits(&self) -> &HashMap<String, u64> {
+        &self.rule_hit_counters
+    }
+
+    /// When the output device was last switched, if ever
+    pub fn get_last_output_switch_time(&self) -> Option<SystemTime> {
+        self.last_output_switch
     }
 
-    pub fn update_current_input(&mut self, device_name: String) {
-        self.current_input = Some(device_name);
+    /// When the input device was last switched, if ever
+    pub fn get_last_input_switch_time(&self) -> Option<SystemTime> {
+        self.last_input_switch
+    }
+
+    /// Forget a current device that went away, releasing a sticky rule's hold on it,
+    /// and start its weight decay window
+    pub fn device_disconnected(&mut self, device: &AudioDevice) {
+        let is_device = |current: &Option<AudioDevice>| {
+            current
+                .as_ref()
+                .is_some_and(|current| current.name == device.name)
+        };
+        if is_device(&self.current_output) {
+            self.current_output = None;
+        }
+        if is_device(&self.current_input) {
+            self.current_input = None;
+        }
+        self.prune_disconnects();
+        self.disconnected_at
+            .insert(device.id.clone(), Instant::now());
+    }
+
+    /// IDs of the devices whose last disconnect is still remembered for weight decay
+    // Called by tests checking that expired disconnects are forgotten
+    #[allow(dead_code)]
+    pub fn recently_disconnected(&self) -> Vec<&str> {
+        self.disconnected_at.keys().map(String::as_str).collect()
+    }
+
+    /// Forget disconnects older than every rule's `weight_decay_after_disconnect_ms`,
+    /// which no longer decay any weight
+    fn prune_disconnects(&mut self) {
+        let longest_window = self
+            .output_priorities
+            .iter()
+            .chain(&self.input_priorities)
+            .filter_map(|rule| rule.weight_decay_after_disconnect_ms)
+            .max()
+            .unwrap_or(0);
+        let longest_window = Duration::from_millis(longest_window);
+        self.disconnected_at
+            .retain(|_, disconnected_at| disconnected_at.elapsed() < longest_window);
     }
 }
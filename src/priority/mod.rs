@@ -1,3 +1,5 @@
 pub mod manager;
 
 pub use manager::DevicePriorityManager;
+#[allow(unused_imports)] // Used by library consumers and tests
+pub use manager::{RankedDevice, RuleMatchResult, SelectionExplanation, SwitchDecision};
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
@@ -36,7 +36,7 @@ impl ServiceManager {
         info!("Starting audio device monitor service");
 
         // Initialize the audio device monitor
-        self.monitor = Some(AudioDeviceMonitor::new(self.config.clone())?);
+        self.monitor = Some(AudioDeviceMonitor::new_production(&self.config)?);
         let monitor = self.monitor.as_ref().unwrap();
 
         // Create signal channel
@@ -138,7 +138,7 @@ impl ServiceManager {
 
         // Update config and restart monitor
         self.config = new_config;
-        self.monitor = Some(AudioDeviceMonitor::new(self.config.clone())?);
+        self.monitor = Some(AudioDeviceMonitor::new_production(&self.config)?);
 
         if let Some(monitor) = &self.monitor {
             monitor.start_monitoring_async().await?;
@@ -149,6 +149,25 @@ impl ServiceManager {
     }
 }
 
+/// Prefix shared by all LaunchAgent plists installed by this tool
+const LAUNCH_AGENT_PREFIX: &str = "com.audiodevicemonitor.";
+
+/// A LaunchAgent plist found on disk and the binary it launches
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledVersion {
+    pub plist_path: PathBuf,
+    pub binary_path: Option<PathBuf>,
+    /// Version reported by the installed binary, if it could be run
+    pub version: Option<String>,
+}
+
+impl InstalledVersion {
+    /// Whether the installed binary reports a different version than the running one
+    pub fn differs_from_current(&self) -> bool {
+        self.version.as_deref() != Some(ServiceInstaller::current_version())
+    }
+}
+
 /// Service installation utilities
 pub struct ServiceInstaller;
 
@@ -177,24 +196,135 @@ impl ServiceInstaller {
         Ok(())
     }
 
-    /// Uninstall the LaunchAgent
-    pub fn uninstall_launch_agent() -> Result<()> {
+    /// Unload and remove the LaunchAgent. `force` skips `launchctl unload`, e.g. when
+    /// launchd no longer knows the service. A missing plist counts as already uninstalled.
+    pub fn uninstall_launch_agent(force: bool) -> Result<()> {
         info!("Uninstalling macOS LaunchAgent");
 
         let plist_path = Self::get_launch_agent_path()?;
+        Self::uninstall_launch_agent_at(&plist_path, force)
+    }
 
-        if plist_path.exists() {
-            std::fs::remove_file(&plist_path)?;
-            info!("LaunchAgent removed from: {}", plist_path.display());
-            info!(
-                "To unload the service, run: launchctl unload {}",
-                plist_path.display()
-            );
-        } else {
+    /// Unload (unless `force`) and delete the plist at `plist_path`, attempting both
+    /// steps and reporting every failure together
+    fn uninstall_launch_agent_at(plist_path: &Path, force: bool) -> Result<()> {
+        if !plist_path.exists() {
             warn!("LaunchAgent plist not found at: {}", plist_path.display());
+            return Ok(());
         }
 
-        Ok(())
+        let mut errors = Vec::new();
+
+        if !force {
+            match std::process::Command::new("launchctl")
+                .arg("unload")
+                .arg(plist_path)
+                .output()
+            {
+                Ok(output) if output.status.success() => {
+                    info!("LaunchAgent unloaded: {}", plist_path.display());
+                }
+                Ok(output) => errors.push(format!(
+                    "launchctl unload failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Err(e) => errors.push(format!("failed to run launchctl: {e}")),
+            }
+        }
+
+        match std::fs::remove_file(plist_path) {
+            Ok(()) => info!("LaunchAgent removed from: {}", plist_path.display()),
+            Err(e) => errors.push(format!("failed to remove {}: {e}", plist_path.display())),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to uninstall LaunchAgent: {}",
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// Version of the currently running binary
+    pub fn current_version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    /// List all installed LaunchAgents for this tool with their binary versions
+    pub fn list_versions() -> Result<Vec<InstalledVersion>> {
+        let launch_agents_dir = Self::get_launch_agent_path()?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow::anyhow!("Failed to get LaunchAgents directory"))?;
+
+        Self::list_versions_in(&launch_agents_dir)
+    }
+
+    /// List installed LaunchAgents in the given directory, sorted by plist path
+    fn list_versions_in(launch_agents_dir: &Path) -> Result<Vec<InstalledVersion>> {
+        if !launch_agents_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = Vec::new();
+        for entry in std::fs::read_dir(launch_agents_dir)? {
+            let plist_path = entry?.path();
+            let is_ours = plist_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LAUNCH_AGENT_PREFIX) && name.ends_with(".plist")
+                });
+            if !is_ours {
+                continue;
+            }
+
+            let binary_path = match std::fs::read_to_string(&plist_path) {
+                Ok(content) => Self::extract_program_path(&content).map(PathBuf::from),
+                Err(e) => {
+                    warn!("Failed to read {}: {}", plist_path.display(), e);
+                    None
+                }
+            };
+            let version = binary_path.as_deref().and_then(Self::query_binary_version);
+
+            versions.push(InstalledVersion {
+                plist_path,
+                binary_path,
+                version,
+            });
+        }
+
+        versions.sort_by(|a, b| a.plist_path.cmp(&b.plist_path));
+        Ok(versions)
+    }
+
+    /// Extract the first `ProgramArguments` entry (the binary path) from plist XML
+    fn extract_program_path(plist_content: &str) -> Option<String> {
+        let after_key = plist_content.split("<key>ProgramArguments</key>").nth(1)?;
+        let array = after_key.split("</array>").next()?;
+        let start = array.find("<string>")? + "<string>".len();
+        let end = array[start..].find("</string>")? + start;
+        Some(array[start..end].trim().to_string())
+    }
+
+    /// Run `<binary> --version` and return the version number it prints
+    fn query_binary_version(binary_path: &Path) -> Option<String> {
+        let output = std::process::Command::new(binary_path)
+            .arg("--version")
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .last()
+            .map(str::to_string)
     }
 
     fn generate_launch_agent_plist() -> Result<String> {
@@ -233,9 +363,124 @@ impl ServiceInstaller {
         Ok(plist)
     }
 
-    fn get_launch_agent_path() -> Result<PathBuf> {
+    /// `~/Library/LaunchAgents/com.audiodevicemonitor.daemon.plist`
+    pub fn get_launch_agent_path() -> Result<PathBuf> {
         let home_dir =
             dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Failed to get home directory"))?;
         Ok(home_dir.join("Library/LaunchAgents/com.audiodevicemonitor.daemon.plist"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn plist_for(binary: &str) -> String {
+        format!(
+            r#"<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.audiodevicemonitor.daemon</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{binary}</string>
+        <string>daemon</string>
+    </array>
+</dict>
+</plist>"#
+        )
+    }
+
+    #[test]
+    fn test_extract_program_path() {
+        let plist = plist_for("/usr/local/bin/audio-device-monitor-0.1.0");
+        assert_eq!(
+            ServiceInstaller::extract_program_path(&plist).as_deref(),
+            Some("/usr/local/bin/audio-device-monitor-0.1.0")
+        );
+    }
+
+    #[test]
+    fn test_extract_program_path_missing_key() {
+        let plist = "<plist><dict><key>Label</key><string>x</string></dict></plist>";
+        assert!(ServiceInstaller::extract_program_path(plist).is_none());
+    }
+
+    #[test]
+    fn test_list_versions_only_includes_our_plists() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(
+            dir.join("com.audiodevicemonitor.daemon.plist"),
+            plist_for("/nonexistent/audio-device-monitor"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("com.audiodevicemonitor.v2.plist"),
+            plist_for("/nonexistent/audio-device-monitor-v2"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("com.example.other.plist"), plist_for("/bin/other")).unwrap();
+
+        let versions = ServiceInstaller::list_versions_in(dir).unwrap();
+
+        assert_eq!(versions.len(), 2);
+        assert_eq!(
+            versions[0].binary_path,
+            Some(PathBuf::from("/nonexistent/audio-device-monitor"))
+        );
+        assert_eq!(
+            versions[1].binary_path,
+            Some(PathBuf::from("/nonexistent/audio-device-monitor-v2"))
+        );
+
+        // Missing binaries have no version and are reported as different
+        assert!(versions[0].version.is_none());
+        assert!(versions[0].differs_from_current());
+    }
+
+    #[test]
+    fn test_list_versions_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("LaunchAgents");
+        assert!(
+            ServiceInstaller::list_versions_in(&missing)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_matching_version_is_not_different() {
+        let installed = InstalledVersion {
+            plist_path: PathBuf::from("/tmp/com.audiodevicemonitor.daemon.plist"),
+            binary_path: Some(PathBuf::from("/usr/local/bin/audio-device-monitor")),
+            version: Some(ServiceInstaller::current_version().to_string()),
+        };
+        assert!(!installed.differs_from_current());
+    }
+
+    #[test]
+    fn test_force_uninstall_removes_plist() {
+        let temp_dir = TempDir::new().unwrap();
+        let plist_path = temp_dir.path().join("com.audiodevicemonitor.daemon.plist");
+        std::fs::write(
+            &plist_path,
+            plist_for("/usr/local/bin/audio-device-monitor"),
+        )
+        .unwrap();
+
+        ServiceInstaller::uninstall_launch_agent_at(&plist_path, true).unwrap();
+
+        assert!(!plist_path.exists());
+    }
+
+    #[test]
+    fn test_uninstall_missing_plist_is_ok() {
+        let temp_dir = TempDir::new().unwrap();
+        let plist_path = temp_dir.path().join("com.audiodevicemonitor.daemon.plist");
+
+        assert!(ServiceInstaller::uninstall_launch_agent_at(&plist_path, false).is_ok());
+    }
+}
@@ -0,0 +1,561 @@
+//! Runtime control of a running service over a Unix domain socket
+//!
+//! The socket takes one JSON request per line and answers each with one JSON
+//! line:
+//!
+//! - `{"cmd":"status"}`: the service status, as `export_status_json` reports it;
+//!   `"include_scores":true` adds the score of each available device
+//! - `{"cmd":"switch","device":"AirPods Pro","type":"output"}`: switch to the
+//!   device with exactly this name; an optional `"requester":"Zoom"` names the
+//!   application asking, for the notification and the switch history
+//! - `{"cmd":"reload"}`: reload the configuration file, as SIGHUP does
+//! - `{"cmd":"list-devices"}`: the devices the service currently sees
+//! - `{"cmd":"stats"}`: switch counts and rule hits since the service started
+//! - `{"cmd":"set-profile","profile":"office"}`: switch to a configuration
+//!   profile; `"profile":null` returns to the rules without a profile
+//! - `{"cmd":"pause"}` / `{"cmd":"resume"}`: stop and restart automatic
+//!   switching, e.g. for the duration of a call
+//!
+//! Replies are `{"ok":true,"data":...}` or `{"ok":false,"error":"..."}`. The
+//! listener thread hands each request to the service loop as an `IpcCommand`
+//! and waits for the loop's answer.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// How many IPC commands can wait for the service loop before senders block
+pub const IPC_COMMAND_QUEUE_SIZE: usize = 32;
+
+/// Control socket location, relative to the home directory
+const IPC_SOCKET_RELATIVE_PATH: &str = ".local/share/audio-device-monitor/control.sock";
+
+/// How long a client waits for a reply. The service loop handles commands
+/// between sleeps, so replies can take up to one check interval.
+pub const IPC_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which default device a switch command targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchDirection {
+    Output,
+    Input,
+}
+
+/// A request from the IPC socket handler for the running service
+// Constructed by the IPC socket handler and tests
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum IpcCommand {
+    /// Reload the configuration file, as SIGHUP does
+    Reload {
+        respond_to: Option<oneshot::Sender<Result<()>>>,
+    },
+    /// Reload the configuration with `profile` applied, or with none
+    SetProfile {
+        profile: Option<String>,
+        respond_to: Option<oneshot::Sender<Result<()>>>,
+    },
+    /// Switch the default device in `direction` to the device named `device`,
+    /// on behalf of the application `requester` if one is named
+    Switch {
+        device: String,
+        direction: SwitchDirection,
+        requester: Option<String>,
+        respond_to: Option<oneshot::Sender<Result<()>>>,
+    },
+    /// Stop automatic switching until `Resume`
+    Pause {
+        respond_to: Option<oneshot::Sender<Result<()>>>,
+    },
+    /// Restart automatic switching after `Pause`
+    Resume {
+        respond_to: Option<oneshot::Sender<Result<()>>>,
+    },
+    /// Reply with the service status JSON, with device scores if asked for
+    GetStatus {
+        include_scores: bool,
+        respond_to: oneshot::Sender<Result<String>>,
+    },
+    /// Reply with the available devices as JSON
+    ListDevices {
+        respond_to: oneshot::Sender<Result<String>>,
+    },
+    /// Reply with the service statistics as JSON
+    GetStats {
+        respond_to: oneshot::Sender<Result<String>>,
+    },
+}
+
+/// One request line on the control socket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum IpcRequest {
+    Status {
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        include_scores: bool,
+    },
+    Switch {
+        device: String,
+        #[serde(rename = "type")]
+        direction: SwitchDirection,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        requester: Option<String>,
+    },
+    Reload,
+    ListDevices,
+    Stats,
+    SetProfile {
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    Pause,
+    Resume,
+}
+
+/// The reply line to a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl IpcResponse {
+    fn from_result(result: Result<Option<serde_json::Value>>) -> Self {
+        match result {
+            Ok(data) => Self {
+                ok: true,
+                data,
+                error: None,
+            },
+            Err(e) => Self {
+                ok: false,
+                data: None,
+                error: Some(format!("{e:#}")),
+            },
+        }
+    }
+
+    /// The reply data, or the service's error
+    pub fn into_result(self) -> Result<Option<serde_json::Value>> {
+        if self.ok {
+            Ok(self.data)
+        } else {
+            Err(anyhow::anyhow!(self.error.unwrap_or_else(|| {
+                "Service reported an unknown error".to_string()
+            })))
+        }
+    }
+}
+
+/// `~/.local/share/audio-device-monitor/control.sock`
+pub fn default_socket_path() -> Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Failed to get home directory"))?;
+    Ok(home_dir.join(IPC_SOCKET_RELATIVE_PATH))
+}
+
+/// A bound control socket. Dropping it removes the socket file; the listener
+/// thread itself lives until the process exits.
+#[derive(Debug)]
+pub struct IpcListener {
+    path: PathBuf,
+}
+
+impl Drop for IpcListener {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("Could not remove IPC socket {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Bind the control socket at `path` and answer requests on a background
+/// thread, forwarding them to the service through `commands`. A socket file
+/// left behind by a crashed service is replaced; one a live service is still
+/// listening on is an error.
+pub fn spawn_ipc_listener(path: &Path, commands: mpsc::Sender<IpcCommand>) -> Result<IpcListener> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create IPC socket directory {}", parent.display())
+        })?;
+    }
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(anyhow::anyhow!(
+                "Another service is already listening on {}",
+                path.display()
+            ));
+        }
+        debug!("Removing stale IPC socket {}", path.display());
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale IPC socket {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind IPC socket {}", path.display()))?;
+
+    std::thread::Builder::new()
+        .name("ipc-listener".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let commands = commands.clone();
+                        std::thread::spawn(move || handle_connection(stream, &commands));
+                    }
+                    Err(e) => warn!("Failed to accept IPC connection: {}", e),
+                }
+            }
+        })
+        .context("Failed to start IPC listener thread")?;
+
+    info!("Listening for IPC commands on {}", path.display());
+    Ok(IpcListener {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Answer each request line on `stream` until the client disconnects
+fn handle_connection(stream: UnixStream, commands: &mpsc::Sender<IpcCommand>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!("Failed to set up IPC connection: {}", e);
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                debug!("IPC connection closed: {}", e);
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = serde_json::from_str::<IpcRequest>(&line)
+            .context("Invalid IPC request")
+            .and_then(|request| dispatch(request, commands));
+        let reply = serde_json::to_string(&IpcResponse::from_result(result))
+            .expect("IPC responses always serialize");
+
+        if let Err(e) = writeln!(writer, "{reply}").and_then(|()| writer.flush()) {
+            debug!("IPC client went away before the reply: {}", e);
+            return;
+        }
+    }
+}
+
+/// Forward a request to the service loop and wait for its answer
+fn dispatch(
+    request: IpcRequest,
+    commands: &mpsc::Sender<IpcCommand>,
+) -> Result<Option<serde_json::Value>> {
+    debug!("Handling IPC request: {:?}", request);
+
+    match request {
+        IpcRequest::Status { include_scores } => {
+            let status = ask_service(commands, |respond_to| IpcCommand::GetStatus {
+                include_scores,
+                respond_to,
+            })?;
+            Ok(Some(serde_json::from_str(&status)?))
+        }
+        IpcRequest::ListDevices => {
+            let devices = ask_service(commands, |respond_to| IpcCommand::ListDevices {
+                respond_to,
+            })?;
+            Ok(Some(serde_json::from_str(&devices)?))
+        }
+        IpcRequest::Stats => {
+            let stats = ask_service(commands, |respond_to| IpcCommand::GetStats { respond_to })?;
+            Ok(Some(serde_json::from_str(&stats)?))
+        }
+        IpcRequest::Switch {
+            device,
+            direction,
+            requester,
+        } => {
+            ask_service(commands, |respond_to| IpcCommand::Switch {
+                device,
+                direction,
+                requester,
+                respond_to: Some(respond_to),
+            })?;
+            Ok(None)
+        }
+        IpcRequest::Reload => {
+            ask_service(commands, |respond_to| IpcCommand::Reload {
+                respond_to: Some(respond_to),
+            })?;
+            Ok(None)
+        }
+        IpcRequest::Pause => {
+            ask_service(commands, |respond_to| IpcCommand::Pause {
+                respond_to: Some(respond_to),
+            })?;
+            Ok(None)
+        }
+        IpcRequest::Resume => {
+            ask_service(commands, |respond_to| IpcCommand::Resume {
+                respond_to: Some(respond_to),
+            })?;
+            Ok(None)
+        }
+        IpcRequest::SetProfile { profile } => {
+            ask_service(commands, |respond_to| IpcCommand::SetProfile {
+                profile,
+                respond_to: Some(respond_to),
+            })?;
+            Ok(None)
+        }
+    }
+}
+
+fn ask_service<T>(
+    commands: &mpsc::Sender<IpcCommand>,
+    command: impl FnOnce(oneshot::Sender<Result<T>>) -> IpcCommand,
+) -> Result<T> {
+    let (respond_to, reply) = oneshot::channel();
+    commands
+        .blocking_send(command(respond_to))
+        .map_err(|_| anyhow::anyhow!("The service is shutting down"))?;
+    reply
+        .blocking_recv()
+        .map_err(|_| anyhow::anyhow!("The service dropped the request"))?
+}
+
+/// A connection to a running service's control socket
+pub struct IpcClient {
+    reader: BufReader<UnixStream>,
+}
+
+impl IpcClient {
+    /// Connect to the service listening on `path`, or `None` if no service is
+    pub fn connect(path: &Path) -> Option<Self> {
+        let stream = match UnixStream::connect(path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("No service listening on {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if let Err(e) = stream.set_read_timeout(Some(IPC_CLIENT_TIMEOUT)) {
+            warn!("Failed to set IPC read timeout: {}", e);
+        }
+
+        Some(Self {
+            reader: BufReader::new(stream),
+        })
+    }
+
+    /// Send a request and wait for the service's reply data
+    pub fn request(&mut self, request: &IpcRequest) -> Result<Option<serde_json::Value>> {
+        let stream = self.reader.get_mut();
+        writeln!(stream, "{}", serde_json::to_string(request)?)?;
+        stream.flush()?;
+
+        let mut reply = String::new();
+        if self.reader.read_line(&mut reply)? == 0 {
+            return Err(anyhow::anyhow!("The service closed the connection"));
+        }
+        let response: IpcResponse =
+            serde_json::from_str(&reply).context("Invalid reply from the service")?;
+        response.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_lines_parse() {
+        let parse = |line| serde_json::from_str::<IpcRequest>(line).unwrap();
+
+        assert_eq!(
+            parse(r#"{"cmd":"status"}"#),
+            IpcRequest::Status {
+                include_scores: false
+            }
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"status","include_scores":true}"#),
+            IpcRequest::Status {
+                include_scores: true
+            }
+        );
+        assert_eq!(parse(r#"{"cmd":"reload"}"#), IpcRequest::Reload);
+        assert_eq!(parse(r#"{"cmd":"list-devices"}"#), IpcRequest::ListDevices);
+        assert_eq!(parse(r#"{"cmd":"stats"}"#), IpcRequest::Stats);
+        assert_eq!(parse(r#"{"cmd":"pause"}"#), IpcRequest::Pause);
+        assert_eq!(parse(r#"{"cmd":"resume"}"#), IpcRequest::Resume);
+        assert_eq!(
+            parse(r#"{"cmd":"set-profile","profile":"office"}"#),
+            IpcRequest::SetProfile {
+                profile: Some("office".to_string())
+            }
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"set-profile","profile":null}"#),
+            IpcRequest::SetProfile { profile: None }
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"switch","device":"AirPods Pro","type":"output"}"#),
+            IpcRequest::Switch {
+                device: "AirPods Pro".to_string(),
+                direction: SwitchDirection::Output,
+                requester: None,
+            }
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"switch","device":"Headset","type":"input","requester":"Zoom"}"#),
+            IpcRequest::Switch {
+                device: "Headset".to_string(),
+                direction: SwitchDirection::Input,
+                requester: Some("Zoom".to_string()),
+            }
+        );
+        assert!(serde_json::from_str::<IpcRequest>(r#"{"cmd":"shutdown"}"#).is_err());
+    }
+
+    /// Answer every command the way a service would, from a stand-in service loop
+    fn spawn_fake_service(mut receiver: mpsc::Receiver<IpcCommand>) {
+        std::thread::spawn(move || {
+            while let Some(command) = receiver.blocking_recv() {
+                match command {
+                    IpcCommand::GetStatus { respond_to, .. } => {
+                        let _ = respond_to.send(Ok(r#"{"current_output":"Speakers"}"#.into()));
+                    }
+                    IpcCommand::ListDevices { respond_to } => {
+                        let _ = respond_to.send(Ok(r#"[{"name":"Speakers"}]"#.into()));
+                    }
+                    IpcCommand::GetStats { respond_to } => {
+                        let _ = respond_to.send(Ok(r#"{"total_switch_count":3}"#.into()));
+                    }
+                    IpcCommand::Switch {
+                        device, respond_to, ..
+                    } => {
+                        let result = if device == "Speakers" {
+                            Ok(())
+                        } else {
+                            Err(anyhow::anyhow!("Output device '{device}' not found"))
+                        };
+                        let _ = respond_to.unwrap().send(result);
+                    }
+                    IpcCommand::Reload { respond_to }
+                    | IpcCommand::Pause { respond_to }
+                    | IpcCommand::Resume { respond_to } => {
+                        let _ = respond_to.unwrap().send(Ok(()));
+                    }
+                    IpcCommand::SetProfile {
+                        profile,
+                        respond_to,
+                    } => {
+                        let result = match profile.as_deref() {
+                            Some("office") | None => Ok(()),
+                            Some(other) => Err(anyhow::anyhow!("Unknown profile '{other}'")),
+                        };
+                        let _ = respond_to.unwrap().send(result);
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_socket_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let (sender, receiver) = mpsc::channel(IPC_COMMAND_QUEUE_SIZE);
+        spawn_fake_service(receiver);
+
+        let _listener = spawn_ipc_listener(&path, sender).unwrap();
+        let mut client = IpcClient::connect(&path).unwrap();
+
+        let status = client
+            .request(&IpcRequest::Status {
+                include_scores: false,
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(status["current_output"], "Speakers");
+
+        let devices = client.request(&IpcRequest::ListDevices).unwrap().unwrap();
+        assert_eq!(devices[0]["name"], "Speakers");
+
+        let stats = client.request(&IpcRequest::Stats).unwrap().unwrap();
+        assert_eq!(stats["total_switch_count"], 3);
+
+        assert_eq!(client.request(&IpcRequest::Reload).unwrap(), None);
+        assert_eq!(client.request(&IpcRequest::Pause).unwrap(), None);
+        assert_eq!(client.request(&IpcRequest::Resume).unwrap(), None);
+
+        let set_profile = |profile: &str| IpcRequest::SetProfile {
+            profile: Some(profile.to_string()),
+        };
+        assert_eq!(client.request(&set_profile("office")).unwrap(), None);
+        let error = client.request(&set_profile("home")).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown profile 'home'");
+
+        let error = client
+            .request(&IpcRequest::Switch {
+                device: "Headphones".to_string(),
+                direction: SwitchDirection::Output,
+                requester: None,
+            })
+            .unwrap_err();
+        assert!(error.to_string().contains("'Headphones' not found"));
+    }
+
+    #[test]
+    fn test_invalid_request_gets_error_reply() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let (sender, _receiver) = mpsc::channel(IPC_COMMAND_QUEUE_SIZE);
+
+        let _listener = spawn_ipc_listener(&path, sender).unwrap();
+        let mut stream = UnixStream::connect(&path).unwrap();
+        writeln!(stream, "not json").unwrap();
+
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        let response: IpcResponse = serde_json::from_str(&reply).unwrap();
+        assert!(!response.ok);
+        assert!(response.error.unwrap().contains("Invalid IPC request"));
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced_and_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        // A socket file nobody listens on, as a crashed service leaves behind
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let (sender, _receiver) = mpsc::channel(IPC_COMMAND_QUEUE_SIZE);
+        let listener = spawn_ipc_listener(&path, sender.clone()).unwrap();
+        assert!(IpcClient::connect(&path).is_some());
+
+        // A second service must not steal the socket
+        assert!(spawn_ipc_listener(&path, sender).is_err());
+
+        drop(listener);
+        assert!(!path.exists());
+        assert!(IpcClient::connect(&path).is_none());
+    }
+}
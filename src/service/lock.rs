@@ -0,0 +1,261 @@
+//! Single-instance lock for the daemon
+//!
+//! Two daemons would both listen for CoreAudio changes and keep switching
+//! devices back and forth. The daemon holds an exclusive `flock` on a PID file
+//! while it runs; the lock disappears with the process, so a crash never leaves
+//! a lock that blocks the next start.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// PID file location, relative to the home directory
+const DAEMON_LOCK_RELATIVE_PATH: &str = ".local/share/audio-device-monitor/daemon.pid";
+
+/// How long `stop_running_instance` waits for the old daemon to exit
+pub const STOP_INSTANCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `stop_running_instance` checks whether the old daemon has exited
+const STOP_INSTANCE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `~/.local/share/audio-device-monitor/daemon.pid`
+pub fn default_lock_path() -> Result<PathBuf> {
+    let home_dir =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Failed to get home directory"))?;
+    Ok(home_dir.join(DAEMON_LOCK_RELATIVE_PATH))
+}
+
+/// The held daemon lock. Dropping it removes the PID file and releases the lock.
+#[derive(Debug)]
+pub struct DaemonLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl Drop for DaemonLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("Could not remove PID file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Lock the PID file at `path` and write `pid` into it, failing with the
+/// holder's PID if another instance has it locked
+pub fn acquire_daemon_lock(path: &Path, pid: u32) -> Result<DaemonLock> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create PID file directory {}", parent.display()))?;
+    }
+
+    // Not truncated on open: the PID belongs to whoever holds the lock
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open PID file {}", path.display()))?;
+
+    // SAFETY: the descriptor is owned by `file`, which outlives the call
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            return Err(match read_pid(path) {
+                Some(holder) => {
+                    anyhow::anyhow!("Another instance is already running (PID {holder})")
+                }
+                None => anyhow::anyhow!("Another instance is already running"),
+            });
+        }
+        return Err(error).with_context(|| format!("Failed to lock PID file {}", path.display()));
+    }
+
+    file.set_len(0)?;
+    writeln!(file, "{pid}")?;
+    file.flush()?;
+
+    debug!("Holding daemon lock {} as PID {}", path.display(), pid);
+    Ok(DaemonLock {
+        path: path.to_path_buf(),
+        _file: file,
+    })
+}
+
+/// What the PID file says about a daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockStatus {
+    NotRunning,
+    Running(u32),
+    /// The file is left over: no process holds its lock any more
+    Stale(u32),
+}
+
+impl LockStatus {
+    /// "running", "stale" or "not_running", for JSON output
+    pub fn state(&self) -> &'static str {
+        match self {
+            LockStatus::NotRunning => "not_running",
+            LockStatus::Running(_) => "running",
+            LockStatus::Stale(_) => "stale",
+        }
+    }
+
+    /// The PID the file names, if any
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            LockStatus::NotRunning => None,
+            LockStatus::Running(pid) | LockStatus::Stale(pid) => Some(*pid),
+        }
+    }
+}
+
+impl std::fmt::Display for LockStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockStatus::NotRunning => write!(f, "not running"),
+            LockStatus::Running(pid) => write!(f, "running (PID {pid})"),
+            LockStatus::Stale(pid) => write!(f, "stale lock (PID {pid})"),
+        }
+    }
+}
+
+/// Read the PID file at `path` and check whether a daemon still holds its lock.
+/// Unlike probing the PID, this cannot mistake an unrelated process that reused
+/// the PID of a crashed daemon for the daemon.
+pub fn lock_status(path: &Path) -> LockStatus {
+    match read_pid(path) {
+        None => LockStatus::NotRunning,
+        Some(pid) if lock_is_held(path) => LockStatus::Running(pid),
+        Some(pid) => LockStatus::Stale(pid),
+    }
+}
+
+/// Send SIGTERM to the daemon holding the lock at `path`, if any, and wait up
+/// to `STOP_INSTANCE_TIMEOUT` for it to release the lock
+pub fn stop_running_instance(path: &Path) -> Result<()> {
+    let LockStatus::Running(pid) = lock_status(path) else {
+        return Ok(());
+    };
+
+    info!("Stopping the running instance (PID {})", pid);
+    let pid_t = libc::pid_t::try_from(pid).context("PID out of range")?;
+    // SAFETY: kill has no memory safety requirements
+    if unsafe { libc::kill(pid_t, libc::SIGTERM) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to stop the running instance (PID {pid})"));
+    }
+
+    let started = Instant::now();
+    while lock_is_held(path) {
+        if started.elapsed() >= STOP_INSTANCE_TIMEOUT {
+            return Err(anyhow::anyhow!(
+                "The running instance (PID {pid}) did not exit within {}s",
+                STOP_INSTANCE_TIMEOUT.as_secs()
+            ));
+        }
+        std::thread::sleep(STOP_INSTANCE_POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Whether another open file holds the `flock` on the PID file at `path`.
+/// A missing file is not held.
+fn lock_is_held(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    // SAFETY: the descriptor is owned by `file`, which outlives the call. A lock
+    // taken here is released when `file` is closed.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return false;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EWOULDBLOCK)
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match contents.trim().parse() {
+        Ok(pid) => Some(pid),
+        Err(_) => {
+            warn!("Ignoring unreadable PID file {}", path.display());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Larger than any PID the kernel hands out
+    const DEAD_PID: u32 = i32::MAX as u32;
+
+    #[test]
+    fn test_second_lock_names_the_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+
+        let _lock = acquire_daemon_lock(&path, 4242).unwrap();
+        let error = acquire_daemon_lock(&path, 4343).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Another instance is already running (PID 4242)"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "4242\n");
+    }
+
+    #[test]
+    fn test_dropping_the_lock_removes_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+
+        drop(acquire_daemon_lock(&path, 4242).unwrap());
+
+        assert!(!path.exists());
+        assert!(acquire_daemon_lock(&path, 4343).is_ok());
+    }
+
+    #[test]
+    fn test_leftover_file_without_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+        std::fs::write(&path, "1234567\n").unwrap();
+
+        let _lock = acquire_daemon_lock(&path, 42).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "42\n");
+    }
+
+    #[test]
+    fn test_lock_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+        assert_eq!(lock_status(&path), LockStatus::NotRunning);
+
+        let lock = acquire_daemon_lock(&path, 4242).unwrap();
+        assert_eq!(lock_status(&path), LockStatus::Running(4242));
+        drop(lock);
+
+        // A live process that reused the PID holds no lock
+        std::fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+        assert_eq!(lock_status(&path), LockStatus::Stale(std::process::id()));
+        assert_eq!(
+            LockStatus::Stale(DEAD_PID).to_string(),
+            format!("stale lock (PID {DEAD_PID})")
+        );
+    }
+
+    #[test]
+    fn test_stopping_without_running_instance_is_a_no_op() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+        std::fs::write(&path, format!("{DEAD_PID}\n")).unwrap();
+
+        assert!(stop_running_instance(&path).is_ok());
+    }
+}
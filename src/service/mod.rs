@@ -1,5 +1,12 @@
 pub mod daemon;
+pub mod ipc;
+pub mod lock;
 pub mod service_v2;
 pub mod signals;
+pub mod updates;
 
-pub use service_v2::AudioDeviceService;
+#[allow(unused_imports)] // Used by the IPC socket handler and tests
+pub use ipc::{IpcCommand, SwitchDirection};
+#[allow(unused_imports)] // Used by library consumers and tests
+pub use service_v2::HealthStatus;
+pub use service_v2::{AudioDeviceService, ServiceStatistics};
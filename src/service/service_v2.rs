@@ -1,13 +1,108 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use tracing::{error, info};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info, warn};
 
-use crate::audio::DeviceControllerV2;
-use crate::config::{Config, ConfigLoader};
-use crate::preference_debugging::{PreferenceChanges, PreferenceStatus};
-use crate::priority::DevicePriorityManager;
+#[cfg(any(test, feature = "test-mocks"))]
+use crate::audio::DeviceEvent;
+use crate::audio::{DeviceControllerV2, DeviceType};
+use crate::config::{Config, ConfigLoader, DeviceRule, MatchType};
+use crate::notifications::SwitchReason;
+use crate::preference_debugging::{
+    PreferenceChanges, PreferenceStatus, SwitchEvent, SwitchHistory, UrgencyLevel,
+};
+use crate::service::daemon::ServiceInstaller;
+use crate::service::ipc::{
+    IPC_COMMAND_QUEUE_SIZE, IpcCommand, IpcListener, SwitchDirection, spawn_ipc_listener,
+};
+use crate::service::lock::{DaemonLock, acquire_daemon_lock};
+use crate::service::updates::{UpdateInfo, fetch_available_updates};
 use crate::system::{AudioSystemInterface, FileSystemInterface, SystemServiceInterface};
 
+/// How often `graceful_shutdown` checks for pending notifications
+const SHUTDOWN_POLL_INTERVAL_MS: u64 = 50;
+
+/// How often `wait_for_device` re-enumerates devices
+const WAIT_FOR_DEVICE_POLL_INTERVAL_MS: u64 = 100;
+
+/// How many enumerations `benchmark_device_enumeration` times
+const BENCHMARK_ENUMERATION_RUNS: usize = 10;
+
+/// Median enumeration time above which the benchmark warns about the check interval
+const SLOW_ENUMERATION_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Result of a service health check, worst problem first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// The service works but routing may be wrong
+    Degraded {
+        reason: String,
+    },
+    /// The audio system cannot be queried at all
+    Critical {
+        reason: String,
+    },
+}
+
+impl HealthStatus {
+    /// Process exit code for health probes: 0 healthy, 1 degraded, 2 critical
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HealthStatus::Healthy => 0,
+            HealthStatus::Degraded { .. } => 1,
+            HealthStatus::Critical { .. } => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthStatus::Healthy => write!(f, "healthy"),
+            HealthStatus::Degraded { reason } => write!(f, "degraded: {reason}"),
+            HealthStatus::Critical { reason } => write!(f, "critical: {reason}"),
+        }
+    }
+}
+
+/// Switching statistics of a service, for the `stats` command
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceStatistics {
+    pub uptime_seconds: u64,
+    pub total_switch_count: u64,
+    pub output_switch_count: u64,
+    pub input_switch_count: u64,
+    pub failed_switch_count: u64,
+    /// Switches each rule caused, by rule name
+    pub rule_hits: HashMap<String, u64>,
+}
+
+impl ServiceStatistics {
+    /// Statistics rebuilt from recorded switches, for when no service is
+    /// running. The history records neither uptime nor failed switches, so
+    /// both are 0. Switches to input-output devices count as output switches.
+    pub fn from_history(events: &[SwitchEvent]) -> Self {
+        let mut statistics = Self::default();
+        for event in events {
+            statistics.total_switch_count += 1;
+            match event.device_type {
+                DeviceType::Input => statistics.input_switch_count += 1,
+                DeviceType::Output | DeviceType::InputOutput => statistics.output_switch_count += 1,
+            }
+            if let Some(rule) = &event.matched_rule {
+                *statistics.rule_hits.entry(rule.clone()).or_insert(0) += 1;
+            }
+        }
+        statistics
+    }
+}
+
 /// Main audio device service with dependency injection for complete testability
 pub struct AudioDeviceService<
     A: AudioSystemInterface,
@@ -18,13 +113,26 @@ pub struct AudioDeviceService<
     config_loader: ConfigLoader<F>,
     system_service: S,
     config: Config,
+    /// Profile applied on top of the configuration file, kept across reloads
+    profile: Option<String>,
     last_config_modified: Option<std::time::SystemTime>,
     last_poll_time: std::time::Instant,
     last_known_device_ids: Vec<String>,
+    last_error: Option<String>,
+    switching_paused: bool,
+    ipc_commands: Option<mpsc::Receiver<IpcCommand>>,
+    ipc_socket_path: Option<PathBuf>,
+    ipc_listener: Option<IpcListener>,
+    lock_path: Option<PathBuf>,
+    daemon_lock: Option<DaemonLock>,
+    started_at: Option<Instant>,
 }
 
-impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
-    AudioDeviceService<A, F, S>
+impl<
+    A: AudioSystemInterface,
+    F: FileSystemInterface + Clone + Send + Sync + 'static,
+    S: SystemServiceInterface + Clone + Send + Sync + 'static,
+> AudioDeviceService<A, F, S>
 {
     pub fn new(
         audio_system: A,
@@ -32,25 +140,65 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         system_service: S,
         config_path: PathBuf,
     ) -> Result<Self> {
+        // Switch hooks run through the same file system as config loading, so
+        // tests with a mock file system never start real commands. Likewise the
+        // controller sleeps through the system service.
+        let hook_runner = Arc::new(file_system.clone());
         let config_loader = ConfigLoader::new(file_system, config_path);
         let config = config_loader.load_config()?;
-        let device_controller = DeviceControllerV2::new(audio_system, &config);
+        let device_controller = DeviceControllerV2::new(audio_system, &config)
+            .with_hook_runner(hook_runner)
+            .with_system_service(Arc::new(system_service.clone()));
 
         Ok(Self {
             device_controller,
             config_loader,
             system_service,
             config,
+            profile: None,
             last_config_modified: None,
             last_poll_time: std::time::Instant::now(),
             last_known_device_ids: Vec::new(),
+            last_error: None,
+            switching_paused: false,
+            ipc_commands: None,
+            ipc_socket_path: None,
+            ipc_listener: None,
+            lock_path: None,
+            daemon_lock: None,
+            started_at: None,
         })
     }
 
+    /// Listen for control requests on a Unix socket at `path` once the service starts
+    pub fn with_ipc_socket(mut self, path: PathBuf) -> Self {
+        self.ipc_socket_path = Some(path);
+        self
+    }
+
+    /// Record every successful switch in the history file at `path`
+    pub fn with_switch_history(mut self, path: PathBuf) -> Self {
+        let history = SwitchHistory::new(path, self.config.general.history_max_lines);
+        self.device_controller = self.device_controller.with_history(history);
+        self
+    }
+
+    /// Refuse to start while another instance holds the PID file lock at `path`
+    pub fn with_daemon_lock(mut self, path: PathBuf) -> Self {
+        self.lock_path = Some(path);
+        self
+    }
+
     /// Initialize and start the audio device service
     pub fn start(&mut self) -> Result<()> {
         info!("Starting audio device service with dependency injection");
 
+        // Before any listener is registered, so a second instance changes nothing
+        if let Some(path) = &self.lock_path {
+            let pid = self.system_service.get_process_id();
+            self.daemon_lock = Some(acquire_daemon_lock(path, pid)?);
+        }
+
         // Register signal handlers
         self.system_service.register_signal_handlers()?;
 
@@ -58,15 +206,22 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         self.device_controller.initialize()?;
 
         // Store initial config modification time for hot reload
-        if let Ok(modified_time) = self
-            .config_loader
-            .get_config_path()
-            .metadata()
-            .and_then(|m| m.modified())
-        {
-            self.last_config_modified = Some(modified_time);
+        self.record_config_modified_time();
+
+        // Prefer file change events; polling modification times remains the fallback
+        if let Err(e) = self.config_loader.watch_config() {
+            warn!("{:#}. Falling back to polling for config changes", e);
+        }
+
+        if let Some(path) = self.ipc_socket_path.clone() {
+            let commands = self.open_ipc_channel();
+            match spawn_ipc_listener(&path, commands) {
+                Ok(listener) => self.ipc_listener = Some(listener),
+                Err(e) => warn!("{:#}. Runtime control over IPC is unavailable", e),
+            }
         }
 
+        self.started_at = Some(Instant::now());
         info!("Audio device service started successfully");
 
         // Enter main service loop
@@ -81,13 +236,37 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
             self.config.general.poll_interval_ms
         );
 
+        let mut consecutive_errors: u32 = 0;
+
         while self.system_service.should_continue_running() {
             // Run one iteration of the event loop
             self.system_service.run_event_loop()?;
 
-            // Check for device changes
-            if let Err(e) = self.device_controller.update_current_devices() {
-                error!("Error updating current devices: {}", e);
+            // CoreAudio can report stale devices for a while after waking
+            if self.system_service.woke_from_sleep() {
+                let recovery = self.recover_from_sleep_wake();
+                if let Err(e) = recovery {
+                    error!("Failed to recover devices after wake from sleep: {}", e);
+                    self.last_error = Some(format!("{e:#}"));
+                }
+            }
+
+            // Check for device changes, only tracking the system defaults while paused
+            let update = if self.switching_paused {
+                self.device_controller.sync_with_system().map(|_| ())
+            } else {
+                self.device_controller.update_current_devices()
+            };
+            match update {
+                Ok(()) => consecutive_errors = 0,
+                Err(e) => {
+                    consecutive_errors += 1;
+                    error!(
+                        "Error updating current devices ({} consecutive): {}",
+                        consecutive_errors, e
+                    );
+                    self.last_error = Some(format!("{e:#}"));
+                }
             }
 
             // Check for SIGHUP configuration reload request
@@ -100,6 +279,9 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
                 }
             }
 
+            // Handle commands queued by the IPC socket
+            self.process_ipc_commands();
+
             // Check for configuration changes (file-based hot reload)
             if let Err(e) = self.check_config_reload() {
                 error!("Error checking config reload: {}", e);
@@ -115,21 +297,184 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
                     "Performing periodic device poll ({}s elapsed)",
                     elapsed.as_secs()
                 );
+                // Base the check on the real defaults in case another app changed them
+                if let Err(e) = self.device_controller.sync_with_system() {
+                    warn!("Failed to sync with system default devices: {}", e);
+                }
                 if let Err(e) = self.periodic_check() {
                     error!("Error during periodic check: {}", e);
+                    self.last_error = Some(format!("{e:#}"));
                 }
                 self.last_poll_time = std::time::Instant::now();
             }
 
-            // Sleep briefly to avoid busy waiting
-            self.system_service
-                .sleep_ms(self.config.general.check_interval_ms.max(100))?;
+            // Sleep briefly to avoid busy waiting, backing off while errors persist
+            let sleep_ms = if consecutive_errors > 0 {
+                self.error_backoff_ms(consecutive_errors)
+            } else {
+                self.config.general.check_interval_ms.max(100)
+            };
+            self.system_service.sleep_ms(sleep_ms)?;
         }
 
         info!("Main service loop exited");
         Ok(())
     }
 
+    /// Create the channel the IPC socket handler uses to reach this service,
+    /// replacing any earlier one. Commands are handled on each main loop iteration.
+    // Called by the IPC socket listener and tests
+    #[allow(dead_code)]
+    pub fn open_ipc_channel(&mut self) -> mpsc::Sender<IpcCommand> {
+        let (sender, receiver) = mpsc::channel(IPC_COMMAND_QUEUE_SIZE);
+        self.ipc_commands = Some(receiver);
+        sender
+    }
+
+    /// Handle every IPC command already queued without waiting for more.
+    /// Returns how many commands were handled.
+    fn process_ipc_commands(&mut self) -> usize {
+        let mut commands = Vec::new();
+        if let Some(receiver) = self.ipc_commands.as_mut() {
+            while let Ok(command) = receiver.try_recv() {
+                commands.push(command);
+            }
+        }
+
+        let handled = commands.len();
+        for command in commands {
+            self.handle_ipc_command(command);
+        }
+        handled
+    }
+
+    /// Handle IPC commands as they arrive until every sender is dropped
+    // Called by async hosts that run the service outside the blocking main loop
+    #[allow(dead_code)]
+    pub async fn watch_ipc_commands(&mut self) -> Result<()> {
+        let Some(mut receiver) = self.ipc_commands.take() else {
+            return Err(anyhow::anyhow!("IPC channel has not been opened"));
+        };
+
+        while let Some(command) = receiver.recv().await {
+            self.handle_ipc_command(command);
+        }
+
+        debug!("All IPC command senders closed");
+        Ok(())
+    }
+
+    fn handle_ipc_command(&mut self, command: IpcCommand) {
+        debug!("Handling IPC command: {:?}", command);
+
+        match command {
+            IpcCommand::Reload { respond_to } => {
+                let result = self.reload_config();
+                if let Err(e) = &result {
+                    error!("Failed to reload configuration from IPC request: {}", e);
+                }
+                Self::reply_to_ipc(respond_to, result);
+            }
+            IpcCommand::Switch {
+                device,
+                direction,
+                requester,
+                respond_to,
+            } => {
+                let reason = match requester {
+                    Some(app_name) => SwitchReason::AppRequest { app_name },
+                    None => SwitchReason::Manual,
+                };
+                let result = match direction {
+                    SwitchDirection::Output => self.set_output_device_for(&device, reason),
+                    SwitchDirection::Input => self.set_input_device_for(&device, reason),
+                };
+                if let Err(e) = &result {
+                    error!("IPC switch to '{}' failed: {}", device, e);
+                }
+                Self::reply_to_ipc(respond_to, result);
+            }
+            IpcCommand::SetProfile {
+                profile,
+                respond_to,
+            } => {
+                let result = self.set_profile(profile.as_deref());
+                if let Err(e) = &result {
+                    error!("Failed to set profile from IPC request: {:#}", e);
+                }
+                Self::reply_to_ipc(respond_to, result);
+            }
+            IpcCommand::Pause { respond_to } => {
+                self.pause_automatic_switching();
+                Self::reply_to_ipc(respond_to, Ok(()));
+            }
+            IpcCommand::Resume { respond_to } => {
+                self.resume_automatic_switching();
+                Self::reply_to_ipc(respond_to, Ok(()));
+            }
+            IpcCommand::GetStatus {
+                include_scores,
+                respond_to,
+            } => {
+                let status = if include_scores {
+                    self.export_status_json_with_scores()
+                } else {
+                    self.export_status_json()
+                };
+                Self::reply_to_ipc(Some(respond_to), status);
+            }
+            IpcCommand::ListDevices { respond_to } => {
+                Self::reply_to_ipc(Some(respond_to), self.export_devices_json());
+            }
+            IpcCommand::GetStats { respond_to } => {
+                let statistics = serde_json::to_string(&self.get_statistics());
+                Self::reply_to_ipc(Some(respond_to), statistics.map_err(Into::into));
+            }
+        }
+    }
+
+    fn reply_to_ipc<T>(respond_to: Option<oneshot::Sender<Result<T>>>, result: Result<T>) {
+        let Some(respond_to) = respond_to else {
+            return;
+        };
+        if respond_to.send(result).is_err() {
+            warn!("IPC requester went away before the reply");
+        }
+    }
+
+    /// Wait for the device list to settle after a wake from sleep, then re-apply
+    /// preferences against it
+    pub fn recover_from_sleep_wake(&mut self) -> Result<()> {
+        info!("Waiting for audio devices to settle after wake from sleep");
+
+        let system_service = &self.system_service;
+        let devices = self
+            .device_controller
+            .wait_for_stable_devices(|milliseconds| system_service.sleep_ms(milliseconds))?;
+        info!("{} audio devices available after wake", devices.len());
+
+        self.device_controller.sync_with_system()?;
+
+        // Devices may have come and gone while asleep; compare against a fresh list
+        self.last_known_device_ids.clear();
+        self.periodic_check()?;
+        self.last_poll_time = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Sleep duration after the given number of consecutive errors:
+    /// `backoff_ms` doubled per additional error, capped at `max_backoff_ms`
+    fn error_backoff_ms(&self, consecutive_errors: u32) -> u64 {
+        let general = &self.config.general;
+        let multiplier = 1u64
+            .checked_shl(consecutive_errors.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        general
+            .backoff_ms
+            .saturating_mul(multiplier)
+            .min(general.max_backoff_ms)
+    }
+
     /// Perform a periodic check of device state and preferences
     /// Only applies preferences if the set of available devices has changed
     fn periodic_check(&mut self) -> Result<()> {
@@ -165,6 +510,11 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
             // Update the known device list
             self.last_known_device_ids = current_device_ids;
 
+            if self.switching_paused {
+                debug!("Periodic check: automatic switching is paused");
+                return Ok(());
+            }
+
             // Check preferences and apply if needed
             let status = self.check_preferences()?;
 
@@ -200,13 +550,43 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         Ok(())
     }
 
-    /// Check if configuration has been modified and reload if necessary
+    /// Check if configuration has been modified and reload if necessary. While the
+    /// file is watched this only checks for a pending write event, so an untouched
+    /// file costs no file system calls.
     fn check_config_reload(&mut self) -> Result<()> {
-        if let Some(last_modified) = self.last_config_modified {
-            if self.config_loader.is_config_modified(last_modified)? {
-                info!("Configuration file changed, reloading");
-                self.reload_config()?;
-            }
+        let modified = match self.last_config_modified {
+            _ if self.config_loader.is_watching() => self.config_loader.watched_change(),
+            Some(last_modified) => self.config_loader.is_config_modified(last_modified)?,
+            None => false,
+        };
+
+        if modified {
+            info!("Configuration file changed, reloading");
+            self.reload_config()?;
+        }
+        Ok(())
+    }
+
+    /// Remember the config file's modification time, read through the injected file system
+    fn record_config_modified_time(&mut self) {
+        if let Ok(modified_time) = self.config_loader.config_modified_time() {
+            self.last_config_modified = Some(modified_time);
+        }
+    }
+
+    /// Switch to the rules of profile `profile`, or back to those of the
+    /// configuration file with `None`, by reloading the configuration. An
+    /// unknown profile leaves the current one in place.
+    pub fn set_profile(&mut self, profile: Option<&str>) -> Result<()> {
+        let previous = std::mem::replace(&mut self.profile, profile.map(str::to_string));
+        if let Err(e) = self.reload_config() {
+            self.profile = previous;
+            return Err(e);
+        }
+
+        match profile {
+            Some(profile) => info!("Using configuration profile '{}'", profile),
+            None => info!("Using the configuration without a profile"),
         }
         Ok(())
     }
@@ -216,9 +596,13 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         info!("Reloading configuration");
 
         // Load new configuration
-        let new_config = self.config_loader.load_config()?;
+        let mut new_config = self.config_loader.load_config()?;
+        if let Some(profile) = &self.profile {
+            new_config = new_config.with_profile(profile)?;
+        }
 
         // Update configuration
+        self.device_controller.update_config(&new_config);
         self.config = new_config;
 
         // Note: In a full implementation, we would recreate the device controller
@@ -227,14 +611,7 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         info!("Configuration reloaded successfully");
 
         // Update last modified time
-        if let Ok(modified_time) = self
-            .config_loader
-            .get_config_path()
-            .metadata()
-            .and_then(|m| m.modified())
-        {
-            self.last_config_modified = Some(modified_time);
-        }
+        self.record_config_modified_time();
 
         Ok(())
     }
@@ -253,29 +630,47 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         self.system_service.get_process_id()
     }
 
+    /// Keep the priority rules' view of the power source current
+    fn refresh_power_source(&mut self) {
+        if self.config.general.prefer_wired_on_battery {
+            self.device_controller
+                .set_on_battery_power(self.system_service.is_on_battery_power());
+        }
+    }
+
     /// Check if current devices match configured preferences
     // Called by CLI commands to verify device selection matches configuration
     #[allow(dead_code)]
-    pub fn check_preferences(&self) -> Result<PreferenceStatus> {
-        let priority_manager = DevicePriorityManager::new(&self.config);
+    pub fn check_preferences(&mut self) -> Result<PreferenceStatus> {
+        self.refresh_power_source();
         let available_devices = self.device_controller.enumerate_devices()?;
 
         let current_output = self.device_controller.get_default_output_device()?;
         let current_input = self.device_controller.get_default_input_device()?;
 
-        let preferred_output = priority_manager.find_best_output_device(&available_devices);
-        let preferred_input = priority_manager.find_best_input_device(&available_devices);
-
-        let output_matches = match (&current_output, &preferred_output) {
-            (Some(current), Some(preferred)) => current.name == preferred.name,
-            (None, None) => true,
-            _ => false,
+        // A device only mismatches when the rules would switch away from it, so
+        // one kept by a sticky rule or min_weight_to_switch matches
+        let output_target = self
+            .device_controller
+            .output_switch_target(current_output.as_ref(), &available_devices);
+        let input_target = self
+            .device_controller
+            .input_switch_target(current_input.as_ref(), &available_devices);
+        let output_matches = output_target.is_none();
+        let input_matches = input_target.is_none();
+
+        let priority_manager = self.device_controller.priority_manager();
+        let preferred_output = match output_target {
+            Some(target) => Some(target),
+            None => priority_manager
+                .find_best_output_device(&available_devices)
+                .and(current_output.clone()),
         };
-
-        let input_matches = match (&current_input, &preferred_input) {
-            (Some(current), Some(preferred)) => current.name == preferred.name,
-            (None, None) => true,
-            _ => false,
+        let preferred_input = match input_target {
+            Some(target) => Some(target),
+            None => priority_manager
+                .find_best_input_device(&available_devices)
+                .and(current_input.clone()),
         };
 
         Ok(PreferenceStatus {
@@ -293,46 +688,40 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
     /// Apply configured preferences by switching to preferred devices
     // Called by CLI commands to force device switching to match configuration
     #[allow(dead_code)]
-    pub fn apply_preferences(&self) -> Result<PreferenceChanges> {
-        let priority_manager = DevicePriorityManager::new(&self.config);
+    pub fn apply_preferences(&mut self) -> Result<PreferenceChanges> {
+        let result = self.switch_to_preferred_devices();
+        self.track_error(result)
+    }
+
+    /// Switch wherever the controller's priority rules would switch away from
+    /// the system default
+    fn switch_to_preferred_devices(&mut self) -> Result<PreferenceChanges> {
+        self.refresh_power_source();
         let available_devices = self.device_controller.enumerate_devices()?;
 
         let current_output = self.device_controller.get_default_output_device()?;
         let current_input = self.device_controller.get_default_input_device()?;
 
-        let preferred_output = priority_manager.find_best_output_device(&available_devices);
-        let preferred_input = priority_manager.find_best_input_device(&available_devices);
-
         let mut changes = PreferenceChanges::no_changes();
 
-        // Switch output device if needed and available
-        if let Some(ref preferred) = preferred_output {
-            let should_switch = match &current_output {
-                Some(current) => current.name != preferred.name,
-                None => true,
-            };
-
-            if should_switch {
-                self.device_controller
-                    .set_default_output_device(&preferred.name)?;
-                changes.output_changed = true;
-                changes.new_output = Some(preferred.name.clone());
-            }
+        if let Some(preferred) = self
+            .device_controller
+            .output_switch_target(current_output.as_ref(), &available_devices)
+        {
+            self.device_controller.switch_to_output_device(&preferred)?;
+            self.last_error = None;
+            changes.output_changed = true;
+            changes.new_output = Some(preferred.name);
         }
 
-        // Switch input device if needed and available
-        if let Some(ref preferred) = preferred_input {
-            let should_switch = match &current_input {
-                Some(current) => current.name != preferred.name,
-                None => true,
-            };
-
-            if should_switch {
-                self.device_controller
-                    .set_default_input_device(&preferred.name)?;
-                changes.input_changed = true;
-                changes.new_input = Some(preferred.name.clone());
-            }
+        if let Some(preferred) = self
+            .device_controller
+            .input_switch_target(current_input.as_ref(), &available_devices)
+        {
+            self.device_controller.switch_to_input_device(&preferred)?;
+            self.last_error = None;
+            changes.input_changed = true;
+            changes.new_input = Some(preferred.name);
         }
 
         Ok(changes)
@@ -351,6 +740,8 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
     pub fn handle_device_connected(&mut self, device_name: &str) -> Result<()> {
         info!("Manually handling device connection: {}", device_name);
 
+        self.refresh_power_source();
+
         // Get current devices to find the newly connected one
         let devices = self.device_controller.enumerate_devices()?;
         if let Some(device) = devices.iter().find(|d| d.name == device_name) {
@@ -391,6 +782,21 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         Ok(())
     }
 
+    /// Feed a device event to `handle_device_connected` or `handle_device_disconnected`,
+    /// so tests can simulate hot-plugging without reaching into the mocks. The
+    /// daemon takes another path: its CoreAudio observer only wakes the main loop,
+    /// which then runs `update_current_devices`.
+    #[cfg(any(test, feature = "test-mocks"))]
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn inject_device_event(&mut self, event: DeviceEvent) -> Result<()> {
+        debug!("Injecting device event: {:?}", event);
+
+        match event {
+            DeviceEvent::Connected(device) => self.handle_device_connected(&device.name),
+            DeviceEvent::Disconnected(device) => self.handle_device_disconnected(&device.name),
+        }
+    }
+
     /// Shutdown the service gracefully
     // Called by CLI commands and signal handlers for graceful service shutdown
     #[allow(dead_code)]
@@ -404,6 +810,38 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         Ok(())
     }
 
+    /// Wait up to `shutdown_timeout_ms` for notifications still being delivered,
+    /// including those of the audio system's device listener, then stop the event
+    /// loop so their osascript processes are not killed mid-flight
+    pub fn graceful_shutdown(&mut self) -> Result<()> {
+        info!("Shutting down audio device service gracefully");
+
+        let timeout_ms = self.config.general.shutdown_timeout_ms;
+        let mut waited_ms = 0;
+        while self.device_controller.pending_notifications() > 0 {
+            if waited_ms >= timeout_ms {
+                warn!(
+                    "Gave up waiting for {} pending notifications after {}ms",
+                    self.device_controller.pending_notifications(),
+                    timeout_ms
+                );
+                break;
+            }
+            let step_ms = SHUTDOWN_POLL_INTERVAL_MS.min(timeout_ms - waited_ms);
+            self.system_service.sleep_ms(step_ms)?;
+            waited_ms += step_ms;
+        }
+
+        self.system_service.stop_event_loop();
+
+        // Removes the socket file so clients fall back to local calls
+        self.ipc_listener = None;
+        self.daemon_lock = None;
+
+        info!("Audio device service shutdown completed");
+        Ok(())
+    }
+
     /// Get device enumeration for external inspection
     // Called by CLI commands that need to list all available audio devices
     #[allow(dead_code)]
@@ -411,6 +849,41 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         self.device_controller.enumerate_devices()
     }
 
+    /// Releases newer than this binary. Checks GitHub when built with the
+    /// `auto-update` feature and reports nothing otherwise.
+    // Called by library consumers; the CLI status command checks without a running service
+    #[allow(dead_code)]
+    pub async fn list_available_updates(&self) -> Result<Vec<UpdateInfo>> {
+        fetch_available_updates(ServiceInstaller::current_version()).await
+    }
+
+    /// Time device enumeration and return the median of several runs
+    // Called by the CLI status command to diagnose slow responses
+    #[allow(dead_code)]
+    pub fn benchmark_device_enumeration(&self) -> Result<Duration> {
+        let mut durations = Vec::with_capacity(BENCHMARK_ENUMERATION_RUNS);
+        for _ in 0..BENCHMARK_ENUMERATION_RUNS {
+            let started = Instant::now();
+            self.enumerate_devices()?;
+            durations.push(started.elapsed());
+        }
+        durations.sort();
+
+        let middle = BENCHMARK_ENUMERATION_RUNS / 2;
+        let median = (durations[middle - 1] + durations[middle]) / 2;
+        debug!("Median device enumeration time: {:?}", median);
+
+        if median > SLOW_ENUMERATION_THRESHOLD {
+            warn!(
+                "Device enumeration takes {}ms; consider increasing check_interval_ms (currently {}ms)",
+                median.as_millis(),
+                self.config.general.check_interval_ms
+            );
+        }
+
+        Ok(median)
+    }
+
     /// Get current output device
     // Called by CLI status and monitoring commands to show current device state
     #[allow(dead_code)]
@@ -425,20 +898,103 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
         self.device_controller.get_current_input_device()
     }
 
+    /// Find input or output devices whose names contain `pattern`, in enumeration order
+    // Called by CLI tools and external control systems to look devices up by partial name
+    #[allow(dead_code)]
+    pub fn find_device_by_pattern(
+        &self,
+        pattern: &str,
+        is_input: bool,
+    ) -> Result<Vec<crate::audio::AudioDevice>> {
+        let rule = DeviceRule::new(pattern, 0, MatchType::Contains);
+        let wanted_type = if is_input {
+            crate::audio::DeviceType::Input
+        } else {
+            crate::audio::DeviceType::Output
+        };
+
+        let matches: Vec<crate::audio::AudioDevice> = self
+            .device_controller
+            .enumerate_devices()?
+            .into_iter()
+            .filter(|device| {
+                (device.device_type == wanted_type
+                    || device.device_type == crate::audio::DeviceType::InputOutput)
+                    && rule.matches_device(device)
+            })
+            .collect();
+
+        debug!("Found {} devices matching '{}'", matches.len(), pattern);
+        Ok(matches)
+    }
+
+    /// Poll until a device whose name contains `device_name` appears, for scripts that
+    /// power devices on and need to wait for CoreAudio to see them
+    pub fn wait_for_device(
+        &self,
+        device_name: &str,
+        timeout_ms: u64,
+    ) -> Result<crate::audio::AudioDevice> {
+        info!(
+            "Waiting up to {}ms for device '{}' to appear",
+            timeout_ms, device_name
+        );
+
+        // Count the time slept rather than reading the clock so tests with a
+        // mock sleep_ms still reach the timeout
+        let mut waited_ms = 0;
+        loop {
+            if let Some(device) = self
+                .device_controller
+                .enumerate_devices()?
+                .into_iter()
+                .find(|device| device.name.contains(device_name))
+            {
+                info!("Device '{}' is available", device.name);
+                return Ok(device);
+            }
+
+            if waited_ms >= timeout_ms {
+                return Err(anyhow::anyhow!(
+                    "Timed out after {}ms waiting for device '{}'",
+                    timeout_ms,
+                    device_name
+                ));
+            }
+            let step_ms = WAIT_FOR_DEVICE_POLL_INTERVAL_MS.min(timeout_ms - waited_ms);
+            self.system_service.sleep_ms(step_ms)?;
+            waited_ms += step_ms;
+        }
+    }
+
     /// Manually set output device (for testing or manual control)
     // Called by CLI switch commands and external control systems for manual device switching
     #[allow(dead_code)]
     pub fn set_output_device(&mut self, device_name: &str) -> Result<()> {
-        info!("Manually setting output device: {}", device_name);
+        self.set_output_device_for(device_name, SwitchReason::Manual)
+    }
 
-        let devices = self.device_controller.enumerate_devices()?;
-        if let Some(device) = devices.iter().find(|d| {
+    /// Switch the output to the device named `device_name`, reporting `reason`,
+    /// e.g. `SwitchReason::AppRequest` when another application asked over IPC
+    pub fn set_output_device_for(&mut self, device_name: &str, reason: SwitchReason) -> Result<()> {
+        info!("Setting output device to {} ({})", device_name, reason);
+
+        let devices = self.device_controller.enumerate_devices();
+        let devices = self.track_error(devices)?;
+        let Some(device) = devices.iter().find(|d| {
             d.name == device_name && matches!(d.device_type, crate::audio::DeviceType::Output)
-        }) {
-            self.device_controller.switch_to_output_device(device)?;
-        } else {
-            return Err(anyhow::anyhow!("Output device '{}' not found", device_name));
-        }
+        }) else {
+            return self.track_error(Err(anyhow::anyhow!(
+                "Output device '{}' not found",
+                device_name
+            )));
+        };
+
+        let result = self
+            .device_controller
+            .switch_to_output_device_for(device, reason);
+        self.track_error(result)?;
+        self.last_error = None;
 
         Ok(())
     }
@@ -447,19 +1003,242 @@ impl<A: AudioSystemInterface, F: FileSystemInterface, S: SystemServiceInterface>
     // Called by CLI switch commands and external control systems for manual device switching
     #[allow(dead_code)]
     pub fn set_input_device(&mut self, device_name: &str) -> Result<()> {
-        info!("Manually setting input device: {}", device_name);
+        self.set_input_device_for(device_name, SwitchReason::Manual)
+    }
 
-        let devices = self.device_controller.enumerate_devices()?;
-        if let Some(device) = devices.iter().find(|d| {
+    /// Input counterpart of `set_output_device_for`
+    pub fn set_input_device_for(&mut self, device_name: &str, reason: SwitchReason) -> Result<()> {
+        info!("Setting input device to {} ({})", device_name, reason);
+
+        let devices = self.device_controller.enumerate_devices();
+        let devices = self.track_error(devices)?;
+        let Some(device) = devices.iter().find(|d| {
             d.name == device_name && matches!(d.device_type, crate::audio::DeviceType::Input)
-        }) {
-            self.device_controller.switch_to_input_device(device)?;
-        } else {
-            return Err(anyhow::anyhow!("Input device '{}' not found", device_name));
-        }
+        }) else {
+            return self.track_error(Err(anyhow::anyhow!(
+                "Input device '{}' not found",
+                device_name
+            )));
+        };
+
+        let result = self
+            .device_controller
+            .switch_to_input_device_for(device, reason);
+        self.track_error(result)?;
+        self.last_error = None;
 
         Ok(())
     }
+
+    /// Get the number of successful (output, input) device switches since start
+    // Called by CLI status command and monitoring systems to gauge routing stability
+    #[allow(dead_code)]
+    pub fn get_device_switch_count(&self) -> (u32, u32) {
+        let counts = self.device_controller.switch_counts();
+        (counts.output, counts.input)
+    }
+
+    /// Switch counts since start and the rules that caused the switches
+    pub fn get_statistics(&self) -> ServiceStatistics {
+        let counts = self.device_controller.switch_counts();
+        let output_switch_count = u64::from(counts.output);
+        let input_switch_count = u64::from(counts.input);
+        ServiceStatistics {
+            uptime_seconds: self
+                .started_at
+                .map_or(0, |started| started.elapsed().as_secs()),
+            total_switch_count: output_switch_count + input_switch_count,
+            output_switch_count,
+            input_switch_count,
+            failed_switch_count: counts.failed,
+            rule_hits: self
+                .device_controller
+                .priority_manager()
+                .rule_hits()
+                .clone(),
+        }
+    }
+
+    /// Stop automatic switching, e.g. for the duration of a call. The main loop
+    /// keeps tracking the system defaults but neither it nor periodic checks
+    /// switch; manual switches still go ahead.
+    pub fn pause_automatic_switching(&mut self) {
+        info!("Pausing automatic device switching");
+        self.switching_paused = true;
+    }
+
+    /// Let the main loop and periodic checks switch devices again after a pause
+    pub fn resume_automatic_switching(&mut self) {
+        info!("Resuming automatic device switching");
+        self.switching_paused = false;
+    }
+
+    /// Check whether automatic switching is currently paused
+    // Called by tests and hosts that embed the service; the CLI reads `switching_paused` from the status JSON
+    #[allow(dead_code)]
+    pub fn is_switching_paused(&self) -> bool {
+        self.switching_paused
+    }
+
+    /// Check that devices can be enumerated, a default output exists and the last
+    /// device operation succeeded
+    // Called by the CLI health command used by watchdogs and monitoring
+    #[allow(dead_code)]
+    pub fn emit_health_check(&mut self) -> HealthStatus {
+        let health = match self.device_controller.enumerate_devices() {
+            Err(e) => HealthStatus::Critical {
+                reason: format!("cannot enumerate audio devices: {e:#}"),
+            },
+            Ok(_) => match self.device_controller.get_default_output_device() {
+                Err(e) => HealthStatus::Critical {
+                    reason: format!("cannot query default output device: {e:#}"),
+                },
+                Ok(None) => HealthStatus::Degraded {
+                    reason: "no default output device".to_string(),
+                },
+                Ok(Some(_)) => match &self.last_error {
+                    Some(error) => HealthStatus::Degraded {
+                        reason: format!("last device operation failed: {error}"),
+                    },
+                    None => self.preference_health(),
+                },
+            },
+        };
+
+        debug!("Health check: {}", health);
+        health
+    }
+
+    /// Health derived from how far the current devices are from the configured preferences
+    fn preference_health(&mut self) -> HealthStatus {
+        let status = match self.check_preferences() {
+            Ok(status) => status,
+            Err(e) => {
+                return HealthStatus::Degraded {
+                    reason: format!("cannot check device preferences: {e:#}"),
+                };
+            }
+        };
+
+        let mut mismatches = Vec::new();
+        if status.output_mismatched() {
+            mismatches.push(describe_mismatch(
+                "output",
+                &status.current_output,
+                &status.preferred_output,
+            ));
+        }
+        if status.input_mismatched() {
+            mismatches.push(describe_mismatch(
+                "input",
+                &status.current_input,
+                &status.preferred_input,
+            ));
+        }
+        let reason = mismatches.join("; ");
+
+        match status.urgency_level() {
+            UrgencyLevel::Ok => HealthStatus::Healthy,
+            UrgencyLevel::Warning => HealthStatus::Degraded { reason },
+            UrgencyLevel::Critical => HealthStatus::Critical { reason },
+        }
+    }
+
+    /// Get the most recent device operation error, cleared by the next successful switch
+    // Called by CLI status command and external monitoring tools to surface silent failures
+    #[allow(dead_code)]
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+
+    /// Remember a failed device operation so it can be reported in the service status
+    fn track_error<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            self.last_error = Some(format!("{e:#}"));
+        }
+        result
+    }
+
+    /// Export a point-in-time view of device state as a JSON string for bug reports
+    // Called by CLI diagnostics and external monitoring tools
+    #[allow(dead_code)]
+    pub fn export_diagnostics(&self) -> Result<String> {
+        let diagnostics = serde_json::json!({
+            "devices": self.device_controller.snapshot()?,
+            "last_error": self.last_error,
+            "switching_paused": self.switching_paused,
+        });
+
+        Ok(serde_json::to_string_pretty(&diagnostics)?)
+    }
+
+    /// Export the available devices as a JSON array
+    pub fn export_devices_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.enumerate_devices()?)?)
+    }
+
+    /// Export current service status, including its health check, as a JSON string
+    // Called by CLI status command and external monitoring tools
+    #[allow(dead_code)]
+    pub fn export_status_json(&mut self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.status())?)
+    }
+
+    /// Export the status as `export_status_json` does, plus the score the rules
+    /// give each available device right now
+    pub fn export_status_json_with_scores(&mut self) -> Result<String> {
+        let mut status = self.status();
+        let devices = self.enumerate_devices()?;
+        let priority_manager = self.device_controller.priority_manager();
+        status["scores"] = serde_json::json!({
+            "output": priority_manager.score_all_output_devices(&devices),
+            "input": priority_manager.score_all_input_devices(&devices),
+        });
+        Ok(serde_json::to_string_pretty(&status)?)
+    }
+
+    /// The fields both status exports share
+    fn status(&mut self) -> serde_json::Value {
+        let health = self.emit_health_check();
+        let priority_manager = self.device_controller.priority_manager();
+        let switch_counts = self.device_controller.switch_counts();
+        serde_json::json!({
+            "process_id": self.system_service.get_process_id(),
+            "uptime_seconds": self.started_at.map(|started| started.elapsed().as_secs()),
+            "summary": self.config.summarize(),
+            "profile": self.profile,
+            "current_output": self.get_current_output_device().map(|d| &d.name),
+            "current_input": self.get_current_input_device().map(|d| &d.name),
+            "metrics": {
+                "output_switches": switch_counts.output,
+                "input_switches": switch_counts.input,
+            },
+            "last_output_switch": unix_seconds(priority_manager.get_last_output_switch_time()),
+            "last_input_switch": unix_seconds(priority_manager.get_last_input_switch_time()),
+            "last_error": self.last_error,
+            "switching_paused": self.switching_paused,
+            "health": health,
+        })
+    }
+}
+
+/// Seconds since the Unix epoch, for timestamps in status JSON
+fn unix_seconds(time: Option<SystemTime>) -> Option<u64> {
+    time.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs())
+}
+
+/// "output is Speakers, preferred Headphones" for health check reasons
+fn describe_mismatch(
+    direction: &str,
+    current: &Option<String>,
+    preferred: &Option<String>,
+) -> String {
+    format!(
+        "{direction} is {}, preferred {}",
+        current.as_deref().unwrap_or("none"),
+        preferred.as_deref().unwrap_or("none")
+    )
 }
 
 // Convenience constructor for production use
@@ -527,12 +1306,25 @@ impl
         self.device_controller.get_audio_system()
     }
 
+    /// Mutable access to the mock audio system for test setup
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn mock_audio_system_mut(&mut self) -> &mut crate::system::MockAudioSystem {
+        self.device_controller.get_audio_system_mut()
+    }
+
     /// Access the mock file system for test control
     #[allow(dead_code)] // Used by integration tests which run in different compilation context
     pub fn mock_file_system(&self) -> &crate::system::MockFileSystem {
         self.config_loader.get_file_system()
     }
 
+    /// Access the notification manager for testing
+    #[cfg(any(test, feature = "test-mocks"))]
+    #[allow(dead_code)] // Used by integration tests which run in different compilation context
+    pub fn notification_manager(&self) -> &crate::notifications::DefaultNotificationManager {
+        self.device_controller.notification_manager()
+    }
+
     /// Get the config path for testing
     #[allow(dead_code)] // Used by integration tests which run in different compilation context
     pub fn config_path(&self) -> &std::path::Path {
@@ -546,6 +1338,35 @@ mod tests {
     use crate::system::{MockAudioSystem, MockFileSystem, MockSystemService};
     use std::path::PathBuf;
 
+    #[test]
+    fn test_statistics_from_history() {
+        let event = |device_name: &str, device_type, matched_rule: Option<&str>| SwitchEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            device_name: device_name.to_string(),
+            device_type,
+            reason: crate::notifications::SwitchReason::HigherPriority,
+            matched_rule: matched_rule.map(str::to_string),
+        };
+        let events = [
+            event("AirPods Pro", DeviceType::Output, Some("AirPods")),
+            event("Studio Mic", DeviceType::Input, Some("Studio Mic")),
+            event("AirPods Max", DeviceType::Output, Some("AirPods")),
+            event("Headset", DeviceType::InputOutput, None),
+        ];
+
+        let statistics = ServiceStatistics::from_history(&events);
+
+        assert_eq!(statistics.total_switch_count, 4);
+        assert_eq!(statistics.output_switch_count, 3);
+        assert_eq!(statistics.input_switch_count, 1);
+        assert_eq!(statistics.failed_switch_count, 0);
+        assert_eq!(statistics.uptime_seconds, 0);
+        assert_eq!(
+            statistics.rule_hits,
+            HashMap::from([("AirPods".to_string(), 2), ("Studio Mic".to_string(), 1)])
+        );
+    }
+
     #[test]
     fn test_service_creation() {
         let audio_system = MockAudioSystem::new();
@@ -650,4 +1471,150 @@ show_switching_actions = true
         system_service.stop_service();
         assert!(!service.should_continue_running());
     }
+
+    #[test]
+    fn test_set_profile_switches_rules_and_survives_reload() {
+        let file_system = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        let config_content = r#"
+[[output_devices]]
+name = "Studio Display"
+weight = 100
+match_type = "contains"
+enabled = true
+
+[[output_devices]]
+name = "Dell Monitor"
+weight = 50
+match_type = "contains"
+enabled = true
+
+[[profiles.office.output_devices]]
+name = "Dell Monitor"
+weight = 100
+match_type = "contains"
+enabled = true
+"#;
+        file_system.add_file(&config_path, config_content.to_string());
+        let mut service = AudioDeviceService::new(
+            MockAudioSystem::new(),
+            file_system,
+            MockSystemService::new(),
+            config_path,
+        )
+        .unwrap();
+        let devices = [
+            crate::audio::AudioDevice::new(
+                "1".to_string(),
+                "Studio Display Speakers".to_string(),
+                DeviceType::Output,
+            ),
+            crate::audio::AudioDevice::new(
+                "2".to_string(),
+                "Dell Monitor Speakers".to_string(),
+                DeviceType::Output,
+            ),
+        ];
+        let best_output = |service: &AudioDeviceService<_, _, _>| {
+            service
+                .device_controller
+                .priority_manager()
+                .find_best_output_device(&devices)
+                .map(|device| device.name)
+        };
+        assert_eq!(best_output(&service).unwrap(), "Studio Display Speakers");
+
+        service.set_profile(Some("office")).unwrap();
+        assert_eq!(best_output(&service).unwrap(), "Dell Monitor Speakers");
+
+        service.reload_config().unwrap();
+        assert_eq!(service.config.output_devices.len(), 1);
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert_eq!(status["profile"], "office");
+
+        // An unknown profile keeps the current one
+        assert!(service.set_profile(Some("home")).is_err());
+        assert_eq!(service.profile.as_deref(), Some("office"));
+        assert_eq!(best_output(&service).unwrap(), "Dell Monitor Speakers");
+
+        service.set_profile(None).unwrap();
+        assert_eq!(best_output(&service).unwrap(), "Studio Display Speakers");
+    }
+
+    #[test]
+    fn test_check_config_reload_detects_modified_time() {
+        let file_system = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        file_system.add_file(
+            &config_path,
+            "[general]\ncheck_interval_ms = 1000\nlog_level = \"info\"\ndaemon_mode = false\n"
+                .to_string(),
+        );
+
+        let t0 = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        file_system.set_config_modified_time(&config_path, t0);
+
+        let mut service = AudioDeviceService::new(
+            MockAudioSystem::new(),
+            file_system.clone(),
+            MockSystemService::new(),
+            config_path.clone(),
+        )
+        .unwrap();
+        service.record_config_modified_time();
+        assert_eq!(service.last_config_modified, Some(t0));
+
+        // Unchanged file: no reload
+        file_system.set_file_content(
+            &config_path,
+            "[general]\ncheck_interval_ms = 2000\nlog_level = \"info\"\ndaemon_mode = false\n",
+        );
+        file_system.set_config_modified_time(&config_path, t0);
+        service.check_config_reload().unwrap();
+        assert_eq!(service.config.general.check_interval_ms, 1000);
+
+        // Newer modification time: reload picks up the new content
+        let t1 = t0 + std::time::Duration::from_secs(1);
+        file_system.set_config_modified_time(&config_path, t1);
+        service.check_config_reload().unwrap();
+        assert_eq!(service.config.general.check_interval_ms, 2000);
+        assert_eq!(service.last_config_modified, Some(t1));
+    }
+
+    #[test]
+    fn test_watched_config_reloads_only_on_change_event() {
+        let file_system = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        file_system.add_file(
+            &config_path,
+            "[general]\ncheck_interval_ms = 1000\nlog_level = \"info\"\ndaemon_mode = false\n"
+                .to_string(),
+        );
+
+        let mut service = AudioDeviceService::new(
+            MockAudioSystem::new(),
+            file_system.clone(),
+            MockSystemService::new(),
+            config_path.clone(),
+        )
+        .unwrap();
+        service.record_config_modified_time();
+        service.config_loader.watch_config().unwrap();
+        file_system.set_file_content(
+            &config_path,
+            "[general]\ncheck_interval_ms = 2000\nlog_level = \"info\"\ndaemon_mode = false\n",
+        );
+        file_system.clear_call_history();
+
+        // No write event: nothing is reloaded and the file is not polled
+        service.check_config_reload().unwrap();
+        service.check_config_reload().unwrap();
+        assert_eq!(service.config.general.check_interval_ms, 1000);
+        assert!(file_system.get_modified_time_calls().is_empty());
+
+        file_system.trigger_config_change();
+        service.check_config_reload().unwrap();
+        assert_eq!(service.config.general.check_interval_ms, 2000);
+    }
 }
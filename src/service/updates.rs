@@ -0,0 +1,155 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Latest release of this tool on GitHub
+#[cfg_attr(not(feature = "auto-update"), allow(dead_code))]
+pub const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/tizzo/auto-audio-device-selector/releases/latest";
+
+/// A release newer than the running binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub release_notes: String,
+    pub download_url: String,
+}
+
+/// The fields we use from the GitHub releases API response
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+}
+
+/// Turn a GitHub "latest release" response into the updates newer than `current_version`
+#[cfg_attr(not(feature = "auto-update"), allow(dead_code))]
+pub fn updates_from_release_json(json: &str, current_version: &str) -> Result<Vec<UpdateInfo>> {
+    let release: GitHubRelease = serde_json::from_str(json)?;
+
+    if !is_newer_version(&release.tag_name, current_version) {
+        return Ok(Vec::new());
+    }
+
+    Ok(vec![UpdateInfo {
+        version: release.tag_name,
+        release_notes: release.body.unwrap_or_default(),
+        download_url: release.html_url,
+    }])
+}
+
+/// Compare dotted versions such as "v1.2.3" numerically, ignoring a leading "v"
+/// and any pre-release suffix. Unparseable components count as 0.
+pub fn is_newer_version(candidate: &str, current: &str) -> bool {
+    version_components(candidate) > version_components(current)
+}
+
+fn version_components(version: &str) -> Vec<u64> {
+    let version = version.trim().trim_start_matches('v');
+    let release = version.split(['-', '+']).next().unwrap_or_default();
+
+    let mut components: Vec<u64> = release
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+
+    // "1.2" and "1.2.0" are the same version
+    while components.last() == Some(&0) {
+        components.pop();
+    }
+    components
+}
+
+/// Ask GitHub for the latest release and return it if it is newer than `current_version`
+#[cfg(feature = "auto-update")]
+pub async fn fetch_available_updates(current_version: &str) -> Result<Vec<UpdateInfo>> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("audio-device-monitor/", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+
+    let body = client
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    updates_from_release_json(&body, current_version)
+}
+
+/// Built without the auto-update feature: never report updates
+#[cfg(not(feature = "auto-update"))]
+pub async fn fetch_available_updates(_current_version: &str) -> Result<Vec<UpdateInfo>> {
+    tracing::debug!("Update check skipped: built without the auto-update feature");
+    Ok(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELEASE_JSON: &str = r#"{
+        "tag_name": "v1.2.3",
+        "name": "1.2.3",
+        "body": "Fixes AirPods reconnect handling",
+        "html_url": "https://github.com/tizzo/auto-audio-device-selector/releases/tag/v1.2.3",
+        "draft": false,
+        "prerelease": false
+    }"#;
+
+    #[test]
+    fn test_newer_release_is_reported() {
+        let updates = updates_from_release_json(RELEASE_JSON, "0.1.0").unwrap();
+
+        assert_eq!(
+            updates,
+            vec![UpdateInfo {
+                version: "v1.2.3".to_string(),
+                release_notes: "Fixes AirPods reconnect handling".to_string(),
+                download_url:
+                    "https://github.com/tizzo/auto-audio-device-selector/releases/tag/v1.2.3"
+                        .to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_same_or_older_release_is_ignored() {
+        assert!(
+            updates_from_release_json(RELEASE_JSON, "1.2.3")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            updates_from_release_json(RELEASE_JSON, "2.0.0")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_release_without_notes() {
+        let json = r#"{"tag_name": "v9.0.0", "body": null, "html_url": "https://example.com"}"#;
+
+        let updates = updates_from_release_json(json, "0.1.0").unwrap();
+        assert_eq!(updates[0].release_notes, "");
+    }
+
+    #[test]
+    fn test_malformed_response_is_error() {
+        assert!(updates_from_release_json(r#"{"message": "Not Found"}"#, "0.1.0").is_err());
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer_version("v0.2.0", "0.1.9"));
+        assert!(is_newer_version("1.10.0", "1.9.0"));
+        assert!(is_newer_version("v1.0.1", "1.0"));
+        assert!(!is_newer_version("v1.0", "1.0.0"));
+        assert!(!is_newer_version("v1.0.0-beta.1", "1.0.0"));
+    }
+}
@@ -1,25 +1,47 @@
 use anyhow::Result;
+#[cfg(feature = "bluetooth-detection")]
+use core_foundation::base::{CFRelease, CFTypeRef, TCFType};
+use core_foundation::runloop::CFRunLoop;
+#[cfg(feature = "bluetooth-detection")]
+use core_foundation::runloop::{CFRunLoopSource, CFRunLoopSourceRef, kCFRunLoopDefaultMode};
+#[cfg(feature = "bluetooth-detection")]
+use core_foundation::string::{CFString, CFStringRef};
+use notify::{RecursiveMode, Watcher};
 use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
 use signal_hook::flag;
+use std::any::Any;
+#[cfg(feature = "bluetooth-detection")]
+use std::ffi::c_void;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
 use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::info;
 
 use crate::audio::listener::CoreAudioListener;
 use crate::audio::{AudioDevice, DeviceController};
+use crate::system::sleep_wake::wait_for_stable_devices;
 use crate::system::traits::{AudioSystemInterface, FileSystemInterface, SystemServiceInterface};
 
 type CallbackFn = Box<dyn Fn() + Send + Sync>;
 
+/// How often a running switch hook is checked for completion
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Production implementation of AudioSystemInterface using CoreAudio
 pub struct CoreAudioSystem {
     controller: DeviceController,
-    listener: Option<CoreAudioListener>,
+    listener: Option<Arc<CoreAudioListener>>,
     callbacks: Arc<Mutex<Vec<CallbackFn>>>,
 }
 
 impl CoreAudioSystem {
+    /// CoreAudio access with default settings and no property listener.
+    /// Device callbacks are stored but never fired, so this suits one-shot
+    /// queries and switches rather than long-running monitoring.
     pub fn new() -> Result<Self> {
         Ok(Self {
             controller: DeviceController::new()?,
@@ -28,16 +50,51 @@ impl CoreAudioSystem {
         })
     }
 
+    /// Same device controller as `new`, plus an observing `CoreAudioListener`
+    /// configured from `config` so registered callbacks fire on device changes.
+    /// The listener never switches devices itself: the controller that owns
+    /// this audio system is the only thing that does.
     pub fn new_with_config(config: &crate::config::Config) -> Result<Self> {
-        let listener = CoreAudioListener::new(config)?;
+        let callbacks: Arc<Mutex<Vec<CallbackFn>>> = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&callbacks);
+        let listener = Arc::new(CoreAudioListener::observer(config, move || {
+            if let Ok(callbacks) = observed.lock() {
+                callbacks.iter().for_each(|callback| callback());
+            }
+        })?);
         Ok(Self {
             controller: DeviceController::new()?,
             listener: Some(listener),
-            callbacks: Arc::new(Mutex::new(Vec::new())),
+            callbacks,
         })
     }
 }
 
+/// Deregisters the listener's property listeners so device callbacks stop
+/// once the audio system is gone
+impl Drop for CoreAudioSystem {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.deregister_listeners();
+        }
+    }
+}
+
+impl CoreAudioSystem {
+    /// Re-enumerate devices after a wake from sleep until CoreAudio reports the
+    /// same device IDs twice in a row, since it can briefly report stale devices
+    // Called by hosts that drive CoreAudioSystem directly rather than through AudioDeviceService
+    #[allow(dead_code)]
+    pub fn recover_from_sleep_wake(&mut self) -> Result<()> {
+        let devices = wait_for_stable_devices(&*self, |milliseconds| {
+            std::thread::sleep(std::time::Duration::from_millis(milliseconds));
+            Ok(())
+        })?;
+        info!("Recovered {} devices after wake from sleep", devices.len());
+        Ok(())
+    }
+}
+
 impl AudioSystemInterface for CoreAudioSystem {
     fn enumerate_devices(&self) -> Result<Vec<AudioDevice>> {
         self.controller.enumerate_devices()
@@ -52,17 +109,21 @@ impl AudioSystemInterface for CoreAudioSystem {
     }
 
     fn set_default_output_device(&self, device_id: &str) -> Result<()> {
-        // DeviceController expects device name, but we're passing device_id
-        // For now, treat device_id as device name - this may need refinement
+        // Callers usually pass a device name. DeviceController matches names first
+        // and only then treats a numeric argument as a CoreAudio ID
         self.controller.set_default_output_device(device_id)
     }
 
     fn set_default_input_device(&self, device_id: &str) -> Result<()> {
-        // DeviceController expects device name, but we're passing device_id
-        // For now, treat device_id as device name - this may need refinement
+        // Resolved like set_default_output_device: by name, then by numeric ID
         self.controller.set_default_input_device(device_id)
     }
 
+    fn set_default_system_alert_device(&self, device_id: &str) -> Result<()> {
+        // Like the other setters, device_id is treated as the device name
+        self.controller.set_default_system_alert_device(device_id)
+    }
+
     fn add_device_change_listener(&self, callback: Box<dyn Fn() + Send + Sync>) -> Result<()> {
         // Store the callback
         self.callbacks.lock().unwrap().push(callback);
@@ -74,6 +135,10 @@ impl AudioSystemInterface for CoreAudioSystem {
         Ok(())
     }
 
+    fn get_device_name_by_id(&self, device_id: &str) -> Result<Option<String>> {
+        self.controller.get_device_name_by_id(device_id)
+    }
+
     fn is_device_available(&self, device_id: &str) -> Result<bool> {
         // Check if device exists in enumerated devices
         let devices = self.enumerate_devices()?;
@@ -81,9 +146,46 @@ impl AudioSystemInterface for CoreAudioSystem {
             .iter()
             .any(|d| d.id == device_id || d.name == device_id))
     }
+
+    fn get_buffer_frame_size_range(&self, device_id: &str) -> Result<(u32, u32)> {
+        self.controller.get_device_buffer_size_range(device_id)
+    }
+
+    fn set_buffer_frame_size(&self, device_id: &str, frames: u32) -> Result<()> {
+        self.controller.set_device_buffer_size(device_id, frames)
+    }
+
+    fn get_device_latency_ms(&self, device: &AudioDevice) -> Result<f64> {
+        self.controller.get_device_latency_in_ms(device)
+    }
+
+    fn get_system_volume(&self) -> Result<f32> {
+        self.controller.get_system_volume()
+    }
+
+    fn set_system_volume(&self, volume: f32) -> Result<()> {
+        self.controller.set_system_volume(volume)
+    }
+
+    fn get_device_volume(&self, device_id: &str) -> Result<f32> {
+        self.controller
+            .get_output_volume(DeviceController::parse_device_id(device_id)?)
+    }
+
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<()> {
+        self.controller
+            .set_output_volume(DeviceController::parse_device_id(device_id)?, volume)
+    }
+
+    fn pending_notifications(&self) -> usize {
+        self.listener
+            .as_ref()
+            .map_or(0, |listener| listener.pending_notifications())
+    }
 }
 
 /// Production implementation of FileSystemInterface using std::fs
+#[derive(Clone)]
 pub struct StandardFileSystem;
 
 impl FileSystemInterface for StandardFileSystem {
@@ -93,8 +195,8 @@ impl FileSystemInterface for StandardFileSystem {
     }
 
     fn write_config_file(&self, path: &Path, content: &str) -> Result<()> {
-        std::fs::write(path, content)
-            .map_err(|e| anyhow::anyhow!("Failed to write config file: {}", e))
+        crate::config::write_file_atomically(path, |file| file.write_all(content.as_bytes()))
+            .map_err(|e| anyhow::anyhow!("Failed to write config file: {:#}", e))
     }
 
     fn config_file_exists(&self, path: &Path) -> bool {
@@ -113,12 +215,95 @@ impl FileSystemInterface for StandardFileSystem {
             .modified()
             .map_err(|e| anyhow::anyhow!("Failed to get modified time: {}", e))
     }
+
+    fn watch_file(
+        &self,
+        path: &Path,
+        callback: Box<dyn Fn() + Send>,
+    ) -> Result<Box<dyn Any + Send>> {
+        // Watch the parent directory: editors often save by replacing the file,
+        // which would silently end a watch on the file itself
+        let watch_dir = path.parent().ok_or_else(|| {
+            anyhow::anyhow!("Cannot watch {}: no parent directory", path.display())
+        })?;
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let touches_file = event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == file_name.as_deref());
+                if touches_file && (event.kind.is_modify() || event.kind.is_create()) {
+                    callback();
+                }
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to create file watcher: {}", e))?;
+
+        watcher
+            .watch(watch_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| anyhow::anyhow!("Failed to watch {}: {}", watch_dir.display(), e))?;
+
+        info!("Watching {} for changes", path.display());
+        Ok(Box::new(watcher))
+    }
+
+    fn execute_hook(&self, command: &str, timeout: Duration) -> Result<ExitStatus> {
+        // In a process group of its own, so a timeout also kills what the shell started
+        let mut child = Command::new("/bin/sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run hook '{}': {}", command, e))?;
+        let wait_error = |e| anyhow::anyhow!("Failed to wait for hook '{}': {}", command, e);
+
+        // Poll rather than wait on another thread, so the shell is only ever
+        // reaped here and its PID cannot be reused before a timeout kills it
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = child.try_wait().map_err(wait_error)? {
+                return Ok(status);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            std::thread::sleep(HOOK_POLL_INTERVAL.min(deadline - now));
+        }
+
+        match libc::pid_t::try_from(child.id()) {
+            // SAFETY: kill has no memory safety requirements. The shell is not
+            // reaped until the wait below, so its PID is still the group's ID.
+            Ok(process_group) => unsafe {
+                libc::killpg(process_group, libc::SIGKILL);
+            },
+            Err(_) => {
+                let _ = child.kill();
+            }
+        }
+        child.wait().map_err(wait_error)?;
+
+        Err(anyhow::anyhow!(
+            "Hook '{}' did not finish within {}ms",
+            command,
+            timeout.as_millis()
+        ))
+    }
 }
 
-/// Production implementation of SystemServiceInterface for macOS
+/// Production implementation of SystemServiceInterface for macOS. Clones
+/// share the signal and power flags.
+#[derive(Clone)]
 pub struct MacOSSystemService {
     config_reload_requested: Arc<std::sync::atomic::AtomicBool>,
     shutdown_requested: Arc<std::sync::atomic::AtomicBool>,
+    woke_from_sleep: Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl MacOSSystemService {
@@ -126,6 +311,7 @@ impl MacOSSystemService {
         Self {
             config_reload_requested: Arc::new(AtomicBool::new(false)),
             shutdown_requested: Arc::new(AtomicBool::new(false)),
+            woke_from_sleep: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -146,14 +332,22 @@ impl SystemServiceInterface for MacOSSystemService {
         // Register SIGHUP to set config reload flag
         flag::register(SIGHUP, Arc::clone(&self.config_reload_requested))?;
 
+        // Power notifications are delivered through this thread's run loop
+        register_wake_notification(Arc::clone(&self.woke_from_sleep))?;
+
         info!("Signal handlers registered successfully");
         Ok(())
     }
 
     fn run_event_loop(&self) -> Result<()> {
-        // Simple event loop that sleeps
-        // TODO: Implement proper Core Foundation event loop
-        self.sleep_ms(100)?;
+        // Run the Core Foundation run loop briefly so power notifications are delivered
+        unsafe {
+            CFRunLoop::run_in_mode(
+                core_foundation::runloop::kCFRunLoopDefaultMode,
+                std::time::Duration::from_millis(100),
+                false,
+            );
+        }
         Ok(())
     }
 
@@ -174,6 +368,155 @@ impl SystemServiceInterface for MacOSSystemService {
     fn is_config_reload_requested(&self) -> bool {
         self.is_config_reload_requested()
     }
+
+    fn woke_from_sleep(&self) -> bool {
+        self.woke_from_sleep.swap(false, Ordering::Relaxed)
+    }
+
+    fn is_on_battery_power(&self) -> bool {
+        query_on_battery_power()
+    }
+
+    fn stop_event_loop(&self) {
+        CFRunLoop::get_current().stop();
+    }
+}
+
+// IOKit power source API; the framework is linked by build.rs
+#[cfg(feature = "bluetooth-detection")]
+unsafe extern "C" {
+    fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+    fn IOPSGetProvidingPowerSourceType(snapshot: CFTypeRef) -> CFStringRef;
+}
+
+/// Providing power source type reported on battery (kIOPMBatteryPowerKey)
+#[cfg(feature = "bluetooth-detection")]
+const BATTERY_POWER_SOURCE: &str = "Battery Power";
+
+/// Ask IOKit whether the Mac is drawing power from its battery
+/// Machines without a battery report AC power
+#[cfg(feature = "bluetooth-detection")]
+pub fn query_on_battery_power() -> bool {
+    unsafe {
+        let snapshot = IOPSCopyPowerSourcesInfo();
+        if snapshot.is_null() {
+            return false;
+        }
+
+        // The source type follows the get rule: it is owned by the snapshot
+        let source_type = IOPSGetProvidingPowerSourceType(snapshot);
+        let on_battery = !source_type.is_null()
+            && CFString::wrap_under_get_rule(source_type)
+                == CFString::from_static_string(BATTERY_POWER_SOURCE);
+
+        CFRelease(snapshot);
+        on_battery
+    }
+}
+
+/// Built without IOKit: assume AC power so battery-only rules never apply
+#[cfg(not(feature = "bluetooth-detection"))]
+pub fn query_on_battery_power() -> bool {
+    false
+}
+
+// IOKit power management API; the framework is linked by build.rs
+#[cfg(feature = "bluetooth-detection")]
+unsafe extern "C" {
+    fn IORegisterForSystemPower(
+        refcon: *mut c_void,
+        notify_port: *mut *mut c_void,
+        callback: extern "C" fn(*mut c_void, u32, u32, *mut c_void),
+        notifier: *mut u32,
+    ) -> u32;
+    fn IONotificationPortGetRunLoopSource(notify_port: *mut c_void) -> CFRunLoopSourceRef;
+    fn IOAllowPowerChange(kernel_port: u32, notification_id: isize) -> i32;
+}
+
+/// kIOMessageCanSystemSleep: idle sleep is about to happen and may be vetoed
+#[cfg(feature = "bluetooth-detection")]
+const IO_MESSAGE_CAN_SYSTEM_SLEEP: u32 = 0xe000_0270;
+
+/// kIOMessageSystemWillSleep: the system is going to sleep
+#[cfg(feature = "bluetooth-detection")]
+const IO_MESSAGE_SYSTEM_WILL_SLEEP: u32 = 0xe000_0280;
+
+/// kIOMessageSystemHasPoweredOn: the system finished waking up
+#[cfg(feature = "bluetooth-detection")]
+const IO_MESSAGE_SYSTEM_HAS_POWERED_ON: u32 = 0xe000_0300;
+
+/// State shared with the power notification callback for the life of the process
+#[cfg(feature = "bluetooth-detection")]
+struct PowerNotificationContext {
+    root_port: std::sync::atomic::AtomicU32,
+    woke_from_sleep: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "bluetooth-detection")]
+extern "C" fn power_notification_callback(
+    refcon: *mut c_void,
+    _service: u32,
+    message_type: u32,
+    message_argument: *mut c_void,
+) {
+    let context = unsafe { &*(refcon as *const PowerNotificationContext) };
+
+    match message_type {
+        // Sleep must be acknowledged or macOS waits 30 seconds for us
+        IO_MESSAGE_CAN_SYSTEM_SLEEP | IO_MESSAGE_SYSTEM_WILL_SLEEP => unsafe {
+            IOAllowPowerChange(
+                context.root_port.load(Ordering::SeqCst),
+                message_argument as isize,
+            );
+        },
+        IO_MESSAGE_SYSTEM_HAS_POWERED_ON => {
+            info!("System woke from sleep");
+            context.woke_from_sleep.store(true, Ordering::SeqCst);
+        }
+        _ => {}
+    }
+}
+
+/// Set `woke_from_sleep` whenever the system wakes, via IOKit power notifications
+/// delivered on the calling thread's run loop
+#[cfg(feature = "bluetooth-detection")]
+fn register_wake_notification(woke_from_sleep: Arc<AtomicBool>) -> Result<()> {
+    // Leaked on purpose: the callback may run until the process exits
+    let context = Box::into_raw(Box::new(PowerNotificationContext {
+        root_port: std::sync::atomic::AtomicU32::new(0),
+        woke_from_sleep,
+    }));
+
+    unsafe {
+        let mut notify_port: *mut c_void = std::ptr::null_mut();
+        let mut notifier: u32 = 0;
+        let root_port = IORegisterForSystemPower(
+            context as *mut c_void,
+            &mut notify_port,
+            power_notification_callback,
+            &mut notifier,
+        );
+        if root_port == 0 {
+            drop(Box::from_raw(context));
+            return Err(anyhow::anyhow!(
+                "Failed to register for system power notifications"
+            ));
+        }
+        (*context).root_port.store(root_port, Ordering::SeqCst);
+
+        let source =
+            CFRunLoopSource::wrap_under_get_rule(IONotificationPortGetRunLoopSource(notify_port));
+        CFRunLoop::get_current().add_source(&source, kCFRunLoopDefaultMode);
+    }
+
+    info!("Registered for sleep/wake notifications");
+    Ok(())
+}
+
+/// Built without IOKit: wakes are never reported
+#[cfg(not(feature = "bluetooth-detection"))]
+fn register_wake_notification(_woke_from_sleep: Arc<AtomicBool>) -> Result<()> {
+    Ok(())
 }
 
 // Default implementations for production use
@@ -194,3 +537,59 @@ impl Default for MacOSSystemService {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_exit_status_is_reported() {
+        let file_system = StandardFileSystem;
+
+        let status = file_system
+            .execute_hook("exit 0", Duration::from_secs(5))
+            .unwrap();
+        assert!(status.success());
+
+        let status = file_system
+            .execute_hook("exit 3", Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn test_hung_hook_is_killed_at_timeout() {
+        let started = std::time::Instant::now();
+
+        let error = StandardFileSystem
+            .execute_hook("sleep 30", Duration::from_millis(100))
+            .unwrap_err();
+
+        assert!(error.to_string().contains("did not finish within 100ms"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_hook_timeout_kills_commands_the_shell_started() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("sleep.pid");
+        let command = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+
+        StandardFileSystem
+            .execute_hook(&command, Duration::from_millis(200))
+            .unwrap_err();
+
+        let pid: libc::pid_t = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        // The orphaned sleep is reparented and reaped by init once killed
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        // SAFETY: signal 0 only checks whether the process exists
+        while unsafe { libc::kill(pid, 0) } == 0 {
+            assert!(std::time::Instant::now() < deadline, "sleep {pid} survived");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -1,11 +1,16 @@
 #![cfg(any(test, feature = "test-mocks"))]
 
 use anyhow::Result;
+use std::any::Any;
 use std::collections::HashMap;
+use std::os::unix::process::ExitStatusExt;
 use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-use crate::audio::AudioDevice;
+use crate::audio::{AudioDevice, DeviceType};
+use crate::config::{Config, DeviceRule};
 use crate::system::traits::{AudioSystemInterface, FileSystemInterface, SystemServiceInterface};
 
 /// Mock audio system for testing - provides controllable device behavior
@@ -17,9 +22,26 @@ pub struct MockAudioSystem {
     pub device_change_callbacks: Arc<Mutex<Vec<Box<dyn Fn() + Send + Sync>>>>,
     pub set_device_calls: Arc<Mutex<Vec<(String, String)>>>, // (device_id, call_type)
     pub should_fail_enumeration: Arc<Mutex<bool>>,
+    pub enumeration_delay: Arc<Mutex<Duration>>,
     pub should_fail_set_device: Arc<Mutex<bool>>,
+    /// Default device changes still to be refused after being recorded
+    pub transient_set_device_failures: Arc<Mutex<u32>>,
+    pub buffer_size_ranges: Arc<Mutex<HashMap<String, (u32, u32)>>>,
+    pub buffer_size_calls: Arc<Mutex<Vec<(String, u32)>>>, // (device_id, frames)
+    pub device_latencies_ms: Arc<Mutex<HashMap<String, f64>>>,
+    pub system_volume: Arc<Mutex<f32>>,
+    pub set_volume_calls: Arc<Mutex<Vec<f32>>>,
+    /// Per-device output volumes; devices without an entry report full volume
+    pub device_volumes: Arc<Mutex<HashMap<String, f32>>>,
+    /// Simulated current time, moved forward only by `advance_time`
+    pub clock: Arc<Mutex<SystemTime>>,
+    /// Notifications reported as still being delivered by the audio system
+    pub pending_notifications: Arc<std::sync::atomic::AtomicUsize>,
 }
 
+/// Buffer size range reported for devices without an explicit mock range
+pub const MOCK_DEFAULT_BUFFER_SIZE_RANGE: (u32, u32) = (32, 4096);
+
 impl MockAudioSystem {
     pub fn new() -> Self {
         Self {
@@ -29,10 +51,67 @@ impl MockAudioSystem {
             device_change_callbacks: Arc::new(Mutex::new(Vec::new())),
             set_device_calls: Arc::new(Mutex::new(Vec::new())),
             should_fail_enumeration: Arc::new(Mutex::new(false)),
+            enumeration_delay: Arc::new(Mutex::new(Duration::ZERO)),
             should_fail_set_device: Arc::new(Mutex::new(false)),
+            transient_set_device_failures: Arc::new(Mutex::new(0)),
+            buffer_size_ranges: Arc::new(Mutex::new(HashMap::new())),
+            buffer_size_calls: Arc::new(Mutex::new(Vec::new())),
+            device_latencies_ms: Arc::new(Mutex::new(HashMap::new())),
+            system_volume: Arc::new(Mutex::new(1.0)),
+            set_volume_calls: Arc::new(Mutex::new(Vec::new())),
+            device_volumes: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(Mutex::new(SystemTime::now())),
+            pending_notifications: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
         }
     }
 
+    /// Create a mock system that already has these devices available
+    // Called by test code for one-line device setup
+    #[allow(dead_code)]
+    pub fn with_devices(devices: Vec<AudioDevice>) -> Self {
+        let audio_system = Self::new();
+        *audio_system.devices.lock().unwrap() = devices;
+        audio_system
+    }
+
+    /// Create a mock system with a device named after every configured rule that
+    /// matches its own name: output rules add output devices, input rules input devices
+    // Called by test code to get devices the config's rules will select
+    #[allow(dead_code)]
+    pub fn from_config(config: &Config) -> Self {
+        let devices_for = |rules: Vec<DeviceRule>, device_type: DeviceType| {
+            rules
+                .into_iter()
+                .filter(|rule| rule.matches(&rule.name))
+                .enumerate()
+                .map(move |(i, rule)| {
+                    let id = format!("mock-{}-{}", device_type, i).to_lowercase();
+                    AudioDevice::new(id, rule.name, device_type.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut devices = devices_for(config.output_rules(), DeviceType::Output);
+        devices.extend(devices_for(config.input_rules(), DeviceType::Input));
+        Self::with_devices(devices)
+    }
+
+    /// Make `device` the default output device
+    // Called by test code when chaining mock setup
+    #[allow(dead_code)]
+    pub fn with_default_output(self, device: AudioDevice) -> Self {
+        *self.default_output.lock().unwrap() = Some(device);
+        self
+    }
+
+    /// Make `device` the default input device
+    // Called by test code when chaining mock setup
+    #[allow(dead_code)]
+    pub fn with_default_input(self, device: AudioDevice) -> Self {
+        *self.default_input.lock().unwrap() = Some(device);
+        self
+    }
+
     /// Add a device to the mock system
     // Called by test code to simulate device connections during testing
     #[allow(dead_code)]
@@ -41,6 +120,28 @@ impl MockAudioSystem {
         self.trigger_device_change();
     }
 
+    /// Add a device connected at the simulated current time, for stability checks
+    // Called by test code to simulate device connections that need debouncing
+    #[allow(dead_code)]
+    pub fn connect_device(&self, device: AudioDevice) {
+        let connected_since = self.now();
+        self.add_device(device.with_connected_since(connected_since));
+    }
+
+    /// The simulated current time
+    // Called by test code to check device stability without sleeping
+    #[allow(dead_code)]
+    pub fn now(&self) -> SystemTime {
+        *self.clock.lock().unwrap()
+    }
+
+    /// Move the simulated clock forward
+    // Called by test code to simulate time passing without sleeping
+    #[allow(dead_code)]
+    pub fn advance_time(&self, elapsed: Duration) {
+        *self.clock.lock().unwrap() += elapsed;
+    }
+
     /// Remove a device from the mock system
     // Called by test code to simulate device disconnections during testing
     #[allow(dead_code)]
@@ -107,6 +208,13 @@ impl MockAudioSystem {
         *self.should_fail_enumeration.lock().unwrap() = should_fail;
     }
 
+    /// Make every enumeration take at least this long
+    // Called by test code to simulate a slow audio system
+    #[allow(dead_code)]
+    pub fn set_enumeration_delay(&self, delay: Duration) {
+        *self.enumeration_delay.lock().unwrap() = delay;
+    }
+
     /// Configure the mock to fail device setting
     // Called by test code to simulate audio system device switching failures
     #[allow(dead_code)]
@@ -114,6 +222,59 @@ impl MockAudioSystem {
         *self.should_fail_set_device.lock().unwrap() = should_fail;
     }
 
+    /// Refuse the next `count` default output or input changes. Unlike
+    /// `set_device_setting_failure`, refused calls are still recorded, so
+    /// tests can count attempts.
+    // Called by test code to simulate a device that is still initialising
+    #[allow(dead_code)]
+    pub fn fail_next_set_device_calls(&self, count: u32) {
+        *self.transient_set_device_failures.lock().unwrap() = count;
+    }
+
+    /// Set the buffer size range reported for a device
+    // Called by test code to simulate device-specific buffer size limits
+    #[allow(dead_code)]
+    pub fn set_buffer_frame_size_range(&self, device_id: &str, minimum: u32, maximum: u32) {
+        self.buffer_size_ranges
+            .lock()
+            .unwrap()
+            .insert(device_id.to_string(), (minimum, maximum));
+    }
+
+    /// Set the latency in milliseconds reported for a device; devices without one report an error
+    // Called by test code to simulate device latency queries
+    #[allow(dead_code)]
+    pub fn set_device_latency_ms(&self, device_id: &str, latency_ms: f64) {
+        self.device_latencies_ms
+            .lock()
+            .unwrap()
+            .insert(device_id.to_string(), latency_ms);
+    }
+
+    /// Get all buffer size changes that were applied
+    // Called by test code to verify buffer sizes applied after switching
+    #[allow(dead_code)]
+    pub fn get_buffer_size_calls(&self) -> Vec<(String, u32)> {
+        self.buffer_size_calls.lock().unwrap().clone()
+    }
+
+    /// Get all output volume changes that were applied
+    // Called by test code to verify volumes applied after switching
+    #[allow(dead_code)]
+    pub fn get_set_volume_calls(&self) -> Vec<f32> {
+        self.set_volume_calls.lock().unwrap().clone()
+    }
+
+    /// Set the output volume a device reports
+    // Called by test code to set up volumes before switching
+    #[allow(dead_code)]
+    pub fn set_mock_device_volume(&self, device_id: &str, volume: f32) {
+        self.device_volumes
+            .lock()
+            .unwrap()
+            .insert(device_id.to_string(), volume);
+    }
+
     /// Get count of registered callbacks
     // Called by test code to verify device change callback registration
     #[allow(dead_code)]
@@ -155,6 +316,19 @@ impl MockAudioSystem {
             .collect()
     }
 
+    /// Get set system alert device calls
+    // Called by test code to verify system alert sounds follow output switches
+    #[allow(dead_code)]
+    pub fn get_set_default_system_alert_calls(&self) -> Vec<String> {
+        self.set_device_calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, call_type)| call_type == "set_default_system_alert")
+            .map(|(device_id, _)| device_id.clone())
+            .collect()
+    }
+
     /// Get default output calls count
     // Called by test code to verify number of output device operations
     #[allow(dead_code)]
@@ -175,6 +349,10 @@ impl AudioSystemInterface for MockAudioSystem {
         if *self.should_fail_enumeration.lock().unwrap() {
             return Err(anyhow::anyhow!("Mock enumeration failure"));
         }
+        let delay = *self.enumeration_delay.lock().unwrap();
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
         Ok(self.devices.lock().unwrap().clone())
     }
 
@@ -196,11 +374,19 @@ impl AudioSystemInterface for MockAudioSystem {
             .unwrap()
             .push((device_id.to_string(), "set_default_output".to_string()));
 
-        // Find and set the device as default if it exists
+        let mut transient_failures = self.transient_set_device_failures.lock().unwrap();
+        if *transient_failures > 0 {
+            *transient_failures -= 1;
+            return Err(anyhow::anyhow!("Mock transient set device failure"));
+        }
+        drop(transient_failures);
+
+        // Find and set the device as default if it exists, by name before ID
         let devices = self.devices.lock().unwrap();
         if let Some(device) = devices
             .iter()
-            .find(|d| d.id == device_id || d.name == device_id)
+            .find(|d| d.name == device_id)
+            .or_else(|| devices.iter().find(|d| d.id == device_id))
         {
             *self.default_output.lock().unwrap() = Some(device.clone());
         }
@@ -218,11 +404,19 @@ impl AudioSystemInterface for MockAudioSystem {
             .unwrap()
             .push((device_id.to_string(), "set_default_input".to_string()));
 
-        // Find and set the device as default if it exists
+        let mut transient_failures = self.transient_set_device_failures.lock().unwrap();
+        if *transient_failures > 0 {
+            *transient_failures -= 1;
+            return Err(anyhow::anyhow!("Mock transient set device failure"));
+        }
+        drop(transient_failures);
+
+        // Find and set the device as default if it exists, by name before ID
         let devices = self.devices.lock().unwrap();
         if let Some(device) = devices
             .iter()
-            .find(|d| d.id == device_id || d.name == device_id)
+            .find(|d| d.name == device_id)
+            .or_else(|| devices.iter().find(|d| d.id == device_id))
         {
             *self.default_input.lock().unwrap() = Some(device.clone());
         }
@@ -230,17 +424,115 @@ impl AudioSystemInterface for MockAudioSystem {
         Ok(())
     }
 
+    fn set_default_system_alert_device(&self, device_id: &str) -> Result<()> {
+        if *self.should_fail_set_device.lock().unwrap() {
+            return Err(anyhow::anyhow!("Mock set device failure"));
+        }
+
+        self.set_device_calls.lock().unwrap().push((
+            device_id.to_string(),
+            "set_default_system_alert".to_string(),
+        ));
+
+        Ok(())
+    }
+
     fn add_device_change_listener(&self, callback: Box<dyn Fn() + Send + Sync>) -> Result<()> {
         self.device_change_callbacks.lock().unwrap().push(callback);
         Ok(())
     }
 
+    fn get_device_name_by_id(&self, device_id: &str) -> Result<Option<String>> {
+        let devices = self.devices.lock().unwrap();
+        Ok(devices
+            .iter()
+            .find(|d| d.id == device_id)
+            .map(|d| d.name.clone()))
+    }
+
     fn is_device_available(&self, device_id: &str) -> Result<bool> {
         let devices = self.devices.lock().unwrap();
         Ok(devices
             .iter()
             .any(|d| d.id == device_id || d.name == device_id))
     }
+
+    fn get_buffer_frame_size_range(&self, device_id: &str) -> Result<(u32, u32)> {
+        Ok(self
+            .buffer_size_ranges
+            .lock()
+            .unwrap()
+            .get(device_id)
+            .copied()
+            .unwrap_or(MOCK_DEFAULT_BUFFER_SIZE_RANGE))
+    }
+
+    fn set_buffer_frame_size(&self, device_id: &str, frames: u32) -> Result<()> {
+        if *self.should_fail_set_device.lock().unwrap() {
+            return Err(anyhow::anyhow!("Mock set buffer size failure"));
+        }
+
+        self.buffer_size_calls
+            .lock()
+            .unwrap()
+            .push((device_id.to_string(), frames));
+        Ok(())
+    }
+
+    fn get_device_latency_ms(&self, device: &AudioDevice) -> Result<f64> {
+        self.device_latencies_ms
+            .lock()
+            .unwrap()
+            .get(&device.id)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Mock latency unavailable for {}", device.name))
+    }
+
+    fn get_system_volume(&self) -> Result<f32> {
+        Ok(*self.system_volume.lock().unwrap())
+    }
+
+    fn set_system_volume(&self, volume: f32) -> Result<()> {
+        if *self.should_fail_set_device.lock().unwrap() {
+            return Err(anyhow::anyhow!("Mock set volume failure"));
+        }
+
+        let volume = volume.clamp(0.0, 1.0);
+        *self.system_volume.lock().unwrap() = volume;
+        self.set_volume_calls.lock().unwrap().push(volume);
+        Ok(())
+    }
+
+    fn get_device_volume(&self, device_id: &str) -> Result<f32> {
+        Ok(self
+            .device_volumes
+            .lock()
+            .unwrap()
+            .get(device_id)
+            .copied()
+            .unwrap_or(1.0))
+    }
+
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<()> {
+        if *self.should_fail_set_device.lock().unwrap() {
+            return Err(anyhow::anyhow!("Mock set volume failure"));
+        }
+
+        self.device_volumes
+            .lock()
+            .unwrap()
+            .insert(device_id.to_string(), volume.clamp(0.0, 1.0));
+        Ok(())
+    }
+
+    fn now(&self) -> SystemTime {
+        MockAudioSystem::now(self)
+    }
+
+    fn pending_notifications(&self) -> usize {
+        self.pending_notifications
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
 }
 
 impl Default for MockAudioSystem {
@@ -249,6 +541,9 @@ impl Default for MockAudioSystem {
     }
 }
 
+type WatchCallback = Box<dyn Fn() + Send>;
+type SleepHook = Box<dyn Fn() + Send>;
+
 /// Mock file system for testing - provides controllable file operations
 #[derive(Clone)]
 pub struct MockFileSystem {
@@ -257,9 +552,16 @@ pub struct MockFileSystem {
     pub read_calls: Arc<Mutex<Vec<PathBuf>>>,
     pub write_calls: Arc<Mutex<Vec<(PathBuf, String)>>>,
     pub directory_creation_calls: Arc<Mutex<Vec<PathBuf>>>,
+    pub modified_time_calls: Arc<Mutex<Vec<PathBuf>>>,
+    pub file_watchers: Arc<Mutex<Vec<(PathBuf, WatchCallback)>>>,
     pub should_fail_read: Arc<Mutex<bool>>,
     pub should_fail_write: Arc<Mutex<bool>>,
     pub should_fail_create_dir: Arc<Mutex<bool>>,
+    pub should_fail_watch: Arc<Mutex<bool>>,
+    /// Exit codes for hook commands; commands without an entry exit with 0
+    pub hook_results: Arc<Mutex<HashMap<String, i32>>>,
+    pub hook_calls: Arc<Mutex<Vec<String>>>,
+    pub should_fail_hook: Arc<Mutex<bool>>,
 }
 
 impl MockFileSystem {
@@ -270,9 +572,15 @@ impl MockFileSystem {
             read_calls: Arc::new(Mutex::new(Vec::new())),
             write_calls: Arc::new(Mutex::new(Vec::new())),
             directory_creation_calls: Arc::new(Mutex::new(Vec::new())),
+            modified_time_calls: Arc::new(Mutex::new(Vec::new())),
+            file_watchers: Arc::new(Mutex::new(Vec::new())),
             should_fail_read: Arc::new(Mutex::new(false)),
             should_fail_write: Arc::new(Mutex::new(false)),
             should_fail_create_dir: Arc::new(Mutex::new(false)),
+            should_fail_watch: Arc::new(Mutex::new(false)),
+            hook_results: Arc::new(Mutex::new(HashMap::new())),
+            hook_calls: Arc::new(Mutex::new(Vec::new())),
+            should_fail_hook: Arc::new(Mutex::new(false)),
         }
     }
 
@@ -288,6 +596,20 @@ impl MockFileSystem {
             .insert(path_buf, std::time::SystemTime::now());
     }
 
+    /// Override the modification time reported for a file
+    // Called by test code to simulate config edits for hot-reload detection
+    #[allow(dead_code)]
+    pub fn set_config_modified_time<P: AsRef<Path>>(
+        &self,
+        path: P,
+        modified: std::time::SystemTime,
+    ) {
+        self.modification_times
+            .lock()
+            .unwrap()
+            .insert(path.as_ref().to_path_buf(), modified);
+    }
+
     /// Set file content (alias for add_file for API consistency)
     // Called by test code to set mock file content
     #[allow(dead_code)]
@@ -326,6 +648,45 @@ impl MockFileSystem {
         self.directory_creation_calls.lock().unwrap().clone()
     }
 
+    /// Get the paths of every modification time lookup
+    // Called by test code to verify that watched configs are not polled
+    #[allow(dead_code)]
+    pub fn get_modified_time_calls(&self) -> Vec<PathBuf> {
+        self.modified_time_calls.lock().unwrap().clone()
+    }
+
+    /// Get the paths of all registered file watches
+    // Called by test code to verify that config watching was set up
+    #[allow(dead_code)]
+    pub fn get_watch_calls(&self) -> Vec<PathBuf> {
+        self.file_watchers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Invoke the callbacks of every watch registered for a path
+    // Called by test code to simulate a file change event
+    #[allow(dead_code)]
+    pub fn trigger_file_change<P: AsRef<Path>>(&self, path: P) {
+        for (watched_path, callback) in self.file_watchers.lock().unwrap().iter() {
+            if watched_path == path.as_ref() {
+                callback();
+            }
+        }
+    }
+
+    /// Simulate a write to every watched file, as FSEvents would report it
+    // Called by test code to trigger a config reload without touching the filesystem
+    #[allow(dead_code)]
+    pub fn trigger_config_change(&self) {
+        for (_, callback) in self.file_watchers.lock().unwrap().iter() {
+            callback();
+        }
+    }
+
     /// Clear all call histories
     // Called by test code to reset operation history between test cases
     #[allow(dead_code)]
@@ -333,6 +694,7 @@ impl MockFileSystem {
         self.read_calls.lock().unwrap().clear();
         self.write_calls.lock().unwrap().clear();
         self.directory_creation_calls.lock().unwrap().clear();
+        self.modified_time_calls.lock().unwrap().clear();
     }
 
     /// Configure the mock to fail read operations
@@ -356,6 +718,37 @@ impl MockFileSystem {
         *self.should_fail_create_dir.lock().unwrap() = should_fail;
     }
 
+    /// Configure the mock to fail file watch registration
+    // Called by test code to exercise the polling fallback
+    #[allow(dead_code)]
+    pub fn set_watch_failure(&self, should_fail: bool) {
+        *self.should_fail_watch.lock().unwrap() = should_fail;
+    }
+
+    /// Make the hook `command` exit with `exit_code`
+    // Called by test code to simulate failing switch hooks
+    #[allow(dead_code)]
+    pub fn set_hook_result(&self, command: &str, exit_code: i32) {
+        self.hook_results
+            .lock()
+            .unwrap()
+            .insert(command.to_string(), exit_code);
+    }
+
+    /// Configure every hook to fail as if it had timed out
+    // Called by test code to simulate hung switch hooks
+    #[allow(dead_code)]
+    pub fn set_hook_failure(&self, should_fail: bool) {
+        *self.should_fail_hook.lock().unwrap() = should_fail;
+    }
+
+    /// Get all hook commands that were run, in order
+    // Called by test code to verify switch hooks ran
+    #[allow(dead_code)]
+    pub fn get_hook_calls(&self) -> Vec<String> {
+        self.hook_calls.lock().unwrap().clone()
+    }
+
     /// Check if a file exists in the mock system
     // Called by test code to verify file existence in mock file system
     #[allow(dead_code)]
@@ -423,6 +816,11 @@ impl FileSystemInterface for MockFileSystem {
     }
 
     fn get_config_modified_time(&self, path: &Path) -> Result<std::time::SystemTime> {
+        self.modified_time_calls
+            .lock()
+            .unwrap()
+            .push(path.to_path_buf());
+
         if !self.config_file_exists(path) {
             return Err(anyhow::anyhow!("File not found: {}", path.display()));
         }
@@ -434,6 +832,44 @@ impl FileSystemInterface for MockFileSystem {
             .copied()
             .unwrap_or(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000)))
     }
+
+    fn watch_file(
+        &self,
+        path: &Path,
+        callback: Box<dyn Fn() + Send>,
+    ) -> Result<Box<dyn Any + Send>> {
+        if *self.should_fail_watch.lock().unwrap() {
+            return Err(anyhow::anyhow!("Mock watch failure"));
+        }
+
+        self.file_watchers
+            .lock()
+            .unwrap()
+            .push((path.to_path_buf(), callback));
+        Ok(Box::new(()))
+    }
+
+    fn execute_hook(&self, command: &str, timeout: Duration) -> Result<ExitStatus> {
+        self.hook_calls.lock().unwrap().push(command.to_string());
+
+        if *self.should_fail_hook.lock().unwrap() {
+            return Err(anyhow::anyhow!(
+                "Hook '{}' did not finish within {}ms",
+                command,
+                timeout.as_millis()
+            ));
+        }
+
+        let exit_code = self
+            .hook_results
+            .lock()
+            .unwrap()
+            .get(command)
+            .copied()
+            .unwrap_or(0);
+        // A wait status carries the exit code in its second byte
+        Ok(ExitStatus::from_raw(exit_code << 8))
+    }
 }
 
 impl Default for MockFileSystem {
@@ -451,6 +887,12 @@ pub struct MockSystemService {
     pub sleep_calls: Arc<Mutex<Vec<u64>>>,
     pub should_fail_signal_registration: Arc<std::sync::atomic::AtomicBool>,
     pub should_fail_event_loop: Arc<std::sync::atomic::AtomicBool>,
+    pub stop_after_sleep_calls: Arc<Mutex<Option<usize>>>,
+    pub on_battery_power: Arc<std::sync::atomic::AtomicBool>,
+    pub event_loop_stopped: Arc<std::sync::atomic::AtomicBool>,
+    pub sleep_hook: Arc<Mutex<Option<SleepHook>>>,
+    pub config_reload_requested: Arc<std::sync::atomic::AtomicBool>,
+    pub woke_from_sleep: Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl MockSystemService {
@@ -462,6 +904,12 @@ impl MockSystemService {
             sleep_calls: Arc::new(Mutex::new(Vec::new())),
             should_fail_signal_registration: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             should_fail_event_loop: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            stop_after_sleep_calls: Arc::new(Mutex::new(None)),
+            on_battery_power: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            event_loop_stopped: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            sleep_hook: Arc::new(Mutex::new(None)),
+            config_reload_requested: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            woke_from_sleep: Arc::new(std::sync::atomic::AtomicBool::new(false)),
         }
     }
 
@@ -527,6 +975,52 @@ impl MockSystemService {
             .store(should_fail, std::sync::atomic::Ordering::Relaxed);
     }
 
+    /// Simulate running on battery (true) or AC power (false)
+    // Called by test code to exercise power-source dependent device selection
+    #[allow(dead_code)]
+    pub fn set_on_battery_power(&self, on_battery_power: bool) {
+        self.on_battery_power
+            .store(on_battery_power, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Stop the service automatically once the given number of sleeps has been recorded
+    // Called by test code to run the service main loop for a bounded number of iterations
+    #[allow(dead_code)]
+    pub fn stop_after_sleep_calls(&self, count: usize) {
+        *self.stop_after_sleep_calls.lock().unwrap() = Some(count);
+    }
+
+    /// Run `hook` on every sleep, standing in for work that finishes while the caller waits
+    // Called by test code to complete background work during service waits
+    #[allow(dead_code)]
+    pub fn on_sleep(&self, hook: impl Fn() + Send + 'static) {
+        *self.sleep_hook.lock().unwrap() = Some(Box::new(hook));
+    }
+
+    /// Request a config reload as SIGHUP would; cleared once the service reads it
+    // Called by test code to exercise the signal-driven config reload path
+    #[allow(dead_code)]
+    pub fn simulate_sighup(&self) {
+        self.config_reload_requested
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Report a wake from sleep as the power notification would; cleared once read
+    // Called by test code to exercise sleep/wake recovery
+    #[allow(dead_code)]
+    pub fn simulate_wake(&self) {
+        self.woke_from_sleep
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Check if the event loop was asked to stop
+    // Called by test code to verify service shutdown
+    #[allow(dead_code)]
+    pub fn was_event_loop_stopped(&self) -> bool {
+        self.event_loop_stopped
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
     /// Reset all counters and state
     // Called by test code to reset mock state between test cases
     #[allow(dead_code)]
@@ -542,6 +1036,14 @@ impl MockSystemService {
             .store(false, std::sync::atomic::Ordering::Relaxed);
         self.should_fail_event_loop
             .store(false, std::sync::atomic::Ordering::Relaxed);
+        *self.stop_after_sleep_calls.lock().unwrap() = None;
+        self.event_loop_stopped
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        *self.sleep_hook.lock().unwrap() = None;
+        self.config_reload_requested
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        self.woke_from_sleep
+            .store(false, std::sync::atomic::Ordering::Relaxed);
     }
 }
 
@@ -577,7 +1079,15 @@ impl SystemServiceInterface for MockSystemService {
     }
 
     fn sleep_ms(&self, milliseconds: u64) -> Result<()> {
-        self.sleep_calls.lock().unwrap().push(milliseconds);
+        let mut sleep_calls = self.sleep_calls.lock().unwrap();
+        sleep_calls.push(milliseconds);
+        let stop_after = *self.stop_after_sleep_calls.lock().unwrap();
+        if stop_after.is_some_and(|limit| sleep_calls.len() >= limit) {
+            self.stop_service();
+        }
+        if let Some(hook) = self.sleep_hook.lock().unwrap().as_ref() {
+            hook();
+        }
         // Don't actually sleep in tests
         Ok(())
     }
@@ -588,8 +1098,23 @@ impl SystemServiceInterface for MockSystemService {
     }
 
     fn is_config_reload_requested(&self) -> bool {
-        // For testing, just return false unless we need specific behavior
-        false
+        self.config_reload_requested
+            .swap(false, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn woke_from_sleep(&self) -> bool {
+        self.woke_from_sleep
+            .swap(false, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn is_on_battery_power(&self) -> bool {
+        self.on_battery_power
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn stop_event_loop(&self) {
+        self.event_loop_stopped
+            .store(true, std::sync::atomic::Ordering::Relaxed);
     }
 }
 
@@ -1,5 +1,6 @@
 pub mod adapters;
 pub mod integration;
+pub mod sleep_wake;
 pub mod traits;
 
 // Mock implementations for testing (available for both unit and integration tests)
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use tracing::{debug, warn};
+
+use crate::audio::AudioDevice;
+use crate::system::traits::AudioSystemInterface;
+
+/// Delay between enumerations while waiting for CoreAudio to settle after a wake
+pub const SLEEP_WAKE_RETRY_DELAY_MS: u64 = 500;
+
+/// Enumerations before giving up on a stable list; CoreAudio can report stale
+/// devices for up to about 2 seconds after waking
+pub const SLEEP_WAKE_MAX_ATTEMPTS: usize = 6;
+
+/// Enumerate devices until two consecutive calls return the same device IDs,
+/// calling `sleep_ms` with `SLEEP_WAKE_RETRY_DELAY_MS` between attempts.
+/// Gives up after `SLEEP_WAKE_MAX_ATTEMPTS` and returns the last list.
+pub fn wait_for_stable_devices<A: AudioSystemInterface + ?Sized>(
+    audio_system: &A,
+    mut sleep_ms: impl FnMut(u64) -> Result<()>,
+) -> Result<Vec<AudioDevice>> {
+    let mut previous = audio_system.enumerate_devices();
+
+    for attempt in 2..=SLEEP_WAKE_MAX_ATTEMPTS {
+        sleep_ms(SLEEP_WAKE_RETRY_DELAY_MS)?;
+        let current = audio_system.enumerate_devices();
+
+        let stable = match (&previous, &current) {
+            (Ok(previous), Ok(current)) => device_ids(previous) == device_ids(current),
+            _ => false,
+        };
+        if stable {
+            debug!("Device list stable after {} enumerations", attempt);
+            return current;
+        }
+
+        debug!("Device list still changing after {} enumerations", attempt);
+        previous = current;
+    }
+
+    warn!(
+        "Device list did not settle after {} enumerations, using the latest",
+        SLEEP_WAKE_MAX_ATTEMPTS
+    );
+    previous
+}
+
+fn device_ids(devices: &[AudioDevice]) -> Vec<&str> {
+    let mut ids: Vec<&str> = devices.iter().map(|device| device.id.as_str()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::DeviceType;
+    use crate::system::MockAudioSystem;
+
+    fn device(id: &str) -> AudioDevice {
+        AudioDevice::new(id.to_string(), format!("Device {id}"), DeviceType::Output)
+    }
+
+    #[test]
+    fn test_stable_list_returns_after_two_enumerations() {
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(device("1"));
+        let mut sleeps = Vec::new();
+
+        let devices = wait_for_stable_devices(&audio_system, |ms| {
+            sleeps.push(ms);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(devices.len(), 1);
+        assert_eq!(sleeps, vec![SLEEP_WAKE_RETRY_DELAY_MS]);
+    }
+
+    #[test]
+    fn test_gives_up_when_list_keeps_changing() {
+        let audio_system = MockAudioSystem::new();
+        let mut next_id = 0;
+        let mut sleeps = 0;
+
+        // Every wait brings another device
+        let devices = wait_for_stable_devices(&audio_system, |_| {
+            next_id += 1;
+            sleeps += 1;
+            audio_system.add_device(device(&next_id.to_string()));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(sleeps, SLEEP_WAKE_MAX_ATTEMPTS - 1);
+        assert_eq!(devices.len(), SLEEP_WAKE_MAX_ATTEMPTS - 1);
+    }
+
+    #[test]
+    fn test_enumeration_errors_are_retried() {
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(device("1"));
+        audio_system.set_enumeration_failure(true);
+
+        let devices = wait_for_stable_devices(&audio_system, |_| {
+            audio_system.set_enumeration_failure(false);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(devices.len(), 1);
+    }
+}
@@ -1,5 +1,10 @@
 use anyhow::Result;
+use std::any::Any;
 use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
 
 use crate::audio::AudioDevice;
 
@@ -20,14 +25,106 @@ pub trait AudioSystemInterface {
     /// Set the system default input device by device ID
     fn set_default_input_device(&self, device_id: &str) -> Result<()>;
 
+    /// Set the system default output device, trying again up to `retries` more
+    /// times with `delay` in between. CoreAudio can refuse a device that is
+    /// still initialising. Returns the last error once every attempt failed.
+    fn set_default_output_device_with_retry(
+        &self,
+        device_id: &str,
+        retries: u32,
+        delay: Duration,
+    ) -> Result<()> {
+        retry_with_delay(retries, delay, || self.set_default_output_device(device_id))
+    }
+
+    /// Input counterpart of `set_default_output_device_with_retry`
+    fn set_default_input_device_with_retry(
+        &self,
+        device_id: &str,
+        retries: u32,
+        delay: Duration,
+    ) -> Result<()> {
+        retry_with_delay(retries, delay, || self.set_default_input_device(device_id))
+    }
+
+    /// Set the device macOS plays system alert sounds on by device ID
+    fn set_default_system_alert_device(&self, device_id: &str) -> Result<()>;
+
     /// Register a callback for device change notifications
     /// The callback will be invoked when devices are added, removed, or default devices change
     fn add_device_change_listener(&self, callback: Box<dyn Fn() + Send + Sync>) -> Result<()>;
 
+    /// Look up a device's name by device ID without enumerating every device
+    /// Returns None when no device has this ID
+    // Called by library consumers and tests; CoreAudio switching resolves IDs in DeviceController
+    #[allow(dead_code)]
+    fn get_device_name_by_id(&self, device_id: &str) -> Result<Option<String>>;
+
     /// Check if a specific device is currently available
     // Called by device controller and CLI commands to verify device availability
     #[allow(dead_code)]
     fn is_device_available(&self, device_id: &str) -> Result<bool>;
+
+    /// Get the supported I/O buffer size range (min, max) in frames for a device
+    fn get_buffer_frame_size_range(&self, device_id: &str) -> Result<(u32, u32)>;
+
+    /// Set the I/O buffer size in frames for a device
+    fn set_buffer_frame_size(&self, device_id: &str, frames: u32) -> Result<()>;
+
+    /// Get a device's reported latency in milliseconds at its current sample rate
+    fn get_device_latency_ms(&self, device: &AudioDevice) -> Result<f64>;
+
+    /// Get the output volume of the default output device, from 0.0 to 1.0
+    // Called by tests and external control systems to read the current volume
+    #[allow(dead_code)]
+    fn get_system_volume(&self) -> Result<f32>;
+
+    /// Set the output volume of the default output device, from 0.0 to 1.0
+    fn set_system_volume(&self, volume: f32) -> Result<()>;
+
+    /// Get the output volume of a device by device ID, from 0.0 to 1.0
+    fn get_device_volume(&self, device_id: &str) -> Result<f32>;
+
+    /// Set the output volume of a device by device ID, from 0.0 to 1.0
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<()>;
+
+    /// The current time, against which device connection times are measured
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// Device change notifications the audio system sent itself that are still
+    /// being delivered
+    fn pending_notifications(&self) -> usize {
+        0
+    }
+}
+
+/// Run `operation` once, then up to `retries` more times while it fails,
+/// sleeping `delay` before each retry
+fn retry_with_delay(
+    retries: u32,
+    delay: Duration,
+    mut operation: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match operation() {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                warn!(
+                    "Attempt {} of {} failed: {}, retrying in {}ms",
+                    attempt,
+                    retries + 1,
+                    e,
+                    delay.as_millis()
+                );
+                std::thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
 }
 
 /// Trait for file system operations - abstracts std::fs for testability
@@ -46,6 +143,48 @@ pub trait FileSystemInterface {
 
     /// Get the last modified time of a config file (for watching changes)
     fn get_config_modified_time(&self, path: &Path) -> Result<std::time::SystemTime>;
+
+    /// Invoke `callback` whenever the file at `path` changes
+    /// The watch stays active until the returned handle is dropped
+    fn watch_file(
+        &self,
+        path: &Path,
+        callback: Box<dyn Fn() + Send>,
+    ) -> Result<Box<dyn Any + Send>>;
+
+    /// Run a rule's hook command through the shell, killing it if it outlasts `timeout`
+    fn execute_hook(&self, command: &str, timeout: Duration) -> Result<ExitStatus>;
+
+    /// Watch a config file, reporting writes on a channel instead of a callback
+    fn watch_config_path(&self, path: &Path) -> Result<FileWatch> {
+        let (sender, receiver) = mpsc::channel();
+        let handle = self.watch_file(
+            path,
+            Box::new(move || {
+                // The receiver is gone only once the watch is being dropped
+                let _ = sender.send(());
+            }),
+        )?;
+
+        Ok(FileWatch {
+            receiver,
+            _handle: handle,
+        })
+    }
+}
+
+/// Change events for a watched file; the watch ends when this is dropped
+pub struct FileWatch {
+    receiver: mpsc::Receiver<()>,
+    _handle: Box<dyn Any + Send>,
+}
+
+impl FileWatch {
+    /// Whether the file was written since the last call, without blocking.
+    /// Several writes in between are reported once.
+    pub fn changed(&self) -> bool {
+        self.receiver.try_iter().count() > 0
+    }
 }
 
 /// Trait for system service operations - abstracts daemon, signals, and event loops
@@ -71,6 +210,21 @@ pub trait SystemServiceInterface {
     fn get_process_id(&self) -> u32;
 
     /// Check if configuration reload was requested (e.g., via SIGHUP)
-    /// Returns true once when reload is requested, false otherwise
-    fn is_config_reload_requested(&self) -> bool;
+    /// Returns true once when reload is requested, false otherwise.
+    /// Services without a reload signal never request one.
+    fn is_config_reload_requested(&self) -> bool {
+        false
+    }
+
+    /// Check if the system woke from sleep since the last call
+    /// Returns true once per wake. Services without power notifications never report one.
+    fn woke_from_sleep(&self) -> bool {
+        false
+    }
+
+    /// Check if the system currently runs on battery rather than AC power
+    fn is_on_battery_power(&self) -> bool;
+
+    /// Stop the event loop of the calling thread so the process can exit
+    fn stop_event_loop(&self);
 }
@@ -376,3 +376,207 @@ enabled = true
         assert!(read_calls.iter().all(|path| path == &config_path));
     }
 }
+
+mod rule_editing_tests {
+    use audio_device_monitor::config::{ConflictKind, DeviceRule, MatchType};
+    use audio_device_monitor::{ConfigLoader, MockFileSystem};
+    use std::path::PathBuf;
+
+    const CONFIG: &str = r#"
+# Headphones first
+[[output_devices]]
+name = "AirPods"
+weight = 100 # beats everything else
+match_type = "contains"
+enabled = true
+
+[[output_devices]]
+name = "Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "Speakers"
+weight = 10
+match_type = "exact"
+enabled = true
+"#;
+
+    fn loader_with_config() -> (ConfigLoader<MockFileSystem>, MockFileSystem) {
+        let file_system = MockFileSystem::new();
+        let config_path = PathBuf::from("/test/config.toml");
+        file_system.add_file(&config_path, CONFIG.to_string());
+        (
+            ConfigLoader::new(file_system.clone(), config_path),
+            file_system,
+        )
+    }
+
+    #[test]
+    fn test_add_rule_appends_and_saves() {
+        let (loader, file_system) = loader_with_config();
+        let rule = DeviceRule::new("Studio Mic", 80, MatchType::StartsWith);
+
+        let edit = loader.add_rule(rule, true, false).unwrap();
+
+        let write_calls = file_system.get_write_calls();
+        assert_eq!(write_calls.len(), 1);
+        assert!(write_calls[0].1.contains(
+            "[[input_devices]]\nname = \"Studio Mic\"\nweight = 80\nmatch_type = \"starts_with\"\nenabled = true\n"
+        ));
+        assert!(write_calls[0].1.contains("name = \"AirPods\""));
+        assert_eq!(
+            edit.to_toml().unwrap(),
+            "[[input_devices]]\nname = \"Studio Mic\"\nweight = 80\nmatch_type = \"starts_with\"\nenabled = true\nexclusive = false\nsticky = false\ncase_sensitive = true\n"
+        );
+
+        let config = loader.load_config().unwrap();
+        assert_eq!(config.output_devices.len(), 3);
+        assert_eq!(config.input_devices.len(), 1);
+        assert_eq!(config.input_devices[0].name, "Studio Mic");
+    }
+
+    #[test]
+    fn test_remove_rule_removes_first_exact_match() {
+        let (loader, file_system) = loader_with_config();
+
+        let edit = loader.remove_rule("Speakers", false, false).unwrap();
+
+        assert_eq!(edit.rule.weight, 50);
+        let write_calls = file_system.get_write_calls();
+        assert_eq!(write_calls.len(), 1);
+        assert!(write_calls[0].1.contains("weight = 10\n"));
+        assert!(!write_calls[0].1.contains("weight = 50\n"));
+
+        let names: Vec<String> = loader
+            .load_config()
+            .unwrap()
+            .output_devices
+            .into_iter()
+            .map(|rule| rule.name)
+            .collect();
+        assert_eq!(names, ["AirPods", "Speakers"]);
+    }
+
+    #[test]
+    fn test_rule_edits_keep_comments() {
+        let (loader, file_system) = loader_with_config();
+
+        loader
+            .add_rule(
+                DeviceRule::new("USB", 70, MatchType::Contains),
+                false,
+                false,
+            )
+            .unwrap();
+        loader.remove_rule("Speakers", false, false).unwrap();
+
+        let write_calls = file_system.get_write_calls();
+        assert_eq!(write_calls.len(), 2);
+        for (_, content) in &write_calls {
+            assert!(content.contains("# Headphones first\n[[output_devices]]"));
+            assert!(content.contains("weight = 100 # beats everything else\n"));
+        }
+    }
+
+    #[test]
+    fn test_remove_rule_requires_exact_name_and_direction() {
+        let (loader, file_system) = loader_with_config();
+
+        let error = loader.remove_rule("Air", false, false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No output rule named 'Air' in /test/config.toml"
+        );
+        assert!(loader.remove_rule("AirPods", true, false).is_err());
+        assert!(file_system.get_write_calls().is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_does_not_write() {
+        let (loader, file_system) = loader_with_config();
+
+        let edit = loader
+            .add_rule(DeviceRule::new("USB", 70, MatchType::Contains), false, true)
+            .unwrap();
+        assert_eq!(edit.rule.name, "USB");
+        let edit = loader.remove_rule("AirPods", false, true).unwrap();
+        assert_eq!(edit.rule.name, "AirPods");
+
+        assert!(file_system.get_write_calls().is_empty());
+        assert_eq!(loader.load_config().unwrap().output_devices.len(), 3);
+    }
+
+    #[test]
+    fn test_dry_run_without_config_file_does_not_create_it() {
+        let file_system = MockFileSystem::new();
+        let loader = ConfigLoader::new(file_system.clone(), PathBuf::from("/test/config.toml"));
+
+        loader
+            .add_rule(DeviceRule::new("USB", 70, MatchType::Contains), false, true)
+            .unwrap();
+
+        assert!(file_system.get_write_calls().is_empty());
+        assert!(file_system.get_directory_creation_calls().is_empty());
+    }
+
+    #[test]
+    fn test_only_new_conflicts_are_reported() {
+        let (loader, _) = loader_with_config();
+        // "AirPods" and the rule below are 100-weight rules matching "AirPods Pro",
+        // and "AirPods Pro" is subsumed by "AirPods"
+        let rule = DeviceRule::new("AirPods Pro", 100, MatchType::Contains);
+
+        let edit = loader.add_rule(rule, false, false).unwrap();
+
+        let kinds: Vec<ConflictKind> = edit
+            .new_conflicts
+            .iter()
+            .map(|conflict| conflict.kind)
+            .collect();
+        assert_eq!(kinds, [ConflictKind::SameWeight, ConflictKind::SubsumedBy]);
+
+        // Removing a rule leaves the existing conflicts unreported
+        let edit = loader.remove_rule("Speakers", false, false).unwrap();
+        assert!(edit.new_conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rule_is_not_saved() {
+        let (loader, file_system) = loader_with_config();
+
+        let error = loader
+            .add_rule(
+                DeviceRule::new("(unclosed", 10, MatchType::Regex),
+                false,
+                false,
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Rule '(unclosed' would make the configuration invalid"
+        );
+        assert!(file_system.get_write_calls().is_empty());
+    }
+
+    #[test]
+    fn test_match_type_parses_config_names() {
+        for match_type in MatchType::ALL {
+            let parsed: MatchType = match_type.config_name().parse().unwrap();
+            assert_eq!(parsed.config_name(), match_type.config_name());
+        }
+
+        // Older spellings parse as aliases
+        let parsed: MatchType = "not_startswith".parse().unwrap();
+        assert_eq!(parsed.config_name(), "not_starts_with");
+
+        let error = "starts-with".parse::<MatchType>().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Unknown match type 'starts-with'")
+        );
+    }
+}
@@ -1,18 +1,13 @@
-use audio_device_monitor::config::{Config, GeneralConfig, MatchType, NotificationConfig};
-use std::path::PathBuf;
+use audio_device_monitor::TransportType;
+use audio_device_monitor::config::{
+    Config, GeneralConfig, MatchType, NotificationConfig, write_file_atomically,
+};
+use std::str::FromStr;
 use tempfile::TempDir;
 
 mod test_utils;
 use test_utils::builders::DeviceRuleBuilder;
 
-/// Helper function to create a temporary config file with given content
-fn create_temp_config(content: &str) -> (TempDir, PathBuf) {
-    let temp_dir = TempDir::new().expect("Failed to create temp directory");
-    let config_path = temp_dir.path().join("config.toml");
-    std::fs::write(&config_path, content).expect("Failed to write temp config");
-    (temp_dir, config_path)
-}
-
 /// Test basic configuration loading and parsing
 #[cfg(test)]
 mod config_loading {
@@ -43,8 +38,7 @@ match_type = "contains"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         // Test general config
         assert_eq!(config.general.check_interval_ms, 2000);
@@ -83,8 +77,7 @@ show_device_availability = false
 show_switching_actions = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         // Should use default values
         assert_eq!(config.general.check_interval_ms, 1000);
@@ -125,8 +118,7 @@ show_switching_actions = true
 invalid toml syntax
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(invalid_content);
-        let result = Config::load(Some(config_path.to_str().unwrap()));
+        let result = Config::from_str(invalid_content);
 
         assert!(result.is_err());
     }
@@ -150,8 +142,7 @@ show_device_changes = true
 show_switching_actions = false
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(old_config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(old_config_content).unwrap();
 
         // Old show_device_changes should migrate to show_device_availability
         assert!(config.notifications.show_device_availability);
@@ -175,8 +166,7 @@ show_switching_actions = true
 show_device_changes = true  # This should be ignored in favor of the new field
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(new_config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(new_config_content).unwrap();
 
         // New field should take precedence
         assert!(!config.notifications.show_device_availability);
@@ -196,8 +186,7 @@ show_switching_actions = true
 show_device_changes = true  # Only old field present
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(mixed_config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(mixed_config_content).unwrap();
 
         // Should migrate old field when new field is not present
         assert!(config.notifications.show_device_availability);
@@ -237,13 +226,13 @@ enabled = true
 [[output_devices]]
 name = "StartsWith"
 weight = 100
-match_type = "startswith"
+match_type = "starts_with"
 enabled = true
 
 [[output_devices]]
 name = "EndsWith"
 weight = 100
-match_type = "endswith"
+match_type = "ends_with"
 enabled = true
 
 [[output_devices]]
@@ -253,8 +242,7 @@ match_type = "regex"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         assert_eq!(config.output_devices.len(), 5);
 
@@ -280,6 +268,97 @@ enabled = true
         ));
     }
 
+    #[test]
+    fn test_match_type_aliases_without_underscore() {
+        let config_content = r#"
+[[output_devices]]
+name = "USB"
+weight = 100
+match_type = "startswith"
+enabled = true
+
+[[output_devices]]
+name = "Speakers"
+weight = 100
+match_type = "not_endswith"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert!(matches!(
+            config.output_devices[0].match_type,
+            MatchType::StartsWith
+        ));
+        assert!(matches!(
+            config.output_devices[1].match_type,
+            MatchType::NotEndsWith
+        ));
+        // Saved under the canonical name
+        assert!(
+            toml::to_string(&config)
+                .unwrap()
+                .contains("match_type = \"starts_with\"")
+        );
+    }
+
+    #[test]
+    fn test_inverted_match_types() {
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[notifications]
+show_device_availability = false
+show_switching_actions = true
+
+[[output_devices]]
+name = "Built-in"
+weight = 100
+match_type = "not_contains"
+enabled = true
+
+[[output_devices]]
+name = "Speakers"
+weight = 100
+match_type = "not_ends_with"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert!(matches!(
+            config.output_devices[0].match_type,
+            MatchType::NotContains
+        ));
+        assert!(matches!(
+            config.output_devices[1].match_type,
+            MatchType::NotEndsWith
+        ));
+    }
+
+    #[test]
+    fn test_config_name_matches_serialized_name() {
+        for match_type in [
+            MatchType::Exact,
+            MatchType::Contains,
+            MatchType::StartsWith,
+            MatchType::EndsWith,
+            MatchType::Regex,
+            MatchType::NotExact,
+            MatchType::NotContains,
+            MatchType::NotStartsWith,
+            MatchType::NotEndsWith,
+        ] {
+            assert_eq!(
+                serde_json::to_value(&match_type).unwrap(),
+                match_type.config_name()
+            );
+        }
+    }
+
     #[test]
     fn test_invalid_match_type() {
         let config_content = r#"
@@ -299,12 +378,219 @@ match_type = "invalid_type"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let result = Config::load(Some(config_path.to_str().unwrap()));
+        let result = Config::from_str(config_content);
 
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_regex_pattern_is_compiled_at_load() {
+        let config_content = r#"
+[[output_devices]]
+name = "AirPods.*(Pro|Max)"
+weight = 100
+match_type = "regex"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+        let rule = &config.output_devices[0];
+
+        assert!(rule.compiled_regex.is_some());
+        assert!(rule.matches("AirPods Max"));
+        assert!(!rule.matches("AirPods"));
+    }
+
+    #[test]
+    fn test_transport_type_filter_is_parsed() {
+        let config_content = r#"
+[[output_devices]]
+name = "Jabra"
+weight = 100
+match_type = "contains"
+enabled = true
+transport_type = "usb"
+
+[[output_devices]]
+name = "Speakers"
+weight = 50
+match_type = "contains"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert_eq!(
+            config.output_devices[0].transport_type,
+            Some(TransportType::Usb)
+        );
+        assert_eq!(config.output_devices[1].transport_type, None);
+    }
+
+    #[test]
+    fn test_uid_pattern_is_parsed() {
+        let config_content = r#"
+[[output_devices]]
+name = "Desk DAC"
+uid = "AppleUSBAudioEngine:Schiit"
+weight = 100
+match_type = "startswith"
+enabled = true
+
+[[output_devices]]
+name = "Speakers"
+weight = 50
+match_type = "contains"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert_eq!(
+            config.output_devices[0].uid.as_deref(),
+            Some("AppleUSBAudioEngine:Schiit")
+        );
+        assert_eq!(config.output_devices[1].uid, None);
+    }
+
+    #[test]
+    fn test_stability_ms_is_parsed() {
+        let config_content = r#"
+[[output_devices]]
+name = "USB Hub Audio"
+weight = 100
+match_type = "exact"
+enabled = true
+stability_ms = 100
+
+[[output_devices]]
+name = "Speakers"
+weight = 50
+match_type = "contains"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert_eq!(config.output_devices[0].stability_ms, Some(100));
+        assert_eq!(config.output_devices[1].stability_ms, None);
+    }
+
+    #[test]
+    fn test_switch_hooks_are_parsed() {
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+hook_timeout_ms = 2500
+
+[[output_devices]]
+name = "Studio Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+pre_switch_command = "eq-preset studio"
+post_switch_command = "osascript -e 'tell application \"Logic Pro\" to activate'"
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert_eq!(config.general.hook_timeout_ms, 2500);
+        assert_eq!(
+            config.output_devices[0].pre_switch_command.as_deref(),
+            Some("eq-preset studio")
+        );
+        assert_eq!(
+            config.output_devices[0].post_switch_command.as_deref(),
+            Some("osascript -e 'tell application \"Logic Pro\" to activate'")
+        );
+        assert_eq!(Config::default().general.hook_timeout_ms, 5000);
+    }
+
+    #[test]
+    fn test_switch_retry_settings() {
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+switch_retry_count = 5
+switch_retry_delay_ms = 100
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+        assert_eq!(config.general.switch_retry_count, 5);
+        assert_eq!(config.general.switch_retry_delay_ms, 100);
+
+        let defaults = Config::default().general;
+        assert_eq!(defaults.switch_retry_count, 3);
+        assert_eq!(defaults.switch_retry_delay_ms, 250);
+    }
+
+    #[test]
+    fn test_invalid_regex_pattern_names_the_rule() {
+        let config_content = r#"
+[[output_devices]]
+name = "Speakers"
+weight = 50
+match_type = "contains"
+enabled = true
+
+[[input_devices]]
+name = "AirPods (Pro"
+weight = 100
+match_type = "regex"
+enabled = true
+"#;
+
+        let error = Config::from_str(config_content).unwrap_err();
+
+        assert!(
+            format!("{error:#}").contains("Invalid regex pattern 'AirPods (Pro' for rule"),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[test]
+    fn test_rule_with_uid_and_require_uid_is_rejected() {
+        let config_content = r#"
+[[output_devices]]
+name = "Schiit DAC"
+uid = "AppleUSBAudioEngine:Schiit"
+require_uid = "AppleUSBAudioEngine:Other"
+weight = 100
+match_type = "exact"
+enabled = true
+"#;
+
+        let error = Config::from_str(config_content).unwrap_err();
+
+        assert!(
+            format!("{error:#}").contains("sets both uid and require_uid"),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[test]
+    fn test_rule_volume_out_of_range_is_rejected() {
+        let config_content = r#"
+[[output_devices]]
+name = "Schiit DAC"
+weight = 100
+match_type = "exact"
+enabled = true
+on_select_volume = 70.0
+"#;
+
+        let error = Config::from_str(config_content).unwrap_err();
+
+        assert!(
+            format!("{error:#}").contains("sets on_select_volume to 70"),
+            "unexpected error: {error:#}"
+        );
+    }
+
     #[test]
     fn test_case_insensitive_match_types() {
         let config_content = r#"
@@ -330,8 +616,7 @@ match_type = "Contains"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let result = Config::load(Some(config_path.to_str().unwrap()));
+        let result = Config::from_str(config_content);
 
         // Case sensitivity depends on serde configuration
         // This test verifies current behavior
@@ -377,8 +662,7 @@ match_type = "exact"
 enabled = false
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         assert_eq!(config.output_devices.len(), 2);
         assert!(config.output_devices[0].enabled);
@@ -404,8 +688,7 @@ match_type = "exact"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         assert_eq!(config.output_devices[0].weight, 0);
     }
@@ -432,10 +715,34 @@ enabled = true
             u32::MAX
         );
 
-        let (_temp_dir, config_path) = create_temp_config(&config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(&config_content).unwrap();
+
+        // Weights written when the field was unsigned must still load
+        assert_eq!(config.output_devices[0].weight, i64::from(u32::MAX));
+    }
+
+    #[test]
+    fn test_negative_weight_device() {
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
 
-        assert_eq!(config.output_devices[0].weight, u32::MAX);
+[notifications]
+show_device_availability = false
+show_switching_actions = true
+
+[[output_devices]]
+name = "Never Use"
+weight = -1
+match_type = "exact"
+enabled = true
+"#;
+
+        let config = Config::from_str(config_content).unwrap();
+
+        assert_eq!(config.output_devices[0].weight, -1);
     }
 
     #[test]
@@ -457,8 +764,7 @@ match_type = "exact"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         assert_eq!(config.output_devices[0].name, "");
     }
@@ -482,8 +788,7 @@ match_type = "contains"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(config_content).unwrap();
 
         assert_eq!(config.output_devices[0].name, "🎵 Music Device 🎵");
     }
@@ -528,6 +833,49 @@ mod config_saving {
         assert_eq!(reloaded_config.output_devices[0].weight, 150);
     }
 
+    #[test]
+    fn test_description_and_comment_round_trip_independently() {
+        let mut config = Config::default();
+        config.output_devices = vec![
+            DeviceRuleBuilder::new()
+                .name("MDR-7506")
+                .description("Home Studio Headphones - Sony MDR-7506")
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Scarlett")
+                .comment("Needs 256 frames for tracking")
+                .build(),
+            DeviceRuleBuilder::new().name("Speakers").build(),
+        ];
+
+        let serialized = toml::to_string_pretty(&config).unwrap();
+        let reloaded = Config::from_str(&serialized).unwrap();
+
+        let rules = &reloaded.output_devices;
+        assert_eq!(
+            rules[0].description.as_deref(),
+            Some("Home Studio Headphones - Sony MDR-7506")
+        );
+        assert_eq!(rules[0].comment, None);
+        assert_eq!(rules[1].description, None);
+        assert_eq!(
+            rules[1].comment.as_deref(),
+            Some("Needs 256 frames for tracking")
+        );
+        assert_eq!(rules[2].description, None);
+        assert_eq!(rules[2].comment, None);
+
+        // Unset fields are omitted from the file
+        assert_eq!(serialized.matches("description").count(), 1);
+        assert_eq!(serialized.matches("comment").count(), 1);
+
+        assert_eq!(
+            rules[0].label(),
+            "MDR-7506 - Home Studio Headphones - Sony MDR-7506"
+        );
+        assert_eq!(rules[1].label(), "Scarlett");
+    }
+
     #[test]
     fn test_save_creates_directory() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
@@ -543,6 +891,40 @@ mod config_saving {
         assert!(nested_path.exists());
         assert!(nested_path.parent().unwrap().exists());
     }
+
+    #[test]
+    fn test_save_leaves_no_temp_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join("config.toml");
+
+        Config::default()
+            .save(Some(config_path.to_str().unwrap()))
+            .unwrap();
+
+        assert!(config_path.exists());
+        assert!(!temp_dir.path().join("config.toml.tmp").exists());
+    }
+
+    #[test]
+    fn test_failed_write_keeps_original_config() {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.general.check_interval_ms = 3000;
+        config.save(Some(config_path.to_str().unwrap())).unwrap();
+
+        let result = write_file_atomically(&config_path, |file| {
+            file.write_all(b"[general]\ncheck_interval_")?;
+            Err(std::io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        let reloaded = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(reloaded.general.check_interval_ms, 3000);
+        assert!(!temp_dir.path().join("config.toml.tmp").exists());
+    }
 }
 
 /// Test default configuration values
@@ -591,6 +973,487 @@ mod default_values {
     }
 }
 
+/// Test loading rules with a `weight_formula`
+#[cfg(test)]
+mod weight_formulas {
+    use super::*;
+
+    fn config_with_formula(formula: &str) -> String {
+        format!(
+            r#"
+[[output_devices]]
+name = "Studio Display Speakers"
+weight = 10
+weight_formula = "{formula}"
+match_type = "exact"
+enabled = true
+"#
+        )
+    }
+
+    #[test]
+    fn test_formula_is_loaded() {
+        let config = Config::from_str(&config_with_formula("base + 50")).unwrap();
+
+        assert_eq!(
+            config.output_devices[0].weight_formula.as_deref(),
+            Some("base + 50")
+        );
+    }
+
+    #[cfg(feature = "weight-formulas")]
+    #[test]
+    fn test_formula_is_parsed_once_at_load() {
+        let config = Config::from_str(&config_with_formula("base + 50")).unwrap();
+        let rule = &config.output_devices[0];
+
+        assert!(rule.compiled_formula.is_some());
+        assert_eq!(rule.effective_weight(40), 90);
+    }
+
+    #[cfg(feature = "weight-formulas")]
+    #[test]
+    fn test_unknown_variable_rejected_at_load() {
+        let error = Config::from_str(&config_with_formula("speakers + 50")).unwrap_err();
+
+        let message = format!("{error:#}");
+        assert!(message.contains("Studio Display Speakers"));
+        assert!(message.contains("Unknown variable 'speakers'"));
+    }
+
+    #[cfg(not(feature = "weight-formulas"))]
+    #[test]
+    fn test_formula_ignored_without_feature() {
+        let config = Config::from_str(&config_with_formula("speakers + 50")).unwrap();
+
+        assert_eq!(config.output_devices[0].effective_weight(40), 10);
+    }
+}
+
+/// Test the `case_sensitive` rule option and its `check-config` lint
+#[cfg(test)]
+mod case_sensitivity {
+    use super::*;
+
+    #[test]
+    fn test_case_sensitive_defaults_to_true() {
+        let config = Config::from_str(
+            r#"
+[[output_devices]]
+name = "AirPods Pro"
+weight = 100
+match_type = "exact"
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        assert!(config.output_devices[0].case_sensitive);
+        assert!(!config.output_devices[0].matches("AirPods pro"));
+    }
+
+    #[test]
+    fn test_case_insensitive_rule_is_loaded() {
+        let config = Config::from_str(
+            r#"
+[[output_devices]]
+name = "AirPods Pro"
+weight = 100
+match_type = "exact"
+enabled = true
+case_sensitive = false
+"#,
+        )
+        .unwrap();
+
+        assert!(!config.output_devices[0].case_sensitive);
+        assert!(config.output_devices[0].matches("AirPods pro"));
+    }
+
+    #[test]
+    fn test_case_insensitive_regex_is_linted() {
+        let mut config = Config::default();
+        config.output_devices = vec![
+            DeviceRuleBuilder::new()
+                .name("airpods.*pro")
+                .regex_match()
+                .case_insensitive()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Speakers")
+                .contains_match()
+                .case_insensitive()
+                .build(),
+        ];
+
+        let warnings = config.lint_warnings();
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'airpods.*pro'"));
+        assert!(warnings[0].contains("(?i)"));
+    }
+
+    #[test]
+    fn test_default_config_has_no_lint_warnings() {
+        assert!(Config::default().lint_warnings().is_empty());
+    }
+}
+
+/// Test the one-line config summary shown by `status` and `check-config`
+#[cfg(test)]
+mod summary {
+    use super::*;
+
+    #[test]
+    fn test_summary_counts_rules_and_formats_intervals() {
+        let mut config = Config::default();
+        config.output_devices = vec![
+            DeviceRuleBuilder::new().name("AirPods").build(),
+            DeviceRuleBuilder::new().name("Speakers").build(),
+        ];
+        config.input_devices = vec![DeviceRuleBuilder::new().name("Shure MV7").build()];
+        config.general.poll_interval_ms = 30_000;
+        config.general.check_interval_ms = 500;
+        config.notifications.show_switching_actions = true;
+
+        assert_eq!(
+            config.summarize(),
+            "2 output rules, 1 input rules, poll=30s, check=500ms, notifications=ON"
+        );
+    }
+
+    #[test]
+    fn test_summary_includes_shared_device_rules() {
+        let mut config = Config::default();
+        config.output_devices.clear();
+        config.input_devices.clear();
+        config.devices = vec![DeviceRuleBuilder::new().name("AirPods").build()];
+
+        assert!(
+            config
+                .summarize()
+                .starts_with("1 output rules, 1 input rules,")
+        );
+    }
+
+    #[test]
+    fn test_summary_reflects_switching_notifications() {
+        let mut config = Config::default();
+
+        config.notifications.show_switching_actions = true;
+        assert!(config.summarize().ends_with("notifications=ON"));
+
+        config.notifications.show_switching_actions = false;
+        assert!(config.summarize().ends_with("notifications=OFF"));
+    }
+}
+
+/// Test the unified `devices` rule list and its `applies_to` scoping
+#[cfg(test)]
+mod unified_device_rules {
+    use super::*;
+    use audio_device_monitor::config::DeviceTypeFilter;
+
+    const UNIFIED_CONFIG: &str = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "MacBook Pro Speakers"
+weight = 10
+match_type = "exact"
+enabled = true
+
+[[devices]]
+name = "AirPods"
+weight = 100
+match_type = "contains"
+enabled = true
+applies_to = "both"
+
+[[devices]]
+name = "Studio Display"
+weight = 50
+match_type = "contains"
+enabled = true
+applies_to = "output_only"
+
+[[devices]]
+name = "Shure MV7"
+weight = 80
+match_type = "exact"
+enabled = true
+applies_to = "input_only"
+"#;
+
+    #[test]
+    fn test_applies_to_parses_all_variants() {
+        let config = Config::from_str(UNIFIED_CONFIG).unwrap();
+
+        let scopes: Vec<DeviceTypeFilter> =
+            config.devices.iter().map(|rule| rule.applies_to).collect();
+        assert_eq!(
+            scopes,
+            vec![
+                DeviceTypeFilter::Both,
+                DeviceTypeFilter::OutputOnly,
+                DeviceTypeFilter::InputOnly,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_applies_to_defaults_to_both() {
+        let config = Config::from_str(
+            r#"
+[[devices]]
+name = "AirPods"
+weight = 100
+match_type = "contains"
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.devices[0].applies_to, DeviceTypeFilter::Both);
+    }
+
+    #[test]
+    fn test_rules_split_by_direction() {
+        let config = Config::from_str(UNIFIED_CONFIG).unwrap();
+
+        let output_names: Vec<String> = config
+            .output_rules()
+            .into_iter()
+            .map(|rule| rule.name)
+            .collect();
+        let input_names: Vec<String> = config
+            .input_rules()
+            .into_iter()
+            .map(|rule| rule.name)
+            .collect();
+
+        assert_eq!(
+            output_names,
+            vec!["MacBook Pro Speakers", "AirPods", "Studio Display"]
+        );
+        assert_eq!(input_names, vec!["AirPods", "Shure MV7"]);
+    }
+
+    #[test]
+    fn test_legacy_only_config_omits_devices_when_saved() {
+        let content = toml::to_string_pretty(&Config::default()).unwrap();
+
+        assert!(!content.contains("[[devices]]"));
+        assert!(!content.contains("applies_to"));
+    }
+}
+
+/// Test detection of rules that compete for the same devices
+#[cfg(test)]
+mod rule_conflicts {
+    use super::*;
+    use audio_device_monitor::config::{ConflictKind, DeviceTypeFilter};
+
+    fn config_with_outputs(rules: Vec<audio_device_monitor::config::DeviceRule>) -> Config {
+        let mut config = Config::default();
+        config.output_devices = rules;
+        config
+    }
+
+    #[test]
+    fn test_default_config_has_no_conflicts() {
+        assert!(Config::default().validate_rules().is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_rules_with_same_weight_conflict() {
+        let config = config_with_outputs(vec![
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(80)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB")
+                .weight(80)
+                .starts_with_match()
+                .build(),
+        ]);
+
+        let conflicts = config.validate_rules();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::SameWeight);
+        assert_eq!(conflicts[0].example, "USB Headset");
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Rules 'Headset' and 'USB' both match 'USB Headset' with weight 80"
+        );
+    }
+
+    #[test]
+    fn test_same_weight_without_overlap_is_fine() {
+        let config = config_with_outputs(vec![
+            DeviceRuleBuilder::new().name("AirPods").weight(80).build(),
+            DeviceRuleBuilder::new().name("Speakers").weight(80).build(),
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(70)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB")
+                .weight(60)
+                .starts_with_match()
+                .build(),
+        ]);
+
+        assert!(config.validate_rules().is_empty());
+    }
+
+    #[test]
+    fn test_contains_rule_subsumed_by_shorter_text() {
+        let config = config_with_outputs(vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("airpods")
+                .weight(90)
+                .contains_match()
+                .case_insensitive()
+                .build(),
+        ]);
+
+        let conflicts = config.validate_rules();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::SubsumedBy);
+        assert_eq!(conflicts[0].rule.name, "AirPods Pro");
+        assert_eq!(conflicts[0].other.name, "airpods");
+        assert_eq!(conflicts[0].example, "AirPods Pro");
+    }
+
+    #[test]
+    fn test_case_sensitive_text_does_not_subsume_case_insensitive_rule() {
+        let config = config_with_outputs(vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(90)
+                .contains_match()
+                .case_insensitive()
+                .build(),
+        ]);
+
+        assert!(config.validate_rules().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_and_other_direction_rules_are_ignored() {
+        let mut config = config_with_outputs(vec![
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(80)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Gaming Headset")
+                .weight(80)
+                .contains_match()
+                .disabled()
+                .build(),
+        ]);
+        config.input_devices = vec![
+            DeviceRuleBuilder::new()
+                .name("Gaming Headset")
+                .weight(80)
+                .contains_match()
+                .build(),
+        ];
+        config.devices = vec![
+            DeviceRuleBuilder::new()
+                .name("Headset Mic")
+                .weight(80)
+                .contains_match()
+                .applies_to(DeviceTypeFilter::InputOnly)
+                .build(),
+        ];
+
+        let conflicts = config.validate_rules();
+
+        // Only the two input rules overlap
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule.name, "Gaming Headset");
+        assert_eq!(conflicts[0].other.name, "Headset Mic");
+        assert_eq!(conflicts[0].example, "Gaming Headset Headset Mic");
+    }
+
+    #[test]
+    fn test_different_transports_do_not_conflict() {
+        let config = config_with_outputs(vec![
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(80)
+                .transport_type(TransportType::Usb)
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(80)
+                .transport_type(TransportType::Bluetooth)
+                .build(),
+        ]);
+
+        assert!(config.validate_rules().is_empty());
+    }
+}
+
+/// Test default config path resolution
+#[cfg(test)]
+mod default_config_path {
+    use super::*;
+    use audio_device_monitor::ConfigLoader;
+    use audio_device_monitor::StandardFileSystem;
+
+    // A single test mutates XDG_CONFIG_HOME so parallel tests never observe a half-set environment
+    #[test]
+    fn test_xdg_config_home_is_respected() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // SAFETY: no other test in this binary reads or writes XDG_CONFIG_HOME
+        unsafe { std::env::set_var("XDG_CONFIG_HOME", temp_dir.path()) };
+        let xdg_path = ConfigLoader::<StandardFileSystem>::default_config_path().unwrap();
+
+        // Relative values must be ignored per the XDG spec
+        unsafe { std::env::set_var("XDG_CONFIG_HOME", "relative/config") };
+        let relative_path = ConfigLoader::<StandardFileSystem>::default_config_path().unwrap();
+
+        unsafe { std::env::remove_var("XDG_CONFIG_HOME") };
+        let fallback_path = ConfigLoader::<StandardFileSystem>::default_config_path().unwrap();
+
+        assert_eq!(
+            xdg_path,
+            temp_dir.path().join("audio-device-monitor/config.toml")
+        );
+        assert_eq!(relative_path, fallback_path);
+        assert_eq!(
+            fallback_path,
+            dirs::config_dir()
+                .unwrap()
+                .join("audio-device-monitor/config.toml")
+        );
+    }
+}
+
 /// Test error conditions and edge cases
 #[cfg(test)]
 mod error_conditions {
@@ -634,15 +1497,14 @@ show_switching_actions = true
 
 [[output_devices]]
 name = "Device"
-weight = -1
+weight = "high"
 match_type = "exact"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let result = Config::load(Some(config_path.to_str().unwrap()));
+        let result = Config::from_str(config_content);
 
-        // Should fail to parse negative weight (u32 can't be negative)
+        // Should fail to parse a non-numeric weight
         assert!(result.is_err());
     }
 
@@ -665,8 +1527,7 @@ match_type = "exact"
 enabled = true
 "#;
 
-        let (_temp_dir, config_path) = create_temp_config(config_content);
-        let result = Config::load(Some(config_path.to_str().unwrap()));
+        let result = Config::from_str(config_content);
 
         // Should fail due to missing required field
         assert!(result.is_err());
@@ -723,8 +1584,7 @@ enabled = true
             ));
         }
 
-        let (_temp_dir, config_path) = create_temp_config(&config_content);
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(&config_content).unwrap();
 
         assert_eq!(config.output_devices.len(), 50);
         assert_eq!(config.input_devices.len(), 50);
@@ -767,11 +1627,9 @@ enabled = true
             ));
         }
 
-        let (_temp_dir, config_path) = create_temp_config(&config_content);
-
-        // Should load quickly even with many rules
+        // Should parse quickly even with many rules
         let start = std::time::Instant::now();
-        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let config = Config::from_str(&config_content).unwrap();
         let duration = start.elapsed();
 
         assert_eq!(config.output_devices.len(), 100);
@@ -782,3 +1640,145 @@ enabled = true
         );
     }
 }
+
+mod profiles {
+    use super::*;
+
+    const CONFIG_WITH_PROFILES: &str = r#"
+[[output_devices]]
+name = "Studio Display"
+weight = 100
+match_type = "contains"
+enabled = true
+
+[[output_devices]]
+name = "AirPods"
+weight = 80
+match_type = "contains"
+enabled = true
+
+[[input_devices]]
+name = "Studio Mic"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[devices]]
+name = "Jabra"
+weight = 60
+match_type = "contains"
+enabled = true
+
+[[profiles.office.output_devices]]
+name = "Dell Monitor"
+weight = 90
+match_type = "startswith"
+enabled = true
+
+[profiles.travel]
+input_devices = []
+"#;
+
+    fn rule_names(rules: &[audio_device_monitor::config::DeviceRule]) -> Vec<&str> {
+        rules.iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_profile_replaces_the_sections_it_sets() {
+        let config = Config::from_str(CONFIG_WITH_PROFILES).unwrap();
+
+        let office = config.clone().with_profile("office").unwrap();
+        assert_eq!(rule_names(&office.output_devices), ["Dell Monitor"]);
+        assert_eq!(rule_names(&office.input_devices), ["Studio Mic"]);
+        assert_eq!(rule_names(&office.devices), ["Jabra"]);
+
+        // An empty section still replaces the base rules
+        let travel = config.with_profile("travel").unwrap();
+        assert_eq!(
+            rule_names(&travel.output_devices),
+            ["Studio Display", "AirPods"]
+        );
+        assert!(travel.input_devices.is_empty());
+    }
+
+    #[test]
+    fn test_profiles_are_kept_after_applying_one() {
+        let config = Config::from_str(CONFIG_WITH_PROFILES)
+            .unwrap()
+            .with_profile("office")
+            .unwrap()
+            .with_profile("travel")
+            .unwrap();
+
+        // Applying another profile starts from the merged rules
+        assert_eq!(rule_names(&config.output_devices), ["Dell Monitor"]);
+        assert!(config.input_devices.is_empty());
+        assert_eq!(config.profiles.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_profile_is_rejected() {
+        let error = Config::from_str(CONFIG_WITH_PROFILES)
+            .unwrap()
+            .with_profile("home")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile 'home', expected one of: office, travel"
+        );
+
+        let error = Config::default().with_profile("home").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile 'home': no profiles are configured"
+        );
+    }
+
+    #[test]
+    fn test_profile_rules_are_checked_at_load() {
+        let config = format!(
+            "{CONFIG_WITH_PROFILES}\n[[profiles.home.output_devices]]\nname = \"(unclosed\"\nweight = 10\nmatch_type = \"regex\"\nenabled = true\n"
+        );
+
+        let error = Config::from_str(&config).unwrap_err();
+        assert!(format!("{error:#}").contains("Invalid regex pattern '(unclosed'"));
+    }
+
+    #[test]
+    fn test_load_with_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, CONFIG_WITH_PROFILES).unwrap();
+        let config_path = config_path.to_str();
+
+        let config = Config::load_with_profile(config_path, Some("office")).unwrap();
+        assert_eq!(rule_names(&config.output_devices), ["Dell Monitor"]);
+
+        let config = Config::load(config_path).unwrap();
+        assert_eq!(
+            rule_names(&config.output_devices),
+            ["Studio Display", "AirPods"]
+        );
+        assert!(Config::load_with_profile(config_path, Some("home")).is_err());
+    }
+
+    #[test]
+    fn test_profiles_survive_saving() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let config = Config::from_str(CONFIG_WITH_PROFILES).unwrap();
+        config.save(config_path.to_str()).unwrap();
+        let reloaded = Config::load(config_path.to_str()).unwrap();
+
+        assert_eq!(
+            reloaded.profiles.keys().collect::<Vec<_>>(),
+            ["office", "travel"]
+        );
+        assert!(reloaded.profiles["travel"].output_devices.is_none());
+
+        // No empty [profiles] table in a configuration without profiles
+        let content = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(!content.contains("profiles"));
+    }
+}
@@ -1,6 +1,11 @@
+use audio_device_monitor::preference_debugging::SwitchHistory;
 use audio_device_monitor::{
-    AudioDevice, AudioSystemInterface, Config, DeviceControllerV2, DeviceType, MockAudioSystem,
+    AudioDevice, AudioSystemInterface, Config, DeviceControllerV2, DeviceType, DryRunResult,
+    MockAudioSystem, MockFileSystem, MockSystemService, SwitchReason,
 };
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Integration tests for DeviceControllerV2 with dependency injection
 /// These tests verify device enumeration, switching, and priority management
@@ -56,7 +61,7 @@ weight = 40
 match_type = "exact"
 enabled = true
 "#;
-        toml::from_str(config_content).expect("Invalid test configuration")
+        Config::from_str(config_content).expect("Invalid test configuration")
     }
 
     fn setup_test_devices(audio_system: &MockAudioSystem) {
@@ -150,6 +155,28 @@ enabled = true
         assert!(audio_system.get_enumerate_calls() > 0);
     }
 
+    #[test]
+    fn test_device_info_carries_known_details() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+        let device_controller = DeviceControllerV2::new(audio_system, &config);
+
+        let device = AudioDevice::new(
+            "42".to_string(),
+            "Studio Interface".to_string(),
+            DeviceType::Output,
+        )
+        .with_channels(8)
+        .with_sample_rates(vec![44100.0, 96000.0]);
+
+        let info = device_controller.get_device_info(&device).unwrap();
+
+        assert_eq!(info.channels, Some(8));
+        assert_eq!(info.available_sample_rates, vec![44100.0, 96000.0]);
+        // The audio system abstraction does not report the current format
+        assert_eq!(info.current_format(), None);
+    }
+
     #[test]
     fn test_device_switching() {
         let audio_system = MockAudioSystem::new();
@@ -193,6 +220,515 @@ enabled = true
         assert_eq!(current_input.unwrap().name, "Studio Microphone");
     }
 
+    #[test]
+    fn test_snapshot_captures_state_transition() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.initialize().unwrap();
+
+        let before = device_controller.snapshot().unwrap();
+        let premium_headphones = before
+            .available_devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap()
+            .clone();
+
+        device_controller
+            .handle_device_connected(&premium_headphones)
+            .unwrap();
+        let after = device_controller.snapshot().unwrap();
+
+        assert_ne!(before.current_output, after.current_output);
+        assert_eq!(
+            after.current_output.as_ref().map(|d| d.name.as_str()),
+            Some("Premium Headphones")
+        );
+        assert_eq!(
+            after.default_output.as_ref().map(|d| d.name.as_str()),
+            Some("Premium Headphones")
+        );
+        assert_eq!(before.available_devices, after.available_devices);
+    }
+
+    #[test]
+    fn test_rule_buffer_size_applied_after_switch() {
+        let audio_system = MockAudioSystem::new();
+        let mut config = create_test_config();
+        config.output_devices[0].buffer_size_frames = Some(256); // Premium Headphones
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.initialize().unwrap();
+
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        device_controller
+            .handle_device_connected(premium_headphones)
+            .unwrap();
+
+        assert_eq!(
+            audio_system.get_buffer_size_calls(),
+            vec![("premium-1".to_string(), 256)]
+        );
+    }
+
+    #[test]
+    fn test_rule_volume_applied_after_switch() {
+        let audio_system = MockAudioSystem::new();
+        let mut config = create_test_config();
+        config.output_devices[0].on_select_volume = Some(0.7); // Premium Headphones
+
+        setup_test_devices(&audio_system);
+        let system_service = MockSystemService::new();
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config)
+            .with_system_service(Arc::new(system_service.clone()));
+        device_controller.initialize().unwrap();
+
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        device_controller
+            .handle_device_connected(premium_headphones)
+            .unwrap();
+
+        // The switch settles through the injected service, not a real sleep
+        assert_eq!(system_service.get_sleep_calls(), vec![200]);
+        assert_eq!(audio_system.get_set_volume_calls(), vec![0.7]);
+        assert_eq!(audio_system.get_system_volume().unwrap(), 0.7);
+    }
+
+    fn output_device(devices: &[AudioDevice], name: &str) -> AudioDevice {
+        devices
+            .iter()
+            .find(|d| d.name == name && d.device_type == DeviceType::Output)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_restore_volume_brings_back_volume_of_previous_device() {
+        let audio_system = MockAudioSystem::new();
+        let mut config = create_test_config();
+        config.general.restore_volume = true;
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let headphones = output_device(&devices, "Premium Headphones");
+        let speakers = output_device(&devices, "Built-in Speakers");
+
+        device_controller
+            .switch_to_output_device(&headphones)
+            .unwrap();
+        audio_system.set_mock_device_volume("premium-1", 0.3);
+
+        device_controller
+            .switch_to_output_device(&speakers)
+            .unwrap();
+        // macOS resets the headphones' volume while they are not in use
+        audio_system.set_mock_device_volume("premium-1", 1.0);
+        audio_system.set_mock_device_volume("builtin-out-1", 0.8);
+
+        device_controller
+            .switch_to_output_device(&headphones)
+            .unwrap();
+        assert_eq!(audio_system.get_device_volume("premium-1").unwrap(), 0.3);
+
+        audio_system.set_mock_device_volume("builtin-out-1", 0.1);
+        device_controller
+            .switch_to_output_device(&speakers)
+            .unwrap();
+        assert_eq!(
+            audio_system.get_device_volume("builtin-out-1").unwrap(),
+            0.8
+        );
+    }
+
+    #[test]
+    fn test_volume_not_restored_when_disabled() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let headphones = output_device(&devices, "Premium Headphones");
+        let speakers = output_device(&devices, "Built-in Speakers");
+
+        device_controller
+            .switch_to_output_device(&headphones)
+            .unwrap();
+        audio_system.set_mock_device_volume("premium-1", 0.3);
+        device_controller
+            .switch_to_output_device(&speakers)
+            .unwrap();
+        audio_system.set_mock_device_volume("premium-1", 1.0);
+        device_controller
+            .switch_to_output_device(&headphones)
+            .unwrap();
+
+        assert_eq!(audio_system.get_device_volume("premium-1").unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_volume_untouched_without_rule_volume() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.initialize().unwrap();
+
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        device_controller
+            .handle_device_connected(premium_headphones)
+            .unwrap();
+
+        assert!(audio_system.get_set_volume_calls().is_empty());
+    }
+
+    fn hook_controller(
+        audio_system: &MockAudioSystem,
+        file_system: &MockFileSystem,
+    ) -> DeviceControllerV2<MockAudioSystem> {
+        let mut config = create_test_config();
+        config.output_devices[0].pre_switch_command = Some("eq-preset studio".to_string()); // Premium Headphones
+        config.input_devices[0].post_switch_command = Some("daw-notify mic".to_string()); // Studio Microphone
+
+        setup_test_devices(audio_system);
+        DeviceControllerV2::new(audio_system.clone(), &config)
+            .with_hook_runner(Arc::new(file_system.clone()))
+    }
+
+    #[test]
+    fn test_pre_switch_hook_runs_before_switch() {
+        let audio_system = MockAudioSystem::new();
+        let file_system = MockFileSystem::new();
+        let mut device_controller = hook_controller(&audio_system, &file_system);
+        let devices = device_controller.enumerate_devices().unwrap();
+
+        device_controller
+            .switch_to_output_device(&output_device(&devices, "Premium Headphones"))
+            .unwrap();
+
+        assert_eq!(file_system.get_hook_calls(), vec!["eq-preset studio"]);
+        assert_eq!(
+            audio_system.get_set_default_output_calls(),
+            vec!["Premium Headphones"]
+        );
+    }
+
+    #[test]
+    fn test_failed_pre_switch_hook_aborts_switch() {
+        let audio_system = MockAudioSystem::new();
+        let file_system = MockFileSystem::new();
+        file_system.set_hook_result("eq-preset studio", 1);
+        let mut device_controller = hook_controller(&audio_system, &file_system);
+        let devices = device_controller.enumerate_devices().unwrap();
+
+        let error = device_controller
+            .switch_to_output_device(&output_device(&devices, "Premium Headphones"))
+            .unwrap_err();
+
+        assert!(error.to_string().contains("eq-preset studio"));
+        assert!(audio_system.get_set_default_output_calls().is_empty());
+        assert!(device_controller.get_current_output_device().is_none());
+    }
+
+    #[test]
+    fn test_timed_out_pre_switch_hook_aborts_switch() {
+        let audio_system = MockAudioSystem::new();
+        let file_system = MockFileSystem::new();
+        file_system.set_hook_failure(true);
+        let mut device_controller = hook_controller(&audio_system, &file_system);
+        let devices = device_controller.enumerate_devices().unwrap();
+
+        assert!(
+            device_controller
+                .switch_to_output_device(&output_device(&devices, "Premium Headphones"))
+                .is_err()
+        );
+        assert!(audio_system.get_set_default_output_calls().is_empty());
+    }
+
+    #[test]
+    fn test_post_switch_hook_runs_after_switch() {
+        let audio_system = MockAudioSystem::new();
+        let file_system = MockFileSystem::new();
+        let mut device_controller = hook_controller(&audio_system, &file_system);
+        let microphone = device_controller
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Studio Microphone")
+            .unwrap();
+
+        device_controller
+            .switch_to_input_device(&microphone)
+            .unwrap();
+
+        // The post-switch hook runs on its own thread
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while file_system.get_hook_calls().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(file_system.get_hook_calls(), vec!["daw-notify mic"]);
+    }
+
+    #[test]
+    fn test_devices_without_hooks_run_no_commands() {
+        let audio_system = MockAudioSystem::new();
+        let file_system = MockFileSystem::new();
+        let mut device_controller = hook_controller(&audio_system, &file_system);
+        let devices = device_controller.enumerate_devices().unwrap();
+
+        device_controller
+            .switch_to_output_device(&output_device(&devices, "Built-in Speakers"))
+            .unwrap();
+
+        assert!(file_system.get_hook_calls().is_empty());
+    }
+
+    #[test]
+    fn test_successful_switches_are_recorded_in_history() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+        setup_test_devices(&audio_system);
+        let dir = tempfile::tempdir().unwrap();
+        let history = SwitchHistory::new(
+            dir.path().join("history.jsonl"),
+            config.general.history_max_lines,
+        );
+
+        let mut device_controller =
+            DeviceControllerV2::new(audio_system.clone(), &config).with_history(history.clone());
+        let devices = device_controller.enumerate_devices().unwrap();
+        device_controller
+            .switch_to_output_device(&output_device(&devices, "Gaming Headset Pro"))
+            .unwrap();
+
+        // A failed switch leaves no record
+        audio_system.set_device_setting_failure(true);
+        assert!(
+            device_controller
+                .switch_to_output_device(&output_device(&devices, "Built-in Speakers"))
+                .is_err()
+        );
+
+        let events = history.recent(10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].device_name, "Gaming Headset Pro");
+        assert_eq!(events[0].device_type, DeviceType::Output);
+        assert_eq!(events[0].reason, SwitchReason::Manual);
+        assert_eq!(events[0].matched_rule.as_deref(), Some("Gaming Headset"));
+    }
+
+    fn retry_controller(
+        audio_system: &MockAudioSystem,
+        retries: u32,
+    ) -> DeviceControllerV2<MockAudioSystem> {
+        let mut config = create_test_config();
+        config.general.switch_retry_count = retries;
+        config.general.switch_retry_delay_ms = 1;
+
+        setup_test_devices(audio_system);
+        DeviceControllerV2::new(audio_system.clone(), &config)
+    }
+
+    #[test]
+    fn test_refused_switch_is_retried_until_it_succeeds() {
+        let audio_system = MockAudioSystem::new();
+        let mut device_controller = retry_controller(&audio_system, 3);
+        let devices = device_controller.enumerate_devices().unwrap();
+        audio_system.fail_next_set_device_calls(2);
+
+        device_controller
+            .switch_to_output_device(&output_device(&devices, "Premium Headphones"))
+            .unwrap();
+
+        assert_eq!(audio_system.get_set_device_calls().len(), 3);
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Premium Headphones"
+        );
+    }
+
+    #[test]
+    fn test_switch_fails_once_retries_are_exhausted() {
+        let audio_system = MockAudioSystem::new();
+        let mut device_controller = retry_controller(&audio_system, 2);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let microphone = devices
+            .iter()
+            .find(|d| d.name == "Studio Microphone")
+            .unwrap();
+        audio_system.fail_next_set_device_calls(10);
+
+        let error = device_controller
+            .switch_to_input_device(microphone)
+            .unwrap_err();
+
+        // One attempt plus two retries
+        assert_eq!(audio_system.get_set_device_calls().len(), 3);
+        assert!(
+            error
+                .to_string()
+                .contains("Mock transient set device failure")
+        );
+        assert!(device_controller.get_current_input_device().is_none());
+    }
+
+    #[test]
+    fn test_zero_retries_tries_once() {
+        let audio_system = MockAudioSystem::new();
+        let mut device_controller = retry_controller(&audio_system, 0);
+        let devices = device_controller.enumerate_devices().unwrap();
+        audio_system.fail_next_set_device_calls(1);
+
+        assert!(
+            device_controller
+                .switch_to_output_device(&output_device(&devices, "Premium Headphones"))
+                .is_err()
+        );
+        assert_eq!(audio_system.get_set_device_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_system_alerts_follow_output_switch_when_enabled() {
+        let audio_system = MockAudioSystem::new();
+        let mut config = create_test_config();
+        config.general.sync_system_alerts = true;
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+        let studio_mic = devices
+            .iter()
+            .find(|d| d.name == "Studio Microphone")
+            .unwrap();
+
+        device_controller
+            .switch_to_output_device(premium_headphones)
+            .unwrap();
+        device_controller
+            .switch_to_input_device(studio_mic)
+            .unwrap();
+
+        // Only output switches move system alerts
+        assert_eq!(
+            audio_system.get_set_default_system_alert_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_system_alerts_untouched_by_default() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        device_controller
+            .switch_to_output_device(premium_headphones)
+            .unwrap();
+
+        assert!(audio_system.get_set_default_system_alert_calls().is_empty());
+    }
+
+    #[test]
+    fn test_rule_without_buffer_size_leaves_buffer_untouched() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let studio_mic = devices
+            .iter()
+            .find(|d| d.name == "Studio Microphone")
+            .unwrap();
+
+        device_controller
+            .switch_to_input_device(studio_mic)
+            .unwrap();
+
+        assert!(audio_system.get_buffer_size_calls().is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range_buffer_size_rejected_without_failing_switch() {
+        let audio_system = MockAudioSystem::new();
+        let mut config = create_test_config();
+        config.output_devices[0].buffer_size_frames = Some(2048);
+
+        setup_test_devices(&audio_system);
+        audio_system.set_buffer_frame_size_range("premium-1", 64, 1024);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        let devices = device_controller.enumerate_devices().unwrap();
+        let premium_headphones = devices
+            .iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        // The switch itself still succeeds
+        device_controller
+            .switch_to_output_device(premium_headphones)
+            .unwrap();
+        assert!(audio_system.get_buffer_size_calls().is_empty());
+
+        // Direct calls report the range violation
+        assert!(
+            device_controller
+                .set_device_buffer_size("premium-1", 2048)
+                .is_err()
+        );
+        assert!(
+            device_controller
+                .set_device_buffer_size("premium-1", 512)
+                .is_ok()
+        );
+        assert_eq!(
+            audio_system.get_buffer_size_calls(),
+            vec![("premium-1".to_string(), 512)]
+        );
+    }
+
     #[test]
     fn test_device_availability_check() {
         let audio_system = MockAudioSystem::new();
@@ -315,6 +851,246 @@ enabled = true
         assert_eq!(current_input.unwrap().name, "Studio Microphone");
     }
 
+    #[test]
+    fn test_update_switches_away_from_unmatched_system_default() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+        audio_system.add_device(AudioDevice::new(
+            "hdmi-1".to_string(),
+            "HDMI Display".to_string(),
+            DeviceType::Output,
+        ));
+        audio_system.set_default_output_device("hdmi-1").unwrap();
+        audio_system.clear_set_device_calls();
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+
+        // The system default matches no rule, so the rules pick the output device
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Premium Headphones"
+        );
+        assert_eq!(
+            audio_system.get_set_default_output_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_update_keeps_system_default_chosen_by_rules() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+        audio_system.set_default_output_device("premium-1").unwrap();
+        audio_system
+            .set_default_input_device("studio-mic-1")
+            .unwrap();
+        audio_system.clear_set_device_calls();
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+        device_controller.update_current_devices().unwrap();
+
+        assert!(audio_system.get_set_default_output_calls().is_empty());
+        assert!(audio_system.get_set_default_input_calls().is_empty());
+    }
+
+    #[test]
+    fn test_manual_switch_survives_next_update() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+        let speakers = device_controller
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers")
+            .unwrap();
+        device_controller
+            .switch_to_output_device_for(&speakers, SwitchReason::Manual)
+            .unwrap();
+        audio_system.clear_set_device_calls();
+
+        device_controller.update_current_devices().unwrap();
+
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Built-in Speakers"
+        );
+        assert!(audio_system.get_set_default_output_calls().is_empty());
+    }
+
+    #[test]
+    fn test_default_chosen_in_system_settings_survives_next_update() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+        audio_system.clear_set_device_calls();
+
+        // The user picks the speakers in System Settings
+        let speakers = audio_system
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers");
+        audio_system.set_mock_default_output(speakers);
+        device_controller.update_current_devices().unwrap();
+        device_controller.update_current_devices().unwrap();
+
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Built-in Speakers"
+        );
+        assert!(audio_system.get_set_default_output_calls().is_empty());
+    }
+
+    #[test]
+    fn test_manual_switch_yields_to_newly_connected_higher_priority_device() {
+        let audio_system = MockAudioSystem::new();
+        let config = create_test_config();
+
+        setup_test_devices(&audio_system);
+        audio_system.remove_device("premium-1");
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+        let speakers = device_controller
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers")
+            .unwrap();
+        device_controller
+            .switch_to_output_device_for(&speakers, SwitchReason::Manual)
+            .unwrap();
+
+        audio_system.connect_device(AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        ));
+        audio_system.advance_time(Duration::from_secs(1));
+        device_controller.update_current_devices().unwrap();
+
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Premium Headphones"
+        );
+    }
+
+    /// Controller that has picked the speakers, with only `config`'s rules
+    fn controller_on_speakers(
+        config: &Config,
+    ) -> (MockAudioSystem, DeviceControllerV2<MockAudioSystem>) {
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        ));
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), config);
+        device_controller.update_current_devices().unwrap();
+        (audio_system, device_controller)
+    }
+
+    fn connect_premium_headphones(audio_system: &MockAudioSystem) {
+        audio_system.connect_device(AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        ));
+    }
+
+    #[test]
+    fn test_update_waits_for_rule_stability_ms() {
+        let config = Config::from_str(
+            r#"
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+stability_ms = 3000
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+"#,
+        )
+        .unwrap();
+        let (audio_system, mut device_controller) = controller_on_speakers(&config);
+        connect_premium_headphones(&audio_system);
+
+        // Past device_change_debounce_ms but short of the rule's threshold
+        audio_system.advance_time(Duration::from_millis(2999));
+        device_controller.update_current_devices().unwrap();
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Built-in Speakers"
+        );
+
+        audio_system.advance_time(Duration::from_millis(1));
+        device_controller.update_current_devices().unwrap();
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Premium Headphones"
+        );
+    }
+
+    #[test]
+    fn test_update_uses_reloaded_debounce() {
+        let config_with_debounce = |debounce_ms: u64| {
+            Config::from_str(&format!(
+                r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+device_change_debounce_ms = {debounce_ms}
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+"#
+            ))
+            .unwrap()
+        };
+        let (audio_system, mut device_controller) =
+            controller_on_speakers(&config_with_debounce(750));
+
+        device_controller.update_config(&config_with_debounce(100));
+        connect_premium_headphones(&audio_system);
+        audio_system.advance_time(Duration::from_millis(100));
+        device_controller.update_current_devices().unwrap();
+
+        assert_eq!(
+            device_controller.get_current_output_device().unwrap().name,
+            "Premium Headphones"
+        );
+    }
+
     #[test]
     fn test_device_controller_with_disabled_devices() {
         let audio_system = MockAudioSystem::new();
@@ -371,6 +1147,61 @@ enabled = true
         assert!(device_controller.get_current_input_device().is_none());
     }
 
+    #[test]
+    fn test_get_device_name_by_id() {
+        let audio_system = MockAudioSystem::new();
+        setup_test_devices(&audio_system);
+
+        assert_eq!(
+            audio_system.get_device_name_by_id("studio-mic-1").unwrap(),
+            Some("Studio Microphone".to_string())
+        );
+        assert_eq!(audio_system.get_device_name_by_id("missing").unwrap(), None);
+        // Only IDs are looked up, not names
+        assert_eq!(
+            audio_system
+                .get_device_name_by_id("Studio Microphone")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_set_default_output_prefers_name_over_id() {
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(AudioDevice::new(
+            "2".to_string(),
+            "Speakers".to_string(),
+            DeviceType::Output,
+        ));
+        audio_system.add_device(AudioDevice::new(
+            "7".to_string(),
+            "2".to_string(),
+            DeviceType::Output,
+        ));
+
+        audio_system.set_default_output_device("2").unwrap();
+        assert_eq!(
+            audio_system
+                .get_default_output_device()
+                .unwrap()
+                .unwrap()
+                .id,
+            "7"
+        );
+
+        // An ID no device is named after still works
+        audio_system.set_default_output_device("7").unwrap();
+        assert_eq!(
+            audio_system
+                .get_default_output_device()
+                .unwrap()
+                .unwrap()
+                .name,
+            "2"
+        );
+    }
+
     #[test]
     fn test_mock_audio_system_call_tracking() {
         let audio_system = MockAudioSystem::new();
@@ -412,4 +1243,250 @@ enabled = true
             assert!(!audio_system.get_set_default_input_calls().is_empty());
         }
     }
+
+    #[test]
+    fn test_mock_with_devices_and_defaults() {
+        let headphones = AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        );
+        let microphone = AudioDevice::new(
+            "studio-mic-1".to_string(),
+            "Studio Microphone".to_string(),
+            DeviceType::Input,
+        );
+        let audio_system =
+            MockAudioSystem::with_devices(vec![headphones.clone(), microphone.clone()])
+                .with_default_output(headphones.clone())
+                .with_default_input(microphone.clone());
+
+        assert_eq!(audio_system.enumerate_devices().unwrap().len(), 2);
+        assert_eq!(
+            audio_system.get_default_output_device().unwrap(),
+            Some(headphones)
+        );
+        assert_eq!(
+            audio_system.get_default_input_device().unwrap(),
+            Some(microphone)
+        );
+    }
+
+    #[test]
+    fn test_mock_from_config_provides_rule_devices() {
+        let config = create_test_config();
+        let audio_system = MockAudioSystem::from_config(&config);
+
+        let devices = audio_system.enumerate_devices().unwrap();
+        assert_eq!(devices.len(), 6);
+
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+
+        assert_eq!(
+            audio_system.get_set_default_output_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+        assert_eq!(
+            audio_system.get_set_default_input_calls(),
+            vec!["Studio Microphone".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_sync_with_system_detects_external_default_change() {
+        let config = create_test_config();
+        let audio_system = MockAudioSystem::from_config(&config);
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.update_current_devices().unwrap();
+        assert!(!device_controller.sync_with_system().unwrap());
+
+        // Another application moves the output to the speakers
+        let speakers = audio_system
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers")
+            .unwrap();
+        audio_system.set_mock_default_output(Some(speakers));
+
+        assert!(device_controller.sync_with_system().unwrap());
+        assert_eq!(
+            device_controller
+                .get_current_output_device()
+                .map(|d| d.name.as_str()),
+            Some("Built-in Speakers")
+        );
+        assert!(!device_controller.sync_with_system().unwrap());
+    }
+
+    #[test]
+    fn test_switch_records_last_switch_time() {
+        let config = create_test_config();
+        let audio_system = MockAudioSystem::from_config(&config);
+        let mut device_controller = DeviceControllerV2::new(audio_system.clone(), &config);
+        device_controller.initialize().unwrap();
+
+        // Syncing with the system default is not a switch
+        device_controller.sync_with_system().unwrap();
+        let priority_manager = device_controller.priority_manager();
+        assert!(priority_manager.get_last_output_switch_time().is_none());
+        assert!(priority_manager.get_last_input_switch_time().is_none());
+
+        let before = SystemTime::now();
+        let speakers = audio_system
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers")
+            .unwrap();
+        device_controller
+            .switch_to_output_device(&speakers)
+            .unwrap();
+
+        let priority_manager = device_controller.priority_manager();
+        let switched_at = priority_manager.get_last_output_switch_time().unwrap();
+        assert!(switched_at >= before);
+        assert!(priority_manager.get_last_input_switch_time().is_none());
+    }
+
+    #[test]
+    fn test_devices_added_through_owned_audio_system() {
+        // The controller owns its only handle to this audio system
+        let mut device_controller =
+            DeviceControllerV2::new(MockAudioSystem::new(), &create_test_config());
+        let headphones = AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        );
+
+        device_controller
+            .get_audio_system_mut()
+            .add_device(headphones.clone());
+        device_controller
+            .handle_device_connected(&headphones)
+            .unwrap();
+
+        assert_eq!(
+            device_controller
+                .get_current_output_device()
+                .map(|d| d.name.as_str()),
+            Some("Premium Headphones")
+        );
+        assert_eq!(
+            device_controller
+                .get_audio_system()
+                .get_set_default_output_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+    }
+
+    /// Controller with the test devices whose current output is the built-in speakers
+    fn dry_run_controller(audio_system: &MockAudioSystem) -> DeviceControllerV2<MockAudioSystem> {
+        setup_test_devices(audio_system);
+        audio_system.set_device_latency_ms("premium-1", 12.5);
+        let mut device_controller =
+            DeviceControllerV2::new(audio_system.clone(), &create_test_config());
+        let speakers = audio_system
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Built-in Speakers")
+            .unwrap();
+        device_controller
+            .switch_to_output_device(&speakers)
+            .unwrap();
+        device_controller
+    }
+
+    #[test]
+    fn test_dry_run_reports_switch_without_switching() {
+        let audio_system = MockAudioSystem::new();
+        let device_controller = dry_run_controller(&audio_system);
+        let calls_before = audio_system.get_set_default_output_calls().len();
+        let headphones = AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        );
+
+        let result = device_controller
+            .dry_run_switch_to_output(&headphones)
+            .unwrap();
+
+        assert_eq!(
+            result,
+            DryRunResult {
+                device_found: true,
+                supports_direction: true,
+                estimated_latency_ms: Some(12.5),
+                current_device: Some("Built-in Speakers".to_string()),
+                would_switch: true,
+            }
+        );
+        assert_eq!(
+            audio_system.get_set_default_output_calls().len(),
+            calls_before
+        );
+    }
+
+    #[test]
+    fn test_dry_run_missing_device() {
+        let audio_system = MockAudioSystem::new();
+        let device_controller = dry_run_controller(&audio_system);
+        let unplugged = AudioDevice::new(
+            "usb-dac-1".to_string(),
+            "USB DAC".to_string(),
+            DeviceType::Output,
+        );
+
+        let result = device_controller
+            .dry_run_switch_to_output(&unplugged)
+            .unwrap();
+
+        assert!(!result.device_found);
+        assert_eq!(result.estimated_latency_ms, None);
+        assert!(!result.would_switch);
+    }
+
+    #[test]
+    fn test_dry_run_input_only_device() {
+        let audio_system = MockAudioSystem::new();
+        let device_controller = dry_run_controller(&audio_system);
+        let microphone = AudioDevice::new(
+            "studio-mic-1".to_string(),
+            "Studio Microphone".to_string(),
+            DeviceType::Input,
+        );
+
+        let result = device_controller
+            .dry_run_switch_to_output(&microphone)
+            .unwrap();
+
+        assert!(result.device_found);
+        assert!(!result.supports_direction);
+        assert!(!result.would_switch);
+    }
+
+    #[test]
+    fn test_dry_run_current_device() {
+        let audio_system = MockAudioSystem::new();
+        let device_controller = dry_run_controller(&audio_system);
+        let speakers = AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        );
+
+        let result = device_controller
+            .dry_run_switch_to_output(&speakers)
+            .unwrap();
+
+        assert!(result.device_found);
+        assert!(result.supports_direction);
+        // No latency configured for this device
+        assert_eq!(result.estimated_latency_ms, None);
+        assert!(!result.would_switch);
+    }
 }
@@ -1,7 +1,12 @@
-use audio_device_monitor::config::{DeviceRule, MatchType};
+use audio_device_monitor::audio::device::{
+    DeviceInfo, FUZZY_MATCH_THRESHOLD, StreamFormat, latency_frames_to_ms, mark_default_devices,
+    name_similarity, sample_rates_from_ranges, sort_stream_formats,
+};
+use audio_device_monitor::config::{DeviceRule, DeviceTypeFilter, MatchType};
+use audio_device_monitor::{AudioDevice, DeviceType, TransportType};
 
 mod test_utils;
-use test_utils::builders::DeviceRuleBuilder;
+use test_utils::builders::{AudioDeviceBuilder, DeviceRuleBuilder};
 
 /// Test exact matching behavior
 #[cfg(test)]
@@ -111,6 +116,54 @@ mod contains_matching {
     }
 }
 
+/// Test inverted matching behavior
+#[cfg(test)]
+mod inverted_matching {
+    use super::*;
+
+    #[test]
+    fn test_not_contains_excludes_matching_devices() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Built-in")
+            .match_type(MatchType::NotContains)
+            .build();
+
+        assert!(rule.matches("AirPods Pro"));
+        assert!(!rule.matches("MacBook Pro Speakers (Built-in)"));
+    }
+
+    #[test]
+    fn test_inverted_match_types() {
+        let cases = [
+            (MatchType::NotExact, "AirPods", "AirPods"),
+            (MatchType::NotStartsWith, "AirPods", "AirPods Pro"),
+            (MatchType::NotEndsWith, "Pro", "AirPods Pro"),
+        ];
+
+        for (match_type, pattern, excluded) in cases {
+            let rule = DeviceRuleBuilder::new()
+                .name(pattern)
+                .match_type(match_type.clone())
+                .build();
+
+            assert!(match_type.is_inverted());
+            assert!(!rule.matches(excluded), "{match_type:?} matched {excluded}");
+            assert!(rule.matches("Built-in Speakers"), "{match_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_disabled_inverted_rule_matches_nothing() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Built-in")
+            .match_type(MatchType::NotContains)
+            .disabled()
+            .build();
+
+        assert!(!rule.matches("AirPods Pro"));
+    }
+}
+
 /// Test starts_with matching behavior
 #[cfg(test)]
 mod starts_with_matching {
@@ -196,6 +249,199 @@ mod ends_with_matching {
     }
 }
 
+/// Test regex matching behavior
+#[cfg(test)]
+mod regex_matching {
+    use super::*;
+
+    #[test]
+    fn test_regex_match_success() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods.*(Pro|Max)")
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("AirPods Pro"));
+        assert!(rule.matches("AirPods Max"));
+        assert!(rule.matches("Tim's AirPods Pro #2"));
+    }
+
+    #[test]
+    fn test_regex_match_failure() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods.*(Pro|Max)")
+            .regex_match()
+            .build();
+
+        assert!(!rule.matches("AirPods"));
+        assert!(!rule.matches("airpods pro")); // Case sensitive
+        assert!(!rule.matches("Pro AirPods"));
+    }
+
+    #[test]
+    fn test_regex_unanchored_matches_anywhere() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Speakers")
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("MacBook Pro Speakers"));
+        assert!(rule.matches("Speakers (USB)"));
+    }
+
+    #[test]
+    fn test_regex_anchored_matches_whole_name() {
+        let rule = DeviceRuleBuilder::new()
+            .name("^MacBook Pro (Speakers|Microphone)$")
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("MacBook Pro Speakers"));
+        assert!(rule.matches("MacBook Pro Microphone"));
+        assert!(!rule.matches("MacBook Pro Speakers 2"));
+        assert!(!rule.matches("My MacBook Pro Speakers"));
+    }
+
+    #[test]
+    fn test_regex_case_insensitive_flag() {
+        let rule = DeviceRuleBuilder::new()
+            .name("(?i)^airpods")
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("AirPods Pro"));
+        assert!(rule.matches("AIRPODS"));
+    }
+
+    #[test]
+    fn test_regex_unicode_character_classes() {
+        let rule = DeviceRuleBuilder::new()
+            .name(r"^\p{Greek}+ \d$")
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("Ηχεία 2"));
+        assert!(!rule.matches("Speakers 2"));
+
+        let emoji_rule = DeviceRuleBuilder::new()
+            .name(r"🎵\s+\w+")
+            .regex_match()
+            .build();
+
+        assert!(emoji_rule.matches("🎵 Música"));
+        assert!(!emoji_rule.matches("🎵"));
+    }
+
+    #[test]
+    fn test_regex_very_long_pattern() {
+        let names: Vec<String> = (0..500).map(|i| format!("Studio Monitor {i}")).collect();
+        let pattern = format!("^({})$", names.join("|"));
+        let rule = DeviceRuleBuilder::new()
+            .name(&pattern)
+            .regex_match()
+            .build();
+
+        assert!(rule.matches("Studio Monitor 0"));
+        assert!(rule.matches("Studio Monitor 499"));
+        assert!(!rule.matches("Studio Monitor 500"));
+    }
+
+    #[test]
+    fn test_invalid_regex_matches_nothing() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods (Pro")
+            .regex_match()
+            .build();
+
+        assert!(!rule.matches("AirPods (Pro"));
+        assert!(!rule.matches("AirPods Pro"));
+    }
+}
+
+/// Test rules with `case_sensitive = false`
+#[cfg(test)]
+mod case_insensitive_matching {
+    use super::*;
+
+    fn rule(name: &str, match_type: MatchType) -> DeviceRule {
+        DeviceRuleBuilder::new()
+            .name(name)
+            .match_type(match_type)
+            .case_insensitive()
+            .build()
+    }
+
+    #[test]
+    fn test_all_match_types_ignore_case() {
+        let cases = vec![
+            (MatchType::Exact, "AirPods Pro", "airpods PRO"),
+            (MatchType::Contains, "pods pro", "AirPods Pro"),
+            (MatchType::StartsWith, "AIRPODS", "AirPods Pro"),
+            (MatchType::EndsWith, "pro", "AirPods Pro"),
+            (MatchType::Regex, "^airpods (pro|max)$", "AirPods Pro"),
+        ];
+
+        for (match_type, pattern, device_name) in cases {
+            let case_sensitive = DeviceRuleBuilder::new()
+                .name(pattern)
+                .match_type(match_type.clone())
+                .build();
+            let case_insensitive = rule(pattern, match_type.clone());
+
+            assert!(
+                !case_sensitive.matches(device_name),
+                "{match_type:?} '{pattern}' should not match '{device_name}' case-sensitively"
+            );
+            assert!(
+                case_insensitive.matches(device_name),
+                "{match_type:?} '{pattern}' should match '{device_name}' ignoring case"
+            );
+        }
+    }
+
+    #[test]
+    fn test_all_match_types_still_reject_other_names() {
+        let cases = vec![
+            (MatchType::Exact, "AirPods Pro", "AirPods"),
+            (MatchType::Contains, "pods pro", "AirPods Max"),
+            (MatchType::StartsWith, "AIRPODS", "Beats Studio"),
+            (MatchType::EndsWith, "pro", "AirPods Max"),
+            (MatchType::Regex, "^airpods (pro|max)$", "AirPods 3"),
+        ];
+
+        for (match_type, pattern, device_name) in cases {
+            assert!(
+                !rule(pattern, match_type.clone()).matches(device_name),
+                "{match_type:?} '{pattern}' should not match '{device_name}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_inverted_match_ignores_case() {
+        let rule = rule("built-in", MatchType::NotContains);
+
+        assert!(!rule.matches("MacBook Pro Built-in Speakers"));
+        assert!(rule.matches("AirPods Pro"));
+    }
+
+    #[test]
+    fn test_unicode_case_folding() {
+        let rule = rule("ÉCOUTEURS", MatchType::Exact);
+
+        assert!(rule.matches("écouteurs"));
+    }
+
+    #[test]
+    fn test_case_insensitive_regex_keeps_escapes() {
+        // Lowercasing the pattern would turn \S into \s
+        let rule = rule(r"^AirPods\S+$", MatchType::Regex);
+
+        assert!(rule.matches("airpods-pro"));
+        assert!(!rule.matches("airpods pro"));
+    }
+}
+
 /// Test disabled rules
 #[cfg(test)]
 mod disabled_rules {
@@ -230,6 +476,29 @@ mod disabled_rules {
                 weight: 100,
                 match_type: match_type.clone(),
                 enabled: false,
+                exclusive: false,
+                sticky: false,
+                weight_decay_after_disconnect_ms: None,
+                weight_decay_factor: None,
+                buffer_size_frames: None,
+                on_select_volume: None,
+                description: None,
+                comment: None,
+                min_channels: None,
+                max_channels: None,
+                applies_to: DeviceTypeFilter::Both,
+                requires_other_device: None,
+                require_uid: None,
+                weight_formula: None,
+                case_sensitive: true,
+                transport_type: None,
+                uid: None,
+                stability_ms: None,
+                pre_switch_command: None,
+                post_switch_command: None,
+                compiled_regex: None,
+                #[cfg(feature = "weight-formulas")]
+                compiled_formula: None,
             };
 
             assert!(
@@ -336,6 +605,29 @@ mod edge_cases {
                 weight: 100,
                 match_type: match_type.clone(),
                 enabled: true,
+                exclusive: false,
+                sticky: false,
+                weight_decay_after_disconnect_ms: None,
+                weight_decay_factor: None,
+                buffer_size_frames: None,
+                on_select_volume: None,
+                description: None,
+                comment: None,
+                min_channels: None,
+                max_channels: None,
+                applies_to: DeviceTypeFilter::Both,
+                requires_other_device: None,
+                require_uid: None,
+                weight_formula: None,
+                case_sensitive: true,
+                transport_type: None,
+                uid: None,
+                stability_ms: None,
+                pre_switch_command: None,
+                post_switch_command: None,
+                compiled_regex: None,
+                #[cfg(feature = "weight-formulas")]
+                compiled_formula: None,
             };
 
             assert_eq!(
@@ -351,6 +643,577 @@ mod edge_cases {
     }
 }
 
+/// Test fuzzy name similarity used for partial-name CLI lookups
+#[cfg(test)]
+mod fuzzy_name_matching {
+    use super::*;
+
+    #[test]
+    fn test_exact_name_scores_highest() {
+        assert_eq!(name_similarity("AirPods Pro", "AirPods Pro"), 1.0);
+        assert!(name_similarity("AirPods", "AirPods Pro") < 1.0);
+    }
+
+    #[test]
+    fn test_substring_always_meets_threshold() {
+        // "Yeti" is far from "Blue Yeti Microphone" by edit distance but is a clear substring
+        assert!(name_similarity("Yeti", "Blue Yeti Microphone") >= FUZZY_MATCH_THRESHOLD);
+        assert!(name_similarity("yeti", "Blue Yeti Microphone") >= FUZZY_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_typos_still_match() {
+        assert!(name_similarity("AirPod Pro", "AirPods Pro") >= FUZZY_MATCH_THRESHOLD);
+        assert!(
+            name_similarity("macbook pro speakrs", "MacBook Pro Speakers") >= FUZZY_MATCH_THRESHOLD
+        );
+    }
+
+    #[test]
+    fn test_unrelated_names_below_threshold() {
+        assert!(name_similarity("Shure MV7", "MacBook Pro Speakers") < FUZZY_MATCH_THRESHOLD);
+        assert!(name_similarity("", "MacBook Pro Speakers") < FUZZY_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn test_closer_names_score_higher() {
+        let close = name_similarity("AirPods Pro", "AirPods Pro 2");
+        let far = name_similarity("AirPods Pro", "AirPlay Speaker");
+        assert!(close > far);
+    }
+}
+
+/// Test expansion of CoreAudio sample rate ranges
+#[cfg(test)]
+mod sample_rate_ranges {
+    use super::*;
+
+    #[test]
+    fn test_discrete_rates_are_kept() {
+        let ranges = [(48000.0, 48000.0), (44100.0, 44100.0)];
+        assert_eq!(sample_rates_from_ranges(&ranges), vec![44100.0, 48000.0]);
+    }
+
+    #[test]
+    fn test_continuous_range_expands_to_common_rates() {
+        let ranges = [(44100.0, 96000.0)];
+        assert_eq!(
+            sample_rates_from_ranges(&ranges),
+            vec![44100.0, 48000.0, 88200.0, 96000.0]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_ranges_are_deduplicated() {
+        let ranges = [(8000.0, 48000.0), (48000.0, 48000.0), (96000.0, 192000.0)];
+        assert_eq!(
+            sample_rates_from_ranges(&ranges),
+            vec![44100.0, 48000.0, 96000.0, 176400.0, 192000.0]
+        );
+    }
+
+    #[test]
+    fn test_no_ranges() {
+        assert!(sample_rates_from_ranges(&[]).is_empty());
+    }
+
+    fn format(sample_rate: f64, bit_depth: u32, channels: u32, is_float: bool) -> StreamFormat {
+        StreamFormat {
+            sample_rate,
+            bit_depth,
+            channels,
+            is_float,
+        }
+    }
+
+    #[test]
+    fn test_stream_formats_sorted_and_deduplicated() {
+        let formats = vec![
+            format(96000.0, 24, 2, false),
+            format(44100.0, 32, 2, true),
+            format(44100.0, 16, 2, false),
+            format(96000.0, 24, 2, false),
+            format(44100.0, 16, 1, false),
+        ];
+
+        assert_eq!(
+            sort_stream_formats(formats),
+            vec![
+                format(44100.0, 16, 1, false),
+                format(44100.0, 16, 2, false),
+                format(44100.0, 32, 2, true),
+                format(96000.0, 24, 2, false),
+            ]
+        );
+    }
+}
+
+/// Test conversion of device latency from frames to milliseconds
+#[cfg(test)]
+mod latency_conversion {
+    use super::*;
+
+    #[test]
+    fn test_frames_to_ms_at_48k() {
+        let ms = latency_frames_to_ms(256, 48000.0).unwrap();
+        assert!((ms - 5.333).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_frames_to_ms_at_44_1k() {
+        let ms = latency_frames_to_ms(441, 44100.0).unwrap();
+        assert!((ms - 10.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_zero_frames() {
+        assert_eq!(latency_frames_to_ms(0, 96000.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_zero_sample_rate_is_error() {
+        let error = latency_frames_to_ms(256, 0.0).unwrap_err();
+        assert!(error.to_string().contains("sample rate is 0"));
+    }
+}
+
+/// Test flagging the system default devices in an enumeration
+#[cfg(test)]
+mod default_flags {
+    use super::*;
+
+    fn device(id: &str, name: &str, device_type: DeviceType) -> AudioDevice {
+        AudioDevice::new(id.to_string(), name.to_string(), device_type)
+    }
+
+    fn defaults(devices: &[AudioDevice]) -> Vec<(&str, DeviceType)> {
+        devices
+            .iter()
+            .filter(|device| device.is_default)
+            .map(|device| (device.name.as_str(), device.device_type.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_marked_per_direction() {
+        // AirPods appear once per direction with the same CoreAudio ID
+        let mut devices = vec![
+            device("10", "AirPods Pro", DeviceType::Input),
+            device("10", "AirPods Pro", DeviceType::Output),
+            device("20", "MacBook Pro Microphone", DeviceType::Input),
+            device("30", "MacBook Pro Speakers", DeviceType::Output),
+        ];
+
+        mark_default_devices(&mut devices, Some("10"), Some("20"));
+
+        assert_eq!(
+            defaults(&devices),
+            vec![
+                ("AirPods Pro", DeviceType::Output),
+                ("MacBook Pro Microphone", DeviceType::Input),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_default_flags_are_cleared() {
+        let mut devices =
+            vec![device("30", "MacBook Pro Speakers", DeviceType::Output).set_default(true)];
+
+        mark_default_devices(&mut devices, None, None);
+
+        assert!(defaults(&devices).is_empty());
+    }
+}
+
+/// Test channel count bounds on rules
+#[cfg(test)]
+mod channel_bounds {
+    use super::*;
+
+    fn mixer(channels: u32) -> AudioDevice {
+        AudioDeviceBuilder::new()
+            .name("USB Mixer")
+            .channels(channels)
+            .build()
+    }
+
+    #[test]
+    fn test_max_channels_rejects_wide_device() {
+        let rule = DeviceRuleBuilder::new()
+            .name("USB Mixer")
+            .exact_match()
+            .max_channels(2)
+            .build();
+
+        assert!(rule.matches("USB Mixer"));
+        assert!(!rule.matches_device(&mixer(16)));
+        assert!(rule.matches_device(&mixer(2)));
+    }
+
+    #[test]
+    fn test_channel_bounds_are_inclusive() {
+        let rule = DeviceRuleBuilder::new()
+            .name("USB Mixer")
+            .exact_match()
+            .min_channels(2)
+            .max_channels(8)
+            .build();
+
+        assert!(!rule.matches_device(&mixer(1)));
+        assert!(rule.matches_device(&mixer(2)));
+        assert!(rule.matches_device(&mixer(8)));
+        assert!(!rule.matches_device(&mixer(9)));
+    }
+
+    #[test]
+    fn test_unknown_channel_count_passes_bounds() {
+        let rule = DeviceRuleBuilder::new()
+            .name("USB Mixer")
+            .exact_match()
+            .min_channels(4)
+            .max_channels(4)
+            .build();
+        let device = AudioDeviceBuilder::new().name("USB Mixer").build();
+
+        assert!(rule.matches_device(&device));
+    }
+
+    #[test]
+    fn test_name_must_still_match() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Speakers")
+            .exact_match()
+            .max_channels(2)
+            .build();
+
+        assert!(!rule.matches_device(&mixer(2)));
+    }
+}
+
+/// Test pinning rules to a device UID
+#[cfg(test)]
+mod uid_pinning {
+    use super::*;
+
+    fn airpods(uid: &str) -> AudioDevice {
+        AudioDevice::new_with_uid(
+            "42".to_string(),
+            "AirPods Pro".to_string(),
+            DeviceType::Output,
+            uid.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_required_uid_matches_that_device() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods Pro")
+            .exact_match()
+            .require_uid("ABC-123")
+            .build();
+
+        assert!(rule.matches_device(&airpods("ABC-123")));
+    }
+
+    #[test]
+    fn test_same_name_with_other_uid_is_rejected() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods Pro")
+            .exact_match()
+            .require_uid("ABC-123")
+            .build();
+
+        assert!(!rule.matches_device(&airpods("XYZ-789")));
+    }
+
+    #[test]
+    fn test_device_without_uid_is_rejected() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods Pro")
+            .exact_match()
+            .require_uid("ABC-123")
+            .build();
+        let device = AudioDeviceBuilder::new().name("AirPods Pro").build();
+
+        assert!(!rule.matches_device(&device));
+    }
+
+    #[test]
+    fn test_uid_alone_does_not_match_other_name() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Beats Studio")
+            .exact_match()
+            .require_uid("ABC-123")
+            .build();
+
+        assert!(!rule.matches_device(&airpods("ABC-123")));
+    }
+}
+
+/// Test matching rules against device UIDs instead of names
+#[cfg(test)]
+mod uid_matching {
+    use super::*;
+
+    fn device(name: &str, uid: &str) -> AudioDevice {
+        AudioDevice::new_with_uid(
+            "42".to_string(),
+            name.to_string(),
+            DeviceType::Output,
+            uid.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_uid_pattern_matches_renamed_device() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Desk DAC")
+            .starts_with_match()
+            .uid("AppleUSBAudioEngine:Schiit")
+            .build();
+
+        assert!(rule.matches_device(&device(
+            "Schiit Modi+ (renamed)",
+            "AppleUSBAudioEngine:Schiit:Modi:1234"
+        )));
+        assert!(!rule.matches_device(&device("Desk DAC", "BuiltInSpeakerDevice")));
+    }
+
+    #[test]
+    fn test_uid_takes_precedence_over_name() {
+        let rule = DeviceRuleBuilder::new()
+            .name("MacBook Pro Speakers")
+            .exact_match()
+            .uid("BuiltInSpeakerDevice")
+            .build();
+
+        assert!(!rule.matches_device(&device("MacBook Pro Speakers", "Other-UID")));
+        assert!(rule.matches_device(&device("Speakers", "BuiltInSpeakerDevice")));
+    }
+
+    #[test]
+    fn test_uid_regex() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Any AirPods")
+            .regex_match()
+            .uid(r"^[0-9A-F]{2}(-[0-9A-F]{2}){5}:output$")
+            .build();
+
+        assert!(rule.matches_device(&device("AirPods", "AC-90-85-12-34-56:output")));
+        assert!(!rule.matches_device(&device("AirPods", "BuiltInSpeakerDevice")));
+    }
+
+    #[test]
+    fn test_device_without_uid_never_matches_uid_rule() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods")
+            .match_type(MatchType::NotContains)
+            .uid("BuiltIn")
+            .build();
+        let device = AudioDeviceBuilder::new().name("AirPods").build();
+
+        assert!(!rule.matches_device(&device));
+    }
+
+    #[test]
+    fn test_name_rules_still_match_by_name() {
+        let rule = DeviceRuleBuilder::new()
+            .name("AirPods")
+            .contains_match()
+            .build();
+
+        assert!(rule.matches("AirPods Pro"));
+        assert!(rule.matches_device(&device("AirPods Pro", "AC-90-85-12-34-56:output")));
+    }
+}
+
+/// Test restricting rules to a transport type
+#[cfg(test)]
+mod transport_filter {
+    use super::*;
+
+    fn headset(transport_type: Option<TransportType>) -> AudioDevice {
+        let device = AudioDeviceBuilder::new().name("Jabra Evolve2").build();
+        match transport_type {
+            Some(transport_type) => device.with_transport_type(transport_type),
+            None => device,
+        }
+    }
+
+    fn usb_only_rule() -> DeviceRule {
+        DeviceRuleBuilder::new()
+            .name("Jabra")
+            .contains_match()
+            .transport_type(TransportType::Usb)
+            .build()
+    }
+
+    #[test]
+    fn test_transport_filter_rejects_other_transports() {
+        let rule = usb_only_rule();
+
+        assert!(rule.matches_device(&headset(Some(TransportType::Usb))));
+        assert!(!rule.matches_device(&headset(Some(TransportType::Bluetooth))));
+    }
+
+    #[test]
+    fn test_unknown_transport_passes_filter() {
+        let rule = usb_only_rule();
+
+        assert!(rule.matches_device(&headset(None)));
+        assert!(rule.matches_device(&headset(Some(TransportType::Unknown))));
+    }
+
+    #[test]
+    fn test_rule_without_filter_accepts_any_transport() {
+        let rule = DeviceRuleBuilder::new()
+            .name("Jabra")
+            .contains_match()
+            .build();
+
+        assert!(rule.matches_device(&headset(Some(TransportType::Bluetooth))));
+    }
+}
+
+/// Test AudioDevice builder methods
+#[cfg(test)]
+mod device_builder {
+    use super::*;
+
+    #[test]
+    fn test_new_with_uid() {
+        let device = AudioDevice::new_with_uid(
+            "42".to_string(),
+            "AirPods Pro".to_string(),
+            DeviceType::Output,
+            "AirPods-UID".to_string(),
+        );
+
+        assert_eq!(device.id, "42");
+        assert_eq!(device.uid.as_deref(), Some("AirPods-UID"));
+        assert!(!device.is_default);
+        assert!(device.is_available);
+    }
+
+    #[test]
+    fn test_builder_methods_chain() {
+        let device = AudioDevice::new(
+            "7".to_string(),
+            "Scarlett 2i2".to_string(),
+            DeviceType::Input,
+        )
+        .with_uid("Scarlett-UID".to_string())
+        .with_manufacturer("Focusrite".to_string())
+        .with_transport_type(TransportType::Usb)
+        .set_default(true)
+        .set_available(false);
+
+        assert_eq!(device.uid.as_deref(), Some("Scarlett-UID"));
+        assert_eq!(device.manufacturer.as_deref(), Some("Focusrite"));
+        assert_eq!(device.transport_type, Some(TransportType::Usb));
+        assert!(device.is_default);
+        assert!(!device.is_available);
+    }
+}
+
+/// Test AudioDevice display modes
+#[cfg(test)]
+mod display_modes {
+    use super::*;
+
+    fn scarlett() -> AudioDevice {
+        AudioDevice::new_with_uid(
+            "7".to_string(),
+            "Scarlett 2i2".to_string(),
+            DeviceType::Input,
+            "Scarlett-UID".to_string(),
+        )
+        .with_transport_type(TransportType::Usb)
+        .with_sample_rates(vec![44100.0, 48000.0])
+        .with_channels(2)
+    }
+
+    #[test]
+    fn test_display_short_is_name_only() {
+        assert_eq!(scarlett().display_short().to_string(), "Scarlett 2i2");
+    }
+
+    #[test]
+    fn test_display_long_includes_type() {
+        assert_eq!(
+            scarlett().display_long().to_string(),
+            "Scarlett 2i2 [Input]"
+        );
+    }
+
+    #[test]
+    fn test_display_verbose_includes_details() {
+        let output = scarlett().display_verbose().to_string();
+
+        assert!(output.starts_with("Scarlett 2i2 [Input]\n"));
+        assert!(output.contains("UID: Scarlett-UID"));
+        assert!(output.contains("Transport: USB"));
+        assert!(output.contains("Sample Rates: 44100 Hz, 48000 Hz"));
+        assert!(output.contains("Channels: 2"));
+    }
+
+    #[test]
+    fn test_display_verbose_marks_missing_details_unknown() {
+        let device = AudioDevice::new(
+            "1".to_string(),
+            "Mystery Device".to_string(),
+            DeviceType::Output,
+        );
+        let output = device.display_verbose().to_string();
+
+        assert!(output.contains("UID: Unknown"));
+        assert!(output.contains("Transport: Unknown"));
+        assert!(output.contains("Sample Rates: Unknown"));
+        assert!(output.contains("Channels: Unknown"));
+    }
+}
+
+/// Test the current format line shown by `device-info` and `list-devices --verbose`
+#[cfg(test)]
+mod device_info_format {
+    use super::*;
+
+    fn info(sample_rate: Option<f64>, bit_depth: Option<u32>) -> DeviceInfo {
+        DeviceInfo {
+            name: "Scarlett 2i2".to_string(),
+            uid: "Scarlett-UID".to_string(),
+            device_type: DeviceType::Input,
+            sample_rate,
+            available_sample_rates: vec![44100.0, 48000.0],
+            channels: Some(2),
+            bit_depth,
+            is_default: false,
+        }
+    }
+
+    #[test]
+    fn test_current_format_includes_rate_and_depth() {
+        assert_eq!(
+            info(Some(48000.0), Some(24)).current_format().as_deref(),
+            Some("48000 Hz, 24-bit")
+        );
+    }
+
+    #[test]
+    fn test_current_format_leaves_out_unknown_parts() {
+        assert_eq!(
+            info(Some(44100.0), None).current_format().as_deref(),
+            Some("44100 Hz")
+        );
+        assert_eq!(
+            info(None, Some(16)).current_format().as_deref(),
+            Some("16-bit")
+        );
+        assert_eq!(info(None, None).current_format(), None);
+    }
+}
+
 /// Property-based testing for additional coverage
 #[cfg(test)]
 mod property_tests {
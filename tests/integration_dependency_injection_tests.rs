@@ -1,8 +1,10 @@
 use anyhow::Result;
 use audio_device_monitor::{
-    AudioDeviceService, MockAudioSystem, MockFileSystem, MockSystemService, SystemServiceInterface,
+    AudioDeviceService, FileSystemInterface, HealthStatus, IpcCommand, MockAudioSystem,
+    MockFileSystem, MockSystemService, ServiceStatistics, SwitchDirection, SystemServiceInterface,
 };
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 
 /// Integration tests for the complete dependency injection architecture
 /// These tests verify that all components work together seamlessly
@@ -10,7 +12,8 @@ use std::path::PathBuf;
 #[cfg(test)]
 mod integration_tests {
     use super::*;
-    use audio_device_monitor::{AudioDevice, DeviceType};
+    use audio_device_monitor::preference_debugging::SwitchHistory;
+    use audio_device_monitor::{AudioDevice, DeviceEvent, DeviceType, SwitchReason};
 
     /// Test fixture that creates a complete test environment
     struct ServiceTestFixture {
@@ -164,6 +167,64 @@ enabled = true
         assert!(audio_calls > 0);
     }
 
+    #[test]
+    fn test_injected_connect_switches_to_higher_priority_device() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.audio_system.add_device(AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        ));
+
+        let mut service = fixture.create_service().unwrap();
+        service.set_output_device("Built-in Speakers").unwrap();
+
+        // Plug in the headphones and deliver the event the listener would
+        let headphones = AudioDevice::new(
+            "premium-1".to_string(),
+            "Premium Headphones".to_string(),
+            DeviceType::Output,
+        );
+        fixture.audio_system.add_device(headphones.clone());
+        service
+            .inject_device_event(DeviceEvent::Connected(headphones))
+            .unwrap();
+
+        let switch_calls = fixture.audio_system.get_set_default_output_calls();
+        assert_eq!(
+            switch_calls.last().map(String::as_str),
+            Some("Premium Headphones")
+        );
+    }
+
+    #[test]
+    fn test_injected_disconnect_is_handled() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        let headphones = service
+            .enumerate_devices()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.name == "Premium Headphones")
+            .unwrap();
+
+        fixture.audio_system.remove_device(&headphones.id);
+        let result = service.inject_device_event(DeviceEvent::Disconnected(headphones));
+
+        assert!(result.is_ok());
+        assert!(
+            !service
+                .enumerate_devices()
+                .unwrap()
+                .iter()
+                .any(|d| d.name == "Premium Headphones")
+        );
+    }
+
     #[test]
     fn test_device_switching() {
         let fixture = ServiceTestFixture::new();
@@ -172,20 +233,1089 @@ enabled = true
 
         let mut service = fixture.create_service().unwrap();
 
-        // Test switching to premium headphones
-        let result = service.set_output_device("Premium Headphones");
-        assert!(result.is_ok());
+        // Test switching to premium headphones
+        let result = service.set_output_device("Premium Headphones");
+        assert!(result.is_ok());
+
+        // Test switching to premium microphone
+        let result = service.set_input_device("Premium Microphone");
+        assert!(result.is_ok());
+
+        // Verify audio system received switching calls
+        let switch_calls = fixture.audio_system.get_set_default_output_calls();
+        assert!(!switch_calls.is_empty());
+
+        let input_switch_calls = fixture.audio_system.get_set_default_input_calls();
+        assert!(!input_switch_calls.is_empty());
+    }
+
+    #[test]
+    fn test_device_switch_counts() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        assert_eq!(service.get_device_switch_count(), (0, 0));
+
+        service.set_output_device("Premium Headphones").unwrap();
+        service.set_output_device("Built-in Speakers").unwrap();
+        service.set_output_device("Premium Headphones").unwrap();
+        service.set_input_device("Premium Microphone").unwrap();
+
+        // Failed switches are not counted
+        assert!(service.set_output_device("Non-existent Device").is_err());
+
+        assert_eq!(service.get_device_switch_count(), (3, 1));
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert_eq!(status["metrics"]["output_switches"], 3);
+        assert_eq!(status["metrics"]["input_switches"], 1);
+    }
+
+    #[test]
+    fn test_applied_preferences_run_hooks_and_count_switches() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+pre_switch_command = "echo pre"
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        let changes = service.apply_preferences().unwrap();
+
+        // Preference switches take the same path as every other switch
+        assert!(changes.output_changed);
+        assert_eq!(fixture.file_system.get_hook_calls(), vec!["echo pre"]);
+        assert_eq!(service.get_device_switch_count(), (1, 0));
+    }
+
+    #[test]
+    fn test_statistics_count_switches_failures_and_rule_hits() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        service.set_output_device("Premium Headphones").unwrap();
+        service.set_output_device("Built-in Speakers").unwrap();
+        service.set_output_device("Premium Headphones").unwrap();
+        service.set_input_device("Premium Microphone").unwrap();
+
+        fixture.audio_system.set_device_setting_failure(true);
+        assert!(service.set_output_device("Built-in Speakers").is_err());
+        // No switch is attempted for a missing device
+        assert!(service.set_output_device("Non-existent Device").is_err());
+
+        let statistics = service.get_statistics();
+        assert_eq!(statistics.total_switch_count, 4);
+        assert_eq!(statistics.output_switch_count, 3);
+        assert_eq!(statistics.input_switch_count, 1);
+        assert_eq!(statistics.failed_switch_count, 1);
+        assert_eq!(statistics.rule_hits["Premium Headphones"], 2);
+        assert_eq!(statistics.rule_hits["Built-in Speakers"], 1);
+        assert_eq!(statistics.rule_hits["Premium Microphone"], 1);
+    }
+
+    #[test]
+    fn test_status_json_includes_config_summary() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert_eq!(status["summary"], service.get_config().summarize());
+        // Not started yet
+        assert!(status["uptime_seconds"].is_null());
+        assert!(
+            status["summary"]
+                .as_str()
+                .unwrap()
+                .starts_with("2 output rules, 2 input rules,")
+        );
+    }
+
+    #[test]
+    fn test_status_json_includes_scores_on_request() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert!(status["scores"].is_null());
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json_with_scores().unwrap()).unwrap();
+        let output_scores = &status["scores"]["output"];
+        assert!(
+            output_scores["Premium Headphones"].as_u64().unwrap()
+                > output_scores["Built-in Speakers"].as_u64().unwrap()
+        );
+        assert!(status["scores"]["input"]["Premium Microphone"].is_u64());
+    }
+
+    #[test]
+    fn test_last_error_tracks_failed_switches() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        assert_eq!(service.last_error(), None);
+
+        fixture.audio_system.set_device_setting_failure(true);
+        assert!(service.set_output_device("Premium Headphones").is_err());
+
+        let last_error = service
+            .last_error()
+            .expect("failed switch should be recorded");
+        assert!(last_error.contains("Mock set device failure"));
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert_eq!(status["last_error"], last_error.as_str());
+
+        // A successful switch clears the error
+        fixture.audio_system.set_device_setting_failure(false);
+        service.set_output_device("Premium Headphones").unwrap();
+        assert_eq!(service.last_error(), None);
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert!(status["last_error"].is_null());
+    }
+
+    #[test]
+    fn test_last_error_records_missing_device() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        assert!(service.set_input_device("Non-existent Device").is_err());
+
+        assert_eq!(
+            service.last_error().as_deref(),
+            Some("Input device 'Non-existent Device' not found")
+        );
+    }
+
+    #[test]
+    fn test_export_diagnostics_includes_device_snapshot() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        service.set_output_device("Premium Headphones").unwrap();
+
+        let diagnostics: serde_json::Value =
+            serde_json::from_str(&service.export_diagnostics().unwrap()).unwrap();
+        let devices = &diagnostics["devices"];
+
+        assert_eq!(devices["current_output"]["name"], "Premium Headphones");
+        assert!(devices["current_input"].is_null());
+        assert_eq!(devices["default_output"]["name"], "Premium Headphones");
+        assert!(!devices["available_devices"].as_array().unwrap().is_empty());
+        assert!(diagnostics["last_error"].is_null());
+    }
+
+    #[test]
+    fn test_find_device_by_pattern_returns_partial_matches() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture.audio_system.add_device(AudioDevice::new(
+            "premium-monitors-1".to_string(),
+            "Premium Studio Monitors".to_string(),
+            DeviceType::Output,
+        ));
+
+        let service = fixture.create_service().unwrap();
+
+        let outputs: Vec<String> = service
+            .find_device_by_pattern("Premium", false)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(
+            outputs,
+            vec!["Premium Headphones", "Premium Studio Monitors"]
+        );
+
+        let inputs: Vec<String> = service
+            .find_device_by_pattern("Premium", true)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(inputs, vec!["Premium Microphone"]);
+    }
+
+    #[test]
+    fn test_find_device_by_pattern_without_match_is_empty() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let service = fixture.create_service().unwrap();
+
+        assert!(
+            service
+                .find_device_by_pattern("Nonexistent", false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_prefer_wired_on_battery_follows_power_source() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+prefer_wired_on_battery = true
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "USB Headphones"
+weight = 10
+match_type = "exact"
+enabled = true
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+        fixture.audio_system.add_device(
+            AudioDevice::new(
+                "usb-out-1".to_string(),
+                "USB Headphones".to_string(),
+                DeviceType::Output,
+            )
+            .with_transport_type(audio_device_monitor::TransportType::Usb),
+        );
+
+        let mut service = fixture.create_service().unwrap();
+
+        let status = service.check_preferences().unwrap();
+        assert_eq!(
+            status.preferred_output.as_deref(),
+            Some("Premium Headphones")
+        );
+
+        fixture.system_service.set_on_battery_power(true);
+        let status = service.check_preferences().unwrap();
+        assert_eq!(status.preferred_output.as_deref(), Some("USB Headphones"));
+    }
+
+    #[test]
+    fn test_apply_preferences_respects_min_weight_to_switch() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+min_weight_to_switch = 60
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+
+        let mut service = fixture.create_service().unwrap();
+        let changes = service.apply_preferences().unwrap();
+
+        // The headphones lead by 50, short of the 60 needed to switch
+        assert!(!changes.output_changed);
+        assert!(
+            fixture
+                .audio_system
+                .get_set_default_output_calls()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_device_kept_by_sticky_rule_matches_preferences() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 100
+match_type = "exact"
+enabled = true
+sticky = true
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+
+        let mut service = fixture.create_service().unwrap();
+        let status = service.check_preferences().unwrap();
+
+        assert!(status.output_matches);
+        assert_eq!(
+            status.preferred_output.as_deref(),
+            Some("Built-in Speakers")
+        );
+        assert!(!service.apply_preferences().unwrap().output_changed);
+    }
+
+    /// Config that runs a periodic check on every loop iteration
+    fn setup_polling_config(fixture: &ServiceTestFixture) {
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+poll_interval_ms = 0
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+        fixture.system_service.stop_after_sleep_calls(1);
+    }
+
+    #[test]
+    fn test_periodic_check_switches_when_not_paused() {
+        let fixture = ServiceTestFixture::new();
+        setup_polling_config(&fixture);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        assert_eq!(
+            fixture.audio_system.get_set_default_output_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_paused_switching_skips_periodic_switch() {
+        let fixture = ServiceTestFixture::new();
+        setup_polling_config(&fixture);
+
+        let mut service = fixture.create_service().unwrap();
+        service.pause_automatic_switching();
+        assert!(service.is_switching_paused());
+        service.start().unwrap();
+
+        assert!(
+            fixture
+                .audio_system
+                .get_set_default_output_calls()
+                .is_empty()
+        );
+
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert_eq!(status["switching_paused"], true);
+
+        service.resume_automatic_switching();
+        assert!(!service.is_switching_paused());
+    }
+
+    #[test]
+    fn test_main_loop_waits_for_new_device_to_settle() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.audio_system.add_device(AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        ));
+
+        let audio_system = fixture.audio_system.clone();
+        let calls_while_settling = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let calls_seen = calls_while_settling.clone();
+        let sleeps = std::sync::atomic::AtomicUsize::new(0);
+        fixture.system_service.on_sleep(move || {
+            match sleeps.fetch_add(1, Ordering::SeqCst) {
+                0 => audio_system.connect_device(AudioDevice::new(
+                    "premium-1".to_string(),
+                    "Premium Headphones".to_string(),
+                    DeviceType::Output,
+                )),
+                1 => {
+                    *calls_seen.lock().unwrap() = audio_system.get_set_default_output_calls();
+                    // The default device_change_debounce_ms
+                    audio_system.advance_time(std::time::Duration::from_millis(750));
+                }
+                _ => {}
+            }
+        });
+        fixture.system_service.stop_after_sleep_calls(3);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        assert_eq!(
+            *calls_while_settling.lock().unwrap(),
+            vec!["Built-in Speakers".to_string()]
+        );
+        assert_eq!(
+            fixture.audio_system.get_set_default_output_calls(),
+            vec![
+                "Built-in Speakers".to_string(),
+                "Premium Headphones".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_main_loop_decays_weight_of_disconnected_device() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[[output_devices]]
+name = "Premium Headphones"
+weight = 100
+match_type = "exact"
+enabled = true
+weight_decay_after_disconnect_ms = 60000
+weight_decay_factor = 0.4
+
+[[output_devices]]
+name = "Built-in Speakers"
+weight = 50
+match_type = "exact"
+enabled = true
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+        fixture.setup_test_devices();
+
+        let audio_system = fixture.audio_system.clone();
+        let sleeps = std::sync::atomic::AtomicUsize::new(0);
+        fixture.system_service.on_sleep(move || {
+            match sleeps.fetch_add(1, Ordering::SeqCst) {
+                0 => {
+                    // The system falls back to the speakers when the headphones go
+                    audio_system.remove_device("premium-1");
+                    audio_system.set_mock_default_output(Some(AudioDevice::new(
+                        "builtin-out-1".to_string(),
+                        "Built-in Speakers".to_string(),
+                        DeviceType::Output,
+                    )));
+                }
+                1 => {
+                    audio_system.connect_device(AudioDevice::new(
+                        "premium-1".to_string(),
+                        "Premium Headphones".to_string(),
+                        DeviceType::Output,
+                    ));
+                    audio_system.advance_time(std::time::Duration::from_secs(1));
+                }
+                _ => {}
+            }
+        });
+        fixture.system_service.stop_after_sleep_calls(3);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        // Back within the decay window the headphones weigh 40, below the speakers
+        assert_eq!(
+            fixture.audio_system.get_set_default_output_calls(),
+            vec!["Premium Headphones".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ipc_pause_and_resume_commands() {
+        let fixture = ServiceTestFixture::new();
+        setup_polling_config(&fixture);
+
+        let mut service = fixture.create_service().unwrap();
+        let ipc = service.open_ipc_channel();
+        ipc.send(IpcCommand::Pause { respond_to: None })
+            .await
+            .unwrap();
+        drop(ipc);
+        service.watch_ipc_commands().await.unwrap();
+        assert!(service.is_switching_paused());
+
+        let ipc = service.open_ipc_channel();
+        ipc.send(IpcCommand::Resume { respond_to: None })
+            .await
+            .unwrap();
+        drop(ipc);
+        service.watch_ipc_commands().await.unwrap();
+        assert!(!service.is_switching_paused());
+    }
+
+    #[test]
+    fn test_health_check_healthy_with_default_output() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "premium-1".to_string(),
+                "Premium Headphones".to_string(),
+                DeviceType::Output,
+            )));
+
+        let mut service = fixture.create_service().unwrap();
+
+        assert_eq!(service.emit_health_check(), HealthStatus::Healthy);
+        assert_eq!(service.emit_health_check().exit_code(), 0);
+    }
+
+    #[test]
+    fn test_health_check_healthy_without_configured_device() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        let dac = AudioDevice::new(
+            "usb-dac-1".to_string(),
+            "USB DAC".to_string(),
+            DeviceType::Output,
+        );
+        fixture.audio_system.add_device(dac.clone());
+        fixture.audio_system.set_mock_default_output(Some(dac));
+
+        let mut service = fixture.create_service().unwrap();
+
+        // No rule matches anything connected, so there is nothing better to use
+        assert_eq!(service.emit_health_check(), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_health_check_degraded_without_default_output() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        let health = service.emit_health_check();
+
+        assert!(matches!(health, HealthStatus::Degraded { .. }));
+        assert_eq!(health.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_health_check_degraded_after_failed_switch() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+
+        let mut service = fixture.create_service().unwrap();
+        fixture.audio_system.set_device_setting_failure(true);
+        assert!(service.set_output_device("Premium Headphones").is_err());
+
+        match service.emit_health_check() {
+            HealthStatus::Degraded { reason } => {
+                assert!(reason.contains("Mock set device failure"))
+            }
+            other => panic!("expected degraded health, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_status_reports_health_after_failed_switch() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+
+        let mut service = fixture.create_service().unwrap();
+        fixture.audio_system.set_device_setting_failure(true);
+        assert!(service.set_output_device("Premium Headphones").is_err());
+
+        // The health command reads the daemon's health from its status
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        match serde_json::from_value(status["health"].clone()).unwrap() {
+            HealthStatus::Degraded { reason } => {
+                assert!(reason.contains("Mock set device failure"))
+            }
+            other => panic!("expected degraded health, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_health_check_degraded_when_devices_differ_from_preferences() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture
+            .audio_system
+            .set_mock_default_output(Some(AudioDevice::new(
+                "builtin-out-1".to_string(),
+                "Built-in Speakers".to_string(),
+                DeviceType::Output,
+            )));
+        fixture
+            .audio_system
+            .set_mock_default_input(Some(AudioDevice::new(
+                "premium-mic-1".to_string(),
+                "Premium Microphone".to_string(),
+                DeviceType::Input,
+            )));
 
-        // Test switching to premium microphone
-        let result = service.set_input_device("Premium Microphone");
-        assert!(result.is_ok());
+        let mut service = fixture.create_service().unwrap();
 
-        // Verify audio system received switching calls
-        let switch_calls = fixture.audio_system.get_set_default_output_calls();
-        assert!(!switch_calls.is_empty());
+        match service.emit_health_check() {
+            HealthStatus::Degraded { reason } => {
+                assert_eq!(
+                    reason,
+                    "output is Built-in Speakers, preferred Premium Headphones"
+                )
+            }
+            other => panic!("expected degraded health, got {other:?}"),
+        }
+    }
 
-        let input_switch_calls = fixture.audio_system.get_set_default_input_calls();
-        assert!(!input_switch_calls.is_empty());
+    #[test]
+    fn test_health_check_critical_when_enumeration_fails() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.audio_system.set_enumeration_failure(true);
+
+        let mut service = fixture.create_service().unwrap();
+        let health = service.emit_health_check();
+
+        assert!(matches!(health, HealthStatus::Critical { .. }));
+        assert_eq!(health.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_main_loop_exponential_backoff_on_errors() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        // No current devices and failing enumeration makes every update fail
+        fixture.audio_system.set_enumeration_failure(true);
+        fixture.system_service.stop_after_sleep_calls(5);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        assert_eq!(
+            fixture.system_service.get_sleep_calls(),
+            vec![100, 200, 400, 800, 1600]
+        );
+    }
+
+    #[test]
+    fn test_main_loop_reloads_config_on_sighup() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        let loaded_at = fixture
+            .file_system
+            .get_config_modified_time(&fixture.config_path)
+            .unwrap();
+
+        // Change the file without bumping its modification time, so only the
+        // signal can trigger the reload
+        let updated_config = r#"
+[general]
+check_interval_ms = 2500
+log_level = "info"
+daemon_mode = false
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, updated_config.to_string());
+        fixture
+            .file_system
+            .set_config_modified_time(&fixture.config_path, loaded_at);
+
+        fixture.system_service.simulate_sighup();
+        fixture.system_service.stop_after_sleep_calls(1);
+        service.start().unwrap();
+
+        assert_eq!(service.get_config().general.check_interval_ms, 2500);
+        // The request is one-shot
+        assert!(!fixture.system_service.is_config_reload_requested());
+    }
+
+    #[test]
+    fn test_main_loop_handles_queued_ipc_switch() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let history_dir = tempfile::tempdir().unwrap();
+        let history_path = history_dir.path().join("history.jsonl");
+        let mut service = fixture
+            .create_service()
+            .unwrap()
+            .with_switch_history(history_path.clone());
+        let ipc = service.open_ipc_channel();
+        ipc.try_send(IpcCommand::Switch {
+            device: "Built-in Microphone".to_string(),
+            direction: SwitchDirection::Input,
+            requester: Some("Zoom".to_string()),
+            respond_to: None,
+        })
+        .unwrap();
+
+        fixture.system_service.stop_after_sleep_calls(1);
+        service.start().unwrap();
+
+        // Startup picks the preferred microphone first; the command overrides it
+        assert_eq!(
+            fixture
+                .audio_system
+                .get_set_default_input_calls()
+                .last()
+                .map(String::as_str),
+            Some("Built-in Microphone")
+        );
+        // The startup switches count too, since every switch goes through the controller
+        assert_eq!(service.get_device_switch_count(), (1, 2));
+
+        let history = SwitchHistory::new(history_path, 100);
+        let last = history.recent(1).unwrap().pop().unwrap();
+        assert_eq!(last.device_name, "Built-in Microphone");
+        assert_eq!(
+            last.reason,
+            SwitchReason::AppRequest {
+                app_name: "Zoom".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_ipc_commands_dispatches_until_senders_close() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+
+        let mut service = fixture.create_service().unwrap();
+        let ipc = service.open_ipc_channel();
+
+        let updated_config = r#"
+[general]
+check_interval_ms = 750
+log_level = "info"
+daemon_mode = false
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, updated_config.to_string());
+
+        let (status_sender, status_receiver) = tokio::sync::oneshot::channel();
+        ipc.send(IpcCommand::Switch {
+            device: "Premium Headphones".to_string(),
+            direction: SwitchDirection::Output,
+            requester: None,
+            respond_to: None,
+        })
+        .await
+        .unwrap();
+        ipc.send(IpcCommand::Reload { respond_to: None })
+            .await
+            .unwrap();
+        let (stats_sender, stats_receiver) = tokio::sync::oneshot::channel();
+        ipc.send(IpcCommand::GetStatus {
+            include_scores: false,
+            respond_to: status_sender,
+        })
+        .await
+        .unwrap();
+        ipc.send(IpcCommand::GetStats {
+            respond_to: stats_sender,
+        })
+        .await
+        .unwrap();
+        drop(ipc);
+
+        service.watch_ipc_commands().await.unwrap();
+
+        assert_eq!(service.get_config().general.check_interval_ms, 750);
+        let status: serde_json::Value =
+            serde_json::from_str(&status_receiver.await.unwrap().unwrap()).unwrap();
+        assert_eq!(status["current_output"], "Premium Headphones");
+        assert_eq!(status["metrics"]["output_switches"], 1);
+        let stats: ServiceStatistics =
+            serde_json::from_str(&stats_receiver.await.unwrap().unwrap()).unwrap();
+        assert_eq!(stats.output_switch_count, 1);
+        assert_eq!(stats.rule_hits["Premium Headphones"], 1);
+    }
+
+    #[test]
+    fn test_start_opens_ipc_socket_until_shutdown() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("control.sock");
+
+        let mut service = fixture
+            .create_service()
+            .unwrap()
+            .with_ipc_socket(socket_path.clone());
+        fixture.system_service.stop_after_sleep_calls(1);
+        service.start().unwrap();
+
+        assert!(socket_path.exists());
+        let status: serde_json::Value =
+            serde_json::from_str(&service.export_status_json().unwrap()).unwrap();
+        assert!(status["uptime_seconds"].is_u64());
+
+        service.graceful_shutdown().unwrap();
+        assert!(!socket_path.exists());
+    }
+
+    #[test]
+    fn test_second_instance_is_refused_while_lock_is_held() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join("daemon.pid");
+
+        let mut first = fixture
+            .create_service()
+            .unwrap()
+            .with_daemon_lock(lock_path.clone());
+        fixture.system_service.stop_after_sleep_calls(1);
+        first.start().unwrap();
+
+        let mut second = fixture
+            .create_service()
+            .unwrap()
+            .with_daemon_lock(lock_path.clone());
+        let error = second.start().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Another instance is already running (PID 12345)"
+        );
+
+        first.graceful_shutdown().unwrap();
+        assert!(!lock_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_watch_ipc_commands_requires_open_channel() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        let mut service = fixture.create_service().unwrap();
+
+        assert!(service.watch_ipc_commands().await.is_err());
+    }
+
+    /// Headphones that only show up in the device list after the first wait
+    fn headphones_arrive_on_first_sleep(fixture: &ServiceTestFixture) {
+        let audio_system = fixture.audio_system.clone();
+        let sleeps = std::sync::atomic::AtomicUsize::new(0);
+        fixture.system_service.on_sleep(move || {
+            if sleeps.fetch_add(1, Ordering::SeqCst) == 0 {
+                audio_system.add_device(AudioDevice::new(
+                    "premium-1".to_string(),
+                    "Premium Headphones".to_string(),
+                    DeviceType::Output,
+                ));
+            }
+        });
+    }
+
+    #[test]
+    fn test_sleep_wake_recovery_waits_for_stable_device_list() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.audio_system.add_device(AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        ));
+
+        let mut service = fixture.create_service().unwrap();
+        headphones_arrive_on_first_sleep(&fixture);
+
+        service.recover_from_sleep_wake().unwrap();
+
+        // First and second lists differ, second and third match
+        assert_eq!(fixture.system_service.get_sleep_calls(), vec![500, 500]);
+        assert_eq!(
+            fixture
+                .audio_system
+                .get_set_default_output_calls()
+                .last()
+                .map(String::as_str),
+            Some("Premium Headphones")
+        );
+    }
+
+    #[test]
+    fn test_main_loop_recovers_after_wake() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.audio_system.add_device(AudioDevice::new(
+            "builtin-out-1".to_string(),
+            "Built-in Speakers".to_string(),
+            DeviceType::Output,
+        ));
+
+        let mut service = fixture.create_service().unwrap();
+        headphones_arrive_on_first_sleep(&fixture);
+        fixture.system_service.simulate_wake();
+        fixture.system_service.stop_after_sleep_calls(3);
+
+        service.start().unwrap();
+
+        // Two recovery waits, then the regular check interval
+        assert_eq!(
+            fixture.system_service.get_sleep_calls(),
+            vec![500, 500, 1000]
+        );
+        assert!(!fixture.system_service.woke_from_sleep());
+    }
+
+    #[test]
+    fn test_main_loop_backoff_capped_at_max() {
+        let fixture = ServiceTestFixture::new();
+        let config_content = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+backoff_ms = 250
+max_backoff_ms = 1000
+"#;
+        fixture
+            .file_system
+            .add_file(&fixture.config_path, config_content.to_string());
+
+        fixture.audio_system.set_enumeration_failure(true);
+        fixture.system_service.stop_after_sleep_calls(5);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        assert_eq!(
+            fixture.system_service.get_sleep_calls(),
+            vec![250, 500, 1000, 1000, 1000]
+        );
+    }
+
+    #[test]
+    fn test_main_loop_uses_check_interval_without_errors() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        fixture.system_service.stop_after_sleep_calls(3);
+
+        let mut service = fixture.create_service().unwrap();
+        service.start().unwrap();
+
+        assert_eq!(
+            fixture.system_service.get_sleep_calls(),
+            vec![1000, 1000, 1000]
+        );
     }
 
     #[test]
@@ -252,6 +1382,148 @@ enabled = true
         assert!(!service.should_continue_running());
     }
 
+    #[test]
+    fn test_graceful_shutdown_waits_for_pending_notifications() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        let mut service = fixture.create_service().unwrap();
+        let pending = service.notification_manager().sender().pending.clone();
+        pending.store(2, Ordering::SeqCst);
+
+        // One notification finishes delivering per poll
+        let in_flight = pending.clone();
+        fixture.system_service.on_sleep(move || {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        service.graceful_shutdown().unwrap();
+
+        assert_eq!(pending.load(Ordering::SeqCst), 0);
+        assert_eq!(fixture.system_service.get_sleep_calls().len(), 2);
+        assert!(fixture.system_service.was_event_loop_stopped());
+    }
+
+    #[test]
+    fn test_graceful_shutdown_waits_for_audio_system_notifications() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        let mut service = fixture.create_service().unwrap();
+        // A connection notification from the device listener is still in flight
+        let pending = fixture.audio_system.pending_notifications.clone();
+        pending.store(1, Ordering::SeqCst);
+
+        let in_flight = pending.clone();
+        fixture.system_service.on_sleep(move || {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        service.graceful_shutdown().unwrap();
+
+        assert_eq!(pending.load(Ordering::SeqCst), 0);
+        assert_eq!(fixture.system_service.get_sleep_calls().len(), 1);
+        assert!(fixture.system_service.was_event_loop_stopped());
+    }
+
+    #[test]
+    fn test_graceful_shutdown_gives_up_after_timeout() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        let mut service = fixture.create_service().unwrap();
+        let pending = service.notification_manager().sender().pending.clone();
+        pending.store(1, Ordering::SeqCst);
+
+        service.graceful_shutdown().unwrap();
+
+        let waited_ms: u64 = fixture.system_service.get_sleep_calls().iter().sum();
+        assert_eq!(waited_ms, 2000);
+        assert_eq!(pending.load(Ordering::SeqCst), 1);
+        assert!(fixture.system_service.was_event_loop_stopped());
+    }
+
+    #[test]
+    fn test_graceful_shutdown_without_pending_notifications() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+
+        let mut service = fixture.create_service().unwrap();
+        service.graceful_shutdown().unwrap();
+
+        assert!(fixture.system_service.get_sleep_calls().is_empty());
+        assert!(fixture.system_service.was_event_loop_stopped());
+    }
+
+    #[test]
+    fn test_wait_for_device_sees_late_arrival() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        let service = fixture.create_service().unwrap();
+
+        // The device shows up after the second poll
+        let audio_system = fixture.audio_system.clone();
+        let sleeps = std::sync::atomic::AtomicUsize::new(0);
+        fixture.system_service.on_sleep(move || {
+            if sleeps.fetch_add(1, Ordering::SeqCst) == 1 {
+                audio_system.add_device(AudioDevice::new(
+                    "scarlett-1".to_string(),
+                    "Scarlett 2i2 USB".to_string(),
+                    DeviceType::Output,
+                ));
+            }
+        });
+
+        let device = service.wait_for_device("Scarlett", 5000).unwrap();
+
+        assert_eq!(device.id, "scarlett-1");
+        assert_eq!(fixture.system_service.get_sleep_calls(), vec![100, 100]);
+    }
+
+    #[test]
+    fn test_wait_for_device_returns_present_device_immediately() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        let service = fixture.create_service().unwrap();
+
+        let device = service.wait_for_device("Premium", 0).unwrap();
+
+        assert_eq!(device.name, "Premium Headphones");
+        assert!(fixture.system_service.get_sleep_calls().is_empty());
+    }
+
+    #[test]
+    fn test_benchmark_device_enumeration_reports_median() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        let service = fixture.create_service().unwrap();
+        fixture
+            .audio_system
+            .set_enumeration_delay(std::time::Duration::from_millis(10));
+
+        let median = service.benchmark_device_enumeration().unwrap();
+
+        assert!(median >= std::time::Duration::from_millis(10));
+        assert!(median < std::time::Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_wait_for_device_times_out() {
+        let fixture = ServiceTestFixture::new();
+        fixture.setup_default_config();
+        fixture.setup_test_devices();
+        let service = fixture.create_service().unwrap();
+
+        let err = service.wait_for_device("Scarlett", 250).unwrap_err();
+
+        assert!(err.to_string().contains("Timed out after 250ms"));
+        // The mock sleep returns immediately, so the timeout must come from
+        // the time slept rather than the wall clock
+        assert_eq!(fixture.system_service.get_sleep_calls(), vec![100, 100, 50]);
+    }
+
     #[test]
     fn test_error_handling_missing_device() {
         let fixture = ServiceTestFixture::new();
@@ -20,6 +20,8 @@ mod end_to_end_flows {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -40,6 +42,8 @@ mod end_to_end_flows {
                     .contains_match()
                     .build(),
             ],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         // Create components
@@ -87,6 +91,8 @@ mod end_to_end_flows {
                 show_device_availability: false,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -106,6 +112,8 @@ mod end_to_end_flows {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -164,6 +172,8 @@ mod end_to_end_flows {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -178,6 +188,8 @@ mod end_to_end_flows {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -237,6 +249,8 @@ mod configuration_impact {
                 show_device_availability: false,
                 show_switching_actions: false,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -246,6 +260,8 @@ mod configuration_impact {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -279,6 +295,8 @@ mod configuration_impact {
             notifications: NotificationConfig::default(),
             output_devices: vec![], // No rules
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config_no_rules);
@@ -307,6 +325,8 @@ mod configuration_impact {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -326,6 +346,8 @@ mod configuration_impact {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -375,11 +397,26 @@ mod realistic_scenarios {
                 poll_interval_ms: 10_000,
                 log_level: "info".to_string(),
                 daemon_mode: true,
+                backoff_ms: 100,
+                max_backoff_ms: 30_000,
+                device_change_debounce_ms: 750,
+                bluetooth_device_change_debounce_ms: 1500,
+                sync_system_alerts: false,
+                prefer_wired_on_battery: false,
+                min_weight_to_switch: 0,
+                shutdown_timeout_ms: 2000,
+                restore_volume: false,
+                hook_timeout_ms: 5000,
+                history_max_lines: 1000,
+                switch_retry_count: 3,
+                switch_retry_delay_ms: 250,
             },
             notifications: NotificationConfig {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -415,6 +452,8 @@ mod realistic_scenarios {
                     .exact_match()
                     .build(),
             ],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -537,6 +576,8 @@ mod realistic_scenarios {
                 show_device_availability: false, // Gaming setup - no connection notifications
                 show_switching_actions: true,    // But want switching notifications
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -562,6 +603,8 @@ mod realistic_scenarios {
                     .contains_match()
                     .build(),
             ],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&gaming_config);
@@ -628,6 +671,8 @@ mod cross_component_edge_cases {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -637,6 +682,8 @@ mod cross_component_edge_cases {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -676,6 +723,8 @@ mod cross_component_edge_cases {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -691,6 +740,8 @@ mod cross_component_edge_cases {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -729,7 +780,7 @@ mod cross_component_edge_cases {
             output_rules.push(
                 DeviceRuleBuilder::new()
                     .name(&format!("Device Rule {}", i))
-                    .weight(i as u32)
+                    .weight(i as i64)
                     .contains_match()
                     .build(),
             );
@@ -740,6 +791,8 @@ mod cross_component_edge_cases {
             notifications: NotificationConfig::default(),
             output_devices: output_rules,
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let priority_manager = DevicePriorityManager::new(&config);
@@ -797,6 +850,8 @@ mod cross_component_edge_cases {
                 show_device_availability: false,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: vec![
                 DeviceRuleBuilder::new()
@@ -806,6 +861,8 @@ mod cross_component_edge_cases {
                     .build(),
             ],
             input_devices: vec![],
+            devices: vec![],
+            profiles: Default::default(),
         };
 
         let sender = TestNotificationSender::new();
@@ -16,9 +16,13 @@ fn create_test_notification_manager(
             show_device_availability,
             show_switching_actions,
             show_device_changes: None,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
         },
         output_devices: vec![],
         input_devices: vec![],
+        devices: vec![],
+        profiles: Default::default(),
     };
 
     let sender = TestNotificationSender::new();
@@ -281,6 +285,29 @@ mod switch_reason_formatting {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_app_request_switch_reason_names_app() {
+        let manager = create_test_notification_manager(false, true);
+        let device = AudioDeviceBuilder::new()
+            .name("Studio Monitors")
+            .output()
+            .build();
+
+        manager
+            .device_switched(
+                &device,
+                SwitchReason::AppRequest {
+                    app_name: "Stream Deck".to_string(),
+                },
+            )
+            .unwrap();
+
+        manager.sender().assert_last_notification(
+            "Audio Device Switched",
+            "🔊 Output manually switched to Studio Monitors by Stream Deck",
+        );
+    }
+
     #[test]
     fn test_all_switch_reasons_with_different_device_types() {
         let manager = create_test_notification_manager(false, true);
@@ -288,6 +315,9 @@ mod switch_reason_formatting {
             SwitchReason::HigherPriority,
             SwitchReason::PreviousUnavailable,
             SwitchReason::Manual,
+            SwitchReason::AppRequest {
+                app_name: "Test App".to_string(),
+            },
         ];
 
         for (i, reason) in reasons.into_iter().enumerate() {
@@ -470,3 +500,230 @@ mod configuration_integration {
         }
     }
 }
+
+/// Test the TestNotificationSender inspection helpers
+#[cfg(test)]
+mod sender_helpers {
+    use super::*;
+
+    #[test]
+    fn test_last_notification_and_count() {
+        let manager = create_test_notification_manager(true, true);
+        let device = AudioDeviceBuilder::new()
+            .name("Studio Monitors")
+            .output()
+            .build();
+
+        assert_eq!(manager.sender().count(), 0);
+        assert!(manager.sender().get_last_notification().is_none());
+
+        manager.device_connected(&device).unwrap();
+        manager
+            .device_switched(&device, SwitchReason::HigherPriority)
+            .unwrap();
+
+        assert_eq!(manager.sender().count(), 2);
+        let (title, body) = manager.sender().get_last_notification().unwrap();
+        assert_eq!(title, "Audio Device Switched");
+        assert!(body.contains("Studio Monitors"));
+    }
+
+    #[test]
+    fn test_assert_last_notification_passes() {
+        let manager = create_test_notification_manager(false, true);
+
+        manager.switch_failed("USB DAC", "device busy").unwrap();
+
+        manager
+            .sender()
+            .assert_last_notification("Audio Device Switch Failed", "device busy");
+    }
+
+    #[test]
+    #[should_panic(expected = "got 'Audio Device Connected'")]
+    fn test_assert_last_notification_reports_mismatch() {
+        let manager = create_test_notification_manager(true, false);
+        let device = AudioDeviceBuilder::new().name("Headset").input().build();
+
+        manager.device_connected(&device).unwrap();
+
+        manager
+            .sender()
+            .assert_last_notification("Audio Device Switched", "Headset");
+    }
+
+    #[test]
+    #[should_panic(expected = "no notifications were sent")]
+    fn test_assert_last_notification_with_nothing_sent() {
+        let manager = create_test_notification_manager(false, false);
+
+        manager
+            .sender()
+            .assert_last_notification("Audio Device Switched", "Headset");
+    }
+
+    #[test]
+    fn test_assert_notification_count_and_sent() {
+        let manager = create_test_notification_manager(true, true);
+        let device = AudioDeviceBuilder::new().name("Headset").input().build();
+
+        manager.sender().assert_no_notifications();
+
+        manager.device_connected(&device).unwrap();
+        manager
+            .device_switched(&device, SwitchReason::HigherPriority)
+            .unwrap();
+
+        manager.sender().assert_notification_count(2);
+        // Matches any notification, not just the last one
+        manager
+            .sender()
+            .assert_notification_sent("Connected", "Headset");
+        manager
+            .sender()
+            .assert_notification_sent("Switched", "Headset");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 0 notifications, got 1")]
+    fn test_assert_no_notifications_reports_sent() {
+        let manager = create_test_notification_manager(false, true);
+
+        manager.switch_failed("USB DAC", "device busy").unwrap();
+
+        manager.sender().assert_no_notifications();
+    }
+
+    #[test]
+    #[should_panic(expected = "body containing 'Headset'")]
+    fn test_assert_notification_sent_reports_missing() {
+        let manager = create_test_notification_manager(false, true);
+
+        manager.switch_failed("USB DAC", "device busy").unwrap();
+
+        manager
+            .sender()
+            .assert_notification_sent("Switch Failed", "Headset");
+    }
+}
+
+/// Test suppression of notifications during quiet hours
+#[cfg(test)]
+mod quiet_hours {
+    use super::*;
+    use audio_device_monitor::config::TimeOfDay;
+
+    fn time(s: &str) -> TimeOfDay {
+        s.parse().unwrap()
+    }
+
+    fn quiet_manager(start: &str, end: &str) -> NotificationManager<TestNotificationSender> {
+        let mut config = Config::default();
+        config.notifications.quiet_hours_start = Some(time(start));
+        config.notifications.quiet_hours_end = Some(time(end));
+        NotificationManager::with_sender(&config, TestNotificationSender::new())
+    }
+
+    #[test]
+    fn test_notifications_suppressed_in_overnight_window() {
+        let manager = quiet_manager("22:00", "07:00");
+
+        for now in ["22:00", "23:59", "00:00", "02:00", "06:59"] {
+            manager
+                .send_notification_at(time(now), "Audio Device Switched", now)
+                .unwrap();
+        }
+
+        assert_eq!(manager.sender().count(), 0);
+    }
+
+    #[test]
+    fn test_notifications_sent_outside_overnight_window() {
+        let manager = quiet_manager("22:00", "07:00");
+
+        for now in ["07:00", "12:00", "21:59"] {
+            manager
+                .send_notification_at(time(now), "Audio Device Switched", now)
+                .unwrap();
+        }
+
+        assert_eq!(manager.sender().count(), 3);
+    }
+
+    #[test]
+    fn test_same_day_window() {
+        let manager = quiet_manager("13:00", "14:30");
+
+        manager
+            .send_notification_at(time("13:15"), "Audio Device Switched", "lunch")
+            .unwrap();
+        manager
+            .send_notification_at(time("14:30"), "Audio Device Switched", "after")
+            .unwrap();
+
+        manager
+            .sender()
+            .assert_last_notification("Audio Device Switched", "after");
+        assert_eq!(manager.sender().count(), 1);
+    }
+
+    #[test]
+    fn test_no_quiet_hours_without_both_ends() {
+        let mut config = Config::default();
+        config.notifications.quiet_hours_start = Some(time("00:00"));
+        let manager = NotificationManager::with_sender(&config, TestNotificationSender::new());
+
+        manager
+            .send_notification_at(time("03:00"), "Audio Device Switched", "night")
+            .unwrap();
+
+        assert_eq!(manager.sender().count(), 1);
+    }
+
+    #[test]
+    fn test_quiet_hours_parsed_from_config() {
+        let config: Config = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[notifications]
+show_switching_actions = true
+quiet_hours_start = "22:30"
+quiet_hours_end = "07:00"
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            config.notifications.quiet_hours(),
+            Some((time("22:30"), time("07:00")))
+        );
+        let serialized = toml::to_string_pretty(&config).unwrap();
+        assert!(serialized.contains("quiet_hours_start = \"22:30\""));
+    }
+
+    #[test]
+    fn test_lone_quiet_hours_bound_rejected() {
+        let error = r#"
+[notifications]
+show_switching_actions = true
+quiet_hours_start = "22:30"
+"#
+        .parse::<Config>()
+        .unwrap_err();
+
+        assert!(
+            format!("{error:#}").contains("quiet_hours_end is missing"),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[test]
+    fn test_invalid_time_rejected() {
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("7:60".parse::<TimeOfDay>().is_err());
+        assert!("0700".parse::<TimeOfDay>().is_err());
+    }
+}
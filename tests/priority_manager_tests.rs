@@ -1,18 +1,9 @@
-use audio_device_monitor::config::{Config, DeviceRule, GeneralConfig, NotificationConfig};
-use audio_device_monitor::priority::DevicePriorityManager;
+use audio_device_monitor::AudioDevice;
+use audio_device_monitor::config::{Config, DeviceTypeFilter};
+use audio_device_monitor::priority::{DevicePriorityManager, RankedDevice, SwitchDecision};
 
 mod test_utils;
-use test_utils::builders::{AudioDeviceBuilder, DeviceRuleBuilder};
-
-/// Helper function to create a test config with custom rules
-fn create_test_config(output_rules: Vec<DeviceRule>, input_rules: Vec<DeviceRule>) -> Config {
-    Config {
-        general: GeneralConfig::default(),
-        notifications: NotificationConfig::default(),
-        output_devices: output_rules,
-        input_devices: input_rules,
-    }
-}
+use test_utils::builders::{AudioDeviceBuilder, ConfigBuilder, DeviceRuleBuilder};
 
 /// Test basic device selection functionality
 #[cfg(test)]
@@ -30,8 +21,7 @@ mod device_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -56,8 +46,7 @@ mod device_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -70,6 +59,76 @@ mod device_selection {
         assert!(best_device.is_none());
     }
 
+    #[test]
+    fn test_new_uses_config_rules() {
+        let config = Config::default();
+        let from_config = DevicePriorityManager::new(&config);
+        let from_rules =
+            DevicePriorityManager::new_from_rules(&config.output_devices, &config.input_devices);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("MacBook Pro Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+        ];
+
+        let expected = from_rules.find_best_output_device(&devices).unwrap();
+        let actual = from_config.find_best_output_device(&devices).unwrap();
+        assert_eq!(actual.name, expected.name);
+        assert_eq!(actual.name, "AirPods Pro");
+    }
+
+    #[test]
+    fn test_new_splits_unified_rules_by_direction() {
+        let config = ConfigBuilder::new()
+            .add_device(
+                DeviceRuleBuilder::new()
+                    .name("AirPods")
+                    .weight(100)
+                    .contains_match()
+                    .build(),
+            )
+            .add_device(
+                DeviceRuleBuilder::new()
+                    .name("Studio Display")
+                    .weight(200)
+                    .contains_match()
+                    .applies_to(DeviceTypeFilter::OutputOnly)
+                    .build(),
+            )
+            .build();
+        let manager = DevicePriorityManager::new(&config);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .input()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Studio Display Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Studio Display Microphone")
+                .input()
+                .build(),
+        ];
+
+        let best_output = manager.find_best_output_device(&devices).unwrap();
+        let best_input = manager.find_best_input_device(&devices).unwrap();
+        assert_eq!(best_output.name, "Studio Display Speakers");
+        assert_eq!(best_input.name, "AirPods Pro");
+    }
+
     #[test]
     fn test_empty_device_list() {
         let output_rules = vec![
@@ -81,8 +140,7 @@ mod device_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![];
         let best_device = manager.find_best_output_device(&devices);
@@ -93,8 +151,7 @@ mod device_selection {
     fn test_empty_rules_list() {
         let output_rules = vec![];
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -134,8 +191,7 @@ mod priority_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -173,8 +229,7 @@ mod priority_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new().name("Device B").output().build(),
@@ -207,8 +262,7 @@ mod priority_selection {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -221,188 +275,1814 @@ mod priority_selection {
                 .build(),
         ];
 
-        let best_device = manager.find_best_output_device(&devices);
-        assert!(best_device.is_some());
-        assert_eq!(best_device.unwrap().name, "Low Priority Device");
+        let best_device = manager.find_best_output_device(&devices);
+        assert!(best_device.is_some());
+        assert_eq!(best_device.unwrap().name, "Low Priority Device");
+    }
+
+    #[test]
+    fn test_multiple_rules_same_device() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Pro")
+                .weight(150)
+                .contains_match()
+                .build(),
+        ];
+
+        let input_rules = vec![];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+        ];
+
+        // Device matches both rules, should get the higher weight (150)
+        let best_device = manager.find_best_output_device(&devices);
+        assert!(best_device.is_some());
+        assert_eq!(best_device.unwrap().name, "AirPods Pro");
+    }
+}
+
+/// Test negative "blacklist" weights
+#[cfg(test)]
+mod negative_weights {
+    use super::*;
+
+    #[test]
+    fn test_negative_weight_device_never_selected() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Broken Speaker")
+                .weight(-100)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+
+        // Even as the only matching device it must not be chosen
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("Broken Speaker")
+                .output()
+                .build(),
+        ];
+
+        assert!(manager.find_best_output_device(&devices).is_none());
+    }
+
+    #[test]
+    fn test_negative_weight_device_skipped_for_lower_priority() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("HDMI")
+                .weight(-1)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Speakers")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("LG HDMI Display")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("MacBook Pro Speakers")
+                .output()
+                .build(),
+        ];
+
+        let best_device = manager.find_best_output_device(&devices);
+        assert_eq!(best_device.unwrap().name, "MacBook Pro Speakers");
+    }
+
+    #[test]
+    fn test_highest_matching_weight_decides_blacklist() {
+        // The positive rule outweighs the negative one, so the device stays eligible
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(-50)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+        ];
+
+        let best_device = manager.find_best_output_device(&devices);
+        assert_eq!(best_device.unwrap().name, "AirPods Pro");
+    }
+}
+
+/// Test channel count bounds when selecting devices
+#[cfg(test)]
+mod channel_bounds {
+    use super::*;
+
+    #[test]
+    fn test_wide_mixer_skipped_for_casual_output() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Mixer")
+                .weight(100)
+                .contains_match()
+                .max_channels(2)
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Speakers")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .id("mixer")
+                .name("16ch USB Mixer")
+                .output()
+                .channels(16)
+                .build(),
+            AudioDeviceBuilder::new()
+                .id("speakers")
+                .name("MacBook Pro Speakers")
+                .output()
+                .channels(2)
+                .build(),
+        ];
+
+        let best = manager.find_best_output_device(&devices).unwrap();
+        assert_eq!(best.name, "MacBook Pro Speakers");
+    }
+}
+
+/// Test exclusive rules locking a device to one role
+#[cfg(test)]
+mod exclusive_rules {
+    use super::*;
+
+    fn headset_devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .name("USB Headset")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("USB Headset")
+                .input()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Built-in Microphone")
+                .input()
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_exclusive_output_rule_removes_device_from_input_candidates() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .exclusive()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Microphone")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+        let devices = headset_devices();
+
+        let best_output = manager.find_best_output_device(&devices);
+        let best_input = manager.find_best_input_device(&devices);
+
+        assert_eq!(best_output.unwrap().name, "USB Headset");
+        assert_eq!(best_input.unwrap().name, "Built-in Microphone");
+    }
+
+    #[test]
+    fn test_non_exclusive_rule_allows_both_roles() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+        let devices = headset_devices();
+
+        assert_eq!(
+            manager.find_best_input_device(&devices).unwrap().name,
+            "USB Headset"
+        );
+    }
+
+    #[test]
+    fn test_exclusive_rule_overrides_other_rules_in_same_direction() {
+        // The exclusive rule pins the headset at weight 20, ignoring the broader rule
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB")
+                .weight(200)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(20)
+                .exact_match()
+                .exclusive()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB DAC")
+                .weight(50)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("USB Headset")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new().name("USB DAC").output().build(),
+        ];
+
+        let best_output = manager.find_best_output_device(&devices);
+        assert_eq!(best_output.unwrap().name, "USB DAC");
+    }
+
+    #[test]
+    fn test_disabled_exclusive_rule_does_not_claim_device() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .exclusive()
+                .disabled()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("USB Headset")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+
+        assert_eq!(
+            manager
+                .find_best_input_device(&headset_devices())
+                .unwrap()
+                .name,
+            "USB Headset"
+        );
+    }
+}
+
+/// Test what-if analysis of device arrivals
+#[cfg(test)]
+mod arrival_simulation {
+    use super::*;
+
+    fn create_manager() -> DevicePriorityManager {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Speakers")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Microphone")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+
+        DevicePriorityManager::new_from_rules(&output_rules, &input_rules)
+    }
+
+    fn current_devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .id("speakers")
+                .name("MacBook Pro Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .id("mic")
+                .name("MacBook Pro Microphone")
+                .input()
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_high_priority_arrival_switches_output() {
+        let manager = create_manager();
+        let airpods = AudioDeviceBuilder::new()
+            .id("airpods")
+            .name("AirPods Pro")
+            .output()
+            .build();
+
+        let decision = manager.simulate_device_arrival(&airpods, &current_devices());
+
+        assert_eq!(
+            decision,
+            Some(SwitchDecision {
+                should_switch_output: true,
+                should_switch_input: false,
+                matched_rule: Some("AirPods".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_low_priority_arrival_does_not_switch() {
+        let manager = create_manager();
+        let mut devices = current_devices();
+        devices.push(
+            AudioDeviceBuilder::new()
+                .id("airpods")
+                .name("AirPods Pro")
+                .output()
+                .build(),
+        );
+
+        let speakers = devices[0].clone();
+        let decision = manager
+            .simulate_device_arrival(&speakers, &devices)
+            .unwrap();
+
+        assert!(!decision.should_switch_output);
+        assert!(!decision.should_switch_input);
+        assert_eq!(
+            decision.matched_rule.as_deref(),
+            Some("MacBook Pro Speakers")
+        );
+    }
+
+    #[test]
+    fn test_unmatched_arrival_returns_none() {
+        let manager = create_manager();
+        let unknown = AudioDeviceBuilder::new()
+            .id("hdmi")
+            .name("LG HDMI")
+            .output()
+            .build();
+
+        assert!(
+            manager
+                .simulate_device_arrival(&unknown, &current_devices())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_arrival_into_empty_system_switches() {
+        let manager = create_manager();
+        let mic = AudioDeviceBuilder::new()
+            .id("mic")
+            .name("MacBook Pro Microphone")
+            .input()
+            .build();
+
+        let decision = manager.simulate_device_arrival(&mic, &[]).unwrap();
+
+        assert!(decision.should_switch_input);
+        assert!(!decision.should_switch_output);
+    }
+}
+
+/// Test input vs output device separation
+#[cfg(test)]
+mod device_type_separation {
+    use super::*;
+
+    #[test]
+    fn test_input_output_separation() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Output Device")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Input Device")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("Output Device")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Input Device")
+                .input()
+                .build(),
+        ];
+
+        // Output rules should only match output devices
+        let best_output = manager.find_best_output_device(&devices);
+        assert!(best_output.is_some());
+        assert_eq!(best_output.unwrap().name, "Output Device");
+
+        // Input rules should only match input devices
+        let best_input = manager.find_best_input_device(&devices);
+        assert!(best_input.is_some());
+        assert_eq!(best_input.unwrap().name, "Input Device");
+    }
+
+    #[test]
+    fn test_wrong_device_type_no_match() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Any Device")
+                .weight(100)
+                .contains_match()
+                .build(),
+        ];
+
+        let input_rules = vec![];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+
+        // Only input devices available
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("Any Device Input")
+                .input()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Another Any Device")
+                .input()
+                .build(),
+        ];
+
+        // Output rules shouldn't match input devices
+        let best_output = manager.find_best_output_device(&devices);
+        assert!(best_output.is_none());
+    }
+}
+
+/// Test device state management
+#[cfg(test)]
+mod state_management {
+    use super::*;
+
+    #[test]
+    fn test_should_switch_with_no_current_device() {
+        let manager = DevicePriorityManager::new_from_rules(&[], &[]);
+
+        let new_device = AudioDeviceBuilder::new()
+            .name("New Device")
+            .output()
+            .build();
+
+        // Should switch when no current device is set
+        assert!(manager.should_switch_output(&new_device));
+        assert!(manager.should_switch_input(&new_device));
+    }
+
+    #[test]
+    fn test_should_switch_to_different_device() {
+        let mut manager = DevicePriorityManager::new_from_rules(&[], &[]);
+
+        // Set current devices
+        manager.update_current_output(
+            AudioDeviceBuilder::new()
+                .name("Current Output")
+                .output()
+                .build(),
+        );
+        manager.update_current_input(
+            AudioDeviceBuilder::new()
+                .name("Current Input")
+                .input()
+                .build(),
+        );
+
+        let new_output = AudioDeviceBuilder::new()
+            .name("New Output")
+            .output()
+            .build();
+
+        let new_input = AudioDeviceBuilder::new().name("New Input").input().build();
+
+        // Should switch to different devices
+        assert!(manager.should_switch_output(&new_output));
+        assert!(manager.should_switch_input(&new_input));
+    }
+
+    #[test]
+    fn test_should_not_switch_to_same_device() {
+        let mut manager = DevicePriorityManager::new_from_rules(&[], &[]);
+
+        let device_name = "Same Device";
+
+        // Set current devices
+        manager.update_current_output(AudioDeviceBuilder::new().name(device_name).output().build());
+        manager.update_current_input(AudioDeviceBuilder::new().name(device_name).input().build());
+
+        let same_output = AudioDeviceBuilder::new().name(device_name).output().build();
+
+        let same_input = AudioDeviceBuilder::new().name(device_name).input().build();
+
+        // Should not switch to the same device
+        assert!(!manager.should_switch_output(&same_output));
+        assert!(!manager.should_switch_input(&same_input));
+    }
+
+    #[test]
+    fn test_record_switch_sets_last_switch_time() {
+        let mut manager = DevicePriorityManager::new_from_rules(&[], &[]);
+        assert!(manager.get_last_output_switch_time().is_none());
+        assert!(manager.get_last_input_switch_time().is_none());
+
+        let before = std::time::SystemTime::now();
+        manager.record_output_switch(&AudioDeviceBuilder::new().name("Speakers").output().build());
+
+        assert!(manager.get_last_output_switch_time().unwrap() >= before);
+        assert!(manager.get_last_input_switch_time().is_none());
+
+        manager.record_input_switch(&AudioDeviceBuilder::new().name("Microphone").input().build());
+        assert!(manager.get_last_input_switch_time().unwrap() >= before);
+    }
+
+    #[test]
+    fn test_record_switch_counts_rule_hits() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Studio Microphone")
+                .weight(100)
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+        let airpods = AudioDeviceBuilder::new()
+            .name("AirPods Pro")
+            .output()
+            .build();
+        let speakers = AudioDeviceBuilder::new().name("Speakers").output().build();
+        let microphone = AudioDeviceBuilder::new()
+            .name("Studio Microphone")
+            .input()
+            .build();
+
+        manager.record_output_switch(&airpods);
+        manager.record_output_switch(&speakers);
+        manager.record_output_switch(&airpods);
+        manager.record_input_switch(&microphone);
+
+        // Switches to devices no rule selects count for no rule
+        assert_eq!(manager.rule_hits().len(), 2);
+        assert_eq!(manager.rule_hits()["AirPods"], 2);
+        assert_eq!(manager.rule_hits()["Studio Microphone"], 1);
+    }
+}
+
+/// Test the wired-device bonus applied on battery power
+#[cfg(test)]
+mod wired_on_battery {
+    use super::*;
+    use audio_device_monitor::TransportType;
+
+    fn rules() -> Vec<audio_device_monitor::config::DeviceRule> {
+        vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB Headphones")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ]
+    }
+
+    fn devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .id("airpods")
+                .name("AirPods Pro")
+                .output()
+                .transport_type(TransportType::Bluetooth)
+                .build(),
+            AudioDeviceBuilder::new()
+                .id("usb")
+                .name("USB Headphones")
+                .output()
+                .transport_type(TransportType::Usb)
+                .build(),
+        ]
+    }
+
+    fn best_output(manager: &DevicePriorityManager) -> String {
+        manager.find_best_output_device(&devices()).unwrap().name
+    }
+
+    #[test]
+    fn test_usb_device_wins_on_battery() {
+        let mut manager =
+            DevicePriorityManager::new_from_rules(&rules(), &[]).with_prefer_wired_on_battery(true);
+        manager.set_on_battery_power(true);
+
+        assert_eq!(best_output(&manager), "USB Headphones");
+    }
+
+    #[test]
+    fn test_no_bonus_on_ac_power() {
+        let mut manager =
+            DevicePriorityManager::new_from_rules(&rules(), &[]).with_prefer_wired_on_battery(true);
+        manager.set_on_battery_power(false);
+
+        assert_eq!(best_output(&manager), "AirPods Pro");
+    }
+
+    #[test]
+    fn test_no_bonus_when_disabled() {
+        let mut manager = DevicePriorityManager::new_from_rules(&rules(), &[]);
+        manager.set_on_battery_power(true);
+
+        assert!(!manager.prefers_wired_on_battery());
+        assert_eq!(best_output(&manager), "AirPods Pro");
+    }
+
+    #[test]
+    fn test_blacklisted_usb_device_stays_blacklisted() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB Headphones")
+                .weight(-1)
+                .exact_match()
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&output_rules, &[])
+            .with_prefer_wired_on_battery(true);
+        manager.set_on_battery_power(true);
+
+        assert_eq!(best_output(&manager), "AirPods Pro");
+    }
+
+    #[test]
+    fn test_new_reads_setting_from_config() {
+        let mut config = Config::default();
+        config.general.prefer_wired_on_battery = true;
+
+        assert!(DevicePriorityManager::new(&config).prefers_wired_on_battery());
+    }
+}
+
+/// Test the minimum weight gap required before switching
+#[cfg(test)]
+mod min_weight_to_switch {
+    use super::*;
+
+    fn manager_with_gap(new_weight: i64, min_weight_to_switch: u32) -> DevicePriorityManager {
+        let rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Current Speakers")
+                .weight(100)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("New Speakers")
+                .weight(new_weight)
+                .exact_match()
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&rules, &[])
+            .with_min_weight_to_switch(min_weight_to_switch);
+        manager.update_current_output(
+            AudioDeviceBuilder::new()
+                .name("Current Speakers")
+                .output()
+                .build(),
+        );
+        manager
+    }
+
+    fn new_speakers() -> AudioDevice {
+        AudioDeviceBuilder::new()
+            .name("New Speakers")
+            .output()
+            .build()
+    }
+
+    #[test]
+    fn test_small_lead_does_not_switch() {
+        let manager = manager_with_gap(101, 10);
+
+        assert!(!manager.should_switch_output(&new_speakers()));
+    }
+
+    #[test]
+    fn test_lead_at_threshold_switches() {
+        assert!(manager_with_gap(110, 10).should_switch_output(&new_speakers()));
+        assert!(manager_with_gap(120, 10).should_switch_output(&new_speakers()));
+    }
+
+    #[test]
+    fn test_default_gap_switches_on_any_lead() {
+        let manager = manager_with_gap(101, 0);
+
+        assert!(manager.should_switch_output(&new_speakers()));
+    }
+
+    #[test]
+    fn test_lower_weight_device_replaces_missing_current_device() {
+        // Only offered when the current device has gone away
+        let manager = manager_with_gap(50, 10);
+
+        assert!(manager.should_switch_output(&new_speakers()));
+    }
+
+    #[test]
+    fn test_gap_read_from_config() {
+        let mut config = ConfigBuilder::new()
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("Current Speakers")
+                    .weight(100)
+                    .exact_match()
+                    .build(),
+            )
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("New Speakers")
+                    .weight(101)
+                    .exact_match()
+                    .build(),
+            )
+            .build();
+        config.general.min_weight_to_switch = 10;
+
+        let mut manager = DevicePriorityManager::new(&config);
+        manager.update_current_output(
+            AudioDeviceBuilder::new()
+                .name("Current Speakers")
+                .output()
+                .build(),
+        );
+
+        assert!(!manager.should_switch_output(&new_speakers()));
+    }
+}
+
+/// Test replacing rules on a live manager, as a config reload does
+#[cfg(test)]
+mod rule_updates {
+    use super::*;
+
+    fn devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("MacBook Pro Microphone")
+                .input()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("USB Microphone")
+                .input()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Studio Monitors")
+                .output()
+                .build(),
+        ]
+    }
+
+    fn manager() -> DevicePriorityManager {
+        let config = ConfigBuilder::new()
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("AirPods")
+                    .weight(100)
+                    .contains_match()
+                    .build(),
+            )
+            .add_input_device(
+                DeviceRuleBuilder::new()
+                    .name("MacBook Pro Microphone")
+                    .weight(100)
+                    .exact_match()
+                    .build(),
+            )
+            .build();
+        DevicePriorityManager::new(&config)
+    }
+
+    #[test]
+    fn test_update_config_replaces_rules() {
+        let mut manager = manager();
+        assert_eq!(
+            manager.find_best_output_device(&devices()).unwrap().name,
+            "AirPods Pro"
+        );
+
+        let reloaded = ConfigBuilder::new()
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("Studio Monitors")
+                    .weight(100)
+                    .exact_match()
+                    .build(),
+            )
+            .add_input_device(
+                DeviceRuleBuilder::new()
+                    .name("USB")
+                    .weight(100)
+                    .contains_match()
+                    .build(),
+            )
+            .build();
+        manager.update_config(&reloaded);
+
+        assert_eq!(
+            manager.find_best_output_device(&devices()).unwrap().name,
+            "Studio Monitors"
+        );
+        assert_eq!(
+            manager.find_best_input_device(&devices()).unwrap().name,
+            "USB Microphone"
+        );
+    }
+
+    #[test]
+    fn test_update_config_applies_min_weight_to_switch() {
+        let mut manager = manager();
+        manager.update_current_output(
+            AudioDeviceBuilder::new()
+                .name("Studio Monitors")
+                .output()
+                .build(),
+        );
+
+        let mut reloaded = ConfigBuilder::new()
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("Studio Monitors")
+                    .weight(100)
+                    .exact_match()
+                    .build(),
+            )
+            .add_output_device(
+                DeviceRuleBuilder::new()
+                    .name("AirPods Pro")
+                    .weight(105)
+                    .exact_match()
+                    .build(),
+            )
+            .build();
+        reloaded.general.min_weight_to_switch = 10;
+        manager.update_config(&reloaded);
+
+        let airpods = AudioDeviceBuilder::new()
+            .name("AirPods Pro")
+            .output()
+            .build();
+        assert!(!manager.should_switch_output(&airpods));
+    }
+
+    #[test]
+    fn test_update_output_rules_leaves_input_rules() {
+        let mut manager = manager();
+
+        manager.update_output_rules(&[DeviceRuleBuilder::new()
+            .name("Studio")
+            .weight(100)
+            .contains_match()
+            .build()]);
+
+        assert_eq!(
+            manager.find_best_output_device(&devices()).unwrap().name,
+            "Studio Monitors"
+        );
+        assert_eq!(
+            manager.find_best_input_device(&devices()).unwrap().name,
+            "MacBook Pro Microphone"
+        );
+    }
+
+    #[test]
+    fn test_update_input_rules_with_empty_list() {
+        let mut manager = manager();
+
+        manager.update_input_rules(&[]);
+
+        assert!(manager.find_best_input_device(&devices()).is_none());
+        assert_eq!(
+            manager.find_best_output_device(&devices()).unwrap().name,
+            "AirPods Pro"
+        );
+    }
+}
+
+/// Test rules that only apply while a companion device is connected
+#[cfg(test)]
+mod required_devices {
+    use super::*;
+
+    fn manager() -> DevicePriorityManager {
+        let rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Studio Display Speakers")
+                .weight(200)
+                .exact_match()
+                .requires_other_device("Studio Display Camera")
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Speakers")
+                .weight(100)
+                .exact_match()
+                .build(),
+        ];
+        DevicePriorityManager::new_from_rules(&rules, &[])
+    }
+
+    fn speakers() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .name("Studio Display Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("MacBook Pro Speakers")
+                .output()
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_rule_skipped_without_required_device() {
+        let best = manager().find_best_output_device(&speakers()).unwrap();
+
+        assert_eq!(best.name, "MacBook Pro Speakers");
+    }
+
+    #[test]
+    fn test_rule_applies_with_required_device() {
+        let mut devices = speakers();
+        // The companion is an input device; any direction satisfies the requirement
+        devices.push(
+            AudioDeviceBuilder::new()
+                .name("Studio Display Camera")
+                .input()
+                .build(),
+        );
+
+        let best = manager().find_best_output_device(&devices).unwrap();
+
+        assert_eq!(best.name, "Studio Display Speakers");
+    }
+
+    #[test]
+    fn test_required_device_matches_by_substring() {
+        let mut devices = speakers();
+        devices.push(
+            AudioDeviceBuilder::new()
+                .name("Apple Studio Display Camera (2)")
+                .input()
+                .build(),
+        );
+
+        let best = manager().find_best_output_device(&devices).unwrap();
+
+        assert_eq!(best.name, "Studio Display Speakers");
+    }
+}
+
+/// Test sticky rules holding on to their device
+#[cfg(test)]
+mod sticky_rules {
+    use super::*;
+
+    fn manager(sticky: bool) -> DevicePriorityManager {
+        let studio = DeviceRuleBuilder::new()
+            .name("Studio Interface")
+            .weight(100)
+            .exact_match();
+        let studio = if sticky { studio.sticky() } else { studio };
+        let rules = vec![
+            studio.build(),
+            DeviceRuleBuilder::new()
+                .name("Bluetooth Headset")
+                .weight(1000)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Speakers")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&rules, &[]);
+        manager.update_current_output(output("Studio Interface"));
+        manager
+    }
+
+    fn output(name: &str) -> AudioDevice {
+        AudioDeviceBuilder::new().name(name).output().build()
+    }
+
+    #[test]
+    fn test_sticky_device_not_overridden_by_higher_weight() {
+        let manager = manager(true);
+
+        assert!(!manager.should_switch_output(&output("Bluetooth Headset")));
+    }
+
+    #[test]
+    fn test_non_sticky_device_overridden_by_higher_weight() {
+        let manager = manager(false);
+
+        assert!(manager.should_switch_output(&output("Bluetooth Headset")));
+    }
+
+    #[test]
+    fn test_stickiness_cleared_on_disconnect() {
+        let mut manager = manager(true);
+
+        manager.device_disconnected(&output("Studio Interface"));
+
+        assert!(manager.should_switch_output(&output("Bluetooth Headset")));
+    }
+
+    #[test]
+    fn test_disconnect_of_other_device_keeps_stickiness() {
+        let mut manager = manager(true);
+
+        manager.device_disconnected(&output("Built-in Speakers"));
+
+        assert!(!manager.should_switch_output(&output("Bluetooth Headset")));
+    }
+
+    #[test]
+    fn test_sticky_rule_bound_to_uid_holds_its_device() {
+        let rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Studio Interface")
+                .weight(100)
+                .exact_match()
+                .require_uid("studio-uid")
+                .sticky()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Bluetooth Headset")
+                .weight(1000)
+                .exact_match()
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&rules, &[]);
+        manager.update_current_output(
+            AudioDeviceBuilder::new()
+                .name("Studio Interface")
+                .with_uid("studio-uid")
+                .output()
+                .build(),
+        );
+
+        // The rule only matches with the current device's UID known
+        assert!(!manager.should_switch_output(&output("Bluetooth Headset")));
+    }
+
+    #[test]
+    fn test_sticky_rule_parsed_from_config() {
+        let config: Config = r#"
+[general]
+check_interval_ms = 1000
+log_level = "info"
+daemon_mode = false
+
+[notifications]
+show_device_availability = false
+show_switching_actions = true
+
+[[output_devices]]
+name = "Studio Interface"
+weight = 100
+match_type = "exact"
+enabled = true
+sticky = true
+"#
+        .parse()
+        .unwrap();
+
+        assert!(config.output_devices[0].sticky);
+    }
+}
+
+/// Test raw device scores
+#[cfg(test)]
+mod device_scores {
+    use super::*;
+    use audio_device_monitor::TransportType;
+    use std::collections::HashMap;
+
+    fn manager() -> DevicePriorityManager {
+        let rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(120)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Speakers")
+                .weight(50)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("HDMI")
+                .weight(200)
+                .contains_match()
+                .disabled()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Broken Speaker")
+                .weight(-10)
+                .exact_match()
+                .build(),
+        ];
+        DevicePriorityManager::new_from_rules(&rules, &[])
+    }
+
+    #[test]
+    fn test_score_all_output_devices() {
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("AirPods Max")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Built-in Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("HDMI Display")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("Broken Speaker")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("AirPods Mic")
+                .input()
+                .build(),
+        ];
+
+        let scores = manager().score_all_output_devices(&devices);
+
+        let expected: HashMap<String, u32> = [
+            ("AirPods Pro", 120),
+            ("AirPods Max", 100),
+            ("Built-in Speakers", 50),
+            ("HDMI Display", 0),
+            ("Broken Speaker", 0),
+        ]
+        .into_iter()
+        .map(|(name, score)| (name.to_string(), score))
+        .collect();
+        assert_eq!(scores, expected);
+    }
+
+    #[test]
+    fn test_scores_match_ranking() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(100)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("USB Headphones")
+                .weight(10)
+                .exact_match()
+                .build(),
+        ];
+        // Claims the AirPods for input, keeping them out of the output ranking
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods Pro")
+                .weight(100)
+                .exact_match()
+                .exclusive()
+                .build(),
+        ];
+        let mut manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules)
+            .with_prefer_wired_on_battery(true);
+        manager.set_on_battery_power(true);
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .output()
+                .transport_type(TransportType::Bluetooth)
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("USB Headphones")
+                .output()
+                .transport_type(TransportType::Usb)
+                .build(),
+        ];
+
+        let scores = manager.score_all_output_devices(&devices);
+
+        assert_eq!(scores["AirPods Pro"], 0);
+        assert_eq!(scores["USB Headphones"], 1010);
+        for ranked in manager.rank_output_devices(&devices) {
+            assert_eq!(scores[&ranked.device.name], ranked.score);
+        }
+    }
+
+    #[test]
+    fn test_score_all_input_devices_without_rules() {
+        let devices = vec![
+            AudioDeviceBuilder::new()
+                .name("AirPods Mic")
+                .input()
+                .build(),
+        ];
+
+        let scores = manager().score_all_input_devices(&devices);
+
+        assert_eq!(scores.get("AirPods Mic"), Some(&0));
+        assert_eq!(scores.len(), 1);
+    }
+}
+
+/// Test real-world scenarios
+#[cfg(test)]
+mod selection_explanation {
+    use super::*;
+    use audio_device_monitor::config::MatchType;
+
+    fn manager() -> DevicePriorityManager {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Speakers")
+                .weight(50)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Broken Speaker")
+                .weight(-10)
+                .exact_match()
+                .build(),
+        ];
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Built-in Microphone")
+                .weight(50)
+                .exact_match()
+                .build(),
+        ];
+        DevicePriorityManager::new_from_rules(&output_rules, &input_rules)
+    }
+
+    fn outputs(names: &[&str]) -> Vec<AudioDevice> {
+        names
+            .iter()
+            .map(|name| AudioDeviceBuilder::new().name(name).output().build())
+            .collect()
+    }
+
+    #[test]
+    fn test_explains_selected_device_and_rule() {
+        let devices = outputs(&["Built-in Speakers", "AirPods Pro"]);
+
+        let explanation = manager().explain_output_selection(&devices);
+
+        assert_eq!(explanation.selected_device.unwrap().name, "AirPods Pro");
+        assert_eq!(explanation.matched_rule_name.as_deref(), Some("AirPods"));
+        assert_eq!(explanation.matched_rule_weight, Some(100));
+        assert!(matches!(
+            explanation.matched_rule_match_type,
+            Some(MatchType::Contains)
+        ));
+    }
+
+    #[test]
+    fn test_runners_up_are_ordered_and_exclude_unselectable_devices() {
+        let devices = outputs(&[
+            "Broken Speaker",
+            "Built-in Speakers",
+            "USB Headset",
+            "AirPods Pro",
+        ]);
+
+        let explanation = manager().explain_output_selection(&devices);
+
+        let runners_up: Vec<(&str, &str)> = explanation
+            .runners_up
+            .iter()
+            .map(|(device, rule)| (device.name.as_str(), rule.name.as_str()))
+            .collect();
+        assert_eq!(runners_up, vec![("Built-in Speakers", "Built-in Speakers")]);
+    }
+
+    #[test]
+    fn test_nothing_selected_when_no_rule_matches() {
+        let devices = outputs(&["USB Headset"]);
+
+        let explanation = manager().explain_output_selection(&devices);
+
+        assert!(explanation.selected_device.is_none());
+        assert!(explanation.matched_rule_name.is_none());
+        assert!(explanation.matched_rule_weight.is_none());
+        assert!(explanation.runners_up.is_empty());
+    }
+
+    #[test]
+    fn test_input_selection_ignores_output_devices() {
+        let mut devices = outputs(&["AirPods Pro"]);
+        devices.push(
+            AudioDeviceBuilder::new()
+                .name("Built-in Microphone")
+                .input()
+                .build(),
+        );
+
+        let explanation = manager().explain_input_selection(&devices);
+
+        assert_eq!(
+            explanation.selected_device.unwrap().name,
+            "Built-in Microphone"
+        );
+        assert!(explanation.runners_up.is_empty());
+    }
+}
+
+mod device_ranking {
+    use super::*;
+
+    fn outputs(names: &[&str]) -> Vec<AudioDevice> {
+        names
+            .iter()
+            .map(|name| AudioDeviceBuilder::new().name(name).output().build())
+            .collect()
+    }
+
+    fn ranking(ranked: &[RankedDevice]) -> Vec<(&str, u32, Option<&str>)> {
+        ranked
+            .iter()
+            .map(|ranked| {
+                (
+                    ranked.device.name.as_str(),
+                    ranked.score,
+                    ranked.matched_rule.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unselectable_devices_follow_with_score_zero() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Broken Speaker")
+                .weight(-10)
+                .exact_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Speakers")
+                .weight(50)
+                .contains_match()
+                .build(),
+        ];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+        let mut devices = outputs(&[
+            "USB Headset",
+            "Broken Speaker",
+            "Built-in Speakers",
+            "AirPods Pro",
+        ]);
+        devices.push(
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .input()
+                .build(),
+        );
+
+        let ranked = manager.rank_output_devices(&devices);
+
+        assert_eq!(
+            ranking(&ranked),
+            vec![
+                ("AirPods Pro", 100, Some("AirPods")),
+                ("Built-in Speakers", 50, Some("Speakers")),
+                ("USB Headset", 0, None),
+                ("Broken Speaker", 0, None),
+            ]
+        );
+        assert_eq!(
+            manager.find_best_output_device(&devices).unwrap().name,
+            ranked[0].device.name
+        );
+    }
+
+    #[test]
+    fn test_equal_scores_go_to_the_earlier_rule() {
+        let output_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("Speakers")
+                .weight(50)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Headset")
+                .weight(50)
+                .contains_match()
+                .build(),
+        ];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+        let devices = outputs(&["USB Headset", "Built-in Speakers"]);
+
+        let ranked = manager.rank_output_devices(&devices);
+
+        assert_eq!(
+            ranking(&ranked),
+            vec![
+                ("Built-in Speakers", 50, Some("Speakers")),
+                ("USB Headset", 50, Some("Headset")),
+            ]
+        );
+        assert_eq!(
+            manager.find_best_output_device(&devices).unwrap().name,
+            "Built-in Speakers"
+        );
     }
 
     #[test]
-    fn test_multiple_rules_same_device() {
+    fn test_nothing_selected_when_every_device_scores_zero() {
         let output_rules = vec![
             DeviceRuleBuilder::new()
                 .name("AirPods")
                 .weight(100)
                 .contains_match()
                 .build(),
+        ];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
+        let devices = outputs(&["USB Headset"]);
+
+        assert_eq!(
+            ranking(&manager.rank_output_devices(&devices)),
+            vec![("USB Headset", 0, None)]
+        );
+        assert!(manager.find_best_output_device(&devices).is_none());
+    }
+
+    #[test]
+    fn test_input_ranking_uses_input_rules() {
+        let input_rules = vec![
             DeviceRuleBuilder::new()
-                .name("Pro")
-                .weight(150)
+                .name("Microphone")
+                .weight(40)
                 .contains_match()
                 .build(),
         ];
-
-        let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
-
-        let devices = vec![
+        let manager = DevicePriorityManager::new_from_rules(&[], &input_rules);
+        let mut devices = outputs(&["Built-in Speakers"]);
+        devices.push(
             AudioDeviceBuilder::new()
-                .name("AirPods Pro")
-                .output()
+                .name("Built-in Microphone")
+                .input()
                 .build(),
-        ];
+        );
 
-        // Device matches both rules, should get the higher weight (150)
-        let best_device = manager.find_best_output_device(&devices);
-        assert!(best_device.is_some());
-        assert_eq!(best_device.unwrap().name, "AirPods Pro");
+        assert_eq!(
+            ranking(&manager.rank_input_devices(&devices)),
+            vec![("Built-in Microphone", 40, Some("Microphone"))]
+        );
+        assert_eq!(
+            ranking(&manager.rank_output_devices(&devices)),
+            vec![("Built-in Speakers", 0, None)]
+        );
     }
 }
 
-/// Test input vs output device separation
-#[cfg(test)]
-mod device_type_separation {
+mod device_explanation {
     use super::*;
+    use audio_device_monitor::DeviceType;
 
     #[test]
-    fn test_input_output_separation() {
+    fn test_every_rule_is_checked_in_config_order() {
         let output_rules = vec![
             DeviceRuleBuilder::new()
-                .name("Output Device")
+                .name("AirPods")
                 .weight(100)
+                .contains_match()
+                .build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Speakers")
+                .weight(50)
                 .exact_match()
                 .build(),
-        ];
-
-        let input_rules = vec![
             DeviceRuleBuilder::new()
-                .name("Input Device")
-                .weight(100)
+                .name("AirPods Pro")
+                .weight(200)
                 .exact_match()
+                .disabled()
                 .build(),
         ];
-
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
-
-        let devices = vec![
-            AudioDeviceBuilder::new()
-                .name("Output Device")
-                .output()
-                .build(),
-            AudioDeviceBuilder::new()
-                .name("Input Device")
-                .input()
+        let input_rules = vec![
+            DeviceRuleBuilder::new()
+                .name("AirPods")
+                .weight(80)
+                .contains_match()
                 .build(),
         ];
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
+        let device = AudioDeviceBuilder::new()
+            .name("AirPods Pro")
+            .output()
+            .build();
 
-        // Output rules should only match output devices
-        let best_output = manager.find_best_output_device(&devices);
-        assert!(best_output.is_some());
-        assert_eq!(best_output.unwrap().name, "Output Device");
-
-        // Input rules should only match input devices
-        let best_input = manager.find_best_input_device(&devices);
-        assert!(best_input.is_some());
-        assert_eq!(best_input.unwrap().name, "Input Device");
+        let results = manager.explain_device(&device);
+
+        let summary: Vec<(DeviceType, &str, bool, Option<u32>)> = results
+            .iter()
+            .map(|result| {
+                (
+                    result.direction.clone(),
+                    result.rule.name.as_str(),
+                    result.matched,
+                    result.score,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (DeviceType::Output, "AirPods", true, Some(100)),
+                (DeviceType::Output, "Built-in Speakers", false, None),
+                // Disabled rules still report whether they match, but never score
+                (DeviceType::Output, "AirPods Pro", true, None),
+                (DeviceType::Input, "AirPods", true, Some(80)),
+            ]
+        );
     }
 
     #[test]
-    fn test_wrong_device_type_no_match() {
-        let output_rules = vec![
+    fn test_blacklisting_rule_scores_zero() {
+        let rules = vec![
             DeviceRuleBuilder::new()
-                .name("Any Device")
-                .weight(100)
-                .contains_match()
+                .name("Broken Speaker")
+                .weight(-10)
+                .exact_match()
                 .build(),
         ];
+        let manager = DevicePriorityManager::new_from_rules(&rules, &[]);
+        let device = AudioDeviceBuilder::new()
+            .name("Broken Speaker")
+            .output()
+            .build();
 
-        let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
-
-        // Only input devices available
-        let devices = vec![
-            AudioDeviceBuilder::new()
-                .name("Any Device Input")
-                .input()
-                .build(),
-            AudioDeviceBuilder::new()
-                .name("Another Any Device")
-                .input()
-                .build(),
-        ];
+        let results = manager.explain_device(&device);
 
-        // Output rules shouldn't match input devices
-        let best_output = manager.find_best_output_device(&devices);
-        assert!(best_output.is_none());
+        assert_eq!(results.len(), 1);
+        assert!(results[0].matched);
+        assert_eq!(results[0].score, Some(0));
     }
 }
 
-/// Test device state management
-#[cfg(test)]
-mod state_management {
+mod stability {
     use super::*;
+    use audio_device_monitor::config::{Config, DeviceRule};
+    use audio_device_monitor::{AudioSystemInterface, MockAudioSystem, TransportType};
+    use std::time::Duration;
+
+    fn manager(rules: &[DeviceRule]) -> DevicePriorityManager {
+        DevicePriorityManager::new_from_rules(rules, rules).with_stability_thresholds(750, 1500)
+    }
+
+    fn is_stable(manager: &DevicePriorityManager, audio_system: &MockAudioSystem) -> bool {
+        let devices = audio_system.enumerate_devices().unwrap();
+        manager.is_device_stable(&devices[0], audio_system.now(), &devices)
+    }
 
     #[test]
-    fn test_should_switch_with_no_current_device() {
-        let config = create_test_config(vec![], vec![]);
-        let manager = DevicePriorityManager::new(&config);
+    fn test_rule_stability_overrides_global_threshold() {
+        let rule = DeviceRuleBuilder::new()
+            .name("USB Hub Audio")
+            .stability_ms(100)
+            .build();
+        let device = AudioDeviceBuilder::new().name("USB Hub Audio").build();
 
-        let new_device = AudioDeviceBuilder::new()
-            .name("New Device")
-            .output()
+        assert_eq!(manager(&[rule]).get_stability_threshold(&device), 100);
+        assert_eq!(manager(&[]).get_stability_threshold(&device), 750);
+    }
+
+    #[test]
+    fn test_bluetooth_devices_fall_back_to_bluetooth_threshold() {
+        let headset = AudioDeviceBuilder::new()
+            .name("Kitchen Speaker")
+            .transport_type(TransportType::Bluetooth)
+            .build();
+        let wired = AudioDeviceBuilder::new()
+            .name("Sony USB Headset")
+            .transport_type(TransportType::Usb)
             .build();
 
-        // Should switch when no current device is set
-        assert!(manager.should_switch_output(&new_device));
-        assert!(manager.should_switch_input(&new_device));
+        let manager = manager(&[]);
+        assert_eq!(manager.get_stability_threshold(&headset), 1500);
+        assert_eq!(manager.get_stability_threshold(&wired), 750);
     }
 
     #[test]
-    fn test_should_switch_to_different_device() {
-        let config = create_test_config(vec![], vec![]);
-        let mut manager = DevicePriorityManager::new(&config);
+    fn test_thresholds_come_from_config() {
+        let mut config = Config::default();
+        config.general.device_change_debounce_ms = 200;
+        config.general.bluetooth_device_change_debounce_ms = 400;
+        let manager = DevicePriorityManager::new(&config);
 
-        // Set current devices
-        manager.update_current_output("Current Output".to_string());
-        manager.update_current_input("Current Input".to_string());
+        let device = AudioDeviceBuilder::new().name("AirPods Pro").build();
+        assert_eq!(manager.get_stability_threshold(&device), 400);
+    }
 
-        let new_output = AudioDeviceBuilder::new()
-            .name("New Output")
-            .output()
+    #[test]
+    fn test_device_becomes_stable_after_rule_threshold() {
+        let rule = DeviceRuleBuilder::new()
+            .name("USB Hub Audio")
+            .stability_ms(100)
             .build();
+        let manager = manager(&[rule]);
+        let audio_system = MockAudioSystem::new();
+        audio_system.connect_device(AudioDeviceBuilder::new().name("USB Hub Audio").build());
 
-        let new_input = AudioDeviceBuilder::new().name("New Input").input().build();
+        audio_system.advance_time(Duration::from_millis(99));
+        assert!(!is_stable(&manager, &audio_system));
 
-        // Should switch to different devices
-        assert!(manager.should_switch_output(&new_output));
-        assert!(manager.should_switch_input(&new_input));
+        audio_system.advance_time(Duration::from_millis(1));
+        assert!(is_stable(&manager, &audio_system));
     }
 
     #[test]
-    fn test_should_not_switch_to_same_device() {
-        let config = create_test_config(vec![], vec![]);
-        let mut manager = DevicePriorityManager::new(&config);
+    fn test_untracked_device_is_not_stable() {
+        let manager = manager(&[]);
+        let audio_system = MockAudioSystem::new();
+        audio_system.add_device(AudioDeviceBuilder::new().name("USB Hub Audio").build());
 
-        let device_name = "Same Device";
+        audio_system.advance_time(Duration::from_secs(60));
+        assert!(!is_stable(&manager, &audio_system));
+    }
 
-        // Set current devices
-        manager.update_current_output(device_name.to_string());
-        manager.update_current_input(device_name.to_string());
+    #[test]
+    fn test_bluetooth_device_needs_paired_input() {
+        let manager = manager(&[]);
+        let audio_system = MockAudioSystem::new();
+        audio_system.connect_device(
+            AudioDeviceBuilder::new()
+                .id("bt-out")
+                .name("AirPods Pro")
+                .output()
+                .build(),
+        );
 
-        let same_output = AudioDeviceBuilder::new().name(device_name).output().build();
+        audio_system.advance_time(Duration::from_millis(1500));
+        assert!(!is_stable(&manager, &audio_system));
 
-        let same_input = AudioDeviceBuilder::new().name(device_name).input().build();
+        audio_system.connect_device(
+            AudioDeviceBuilder::new()
+                .id("bt-in")
+                .name("AirPods Pro")
+                .input()
+                .build(),
+        );
+        assert!(is_stable(&manager, &audio_system));
+    }
 
-        // Should not switch to the same device
-        assert!(!manager.should_switch_output(&same_output));
-        assert!(!manager.should_switch_input(&same_input));
+    #[test]
+    fn test_transport_type_overrides_name_heuristic() {
+        let device = |name: &str| AudioDeviceBuilder::new().name(name).build();
+
+        // Named like a Bluetooth headset, but plugged in over USB
+        let usb_headset = AudioDeviceBuilder::new()
+            .name("Sony USB Headset")
+            .transport_type(TransportType::Usb)
+            .build();
+        assert!(!usb_headset.is_bluetooth());
+
+        let renamed = AudioDeviceBuilder::new()
+            .name("Kitchen")
+            .transport_type(TransportType::Bluetooth)
+            .build();
+        assert!(renamed.is_bluetooth());
+
+        // Unknown transport falls back to the name
+        assert!(device("AirPods Pro").is_bluetooth());
+        assert!(
+            AudioDeviceBuilder::new()
+                .name("AirPods Pro")
+                .transport_type(TransportType::Unknown)
+                .build()
+                .is_bluetooth()
+        );
+        assert!(!device("Scarlett 2i2").is_bluetooth());
     }
 }
 
-/// Test real-world scenarios
-#[cfg(test)]
 mod real_world_scenarios {
     use super::*;
 
@@ -445,8 +2125,7 @@ mod real_world_scenarios {
                 .build(),
         ];
 
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         // Scenario 1: All devices available - should pick highest priority
         let all_devices = vec![
@@ -504,8 +2183,7 @@ mod real_world_scenarios {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         // AirPods not available, should fall back to Audioengine
         let devices_without_airpods = vec![
@@ -545,8 +2223,7 @@ mod real_world_scenarios {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         // Only lowest priority device available
         let fallback_devices = vec![
@@ -578,8 +2255,7 @@ mod edge_cases {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![AudioDeviceBuilder::new().name("").output().build()];
 
@@ -599,8 +2275,7 @@ mod edge_cases {
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new()
@@ -619,19 +2294,18 @@ mod edge_cases {
         let output_rules = vec![
             DeviceRuleBuilder::new()
                 .name("Device A")
-                .weight(u32::MAX - 1)
+                .weight(i64::MAX - 1)
                 .exact_match()
                 .build(),
             DeviceRuleBuilder::new()
                 .name("Device B")
-                .weight(u32::MAX)
+                .weight(i64::MAX)
                 .exact_match()
                 .build(),
         ];
 
         let input_rules = vec![];
-        let config = create_test_config(output_rules, input_rules);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &input_rules);
 
         let devices = vec![
             AudioDeviceBuilder::new().name("Device A").output().build(),
@@ -640,7 +2314,7 @@ mod edge_cases {
 
         let best_device = manager.find_best_output_device(&devices);
         assert!(best_device.is_some());
-        assert_eq!(best_device.unwrap().name, "Device B"); // u32::MAX wins
+        assert_eq!(best_device.unwrap().name, "Device B"); // i64::MAX wins
     }
 
     #[test]
@@ -653,7 +2327,7 @@ mod edge_cases {
             output_rules.push(
                 DeviceRuleBuilder::new()
                     .name(&format!("Device {i}"))
-                    .weight(i as u32)
+                    .weight(i as i64)
                     .exact_match()
                     .build(),
             );
@@ -666,8 +2340,7 @@ mod edge_cases {
             );
         }
 
-        let config = create_test_config(output_rules, vec![]);
-        let manager = DevicePriorityManager::new(&config);
+        let manager = DevicePriorityManager::new_from_rules(&output_rules, &[]);
 
         // This should complete quickly even with many devices/rules
         let start = std::time::Instant::now();
@@ -682,3 +2355,187 @@ mod edge_cases {
         );
     }
 }
+
+/// Test reduced weight for devices that reconnect shortly after disconnecting
+#[cfg(test)]
+mod weight_decay {
+    use super::*;
+
+    fn manager(decay_window_ms: Option<u64>) -> DevicePriorityManager {
+        let airpods = DeviceRuleBuilder::new()
+            .name("AirPods Pro")
+            .weight(100)
+            .exact_match();
+        let airpods = match decay_window_ms {
+            Some(window_ms) => airpods.weight_decay(window_ms, 0.5),
+            None => airpods,
+        };
+        let rules = vec![
+            airpods.build(),
+            DeviceRuleBuilder::new()
+                .name("Built-in Speakers")
+                .weight(80)
+                .exact_match()
+                .build(),
+        ];
+        DevicePriorityManager::new_from_rules(&rules, &[])
+    }
+
+    fn airpods() -> AudioDevice {
+        AudioDeviceBuilder::new()
+            .id("airpods-1")
+            .name("AirPods Pro")
+            .output()
+            .build()
+    }
+
+    fn speakers() -> AudioDevice {
+        AudioDeviceBuilder::new()
+            .id("speakers-1")
+            .name("Built-in Speakers")
+            .output()
+            .build()
+    }
+
+    /// Disconnect the AirPods and fall back to the speakers, as the controller would
+    fn power_cycle(manager: &mut DevicePriorityManager) {
+        manager.update_current_output(airpods());
+        manager.device_disconnected(&airpods());
+        manager.update_current_output(speakers());
+    }
+
+    #[test]
+    fn test_rapid_reconnect_does_not_switch_back() {
+        let mut manager = manager(Some(60_000));
+        power_cycle(&mut manager);
+
+        let best = manager
+            .find_best_output_device(&[airpods(), speakers()])
+            .unwrap();
+
+        assert_eq!(best.name, "Built-in Speakers");
+        assert!(!manager.should_switch_output(&best));
+    }
+
+    #[test]
+    fn test_weight_restored_after_decay_window() {
+        let mut manager = manager(Some(1));
+        power_cycle(&mut manager);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let best = manager
+            .find_best_output_device(&[airpods(), speakers()])
+            .unwrap();
+
+        assert_eq!(best.name, "AirPods Pro");
+        assert!(manager.should_switch_output(&best));
+    }
+
+    #[test]
+    fn test_expired_disconnects_are_forgotten() {
+        let mut manager = manager(Some(1));
+        power_cycle(&mut manager);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        manager.device_disconnected(&speakers());
+
+        assert_eq!(manager.recently_disconnected(), vec!["speakers-1"]);
+    }
+
+    #[test]
+    fn test_disconnects_without_window_are_forgotten() {
+        let mut manager = manager(None);
+        power_cycle(&mut manager);
+
+        manager.device_disconnected(&speakers());
+
+        assert_eq!(manager.recently_disconnected(), vec!["speakers-1"]);
+    }
+
+    #[test]
+    fn test_no_decay_without_window() {
+        let mut manager = manager(None);
+        power_cycle(&mut manager);
+
+        let best = manager.find_best_output_device(&[airpods(), speakers()]);
+
+        assert_eq!(best.unwrap().name, "AirPods Pro");
+    }
+}
+
+/// Test rules whose weight comes from a `weight_formula`
+#[cfg(all(test, feature = "weight-formulas"))]
+mod weight_formulas {
+    use super::*;
+
+    fn speakers() -> Vec<AudioDevice> {
+        vec![
+            AudioDeviceBuilder::new()
+                .name("Studio Display Speakers")
+                .output()
+                .build(),
+            AudioDeviceBuilder::new()
+                .name("MacBook Pro Speakers")
+                .output()
+                .build(),
+        ]
+    }
+
+    fn rules(display_formula: Option<&str>) -> Vec<audio_device_monitor::config::DeviceRule> {
+        let mut display = DeviceRuleBuilder::new()
+            .name("Studio Display Speakers")
+            .weight(0)
+            .exact_match();
+        if let Some(formula) = display_formula {
+            display = display.weight_formula(formula);
+        }
+
+        vec![
+            // Every speaker gets a base weight of 40
+            DeviceRuleBuilder::new()
+                .name("Speakers")
+                .weight(40)
+                .contains_match()
+                .build(),
+            display.build(),
+            DeviceRuleBuilder::new()
+                .name("MacBook Pro Speakers")
+                .weight(60)
+                .exact_match()
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_formula_is_relative_to_base_rule() {
+        let manager = DevicePriorityManager::new_from_rules(&rules(Some("base + 50")), &[]);
+
+        let best = manager.find_best_output_device(&speakers()).unwrap();
+        assert_eq!(best.name, "Studio Display Speakers");
+
+        let scores = manager.score_all_output_devices(&speakers());
+        assert_eq!(scores["Studio Display Speakers"], 90);
+        assert_eq!(scores["MacBook Pro Speakers"], 60);
+    }
+
+    #[test]
+    fn test_static_weight_used_without_formula() {
+        let manager = DevicePriorityManager::new_from_rules(&rules(None), &[]);
+
+        let best = manager.find_best_output_device(&speakers()).unwrap();
+        assert_eq!(best.name, "MacBook Pro Speakers");
+    }
+
+    #[test]
+    fn test_lower_formula_weight_loses_to_base_rule() {
+        let manager = DevicePriorityManager::new_from_rules(&rules(Some("base - 100")), &[]);
+
+        // The display's formula rule now weighs -60, so the "Speakers" rule decides it
+        assert_eq!(
+            manager.score_all_output_devices(&speakers())["Studio Display Speakers"],
+            40
+        );
+        let best = manager.find_best_output_device(&speakers()).unwrap();
+        assert_eq!(best.name, "MacBook Pro Speakers");
+    }
+}
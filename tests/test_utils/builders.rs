@@ -5,9 +5,9 @@
 
 #![allow(dead_code)]
 
-use audio_device_monitor::audio::{AudioDevice, DeviceType};
+use audio_device_monitor::audio::{AudioDevice, DeviceType, TransportType};
 use audio_device_monitor::config::{
-    Config, DeviceRule, GeneralConfig, MatchType, NotificationConfig,
+    Config, DeviceRule, DeviceTypeFilter, GeneralConfig, MatchType, NotificationConfig,
 };
 
 /// Builder for creating test AudioDevice instances
@@ -18,6 +18,8 @@ pub struct AudioDeviceBuilder {
     is_default: bool,
     is_available: bool,
     uid: Option<String>,
+    channels: Option<u32>,
+    transport_type: Option<TransportType>,
 }
 
 impl AudioDeviceBuilder {
@@ -29,6 +31,8 @@ impl AudioDeviceBuilder {
             is_default: false,
             is_available: true,
             uid: None,
+            channels: None,
+            transport_type: None,
         }
     }
 
@@ -77,11 +81,27 @@ impl AudioDeviceBuilder {
         self
     }
 
+    pub fn channels(mut self, channels: u32) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    pub fn transport_type(mut self, transport_type: TransportType) -> Self {
+        self.transport_type = Some(transport_type);
+        self
+    }
+
     pub fn build(self) -> AudioDevice {
         let mut device = AudioDevice::new(self.id, self.name, self.device_type);
         if let Some(uid) = self.uid {
             device = device.with_uid(uid);
         }
+        if let Some(channels) = self.channels {
+            device = device.with_channels(channels);
+        }
+        if let Some(transport_type) = self.transport_type {
+            device = device.with_transport_type(transport_type);
+        }
         device = device.set_default(self.is_default);
         device = device.set_available(self.is_available);
         device
@@ -97,9 +117,29 @@ impl Default for AudioDeviceBuilder {
 /// Builder for creating test DeviceRule instances
 pub struct DeviceRuleBuilder {
     name: String,
-    weight: u32,
+    weight: i64,
     match_type: MatchType,
     enabled: bool,
+    exclusive: bool,
+    sticky: bool,
+    weight_decay_after_disconnect_ms: Option<u64>,
+    weight_decay_factor: Option<f32>,
+    buffer_size_frames: Option<u32>,
+    on_select_volume: Option<f32>,
+    description: Option<String>,
+    comment: Option<String>,
+    min_channels: Option<u32>,
+    max_channels: Option<u32>,
+    applies_to: DeviceTypeFilter,
+    requires_other_device: Option<String>,
+    require_uid: Option<String>,
+    weight_formula: Option<String>,
+    case_sensitive: bool,
+    transport_type: Option<TransportType>,
+    uid: Option<String>,
+    stability_ms: Option<u64>,
+    pre_switch_command: Option<String>,
+    post_switch_command: Option<String>,
 }
 
 impl DeviceRuleBuilder {
@@ -109,6 +149,26 @@ impl DeviceRuleBuilder {
             weight: 100,
             match_type: MatchType::Exact,
             enabled: true,
+            exclusive: false,
+            sticky: false,
+            weight_decay_after_disconnect_ms: None,
+            weight_decay_factor: None,
+            buffer_size_frames: None,
+            on_select_volume: None,
+            description: None,
+            comment: None,
+            min_channels: None,
+            max_channels: None,
+            applies_to: DeviceTypeFilter::Both,
+            requires_other_device: None,
+            require_uid: None,
+            weight_formula: None,
+            case_sensitive: true,
+            transport_type: None,
+            uid: None,
+            stability_ms: None,
+            pre_switch_command: None,
+            post_switch_command: None,
         }
     }
 
@@ -117,7 +177,7 @@ impl DeviceRuleBuilder {
         self
     }
 
-    pub fn weight(mut self, weight: u32) -> Self {
+    pub fn weight(mut self, weight: i64) -> Self {
         self.weight = weight;
         self
     }
@@ -147,17 +207,141 @@ impl DeviceRuleBuilder {
         self
     }
 
+    pub fn regex_match(mut self) -> Self {
+        self.match_type = MatchType::Regex;
+        self
+    }
+
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_sensitive = false;
+        self
+    }
+
+    pub fn transport_type(mut self, transport_type: TransportType) -> Self {
+        self.transport_type = Some(transport_type);
+        self
+    }
+
     pub fn disabled(mut self) -> Self {
         self.enabled = false;
         self
     }
 
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    pub fn sticky(mut self) -> Self {
+        self.sticky = true;
+        self
+    }
+
+    pub fn weight_decay(mut self, after_disconnect_ms: u64, factor: f32) -> Self {
+        self.weight_decay_after_disconnect_ms = Some(after_disconnect_ms);
+        self.weight_decay_factor = Some(factor);
+        self
+    }
+
+    pub fn buffer_size_frames(mut self, frames: u32) -> Self {
+        self.buffer_size_frames = Some(frames);
+        self
+    }
+
+    pub fn on_select_volume(mut self, volume: f32) -> Self {
+        self.on_select_volume = Some(volume);
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    pub fn min_channels(mut self, channels: u32) -> Self {
+        self.min_channels = Some(channels);
+        self
+    }
+
+    pub fn max_channels(mut self, channels: u32) -> Self {
+        self.max_channels = Some(channels);
+        self
+    }
+
+    pub fn applies_to(mut self, applies_to: DeviceTypeFilter) -> Self {
+        self.applies_to = applies_to;
+        self
+    }
+
+    pub fn requires_other_device(mut self, device_name: &str) -> Self {
+        self.requires_other_device = Some(device_name.to_string());
+        self
+    }
+
+    pub fn require_uid(mut self, uid: &str) -> Self {
+        self.require_uid = Some(uid.to_string());
+        self
+    }
+
+    pub fn uid(mut self, uid: &str) -> Self {
+        self.uid = Some(uid.to_string());
+        self
+    }
+
+    pub fn weight_formula(mut self, formula: &str) -> Self {
+        self.weight_formula = Some(formula.to_string());
+        self
+    }
+
+    pub fn stability_ms(mut self, stability_ms: u64) -> Self {
+        self.stability_ms = Some(stability_ms);
+        self
+    }
+
+    pub fn pre_switch_command(mut self, command: &str) -> Self {
+        self.pre_switch_command = Some(command.to_string());
+        self
+    }
+
+    pub fn post_switch_command(mut self, command: &str) -> Self {
+        self.post_switch_command = Some(command.to_string());
+        self
+    }
+
     pub fn build(self) -> DeviceRule {
         DeviceRule {
             name: self.name,
             weight: self.weight,
             match_type: self.match_type,
             enabled: self.enabled,
+            exclusive: self.exclusive,
+            sticky: self.sticky,
+            weight_decay_after_disconnect_ms: self.weight_decay_after_disconnect_ms,
+            weight_decay_factor: self.weight_decay_factor,
+            buffer_size_frames: self.buffer_size_frames,
+            on_select_volume: self.on_select_volume,
+            description: self.description,
+            comment: self.comment,
+            min_channels: self.min_channels,
+            max_channels: self.max_channels,
+            applies_to: self.applies_to,
+            requires_other_device: self.requires_other_device,
+            require_uid: self.require_uid,
+            weight_formula: self.weight_formula,
+            case_sensitive: self.case_sensitive,
+            transport_type: self.transport_type,
+            uid: self.uid,
+            stability_ms: self.stability_ms,
+            pre_switch_command: self.pre_switch_command,
+            post_switch_command: self.post_switch_command,
+            compiled_regex: None,
+            #[cfg(feature = "weight-formulas")]
+            compiled_formula: None,
         }
     }
 }
@@ -270,6 +454,7 @@ pub struct ConfigBuilder {
     notifications: NotificationConfig,
     output_devices: Vec<DeviceRule>,
     input_devices: Vec<DeviceRule>,
+    devices: Vec<DeviceRule>,
 }
 
 impl ConfigBuilder {
@@ -280,14 +465,31 @@ impl ConfigBuilder {
                 poll_interval_ms: 10_000,
                 log_level: "info".to_string(),
                 daemon_mode: true,
+                backoff_ms: 100,
+                max_backoff_ms: 30_000,
+                device_change_debounce_ms: 750,
+                bluetooth_device_change_debounce_ms: 1500,
+                sync_system_alerts: false,
+                prefer_wired_on_battery: false,
+                min_weight_to_switch: 0,
+                shutdown_timeout_ms: 2000,
+                restore_volume: false,
+                hook_timeout_ms: 5000,
+                history_max_lines: 1000,
+                switch_retry_count: 3,
+                // Refused switches are retried without waiting
+                switch_retry_delay_ms: 0,
             },
             notifications: NotificationConfig {
                 show_device_availability: true,
                 show_switching_actions: true,
                 show_device_changes: None,
+                quiet_hours_start: None,
+                quiet_hours_end: None,
             },
             output_devices: Vec::new(),
             input_devices: Vec::new(),
+            devices: Vec::new(),
         }
     }
 
@@ -326,12 +528,19 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn add_device(mut self, rule: DeviceRule) -> Self {
+        self.devices.push(rule);
+        self
+    }
+
     pub fn build(self) -> Config {
         Config {
             general: self.general,
             notifications: self.notifications,
             output_devices: self.output_devices,
             input_devices: self.input_devices,
+            devices: self.devices,
+            profiles: Default::default(),
         }
     }
 }